@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS calculations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    op TEXT NOT NULL,
+    x INTEGER NOT NULL,
+    y INTEGER NOT NULL,
+    res INTEGER,
+    error TEXT,
+    timestamp INTEGER NOT NULL
+);
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS quota_usage (
+    key_name TEXT NOT NULL,
+    window_id INTEGER NOT NULL,
+    used INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (key_name, window_id)
+);
@@ -0,0 +1,70 @@
+//! The access-log line as data. The middleware emits one line per
+//! request under `target: "access"` with a stable field set; this module
+//! is the reading side of that contract, used by the replay tool to
+//! reconstruct requests from a captured log.
+
+use serde::Deserialize;
+
+/// One request as the access log recorded it. Fields beyond the stable
+/// set (e.g. a captured body) are optional so older logs still parse.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccessRecord {
+    /// RFC 3339 UTC, as the JSON log layer writes it; lexicographic
+    /// comparison orders records correctly.
+    #[serde(default)]
+    pub timestamp: String,
+    pub method: String,
+    pub path: String,
+    pub route: String,
+    pub status: u16,
+    pub latency_ms: u64,
+    pub request_id: String,
+    #[serde(default)]
+    pub tenant: Option<String>,
+    /// Present only when body capture was enabled at record time.
+    #[serde(default)]
+    pub body: Option<serde_json::Value>,
+}
+
+/// The JSON log layer's envelope around the fields the middleware logged.
+#[derive(Deserialize)]
+struct LogLine {
+    #[serde(default)]
+    timestamp: String,
+    target: String,
+    fields: serde_json::Value,
+}
+
+/// Parses one JSON log line into a record, ignoring lines from other
+/// targets (the access log shares the file with every other tracing
+/// line) and lines that do not parse at all.
+pub fn parse_line(line: &str) -> Option<AccessRecord> {
+    let envelope: LogLine = serde_json::from_str(line).ok()?;
+    if envelope.target != "access" {
+        return None;
+    }
+    let mut record: AccessRecord = serde_json::from_value(envelope.fields).ok()?;
+    record.timestamp = envelope.timestamp;
+    Some(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn access_lines_parse_and_other_targets_are_ignored() {
+        let line = r#"{"timestamp":"2026-08-30T12:00:00.000001Z","level":"INFO","fields":{"message":"request","method":"POST","path":"/api/v0/add","route":"/api/v0/add","status":200,"latency_ms":3,"bytes":42,"request_id":"abc-123","remote_addr":"127.0.0.1:9999","client_ip":"127.0.0.1","tenant":"default"},"target":"access"}"#;
+        let record = parse_line(line).expect("an access line must parse");
+        assert_eq!(record.method, "POST");
+        assert_eq!(record.path, "/api/v0/add");
+        assert_eq!(record.status, 200);
+        assert_eq!(record.tenant.as_deref(), Some("default"));
+        assert!(record.body.is_none());
+        assert_eq!(record.timestamp, "2026-08-30T12:00:00.000001Z");
+
+        let other = r#"{"timestamp":"2026-08-30T12:00:00Z","level":"INFO","fields":{"message":"stats flush"},"target":"stats"}"#;
+        assert!(parse_line(other).is_none());
+        assert!(parse_line("not json at all").is_none());
+    }
+}
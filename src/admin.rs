@@ -0,0 +1,148 @@
+#[cfg(feature = "sentry")]
+use actix_web::HttpMessage;
+use actix_web::{
+    body::{BoxBody, EitherBody},
+    dev::{forward_ready, Service, ServiceFactory, ServiceRequest, ServiceResponse, Transform},
+    web, Error, HttpRequest, Scope,
+};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use tracing::info;
+
+/// The /admin scope with its own bearer authentication. Only mounted
+/// when ADMIN_TOKEN is configured; without one the routes simply do not
+/// exist.
+pub fn admin_scope() -> Scope<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<EitherBody<BoxBody>>,
+        Error = Error,
+        InitError = (),
+    >,
+> {
+    let scope = web::scope("/admin")
+        .wrap(AdminAuth)
+        .service(crate::log_level::get_log_level)
+        .service(crate::log_level::set_log_level)
+        .service(crate::maintenance::get_maintenance)
+        .service(crate::maintenance::set_maintenance)
+        .service(crate::stats::reset_stats);
+    #[cfg(feature = "sentry")]
+    let scope = scope
+        .service(crate::reporting::get_reporting)
+        .service(crate::reporting::set_reporting);
+    scope
+}
+
+/// One audit record per admin action: who (the peer address), what, and
+/// the value transition; when comes from the log timestamp. The
+/// breadcrumb makes recent admin activity visible on subsequent sentry
+/// events.
+pub fn audit(req: &HttpRequest, action: &'static str, previous: &str, new: &str) {
+    let who = req
+        .peer_addr()
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    info!(target: "audit", who, action, previous, new, "admin action");
+
+    #[cfg(feature = "sentry")]
+    {
+        let breadcrumb = sentry::Breadcrumb {
+            ty: "default".into(),
+            category: Some("admin".into()),
+            message: Some(format!("{action}: {previous:?} -> {new:?} (by {who})")),
+            ..Default::default()
+        };
+        // On the parent hub, so the trail survives into later requests'
+        // events; per-request hubs snapshot it at creation.
+        match req.extensions().get::<crate::middleware::ParentHub>() {
+            Some(parent) => parent.0.add_breadcrumb(breadcrumb),
+            None => sentry::add_breadcrumb(breadcrumb),
+        }
+    }
+}
+
+/// Bearer authentication against ADMIN_TOKEN, distinct from the client
+/// API keys so operator credentials can rotate independently.
+pub struct AdminAuth;
+
+impl<S, B> Transform<S, ServiceRequest> for AdminAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = AdminAuthService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AdminAuthService { service }))
+    }
+}
+
+pub struct AdminAuthService<S> {
+    service: S,
+}
+
+impl<S> AdminAuthService<S> {
+    fn reject<B>(req: ServiceRequest, err: crate::error::Error) -> ServiceResponse<EitherBody<B>> {
+        use actix_web::ResponseError;
+
+        let response = crate::error::HTTPError::from(err).error_response();
+        req.into_response(response).map_into_right_body()
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for AdminAuthService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        // The scope is only mounted when a token is configured; a
+        // missing one here still fails closed.
+        let Some(token) = crate::config::Config::global().admin_token.clone() else {
+            return Box::pin(ready(Ok(Self::reject(
+                req,
+                crate::error::Error::InvalidAdminToken,
+            ))));
+        };
+
+        let presented = req
+            .headers()
+            .get(actix_web::http::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        let presented = match presented {
+            Some(presented) => presented,
+            None => {
+                return Box::pin(ready(Ok(Self::reject(
+                    req,
+                    crate::error::Error::MissingAdminToken,
+                ))))
+            }
+        };
+
+        if presented != token {
+            return Box::pin(ready(Ok(Self::reject(
+                req,
+                crate::error::Error::InvalidAdminToken,
+            ))));
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(|res| res.map_into_left_body()) })
+    }
+}
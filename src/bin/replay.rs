@@ -0,0 +1,158 @@
+//! Replays requests from a captured JSON access log against a running
+//! server, comparing the statuses it gets back with the ones the log
+//! recorded. Point it at a new build with yesterday's log to see which
+//! requests changed behaviour:
+//!
+//!     replay access.log --target http://127.0.0.1:8080 --route /api/v0 --rate 50
+
+use std::io::BufRead;
+use std::time::Duration;
+
+use clap::Parser;
+use sentry_rs_demo::access_log::{parse_line, AccessRecord};
+
+#[derive(Debug, Parser)]
+#[command(name = "replay", version, about = "Replay a captured access log")]
+struct Args {
+    /// The JSON access log to read, one line per request.
+    log: String,
+
+    /// Base URL the reconstructed requests are sent to.
+    #[arg(long, default_value = "http://127.0.0.1:8080")]
+    target: String,
+
+    /// Only replay records whose route matches, or whose path starts
+    /// with, this value; repeatable.
+    #[arg(long)]
+    route: Vec<String>,
+
+    /// Only replay records at or after this RFC 3339 timestamp.
+    #[arg(long)]
+    since: Option<String>,
+
+    /// Only replay records before this RFC 3339 timestamp.
+    #[arg(long)]
+    until: Option<String>,
+
+    /// Requests per second; 0 replays as fast as the server answers.
+    #[arg(long, default_value_t = 10.0)]
+    rate: f64,
+
+    /// Print what would be replayed and exit without sending anything.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl Args {
+    fn selects(&self, record: &AccessRecord) -> bool {
+        if !self.route.is_empty()
+            && !self
+                .route
+                .iter()
+                .any(|route| record.route == *route || record.path.starts_with(route.as_str()))
+        {
+            return false;
+        }
+        // RFC 3339 UTC compares lexicographically; no date parsing needed.
+        if let Some(since) = &self.since {
+            if record.timestamp.as_str() < since.as_str() {
+                return false;
+            }
+        }
+        if let Some(until) = &self.until {
+            if record.timestamp.as_str() >= until.as_str() {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// One replayed request, reconstructed from the record: same method and
+/// path, the tenant header when one was recorded, the body when captured.
+fn send(
+    client: &reqwest::blocking::Client,
+    target: &str,
+    record: &AccessRecord,
+) -> eyre::Result<u16> {
+    let method: reqwest::Method = record.method.parse()?;
+    let url = format!("{}{}", target.trim_end_matches('/'), record.path);
+    let mut request = client.request(method, url);
+    if let Some(tenant) = &record.tenant {
+        request = request.header(sentry_rs_demo::tenant::TENANT_HEADER, tenant);
+    }
+    if let Some(body) = &record.body {
+        request = request.json(body);
+    }
+    Ok(request.send()?.status().as_u16())
+}
+
+fn main() -> eyre::Result<()> {
+    let args = Args::parse();
+
+    let file = std::fs::File::open(&args.log).map_err(|err| eyre::eyre!("{}: {err}", args.log))?;
+    let records: Vec<AccessRecord> = std::io::BufReader::new(file)
+        .lines()
+        .map_while(|line| line.ok())
+        .filter_map(|line| parse_line(&line))
+        .filter(|record| args.selects(record))
+        .collect();
+
+    if args.dry_run {
+        println!(
+            "would replay {} requests against {}",
+            records.len(),
+            args.target
+        );
+        for record in &records {
+            println!(
+                "  {} {} (recorded {}, {}ms{})",
+                record.method,
+                record.path,
+                record.status,
+                record.latency_ms,
+                if record.body.is_some() {
+                    ", with body"
+                } else {
+                    ""
+                },
+            );
+        }
+        return Ok(());
+    }
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()?;
+    let pause = (args.rate > 0.0).then(|| Duration::from_secs_f64(1.0 / args.rate));
+
+    let mut sent = 0u64;
+    let mut mismatches = 0u64;
+    let mut failed = 0u64;
+    for record in &records {
+        match send(&client, &args.target, record) {
+            Ok(status) if status == record.status => sent += 1,
+            Ok(status) => {
+                sent += 1;
+                mismatches += 1;
+                println!(
+                    "mismatch: {} {} recorded {}, got {} (request_id {})",
+                    record.method, record.path, record.status, status, record.request_id,
+                );
+            }
+            Err(err) => {
+                failed += 1;
+                println!("failed: {} {}: {err}", record.method, record.path);
+            }
+        }
+        if let Some(pause) = pause {
+            std::thread::sleep(pause);
+        }
+    }
+
+    println!("replayed {sent} requests: {mismatches} status mismatches, {failed} send failures");
+    if mismatches > 0 || failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
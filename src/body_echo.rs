@@ -0,0 +1,137 @@
+use std::rc::Rc;
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    web, Error, FromRequest,
+};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+
+/// Buffers request bodies so server-error events can show what the
+/// client actually sent. Off unless BODY_ECHO_ENABLED is set; the echo
+/// is capped at BODY_ECHO_MAX_BYTES, passed through the PII scrubber,
+/// and suppressed entirely for routes on BODY_ECHO_EXCLUDE_ROUTES.
+pub struct BodyEcho;
+
+impl<S, B> Transform<S, ServiceRequest> for BodyEcho
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = BodyEchoService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(BodyEchoService {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct BodyEchoService<S> {
+    // Rc because the body has to be buffered before the inner call,
+    // inside the boxed future.
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for BodyEchoService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let config = crate::config::Config::global();
+        let applies = config.body_echo_enabled
+            && !config.body_echo_exclude_routes.iter().any(|route| {
+                req.match_pattern().as_deref() == Some(route.as_str()) || req.path() == route
+            });
+
+        Box::pin(async move {
+            if !applies {
+                return service.call(req).await;
+            }
+
+            let (http_req, mut payload) = req.into_parts();
+            let bytes = web::Bytes::from_request(&http_req, &mut payload).await?;
+
+            #[cfg(feature = "sentry")]
+            attach(&http_req, &bytes, &config);
+
+            let req = crate::idempotency::reassemble(http_req, bytes);
+            service.call(req).await
+        })
+    }
+}
+
+/// Attaches the scrubbed, capped echo to the request scope up front:
+/// captures fire mid-request (inside the handler's `?`), so waiting for
+/// the response status would be too late. Client errors are never
+/// captured, so the extra surfaces exactly on server-error events.
+#[cfg(feature = "sentry")]
+fn attach(http_req: &actix_web::HttpRequest, bytes: &web::Bytes, config: &crate::config::Config) {
+    use actix_web::HttpMessage;
+
+    if bytes.is_empty() {
+        return;
+    }
+    let echo = render(bytes, config);
+    if let Some(hub) = http_req.extensions().get::<std::sync::Arc<sentry::Hub>>() {
+        hub.configure_scope(|scope| scope.set_extra("request_body", echo.into()));
+    }
+}
+
+/// The echo itself: text (scrubbed) where the body is UTF-8, base64
+/// otherwise, with a marker when the cap cut anything off. JSON bodies
+/// get the structural scrub so sensitive keys are redacted by name.
+#[cfg(feature = "sentry")]
+fn render(bytes: &[u8], config: &crate::config::Config) -> String {
+    let cap = config.body_echo_max_bytes;
+    let truncated = bytes.len() > cap;
+    let slice = &bytes[..bytes.len().min(cap)];
+
+    let text = match std::str::from_utf8(slice) {
+        Ok(text) => Some(text.to_owned()),
+        // A cap landing inside a multibyte character is still text.
+        Err(err) if truncated && err.error_len().is_none() => {
+            Some(String::from_utf8_lossy(&slice[..err.valid_up_to()]).into_owned())
+        }
+        Err(_) => None,
+    };
+
+    let mut echo = match text {
+        Some(mut text) => match serde_json::from_str::<serde_json::Value>(&text) {
+            Ok(mut value) => {
+                crate::telemetry::scrub_json(
+                    &mut value,
+                    &config.scrub_keys,
+                    &config.scrub_patterns,
+                );
+                value.to_string()
+            }
+            Err(_) => {
+                crate::telemetry::scrub_string(&mut text, &config.scrub_patterns);
+                text
+            }
+        },
+        None => {
+            use base64::Engine;
+            base64::engine::general_purpose::STANDARD.encode(slice)
+        }
+    };
+
+    if truncated {
+        echo.push_str(&format!(" [truncated to {cap} of {} bytes]", bytes.len()));
+    }
+    echo
+}
@@ -0,0 +1,97 @@
+use crate::error::{Error, Result};
+
+/// Loads .env files into the process environment before Config reads it.
+///
+/// DOTENV_PATH names an explicit file and must exist; otherwise
+/// .env.{APP_ENV} (e.g. .env.production) is tried first, then .env, and
+/// either may be absent — a container configured purely through real
+/// environment variables must start without complaint. Variables already
+/// set always win: dotenvy never overwrites the environment, which also
+/// means the APP_ENV-specific file takes precedence over plain .env.
+///
+/// DOTENV_PATH and APP_ENV are necessarily read with env::var here: they
+/// decide what the environment contains before Config can exist.
+pub fn load_env_files() -> Result<()> {
+    if let Ok(path) = std::env::var("DOTENV_PATH") {
+        return load(&path, true);
+    }
+    if let Ok(app_env) = std::env::var("APP_ENV") {
+        load(&format!(".env.{app_env}"), false)?;
+    }
+    load(".env", false)
+}
+
+fn load(path: &str, required: bool) -> Result<()> {
+    match dotenvy::from_path(path) {
+        Ok(()) => Ok(()),
+        Err(dotenvy::Error::Io(err)) if !required && err.kind() == std::io::ErrorKind::NotFound => {
+            // Before the subscriber is installed this is dropped, but a
+            // later reload of the module keeps the breadcrumb honest.
+            tracing::debug!(path, "no env file; using the process environment as-is");
+            Ok(())
+        }
+        // A malformed file is a genuine mistake; dotenvy's message names
+        // the offending line.
+        Err(err) => Err(Error::Config {
+            var: "DOTENV_PATH",
+            message: format!("{path}: {err}"),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "sentry-rs-demo-bootstrap-{name}-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn a_present_file_is_loaded() {
+        let dir = temp_dir("present");
+        let path = dir.join(".env");
+        std::fs::write(&path, "BOOTSTRAP_TEST_PRESENT=yes\n").unwrap();
+
+        load(&path.to_string_lossy(), false).unwrap();
+        assert_eq!(std::env::var("BOOTSTRAP_TEST_PRESENT").unwrap(), "yes");
+
+        std::env::remove_var("BOOTSTRAP_TEST_PRESENT");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn a_missing_file_is_fatal_only_when_named_explicitly() {
+        load("/no/such/.env", false).unwrap();
+
+        let err = load("/no/such/.env", true).unwrap_err();
+        let Error::Config { var, message } = err else {
+            panic!("expected a config error");
+        };
+        assert_eq!(var, "DOTENV_PATH");
+        assert!(message.contains("/no/such/.env"));
+    }
+
+    #[test]
+    fn a_malformed_file_propagates_the_parse_error() {
+        let dir = temp_dir("malformed");
+        let path = dir.join(".env");
+        std::fs::write(&path, "GOOD=fine\nnot a valid line\n").unwrap();
+
+        let err = load(&path.to_string_lossy(), false).unwrap_err();
+        let Error::Config { message, .. } = err else {
+            panic!("expected a config error");
+        };
+        assert!(
+            message.contains("not a valid line"),
+            "offending line missing from: {message}"
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::calculator::{Operation, OverflowMode, Width};
+use crate::handlers::CalcValue;
+
+/// Response header telling clients whether their result came from the
+/// cache; absent when the cache is disabled.
+pub const CACHE_HEADER: &str = "x-cache";
+
+/// Everything that determines a calculation's result. Two requests with
+/// equal keys are interchangeable: the operations are pure, so caching
+/// can never serve a stale answer, only an identical one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub op: Operation,
+    pub x: i64,
+    pub y: i64,
+    pub mode: OverflowMode,
+    pub width: Width,
+}
+
+struct Entry {
+    value: CalcValue,
+    /// Monotonic usage stamp; the smallest one is the LRU victim.
+    last_used: u64,
+}
+
+/// A bounded LRU of successful calculation results, shared app-wide via
+/// web::Data. Eviction scans for the least-recently-used entry the same
+/// way the idempotency cache scans for its oldest — linear, but the
+/// capacity is small and the scan only runs when the table is full.
+pub struct ResultCache {
+    capacity: usize,
+    entries: Mutex<HashMap<CacheKey, Entry>>,
+    tick: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ResultCache {
+    fn new(capacity: usize) -> Self {
+        ResultCache {
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+            tick: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn global() -> Arc<ResultCache> {
+        static CACHE: OnceLock<Arc<ResultCache>> = OnceLock::new();
+        CACHE
+            .get_or_init(|| {
+                let config = crate::config::Config::global();
+                Arc::new(ResultCache::new(config.cache_capacity))
+            })
+            .clone()
+    }
+
+    pub fn get(&self, key: &CacheKey) -> Option<CalcValue> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get_mut(key) {
+            Some(entry) => {
+                entry.last_used = self.tick.fetch_add(1, Ordering::Relaxed);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(entry.value.clone())
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Stores a successful result. Concurrent computations of the same
+    /// key insert interchangeable values, so the last writer winning is
+    /// harmless; errors never reach this method.
+    pub fn put(&self, key: CacheKey, value: CalcValue) {
+        let mut entries = self.entries.lock().unwrap();
+        if !entries.contains_key(&key) && entries.len() >= self.capacity {
+            let victim = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(victim) = victim {
+                entries.remove(&victim);
+            }
+        }
+        entries.insert(
+            key,
+            Entry {
+                value,
+                last_used: self.tick.fetch_add(1, Ordering::Relaxed),
+            },
+        );
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(x: i64) -> CacheKey {
+        CacheKey {
+            op: Operation::Add,
+            x,
+            y: 1,
+            mode: OverflowMode::default(),
+            width: Width::default(),
+        }
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_entry() {
+        let cache = ResultCache::new(2);
+        cache.put(key(1), CalcValue::Number(1));
+        cache.put(key(2), CalcValue::Number(2));
+
+        // Touch key 1 so key 2 becomes the victim.
+        assert!(cache.get(&key(1)).is_some());
+        cache.put(key(3), CalcValue::Number(3));
+
+        assert!(cache.get(&key(1)).is_some());
+        assert!(cache.get(&key(2)).is_none());
+        assert!(cache.get(&key(3)).is_some());
+        assert_eq!(cache.hits(), 3);
+        assert_eq!(cache.misses(), 1);
+    }
+
+    #[test]
+    fn concurrent_identical_writers_cannot_poison_the_cache() {
+        let cache = Arc::new(ResultCache::new(8));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let cache = Arc::clone(&cache);
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        // The miss-then-compute-then-put dance every
+                        // request performs, all on the same key.
+                        if cache.get(&key(1)).is_none() {
+                            cache.put(key(1), CalcValue::Number(2));
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert!(matches!(cache.get(&key(1)), Some(CalcValue::Number(2))));
+        assert_eq!(cache.entries.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,1112 @@
+//! The arithmetic core: pure, synchronous functions with all the edge-case
+//! handling (overflow, division by zero, non-finite floats) in one place.
+//! Handlers wrap these in thin async adapters; behaviour is defined here
+//! and nowhere else.
+
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::error::{Error, Result};
+
+pub mod bitwise;
+
+/// The integer widths the calculator runs at. One trait, one set of
+/// arithmetic functions — the per-width behaviour lives entirely in the
+/// std methods forwarded here.
+pub trait CalcInt: Copy + PartialEq + Eq + PartialOrd + Ord + std::fmt::Debug {
+    const ZERO: Self;
+    const MIN: Self;
+    const MAX: Self;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    fn checked_div(self, rhs: Self) -> Option<Self>;
+    fn checked_rem(self, rhs: Self) -> Option<Self>;
+    fn checked_pow(self, exp: u32) -> Option<Self>;
+    fn wrapping_add(self, rhs: Self) -> Self;
+    fn wrapping_sub(self, rhs: Self) -> Self;
+    fn wrapping_mul(self, rhs: Self) -> Self;
+    fn wrapping_div(self, rhs: Self) -> Self;
+    fn wrapping_rem(self, rhs: Self) -> Self;
+    fn wrapping_pow(self, exp: u32) -> Self;
+    fn saturating_add(self, rhs: Self) -> Self;
+    fn saturating_sub(self, rhs: Self) -> Self;
+    fn saturating_mul(self, rhs: Self) -> Self;
+    fn saturating_div(self, rhs: Self) -> Self;
+    fn saturating_pow(self, exp: u32) -> Self;
+
+    fn from_i128(value: i128) -> Option<Self>;
+    fn to_i128(self) -> i128;
+    fn to_u32(self) -> Option<u32>;
+}
+
+macro_rules! impl_calc_int {
+    ($($t:ty),*) => {
+        $(
+            impl CalcInt for $t {
+                const ZERO: Self = 0;
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                fn checked_add(self, rhs: Self) -> Option<Self> { self.checked_add(rhs) }
+                fn checked_sub(self, rhs: Self) -> Option<Self> { self.checked_sub(rhs) }
+                fn checked_mul(self, rhs: Self) -> Option<Self> { self.checked_mul(rhs) }
+                fn checked_div(self, rhs: Self) -> Option<Self> { self.checked_div(rhs) }
+                fn checked_rem(self, rhs: Self) -> Option<Self> { self.checked_rem(rhs) }
+                fn checked_pow(self, exp: u32) -> Option<Self> { self.checked_pow(exp) }
+                fn wrapping_add(self, rhs: Self) -> Self { self.wrapping_add(rhs) }
+                fn wrapping_sub(self, rhs: Self) -> Self { self.wrapping_sub(rhs) }
+                fn wrapping_mul(self, rhs: Self) -> Self { self.wrapping_mul(rhs) }
+                fn wrapping_div(self, rhs: Self) -> Self { self.wrapping_div(rhs) }
+                fn wrapping_rem(self, rhs: Self) -> Self { self.wrapping_rem(rhs) }
+                fn wrapping_pow(self, exp: u32) -> Self { self.wrapping_pow(exp) }
+                fn saturating_add(self, rhs: Self) -> Self { self.saturating_add(rhs) }
+                fn saturating_sub(self, rhs: Self) -> Self { self.saturating_sub(rhs) }
+                fn saturating_mul(self, rhs: Self) -> Self { self.saturating_mul(rhs) }
+                fn saturating_div(self, rhs: Self) -> Self { self.saturating_div(rhs) }
+                fn saturating_pow(self, exp: u32) -> Self { self.saturating_pow(exp) }
+
+                fn from_i128(value: i128) -> Option<Self> { Self::try_from(value).ok() }
+                fn to_i128(self) -> i128 { self as i128 }
+                fn to_u32(self) -> Option<u32> { u32::try_from(self).ok() }
+            }
+        )*
+    };
+}
+
+impl_calc_int!(i32, i64, i128);
+
+fn overflow<T: CalcInt>(op: &'static str, x: T, y: T) -> Error {
+    Error::Overflow {
+        op,
+        x: x.to_i128(),
+        y: y.to_i128(),
+    }
+}
+
+pub fn add<T: CalcInt>(x: T, y: T) -> Result<T> {
+    x.checked_add(y).ok_or_else(|| overflow("add", x, y))
+}
+
+pub fn sub<T: CalcInt>(x: T, y: T) -> Result<T> {
+    x.checked_sub(y).ok_or_else(|| overflow("sub", x, y))
+}
+
+pub fn mul<T: CalcInt>(x: T, y: T) -> Result<T> {
+    x.checked_mul(y).ok_or_else(|| overflow("mul", x, y))
+}
+
+pub fn div<T: CalcInt>(x: T, y: T) -> Result<T> {
+    if y == T::ZERO {
+        Err(Error::DivideByZero)
+    } else {
+        x.checked_div(y).ok_or_else(|| overflow("div", x, y))
+    }
+}
+
+pub fn modulo<T: CalcInt>(x: T, y: T) -> Result<T> {
+    if y == T::ZERO {
+        Err(Error::DivideByZero)
+    } else {
+        x.checked_rem(y).ok_or_else(|| overflow("mod", x, y))
+    }
+}
+
+/// std exponents are u32; a larger one overflows any width unless
+/// |x| <= 1, and those bases cycle with period two, so a small exponent
+/// of the same parity gives the exact answer.
+fn exponent<T: CalcInt>(x: T, y: T) -> Result<u32> {
+    match y.to_u32() {
+        Some(exp) => Ok(exp),
+        None if x.to_i128().abs() <= 1 => Ok(if y.to_i128() % 2 == 0 { 2 } else { 3 }),
+        None => Err(overflow("pow", x, y)),
+    }
+}
+
+pub fn pow<T: CalcInt>(x: T, y: T) -> Result<T> {
+    if y < T::ZERO {
+        return Err(Error::NegativeExponent {
+            x: x.to_i128(),
+            y: y.to_i128(),
+        });
+    }
+
+    x.checked_pow(exponent(x, y)?)
+        .ok_or_else(|| overflow("pow", x, y))
+}
+
+/// Both gcd and lcm work on absolute values; i32::MIN has none, so it is
+/// rejected as an overflow rather than silently mishandled.
+fn abs_operand(op: &'static str, x: i32, y: i32, value: i32) -> Result<i32> {
+    value.checked_abs().ok_or_else(|| overflow(op, x, y))
+}
+
+/// Euclid's algorithm on absolute values; gcd(0, 0) is defined as 0.
+pub fn gcd(x: i32, y: i32) -> Result<i32> {
+    let mut a = abs_operand("gcd", x, y, x)?;
+    let mut b = abs_operand("gcd", x, y, y)?;
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    Ok(a)
+}
+
+/// lcm via (|x| / gcd) * |y|, with checked multiplication so a product
+/// past i32::MAX is the usual Overflow rather than a wrap.
+pub fn lcm(x: i32, y: i32) -> Result<i32> {
+    let a = abs_operand("lcm", x, y, x)?;
+    let b = abs_operand("lcm", x, y, y)?;
+    if a == 0 || b == 0 {
+        return Ok(0);
+    }
+    let g = gcd(a, b)?;
+    (a / g).checked_mul(b).ok_or_else(|| overflow("lcm", x, y))
+}
+
+/// 34! is the largest factorial representable in u128.
+pub const MAX_FACTORIAL_INPUT: i64 = 34;
+
+/// The largest n for which every C(n, k) fits u128; C(132, 66) does not.
+pub const MAX_CHOOSE_INPUT: i64 = 131;
+
+pub fn factorial(x: i64) -> Result<u128> {
+    if x < 0 {
+        return Err(Error::NegativeInput {
+            field: "x",
+            value: x,
+        });
+    }
+
+    (2..=x as u128)
+        .try_fold(1_u128, u128::checked_mul)
+        .ok_or(Error::CombinatoricOverflow {
+            op: "factorial",
+            x,
+            y: 0,
+            max: MAX_FACTORIAL_INPUT,
+        })
+}
+
+fn gcd_u128(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// n choose k via the multiplicative formula, reducing each factor by
+/// its gcd with the running denominator so intermediates only overflow
+/// when the result itself does.
+pub fn choose(n: i64, k: i64) -> Result<u128> {
+    if n < 0 {
+        return Err(Error::NegativeInput {
+            field: "n",
+            value: n,
+        });
+    }
+    if k < 0 {
+        return Err(Error::NegativeInput {
+            field: "k",
+            value: k,
+        });
+    }
+    if k > n {
+        return Ok(0);
+    }
+
+    let overflow = || Error::CombinatoricOverflow {
+        op: "choose",
+        x: n,
+        y: k,
+        max: MAX_CHOOSE_INPUT,
+    };
+
+    // C(n, k) == C(n, n - k); iterate over the smaller side.
+    let smaller_k = (k.min(n - k)) as u128;
+    let n = n as u128;
+
+    let mut res: u128 = 1;
+    for i in 1..=smaller_k {
+        let mut num = n - smaller_k + i;
+        let mut den = i;
+
+        let g = gcd_u128(num, den);
+        num /= g;
+        den /= g;
+        // Whatever remains of the denominator divides the accumulator,
+        // because every prefix of the product is itself a binomial.
+        res /= den;
+
+        res = res.checked_mul(num).ok_or_else(overflow)?;
+    }
+
+    Ok(res)
+}
+
+fn validate_base(base: u32) -> Result<u32> {
+    if (2..=36).contains(&base) {
+        Ok(base)
+    } else {
+        Err(Error::UnsupportedBase(base))
+    }
+}
+
+/// Render a value in an arbitrary base. The magnitude is taken as u128
+/// so i128::MIN needs no special case.
+fn format_radix(value: i128, base: u32) -> String {
+    let mut magnitude = value.unsigned_abs();
+    let mut digits = Vec::new();
+    loop {
+        let digit = (magnitude % u128::from(base)) as u32;
+        digits.push(char::from_digit(digit, base).expect("digit below base"));
+        magnitude /= u128::from(base);
+        if magnitude == 0 {
+            break;
+        }
+    }
+    if value < 0 {
+        digits.push('-');
+    }
+    digits.iter().rev().collect()
+}
+
+/// Re-render a string number from one base (2..=36) into another. The
+/// intermediate is i128: signs are supported, digit case is ignored, and
+/// anything past i128 is a ConversionOverflow rather than a wrap.
+pub fn convert_base(value: &str, from: u32, to: u32) -> Result<(String, i128)> {
+    use std::num::IntErrorKind;
+
+    let from = validate_base(from)?;
+    let to = validate_base(to)?;
+
+    let parsed = i128::from_str_radix(value, from).map_err(|err| match err.kind() {
+        IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => Error::ConversionOverflow {
+            value: value.to_string(),
+        },
+        _ => Error::InvalidDigit {
+            value: value.to_string(),
+            base: from,
+        },
+    })?;
+
+    Ok((format_radix(parsed, to), parsed))
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AggregateOp {
+    Sum,
+    Product,
+    Min,
+    Max,
+    Mean,
+}
+
+impl AggregateOp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AggregateOp::Sum => "sum",
+            AggregateOp::Product => "product",
+            AggregateOp::Min => "min",
+            AggregateOp::Max => "max",
+            AggregateOp::Mean => "mean",
+        }
+    }
+}
+
+/// An aggregate over i32 values, accumulated in i64 so that summing
+/// i32::MAX copies of i32::MAX still errors honestly instead of wrapping
+/// mid-fold. `remainder` is only set for mean: res is the mean rounded
+/// half to even, and remainder is `sum - res * count`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Aggregated {
+    pub res: i64,
+    pub remainder: Option<i64>,
+}
+
+pub fn aggregate(op: AggregateOp, values: &[i32]) -> Result<Aggregated> {
+    if values.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+
+    let checked_sum = |values: &[i32]| {
+        values.iter().try_fold(0_i64, |acc, &v| {
+            acc.checked_add(v.into())
+                .ok_or_else(|| overflow("sum", acc, v.into()))
+        })
+    };
+
+    let res = match op {
+        AggregateOp::Sum => checked_sum(values)?,
+        AggregateOp::Product => values.iter().try_fold(1_i64, |acc, &v| {
+            acc.checked_mul(v.into())
+                .ok_or_else(|| overflow("product", acc, v.into()))
+        })?,
+        AggregateOp::Min => (*values.iter().min().expect("non-empty")).into(),
+        AggregateOp::Max => (*values.iter().max().expect("non-empty")).into(),
+        AggregateOp::Mean => {
+            let sum = checked_sum(values)?;
+            let count = values.len() as i64;
+            // Round half to even: banker's rounding avoids the upward
+            // bias of always rounding .5 away from zero.
+            let quot = sum.div_euclid(count);
+            let rem = sum.rem_euclid(count);
+            let res = match (2 * rem).cmp(&count) {
+                std::cmp::Ordering::Less => quot,
+                std::cmp::Ordering::Greater => quot + 1,
+                std::cmp::Ordering::Equal => quot + (quot & 1),
+            };
+            return Ok(Aggregated {
+                res,
+                remainder: Some(sum - res * count),
+            });
+        }
+    };
+
+    Ok(Aggregated {
+        res,
+        remainder: None,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+}
+
+impl Operation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Add => "add",
+            Operation::Sub => "sub",
+            Operation::Mul => "mul",
+            Operation::Div => "div",
+            Operation::Mod => "mod",
+            Operation::Pow => "pow",
+        }
+    }
+}
+
+impl std::str::FromStr for Operation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "add" => Ok(Operation::Add),
+            "sub" => Ok(Operation::Sub),
+            "mul" => Ok(Operation::Mul),
+            "div" => Ok(Operation::Div),
+            "mod" => Ok(Operation::Mod),
+            "pow" => Ok(Operation::Pow),
+            _ => Err(Error::UnknownOperation(s.to_string())),
+        }
+    }
+}
+
+/// The integer width a calculation runs at, selectable per request.
+/// Serialize too: the client module sends these in request bodies.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, serde::Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Width {
+    /// The historical default.
+    #[default]
+    I32,
+    I64,
+    I128,
+}
+
+impl Width {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Width::I32 => "i32",
+            Width::I64 => "i64",
+            Width::I128 => "i128",
+        }
+    }
+}
+
+/// What to do when integer arithmetic overflows, selectable per request.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, serde::Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum OverflowMode {
+    /// A 422, the historical behaviour.
+    #[default]
+    Error,
+    /// Two's-complement wraparound.
+    Wrap,
+    /// Clamp to the width's MIN / MAX.
+    Saturate,
+}
+
+impl OverflowMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OverflowMode::Error => "error",
+            OverflowMode::Wrap => "wrap",
+            OverflowMode::Saturate => "saturate",
+        }
+    }
+}
+
+pub fn calculate<T: CalcInt>(op: Operation, x: T, y: T) -> Result<T> {
+    match op {
+        Operation::Add => add(x, y),
+        Operation::Sub => sub(x, y),
+        Operation::Mul => mul(x, y),
+        Operation::Div => div(x, y),
+        Operation::Mod => modulo(x, y),
+        Operation::Pow => pow(x, y),
+    }
+}
+
+/// calculate with the caller's overflow semantics. Division by zero and
+/// negative exponents stay errors in every mode; only overflow changes.
+pub fn calculate_with<T: CalcInt>(op: Operation, x: T, y: T, mode: OverflowMode) -> Result<T> {
+    let negative_exponent = || Error::NegativeExponent {
+        x: x.to_i128(),
+        y: y.to_i128(),
+    };
+
+    match mode {
+        OverflowMode::Error => calculate(op, x, y),
+        OverflowMode::Wrap => match op {
+            Operation::Add => Ok(x.wrapping_add(y)),
+            Operation::Sub => Ok(x.wrapping_sub(y)),
+            Operation::Mul => Ok(x.wrapping_mul(y)),
+            Operation::Div | Operation::Mod if y == T::ZERO => Err(Error::DivideByZero),
+            Operation::Div => Ok(x.wrapping_div(y)),
+            Operation::Mod => Ok(x.wrapping_rem(y)),
+            Operation::Pow if y < T::ZERO => Err(negative_exponent()),
+            Operation::Pow => Ok(x.wrapping_pow(exponent(x, y)?)),
+        },
+        OverflowMode::Saturate => match op {
+            Operation::Add => Ok(x.saturating_add(y)),
+            Operation::Sub => Ok(x.saturating_sub(y)),
+            Operation::Mul => Ok(x.saturating_mul(y)),
+            Operation::Div | Operation::Mod if y == T::ZERO => Err(Error::DivideByZero),
+            Operation::Div => Ok(x.saturating_div(y)),
+            // MIN % -1 is exactly 0; there is nothing to clamp.
+            Operation::Mod => Ok(x.wrapping_rem(y)),
+            Operation::Pow if y < T::ZERO => Err(negative_exponent()),
+            Operation::Pow => Ok(x.saturating_pow(exponent(x, y)?)),
+        },
+    }
+}
+
+/// Rounding strategies for decimal mode, applied when a scale is
+/// requested. The default is banker's rounding, matching /aggregate.
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DecimalRounding {
+    #[default]
+    HalfEven,
+    HalfUp,
+    HalfDown,
+    /// Away from zero.
+    Up,
+    /// Toward zero.
+    Down,
+}
+
+impl DecimalRounding {
+    fn strategy(self) -> rust_decimal::RoundingStrategy {
+        use rust_decimal::RoundingStrategy;
+
+        match self {
+            DecimalRounding::HalfEven => RoundingStrategy::MidpointNearestEven,
+            DecimalRounding::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            DecimalRounding::HalfDown => RoundingStrategy::MidpointTowardZero,
+            DecimalRounding::Up => RoundingStrategy::AwayFromZero,
+            DecimalRounding::Down => RoundingStrategy::ToZero,
+        }
+    }
+}
+
+/// Integer powers by squaring with checked multiplication; negative
+/// exponents go through the reciprocal.
+fn decimal_powi(x: rust_decimal::Decimal, exp: i64) -> Option<rust_decimal::Decimal> {
+    if exp < 0 {
+        return rust_decimal::Decimal::ONE.checked_div(decimal_powi(x, exp.checked_neg()?)?);
+    }
+
+    let mut base = x;
+    let mut exp = exp as u64;
+    let mut acc = rust_decimal::Decimal::ONE;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            acc = acc.checked_mul(base)?;
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = base.checked_mul(base)?;
+        }
+    }
+    Some(acc)
+}
+
+/// Exact decimal arithmetic for money-style calculations: no binary
+/// float in sight, and anything past Decimal's 96-bit mantissa is the
+/// usual Overflow rather than a silent loss of precision.
+pub fn calculate_decimal(
+    op: Operation,
+    x: rust_decimal::Decimal,
+    y: rust_decimal::Decimal,
+    scale: Option<u32>,
+    rounding: DecimalRounding,
+) -> Result<rust_decimal::Decimal> {
+    use rust_decimal::prelude::ToPrimitive;
+
+    let overflow = || Error::DecimalOverflow {
+        op: op.name(),
+        x: x.to_string(),
+        y: y.to_string(),
+    };
+
+    let res = match op {
+        Operation::Add => x.checked_add(y).ok_or_else(overflow)?,
+        Operation::Sub => x.checked_sub(y).ok_or_else(overflow)?,
+        Operation::Mul => x.checked_mul(y).ok_or_else(overflow)?,
+        Operation::Div | Operation::Mod if y.is_zero() => return Err(Error::DivideByZero),
+        Operation::Div => x.checked_div(y).ok_or_else(overflow)?,
+        Operation::Mod => x.checked_rem(y).ok_or_else(overflow)?,
+        Operation::Pow => {
+            // Fractional powers have no exact decimal result; a negative
+            // integer exponent of zero is a division by zero.
+            let exp = match y.fract().is_zero() {
+                true => y.to_i64(),
+                false => None,
+            }
+            .ok_or_else(|| {
+                Error::InvalidRequestBody(
+                    "pow in decimal mode requires an integer exponent".to_string(),
+                )
+            })?;
+            if x.is_zero() && exp < 0 {
+                return Err(Error::DivideByZero);
+            }
+            decimal_powi(x, exp).ok_or_else(overflow)?
+        }
+    };
+
+    Ok(match scale {
+        Some(scale) => res.round_dp_with_strategy(scale, rounding.strategy()),
+        None => res,
+    })
+}
+
+pub fn calculate_float(op: Operation, x: f64, y: f64) -> Result<f64> {
+    if !x.is_finite() || !y.is_finite() {
+        return Err(Error::NonFiniteOperand { x, y });
+    }
+
+    let res = match op {
+        Operation::Add => x + y,
+        Operation::Sub => x - y,
+        Operation::Mul => x * y,
+        Operation::Div | Operation::Mod if y == 0.0 => return Err(Error::DivideByZero),
+        Operation::Div => x / y,
+        Operation::Mod => x % y,
+        Operation::Pow => x.powf(y),
+    };
+
+    if res.is_finite() {
+        Ok(res)
+    } else {
+        Err(Error::NonFiniteResult {
+            op: op.name(),
+            x,
+            y,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    // Regression for the bug where div() computed x + y: exercise every
+    // quadrant plus truncation toward zero.
+    #[test]
+    fn div_returns_the_quotient() {
+        assert_eq!(div(84, 2).unwrap(), 42);
+        assert_eq!(div(7, -2).unwrap(), -3);
+        assert_eq!(div(-7, 2).unwrap(), -3);
+        assert_eq!(div(-7, -2).unwrap(), 3);
+        assert_eq!(div(1, 3).unwrap(), 0);
+    }
+
+    #[test]
+    fn div_by_zero_is_rejected() {
+        assert!(matches!(div(1, 0), Err(Error::DivideByZero)));
+    }
+
+    #[test]
+    fn add_overflows_past_i32_max() {
+        assert!(matches!(
+            add(i32::MAX, 1),
+            Err(Error::Overflow { op: "add", .. })
+        ));
+    }
+
+    #[test]
+    fn sub_overflows_past_i32_min() {
+        assert!(matches!(
+            sub(i32::MIN, 1),
+            Err(Error::Overflow { op: "sub", .. })
+        ));
+    }
+
+    #[test]
+    fn mul_overflows() {
+        assert!(matches!(
+            mul(2_000_000_000, 3),
+            Err(Error::Overflow { op: "mul", .. })
+        ));
+    }
+
+    #[test]
+    fn div_overflows_at_i32_min_by_minus_one() {
+        assert!(matches!(
+            div(i32::MIN, -1),
+            Err(Error::Overflow { op: "div", .. })
+        ));
+    }
+
+    #[test]
+    fn modulo_overflows_at_i32_min_by_minus_one() {
+        assert!(matches!(
+            modulo(i32::MIN, -1),
+            Err(Error::Overflow { op: "mod", .. })
+        ));
+    }
+
+    #[test]
+    fn pow_rejects_negative_exponents() {
+        assert!(matches!(
+            pow(2, -1),
+            Err(Error::NegativeExponent { x: 2, y: -1 })
+        ));
+    }
+
+    #[test]
+    fn pow_overflows_instead_of_wrapping() {
+        assert!(matches!(pow(2, 40), Err(Error::Overflow { op: "pow", .. })));
+    }
+
+    #[test]
+    fn pow_bails_out_quickly_on_huge_exponents() {
+        assert!(matches!(
+            pow(3, 1_000_000),
+            Err(Error::Overflow { op: "pow", .. })
+        ));
+    }
+
+    #[test]
+    fn decimal_mode_is_exact() {
+        let d = |s: &str| s.parse::<rust_decimal::Decimal>().unwrap();
+        let half_even = DecimalRounding::default();
+
+        // The classic float embarrassment, done right.
+        assert_eq!(
+            calculate_decimal(Operation::Add, d("0.1"), d("0.2"), None, half_even).unwrap(),
+            d("0.3")
+        );
+
+        // Division respects the requested scale and strategy.
+        assert_eq!(
+            calculate_decimal(Operation::Div, d("10"), d("3"), Some(2), half_even).unwrap(),
+            d("3.33")
+        );
+        assert_eq!(
+            calculate_decimal(
+                Operation::Div,
+                d("0.05"),
+                d("0.02"),
+                Some(0),
+                DecimalRounding::HalfUp
+            )
+            .unwrap(),
+            d("3")
+        );
+        assert_eq!(
+            calculate_decimal(Operation::Div, d("0.05"), d("0.02"), Some(0), half_even).unwrap(),
+            d("2")
+        );
+
+        assert!(matches!(
+            calculate_decimal(Operation::Div, d("1"), d("0"), None, half_even),
+            Err(Error::DivideByZero)
+        ));
+        assert!(matches!(
+            calculate_decimal(
+                Operation::Mul,
+                rust_decimal::Decimal::MAX,
+                d("2"),
+                None,
+                half_even
+            ),
+            Err(Error::DecimalOverflow { op: "mul", .. })
+        ));
+        assert!(matches!(
+            calculate_decimal(Operation::Pow, d("2"), d("0.5"), None, half_even),
+            Err(Error::InvalidRequestBody(_))
+        ));
+    }
+
+    #[test]
+    fn float_div_by_zero_is_rejected() {
+        assert!(matches!(
+            calculate_float(Operation::Div, 1.0, 0.0),
+            Err(Error::DivideByZero)
+        ));
+        assert!(matches!(
+            calculate_float(Operation::Div, 0.0, 0.0),
+            Err(Error::DivideByZero)
+        ));
+    }
+
+    #[test]
+    fn float_mul_rejects_non_finite_results() {
+        assert!(matches!(
+            calculate_float(Operation::Mul, 1e308, 1e308),
+            Err(Error::NonFiniteResult { op: "mul", .. })
+        ));
+    }
+
+    #[test]
+    fn float_rejects_non_finite_operands() {
+        assert!(matches!(
+            calculate_float(Operation::Add, f64::NAN, 1.0),
+            Err(Error::NonFiniteOperand { .. })
+        ));
+        assert!(matches!(
+            calculate_float(Operation::Add, 1.0, f64::INFINITY),
+            Err(Error::NonFiniteOperand { .. })
+        ));
+    }
+
+    proptest! {
+        #[test]
+        fn division_inverts_multiplication(x in any::<i32>(), y in any::<i32>()) {
+            prop_assume!(y != 0);
+            if let Ok(prod) = mul(x, y) {
+                prop_assert_eq!(div(prod, y).unwrap(), x);
+            }
+        }
+
+        #[test]
+        fn addition_inverts_subtraction(x in any::<i32>(), y in any::<i32>()) {
+            if let Ok(diff) = sub(x, y) {
+                prop_assert_eq!(add(diff, y).unwrap(), x);
+            }
+        }
+
+        #[test]
+        fn div_matches_the_std_quotient(x in any::<i32>(), y in any::<i32>()) {
+            prop_assume!(y != 0);
+            prop_assume!(!(x == i32::MIN && y == -1));
+            prop_assert_eq!(div(x, y).unwrap(), x / y);
+        }
+
+        #[test]
+        fn quotient_and_remainder_recompose(x in any::<i32>(), y in any::<i32>()) {
+            prop_assume!(y != 0);
+            prop_assume!(!(x == i32::MIN && y == -1));
+            let recomposed = div(x, y).unwrap() * y + modulo(x, y).unwrap();
+            prop_assert_eq!(recomposed, x);
+        }
+
+        // The classical identity: gcd(x, y) * lcm(x, y) == |x * y|,
+        // whenever the lcm itself is representable.
+        #[test]
+        fn gcd_times_lcm_is_the_absolute_product(x in any::<i32>(), y in any::<i32>()) {
+            prop_assume!(x != i32::MIN && y != i32::MIN);
+            if let Ok(l) = lcm(x, y) {
+                let g = gcd(x, y).unwrap();
+                prop_assert_eq!(
+                    i64::from(g) * i64::from(l),
+                    (i64::from(x) * i64::from(y)).abs()
+                );
+            }
+        }
+
+        // Wrap and saturate exist so clients never see overflow errors:
+        // for valid operands (no zero divisor, no negative exponent) they
+        // must always produce a value.
+        #[test]
+        fn wrap_and_saturate_never_overflow(x in any::<i32>(), y in any::<i32>()) {
+            for op in [
+                Operation::Add,
+                Operation::Sub,
+                Operation::Mul,
+                Operation::Div,
+                Operation::Mod,
+                Operation::Pow,
+            ] {
+                let y = match op {
+                    Operation::Div | Operation::Mod if y == 0 => 1,
+                    Operation::Pow => y.rem_euclid(1024),
+                    _ => y,
+                };
+                prop_assert!(calculate_with(op, x, y, OverflowMode::Wrap).is_ok());
+                prop_assert!(calculate_with(op, x, y, OverflowMode::Saturate).is_ok());
+            }
+        }
+
+        // In the default mode the new entry point is the old one.
+        #[test]
+        fn error_mode_matches_calculate(x in any::<i32>(), y in any::<i32>()) {
+            for op in [Operation::Add, Operation::Sub, Operation::Mul] {
+                let via_mode = calculate_with(op, x, y, OverflowMode::Error);
+                let direct = calculate(op, x, y);
+                prop_assert_eq!(via_mode.is_ok(), direct.is_ok());
+                if let (Ok(a), Ok(b)) = (via_mode, direct) {
+                    prop_assert_eq!(a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gcd_handles_zeroes_and_signs() {
+        assert_eq!(gcd(0, 0).unwrap(), 0);
+        assert_eq!(gcd(0, 5).unwrap(), 5);
+        assert_eq!(gcd(12, 18).unwrap(), 6);
+        assert_eq!(gcd(-12, 18).unwrap(), 6);
+        assert_eq!(gcd(-12, -18).unwrap(), 6);
+        assert!(matches!(
+            gcd(i32::MIN, 2),
+            Err(Error::Overflow { op: "gcd", .. })
+        ));
+    }
+
+    #[test]
+    fn lcm_checks_the_product() {
+        assert_eq!(lcm(4, 6).unwrap(), 12);
+        assert_eq!(lcm(0, 5).unwrap(), 0);
+        assert_eq!(lcm(-4, 6).unwrap(), 12);
+        assert!(matches!(
+            lcm(i32::MAX, 2),
+            Err(Error::Overflow { op: "lcm", .. })
+        ));
+        assert!(matches!(
+            lcm(2, i32::MIN),
+            Err(Error::Overflow { op: "lcm", .. })
+        ));
+    }
+
+    #[test]
+    fn factorial_is_checked() {
+        assert_eq!(factorial(0).unwrap(), 1);
+        assert_eq!(factorial(5).unwrap(), 120);
+        assert!(factorial(MAX_FACTORIAL_INPUT).is_ok());
+        assert!(matches!(
+            factorial(MAX_FACTORIAL_INPUT + 1),
+            Err(Error::CombinatoricOverflow {
+                op: "factorial",
+                ..
+            })
+        ));
+        assert!(matches!(
+            factorial(-1),
+            Err(Error::NegativeInput { field: "x", .. })
+        ));
+    }
+
+    #[test]
+    fn choose_reduces_before_multiplying() {
+        assert_eq!(choose(5, 2).unwrap(), 10);
+        assert_eq!(choose(5, 0).unwrap(), 1);
+        assert_eq!(choose(5, 5).unwrap(), 1);
+        assert_eq!(choose(3, 5).unwrap(), 0);
+        assert_eq!(choose(52, 5).unwrap(), 2_598_960);
+        // The worst case at the documented bound still fits...
+        assert!(choose(MAX_CHOOSE_INPUT, MAX_CHOOSE_INPUT / 2).is_ok());
+        // ...and one row further does not.
+        assert!(matches!(
+            choose(MAX_CHOOSE_INPUT + 1, (MAX_CHOOSE_INPUT + 1) / 2),
+            Err(Error::CombinatoricOverflow { op: "choose", .. })
+        ));
+        assert!(matches!(choose(-1, 1), Err(Error::NegativeInput { .. })));
+        assert!(matches!(choose(1, -1), Err(Error::NegativeInput { .. })));
+    }
+
+    proptest! {
+        // Pascal's rule, on rows small enough that nothing overflows.
+        #[test]
+        fn choose_satisfies_pascals_rule(n in 1_i64..60, k in 1_i64..60) {
+            prop_assume!(k <= n);
+            prop_assert_eq!(
+                choose(n, k).unwrap(),
+                choose(n - 1, k - 1).unwrap() + choose(n - 1, k).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn convert_base_round_trips() {
+        assert_eq!(convert_base("ff", 16, 2).unwrap(), ("11111111".into(), 255));
+        assert_eq!(convert_base("FF", 16, 10).unwrap(), ("255".into(), 255));
+        assert_eq!(convert_base("-ff", 16, 10).unwrap(), ("-255".into(), -255));
+        assert_eq!(convert_base("0", 2, 36).unwrap(), ("0".into(), 0));
+        // Leading zeros are accepted and not preserved.
+        assert_eq!(convert_base("007", 8, 10).unwrap(), ("7".into(), 7));
+        // i128::MIN survives the unsigned-magnitude rendering.
+        let (rendered, parsed) = convert_base(&i128::MIN.to_string(), 10, 16).unwrap();
+        assert_eq!(parsed, i128::MIN);
+        assert_eq!(rendered, "-80000000000000000000000000000000");
+        assert_eq!(convert_base(&rendered, 16, 10).unwrap().1, i128::MIN);
+    }
+
+    #[test]
+    fn convert_base_rejects_bad_inputs_distinctly() {
+        assert!(matches!(
+            convert_base("1", 1, 10),
+            Err(Error::UnsupportedBase(1))
+        ));
+        assert!(matches!(
+            convert_base("1", 10, 37),
+            Err(Error::UnsupportedBase(37))
+        ));
+        assert!(matches!(
+            convert_base("12", 2, 10),
+            Err(Error::InvalidDigit { base: 2, .. })
+        ));
+        assert!(matches!(
+            convert_base("", 10, 2),
+            Err(Error::InvalidDigit { .. })
+        ));
+        // One past i128::MAX in either direction is an overflow, not an
+        // invalid digit.
+        assert!(matches!(
+            convert_base("170141183460469231731687303715884105728", 10, 16),
+            Err(Error::ConversionOverflow { .. })
+        ));
+        assert!(matches!(
+            convert_base("-170141183460469231731687303715884105729", 10, 16),
+            Err(Error::ConversionOverflow { .. })
+        ));
+    }
+
+    #[test]
+    fn aggregates_accumulate_in_i64() {
+        let sum = aggregate(AggregateOp::Sum, &[i32::MAX, i32::MAX]).unwrap();
+        assert_eq!(sum.res, 2 * i64::from(i32::MAX));
+        assert_eq!(sum.remainder, None);
+
+        assert_eq!(aggregate(AggregateOp::Min, &[3, -7, 5]).unwrap().res, -7);
+        assert_eq!(aggregate(AggregateOp::Max, &[3, -7, 5]).unwrap().res, 5);
+
+        assert!(matches!(
+            aggregate(AggregateOp::Sum, &[]),
+            Err(Error::EmptyInput)
+        ));
+        // ~2^93 overflows even the i64 accumulator.
+        assert!(matches!(
+            aggregate(AggregateOp::Product, &[i32::MAX, i32::MAX, i32::MAX]),
+            Err(Error::Overflow { op: "product", .. })
+        ));
+    }
+
+    #[test]
+    fn mean_rounds_half_to_even() {
+        // Exact halves land on the even neighbour in both directions.
+        assert_eq!(aggregate(AggregateOp::Mean, &[1, 2]).unwrap().res, 2);
+        assert_eq!(aggregate(AggregateOp::Mean, &[2, 3]).unwrap().res, 2);
+        assert_eq!(aggregate(AggregateOp::Mean, &[-1, -2]).unwrap().res, -2);
+
+        // The remainder recovers the exact sum: sum == res * count + rem.
+        let mean = aggregate(AggregateOp::Mean, &[2, 3]).unwrap();
+        assert_eq!(mean.res * 2 + mean.remainder.unwrap(), 5);
+        let mean = aggregate(AggregateOp::Mean, &[1, 2, 3]).unwrap();
+        assert_eq!(mean.res, 2);
+        assert_eq!(mean.remainder, Some(0));
+    }
+
+    // One macro, three widths: overflow, divide-by-zero, negative-exponent
+    // and wrap/saturate behaviour must be identical at i32, i64 and i128.
+    macro_rules! width_semantics {
+        ($name:ident, $t:ty) => {
+            #[test]
+            fn $name() {
+                assert!(matches!(
+                    add(<$t>::MAX, 1),
+                    Err(Error::Overflow { op: "add", .. })
+                ));
+                assert!(matches!(
+                    sub(<$t>::MIN, 1),
+                    Err(Error::Overflow { op: "sub", .. })
+                ));
+                assert!(matches!(
+                    mul(<$t>::MAX, 2),
+                    Err(Error::Overflow { op: "mul", .. })
+                ));
+                assert!(matches!(
+                    div(<$t>::MIN, -1),
+                    Err(Error::Overflow { op: "div", .. })
+                ));
+                assert!(matches!(div(1 as $t, 0), Err(Error::DivideByZero)));
+                assert!(matches!(modulo(1 as $t, 0), Err(Error::DivideByZero)));
+                assert!(matches!(
+                    pow(2 as $t, -1),
+                    Err(Error::NegativeExponent { .. })
+                ));
+                assert_eq!(calculate(Operation::Pow, 2 as $t, 10).unwrap(), 1_024);
+                assert_eq!(
+                    calculate_with(Operation::Add, <$t>::MAX, 1, OverflowMode::Wrap).unwrap(),
+                    <$t>::MIN
+                );
+                assert_eq!(
+                    calculate_with(Operation::Add, <$t>::MAX, 1, OverflowMode::Saturate).unwrap(),
+                    <$t>::MAX
+                );
+                assert_eq!(
+                    calculate_with(Operation::Mul, <$t>::MIN, -1, OverflowMode::Saturate).unwrap(),
+                    <$t>::MAX
+                );
+            }
+        };
+    }
+
+    width_semantics!(semantics_hold_at_i32, i32);
+    width_semantics!(semantics_hold_at_i64, i64);
+    width_semantics!(semantics_hold_at_i128, i128);
+
+    #[test]
+    fn wrap_and_saturate_edge_cases() {
+        assert_eq!(
+            calculate_with(Operation::Add, i32::MAX, 1, OverflowMode::Wrap).unwrap(),
+            i32::MIN
+        );
+        assert_eq!(
+            calculate_with(Operation::Add, i32::MAX, 1, OverflowMode::Saturate).unwrap(),
+            i32::MAX
+        );
+        assert_eq!(
+            calculate_with(Operation::Mul, i32::MIN, -1, OverflowMode::Saturate).unwrap(),
+            i32::MAX
+        );
+        // Division by zero stays an error in every mode.
+        assert!(matches!(
+            calculate_with(Operation::Div, 1, 0, OverflowMode::Wrap),
+            Err(Error::DivideByZero)
+        ));
+        assert!(matches!(
+            calculate_with(Operation::Pow, 2, -1, OverflowMode::Saturate),
+            Err(Error::NegativeExponent { .. })
+        ));
+    }
+}
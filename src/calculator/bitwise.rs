@@ -0,0 +1,71 @@
+//! Bitwise operations over i32. Shift amounts are validated up front:
+//! anything outside 0..=31 is an InvalidShift error, never a panic and
+//! never Rust's silent masking of the shift amount.
+
+use crate::error::{Error, Result};
+
+/// All five operations share one signature so handlers can treat them
+/// uniformly; and/or/xor simply cannot fail.
+pub fn and(x: i32, y: i32) -> Result<i32> {
+    Ok(x & y)
+}
+
+pub fn or(x: i32, y: i32) -> Result<i32> {
+    Ok(x | y)
+}
+
+pub fn xor(x: i32, y: i32) -> Result<i32> {
+    Ok(x ^ y)
+}
+
+fn validate_shift(shift: i32) -> Result<u32> {
+    if (0..=31).contains(&shift) {
+        Ok(shift as u32)
+    } else {
+        Err(Error::InvalidShift(shift))
+    }
+}
+
+/// Left shift; bits shifted past the top are discarded, which is the
+/// defined behaviour for in-range shift amounts.
+pub fn shl(x: i32, y: i32) -> Result<i32> {
+    Ok(x << validate_shift(y)?)
+}
+
+/// Arithmetic right shift: the sign bit is replicated.
+pub fn shr(x: i32, y: i32) -> Result<i32> {
+    Ok(x >> validate_shift(y)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combinational_ops_match_the_operators() {
+        assert_eq!(and(0b1100, 0b1010).unwrap(), 0b1000);
+        assert_eq!(or(0b1100, 0b1010).unwrap(), 0b1110);
+        assert_eq!(xor(0b1100, 0b1010).unwrap(), 0b0110);
+        assert_eq!(and(-1, 0x0f).unwrap(), 0x0f);
+    }
+
+    #[test]
+    fn shift_boundaries_are_exhaustive() {
+        // 0 and 31 are in range; 32 and negatives are not.
+        assert_eq!(shl(1, 0).unwrap(), 1);
+        assert_eq!(shl(1, 31).unwrap(), i32::MIN);
+        assert!(matches!(shl(1, 32), Err(Error::InvalidShift(32))));
+        assert!(matches!(shl(1, -1), Err(Error::InvalidShift(-1))));
+
+        assert_eq!(shr(i32::MIN, 0).unwrap(), i32::MIN);
+        assert_eq!(shr(i32::MIN, 31).unwrap(), -1);
+        assert!(matches!(shr(1, 32), Err(Error::InvalidShift(32))));
+        assert!(matches!(shr(1, -1), Err(Error::InvalidShift(-1))));
+    }
+
+    #[test]
+    fn shr_is_arithmetic() {
+        assert_eq!(shr(-8, 1).unwrap(), -4);
+        assert_eq!(shr(8, 1).unwrap(), 4);
+    }
+}
@@ -0,0 +1,238 @@
+//! An async client for the calculator API, for internal services that
+//! would otherwise hand-roll reqwest calls and drift from the server
+//! types. Requests and responses are the server's own structs, so the
+//! wire format cannot diverge; error responses decode into [`ApiError`]
+//! with the request and sentry event ids the server attached.
+
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::calculator::{Operation, OverflowMode, Width};
+use crate::handlers::{BatchItemResponse, CalcRequest, CalculationRequest, CalculationResponse};
+
+/// The structured error body the server renders for every failure,
+/// decoded out of its `{"error": {...}}` envelope.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiError {
+    pub code: String,
+    pub message: String,
+    pub status: u16,
+    /// Quote this when reporting a failure; it names the exact request
+    /// in the server's logs.
+    #[serde(default)]
+    pub request_id: Option<String>,
+    #[serde(default)]
+    pub trace_id: Option<String>,
+    /// The sentry event the server captured, when it captured one.
+    #[serde(default)]
+    pub event_id: Option<String>,
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}): {}", self.code, self.status, self.message)?;
+        if let Some(request_id) = &self.request_id {
+            write!(f, " [request_id: {request_id}]")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// The server answered with an error response.
+    #[error("{0}")]
+    Api(ApiError),
+    /// The request never got a response: connection, TLS or timeout.
+    #[error("transport: {0}")]
+    Transport(#[from] reqwest::Error),
+    /// A 2xx whose body was not the expected shape — almost always a
+    /// client/server version mismatch.
+    #[error("unexpected response body: {0}")]
+    Decode(String),
+}
+
+pub type ClientResult<T> = std::result::Result<T, ClientError>;
+
+/// A client for one calculator deployment. Construction never fails;
+/// cheap to clone via the shared reqwest connection pool.
+#[derive(Debug, Clone)]
+pub struct CalculatorClient {
+    base_url: String,
+    api_key: Option<String>,
+    timeout: Duration,
+    retries: u32,
+    backoff: Duration,
+    http: reqwest::Client,
+}
+
+impl CalculatorClient {
+    /// A client with a 30s timeout and no retries.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let mut base_url = base_url.into();
+        while base_url.ends_with('/') {
+            base_url.pop();
+        }
+        let mut client = CalculatorClient {
+            base_url,
+            api_key: None,
+            timeout: Duration::from_secs(30),
+            retries: 0,
+            backoff: Duration::from_millis(250),
+            http: reqwest::Client::new(),
+        };
+        client.rebuild();
+        client
+    }
+
+    /// Sent as X-Api-Key on every request.
+    pub fn api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    /// Per-attempt timeout; retries each get the full budget.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self.rebuild();
+        self
+    }
+
+    /// Retry transport failures, 429s and 5xx responses up to `retries`
+    /// extra attempts, doubling `backoff` between them. 4xx responses
+    /// are never retried: the request itself is at fault.
+    pub fn retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.backoff = backoff;
+        self
+    }
+
+    fn rebuild(&mut self) {
+        self.http = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .expect("default reqwest client options are valid");
+    }
+
+    pub async fn add(&self, x: i64, y: i64) -> ClientResult<CalculationResponse> {
+        self.calculate(Operation::Add, x, y).await
+    }
+
+    pub async fn sub(&self, x: i64, y: i64) -> ClientResult<CalculationResponse> {
+        self.calculate(Operation::Sub, x, y).await
+    }
+
+    pub async fn mul(&self, x: i64, y: i64) -> ClientResult<CalculationResponse> {
+        self.calculate(Operation::Mul, x, y).await
+    }
+
+    pub async fn div(&self, x: i64, y: i64) -> ClientResult<CalculationResponse> {
+        self.calculate(Operation::Div, x, y).await
+    }
+
+    /// The default semantics: i32 width, overflow as an error.
+    pub async fn calculate(
+        &self,
+        op: Operation,
+        x: i64,
+        y: i64,
+    ) -> ClientResult<CalculationResponse> {
+        self.calculate_with(op, x, y, None, None).await
+    }
+
+    /// Full control over the per-request overflow mode and width.
+    pub async fn calculate_with(
+        &self,
+        op: Operation,
+        x: i64,
+        y: i64,
+        overflow: Option<OverflowMode>,
+        width: Option<Width>,
+    ) -> ClientResult<CalculationResponse> {
+        let request = CalculationRequest {
+            x,
+            y,
+            overflow,
+            width,
+        };
+        self.post(&format!("/api/v0/{}", op.name()), &request).await
+    }
+
+    /// Per-item results in request order; item failures come back inside
+    /// the Vec, only whole-batch failures (e.g. too many items) error.
+    pub async fn batch(
+        &self,
+        items: &[(Operation, i32, i32)],
+    ) -> ClientResult<Vec<BatchItemResponse>> {
+        let items: Vec<CalcRequest> = items
+            .iter()
+            .map(|&(op, x, y)| CalcRequest {
+                op: op.name().to_string(),
+                x,
+                y,
+            })
+            .collect();
+        self.post("/api/v0/batch", &items).await
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> ClientResult<T> {
+        let url = format!("{}{path}", self.base_url);
+        let mut attempt = 0;
+        loop {
+            let res = self.send(&url, body).await;
+            let retryable = match &res {
+                Err(ClientError::Transport(_)) => true,
+                Err(ClientError::Api(err)) => err.status == 429 || err.status >= 500,
+                _ => false,
+            };
+            if !retryable || attempt >= self.retries {
+                return res;
+            }
+            tokio::time::sleep(self.backoff * 2_u32.saturating_pow(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    async fn send<B: Serialize, T: DeserializeOwned>(
+        &self,
+        url: &str,
+        body: &B,
+    ) -> ClientResult<T> {
+        let mut request = self.http.post(url).json(body);
+        if let Some(key) = &self.api_key {
+            request = request.header(crate::middleware::API_KEY_HEADER, key);
+        }
+
+        let response = request.send().await?;
+        let status = response.status().as_u16();
+        let bytes = response.bytes().await?;
+
+        if (200..300).contains(&status) {
+            return serde_json::from_slice(&bytes)
+                .map_err(|err| ClientError::Decode(err.to_string()));
+        }
+
+        #[derive(Deserialize)]
+        struct Envelope {
+            error: ApiError,
+        }
+        Err(match serde_json::from_slice::<Envelope>(&bytes) {
+            Ok(envelope) => ClientError::Api(envelope.error),
+            // Not our error shape — a proxy or load balancer answered.
+            Err(_) => ClientError::Api(ApiError {
+                code: "unknown".to_string(),
+                message: String::from_utf8_lossy(&bytes).into_owned(),
+                status,
+                request_id: None,
+                trace_id: None,
+                event_id: None,
+            }),
+        })
+    }
+}
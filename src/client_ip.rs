@@ -0,0 +1,205 @@
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use actix_web::dev::ServiceRequest;
+
+use crate::config::Config;
+
+/// A network in CIDR notation ("10.0.0.0/8", "fd00::/8"); a bare address
+/// is a single-host network.
+#[derive(Debug, Clone)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl Cidr {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = if self.prefix_len == 0 {
+                    0
+                } else {
+                    u32::MAX << (32 - self.prefix_len)
+                };
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = if self.prefix_len == 0 {
+                    0
+                } else {
+                    u128::MAX << (128 - self.prefix_len)
+                };
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            // Mixed families never match; a v4-mapped v6 peer is not a
+            // v4 network member here.
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, len) = match s.split_once('/') {
+            Some((addr, len)) => (addr, Some(len)),
+            None => (s, None),
+        };
+        let network: IpAddr = addr
+            .parse()
+            .map_err(|_| format!("bad network address: {addr}"))?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match len {
+            Some(len) => len
+                .parse()
+                .ok()
+                .filter(|len| *len <= max)
+                .ok_or_else(|| format!("bad prefix length: {len}"))?,
+            None => max,
+        };
+        Ok(Cidr {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+/// The resolved client address, stored in request extensions by the
+/// middleware so rate limiting, identity and the access record all see
+/// the same answer.
+#[derive(Debug, Clone)]
+pub struct ClientIp(pub String);
+
+/// APP_TRUSTED_PROXY trusts every peer (the original all-or-nothing
+/// switch); otherwise only peers inside one of the configured networks
+/// may speak for the client.
+fn peer_is_trusted(peer: Option<IpAddr>, config: &Config) -> bool {
+    if config.trusted_proxy {
+        return true;
+    }
+    peer.is_some_and(|ip| {
+        config
+            .trusted_proxy_cidrs
+            .iter()
+            .any(|cidr| cidr.contains(ip))
+    })
+}
+
+/// The client hop from X-Forwarded-For (leftmost entry) or, failing
+/// that, the first `for=` pair of an RFC 7239 Forwarded header.
+fn forwarded_for(headers: &actix_web::http::header::HeaderMap) -> Option<String> {
+    if let Some(first) = headers
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|xff| xff.split(',').next())
+    {
+        let first = first.trim();
+        if !first.is_empty() {
+            return Some(first.to_string());
+        }
+    }
+
+    let forwarded = headers.get("forwarded")?.to_str().ok()?;
+    forwarded
+        .split(';')
+        .flat_map(|part| part.split(','))
+        .find_map(|pair| {
+            let (key, value) = pair.trim().split_once('=')?;
+            if !key.eq_ignore_ascii_case("for") {
+                return None;
+            }
+            Some(strip_port(value.trim().trim_matches('"')))
+        })
+}
+
+/// Bracketed IPv6 ("[2001:db8::1]:443") and v4-with-port node
+/// identifiers both reduce to the bare address.
+fn strip_port(value: &str) -> String {
+    if let Some(rest) = value.strip_prefix('[') {
+        if let Some((ip, _)) = rest.split_once(']') {
+            return ip.to_string();
+        }
+    }
+    if let Ok(addr) = value.parse::<std::net::SocketAddr>() {
+        return addr.ip().to_string();
+    }
+    value.to_string()
+}
+
+/// The real client address: the forwarded one when (and only when) the
+/// directly connected peer is a trusted proxy, the peer itself
+/// otherwise. Spoofed headers from untrusted peers are ignored.
+pub(crate) fn resolve(req: &ServiceRequest, config: &Config) -> String {
+    let peer = req.peer_addr().map(|addr| addr.ip());
+    if peer_is_trusted(peer, config) {
+        if let Some(ip) = forwarded_for(req.headers()) {
+            return ip;
+        }
+    }
+    peer.map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    fn config_with_cidrs(cidrs: &[&str]) -> Config {
+        let mut config = Config::from_env().unwrap();
+        config.trusted_proxy = false;
+        config.trusted_proxy_cidrs = cidrs.iter().map(|cidr| cidr.parse().unwrap()).collect();
+        config
+    }
+
+    #[test]
+    fn multi_hop_xff_resolves_to_the_client_hop() {
+        let config = config_with_cidrs(&["10.0.0.0/8"]);
+        let req = TestRequest::get()
+            .peer_addr("10.1.2.3:4711".parse().unwrap())
+            .insert_header(("x-forwarded-for", "203.0.113.7, 10.0.0.2, 10.1.2.3"))
+            .to_srv_request();
+        assert_eq!(resolve(&req, &config), "203.0.113.7");
+    }
+
+    #[test]
+    fn ipv6_peers_and_forwarded_node_identifiers_work() {
+        let config = config_with_cidrs(&["fd00::/8"]);
+        let req = TestRequest::get()
+            .peer_addr("[fd12::1]:4711".parse().unwrap())
+            .insert_header(("forwarded", r#"for="[2001:db8::9]:443";proto=https"#))
+            .to_srv_request();
+        assert_eq!(resolve(&req, &config), "2001:db8::9");
+    }
+
+    #[test]
+    fn spoofed_headers_from_untrusted_peers_are_ignored() {
+        let config = config_with_cidrs(&["10.0.0.0/8"]);
+        let req = TestRequest::get()
+            .peer_addr("198.51.100.4:4711".parse().unwrap())
+            .insert_header(("x-forwarded-for", "203.0.113.7"))
+            .to_srv_request();
+        assert_eq!(resolve(&req, &config), "198.51.100.4");
+
+        // The all-or-nothing switch restores the old behaviour.
+        let mut config = config;
+        config.trusted_proxy = true;
+        assert_eq!(resolve(&req, &config), "203.0.113.7");
+    }
+
+    #[test]
+    fn cidr_notation_is_validated() {
+        assert!("10.0.0.0/8".parse::<Cidr>().is_ok());
+        assert!("192.0.2.1".parse::<Cidr>().is_ok());
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!("not-a-network/8".parse::<Cidr>().is_err());
+
+        let cidr: Cidr = "2001:db8::/32".parse().unwrap();
+        assert!(cidr.contains("2001:db8::9".parse().unwrap()));
+        assert!(!cidr.contains("2001:db9::9".parse().unwrap()));
+        assert!(!cidr.contains("203.0.113.7".parse().unwrap()));
+    }
+}
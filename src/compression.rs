@@ -0,0 +1,285 @@
+use std::io::{Read, Write};
+use std::rc::Rc;
+
+use actix_web::{
+    body::{BodySize, EitherBody, MessageBody},
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{self, HeaderMap, HeaderValue},
+    web, Error,
+};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use futures_util::StreamExt;
+
+use crate::error::HTTPError;
+
+/// Negotiated response compression plus compressed request support.
+/// JSON responses of known size at or above COMPRESSION_MIN_BYTES are
+/// encoded with whichever of br, gzip or deflate the Accept-Encoding
+/// header allows; streaming responses pass through untouched. On the way
+/// in, gzip and deflate request bodies are inflated before the
+/// extractors run, capped at REQUEST_DECOMPRESS_MAX_BYTES so a zip bomb
+/// dies here with a 413 instead of filling memory.
+pub struct Compression;
+
+impl<S, B> Transform<S, ServiceRequest> for Compression
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = CompressionService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CompressionService {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct CompressionService<S> {
+    service: Rc<S>,
+}
+
+/// Our preference order when the client accepts more than one.
+const ENCODINGS: [&str; 3] = ["br", "gzip", "deflate"];
+
+/// Picks the response encoding: the most preferred of ours that the
+/// Accept-Encoding header lists (or wildcards) with a non-zero quality.
+fn negotiate(headers: &HeaderMap) -> Option<&'static str> {
+    let accept = headers.get(header::ACCEPT_ENCODING)?.to_str().ok()?;
+    let mut offered = Vec::new();
+    for part in accept.split(',') {
+        let mut pieces = part.split(';');
+        let token = pieces.next().unwrap_or("").trim().to_ascii_lowercase();
+        let q = pieces
+            .find_map(|param| param.trim().strip_prefix("q="))
+            .and_then(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if q > 0.0 {
+            offered.push(token);
+        }
+    }
+    ENCODINGS.into_iter().find(|encoding| {
+        offered
+            .iter()
+            .any(|token| token == encoding || token == "*")
+    })
+}
+
+/// Inflates a request body, refusing to produce more than `max` bytes.
+fn decode(encoding: &str, bytes: &[u8], max: usize) -> crate::error::Result<web::Bytes> {
+    let mut out = Vec::new();
+    // Read one byte past the cap so hitting the limit is distinguishable
+    // from a body that lands exactly on it.
+    let limit = max as u64 + 1;
+    let read = match encoding {
+        "gzip" => flate2::read::GzDecoder::new(bytes)
+            .take(limit)
+            .read_to_end(&mut out),
+        // HTTP deflate is the zlib format (RFC 9110 section 8.4.1.2).
+        _ => flate2::read::ZlibDecoder::new(bytes)
+            .take(limit)
+            .read_to_end(&mut out),
+    };
+    read.map_err(|err| {
+        crate::error::Error::InvalidRequestBody(format!("invalid {encoding} body: {err}"))
+    })?;
+    if out.len() > max {
+        return Err(crate::error::Error::DecompressedTooLarge { max });
+    }
+    Ok(out.into())
+}
+
+fn encode(encoding: &str, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    match encoding {
+        "br" => {
+            let mut writer = brotli::CompressorWriter::new(Vec::new(), 4_096, 5, 22);
+            writer.write_all(bytes)?;
+            writer.flush()?;
+            Ok(writer.into_inner())
+        }
+        "gzip" => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(bytes)?;
+            encoder.finish()
+        }
+        _ => {
+            let mut encoder =
+                flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(bytes)?;
+            encoder.finish()
+        }
+    }
+}
+
+/// Only JSON bodies are worth compressing here; everything else the
+/// service produces is either tiny or already encoded.
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| {
+            content_type.starts_with("application/json")
+                || content_type.starts_with(crate::negotiation::PROBLEM_JSON)
+        })
+}
+
+impl<S, B> Service<ServiceRequest> for CompressionService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            let config = crate::config::Config::global();
+
+            let content_encoding = req
+                .headers()
+                .get(header::CONTENT_ENCODING)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_ascii_lowercase);
+            // Negotiate before the request is consumed; the answer is
+            // only acted on once the response is in hand.
+            let accept = negotiate(req.headers());
+
+            let req = match content_encoding.as_deref() {
+                Some(encoding @ ("gzip" | "deflate")) => {
+                    let (http_req, mut payload) = req.into_parts();
+                    // Straight off the payload stream: the Bytes
+                    // extractor would inflate the body itself (actix's
+                    // built-in decompression) and bypass our cap.
+                    let mut bytes = web::BytesMut::new();
+                    while let Some(chunk) = payload.next().await {
+                        bytes.extend_from_slice(&chunk?);
+                    }
+                    let decoded = match decode(encoding, &bytes, config.decompress_max_bytes) {
+                        Ok(decoded) => decoded,
+                        Err(err) => return Err(HTTPError::from(err).into()),
+                    };
+                    let len = decoded.len();
+                    let mut req = crate::idempotency::reassemble(http_req, decoded);
+                    // The extractors (and every inner middleware that
+                    // buffers the body) must see the inflated form.
+                    req.headers_mut().remove(header::CONTENT_ENCODING);
+                    req.headers_mut()
+                        .insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+                    req
+                }
+                None | Some("identity") => req,
+                Some(other) => {
+                    return Err(HTTPError::from(crate::error::Error::UnsupportedMediaType(
+                        format!("content-encoding: {other}"),
+                    ))
+                    .into());
+                }
+            };
+
+            let res = service.call(req).await?;
+
+            let Some(encoding) = accept else {
+                return Ok(res.map_into_left_body());
+            };
+            // Only sized bodies can be checked against the threshold;
+            // streaming responses (the batch stream) pass through as-is.
+            let eligible = res.status().is_success()
+                && !res.headers().contains_key(header::CONTENT_ENCODING)
+                && is_json(res.headers())
+                && matches!(
+                    res.response().body().size(),
+                    BodySize::Sized(n) if n >= config.compression_min_bytes
+                );
+            if !eligible {
+                return Ok(res.map_into_left_body());
+            }
+
+            let (http_req, http_res) = res.into_parts();
+            let (mut head, body) = http_res.into_parts();
+            let bytes = actix_web::body::to_bytes(body).await.map_err(|err| {
+                actix_web::Error::from(HTTPError::from(crate::error::Error::ResponseEncoding(
+                    err.into().to_string(),
+                )))
+            })?;
+
+            // An encoder that fails, or cannot actually shrink the body,
+            // loses: the original goes out unencoded.
+            let compressed = match encode(encoding, &bytes) {
+                Ok(compressed) if compressed.len() < bytes.len() => compressed,
+                _ => {
+                    let res = head.set_body(bytes).map_into_boxed_body();
+                    return Ok(ServiceResponse::new(http_req, res).map_into_right_body());
+                }
+            };
+
+            head.headers_mut()
+                .insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding));
+            // Stale now that the body changed; actix re-derives it.
+            head.headers_mut().remove(header::CONTENT_LENGTH);
+            head.headers_mut()
+                .append(header::VARY, HeaderValue::from_static("accept-encoding"));
+            let res = head.set_body(compressed).map_into_boxed_body();
+            Ok(ServiceResponse::new(http_req, res).map_into_right_body())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::ACCEPT_ENCODING;
+
+    use super::*;
+
+    fn accepting(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_ENCODING, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn negotiation_follows_our_preference_order() {
+        assert_eq!(negotiate(&accepting("gzip, deflate, br")), Some("br"));
+        assert_eq!(negotiate(&accepting("deflate, gzip;q=0.5")), Some("gzip"));
+        assert_eq!(negotiate(&accepting("*")), Some("br"));
+    }
+
+    #[test]
+    fn zero_quality_rules_an_encoding_out() {
+        assert_eq!(negotiate(&accepting("br;q=0, gzip")), Some("gzip"));
+        assert_eq!(negotiate(&accepting("identity")), None);
+        assert_eq!(negotiate(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn bodies_round_trip_through_every_encoding() {
+        let body = br#"{"res": 42} "#.repeat(100);
+        for encoding in ENCODINGS {
+            let packed = encode(encoding, &body).unwrap();
+            assert!(packed.len() < body.len(), "{encoding} did not shrink");
+            if encoding != "br" {
+                assert_eq!(&decode(encoding, &packed, 10_000).unwrap()[..], &body[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn decode_enforces_the_zip_bomb_cap() {
+        let packed = encode("gzip", &vec![0u8; 100_000]).unwrap();
+        assert!(matches!(
+            decode("gzip", &packed, 512),
+            Err(crate::error::Error::DecompressedTooLarge { max: 512 })
+        ));
+    }
+}
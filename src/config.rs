@@ -0,0 +1,1485 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+
+use crate::error::{Error, Result};
+
+static CONFIG: OnceLock<Arc<Config>> = OnceLock::new();
+
+/// Command-line flags, the highest-precedence configuration layer. Only
+/// the settings an operator plausibly overrides at the shell get a flag;
+/// everything else comes from the environment or the file.
+#[derive(Debug, Default, clap::Parser)]
+#[command(name = "sentry-rs-demo", version)]
+pub struct Cli {
+    /// Port to listen on (overrides APP_PORT).
+    #[arg(long)]
+    pub port: Option<u16>,
+
+    /// Address to bind (overrides APP_HOST).
+    #[arg(long)]
+    pub host: Option<String>,
+
+    /// Tracing filter directive (overrides APP_LOG_FILTER).
+    #[arg(long)]
+    pub log_filter: Option<String>,
+
+    /// TOML configuration file; without the flag, ./config.toml is used
+    /// when present.
+    #[arg(long)]
+    pub config: Option<String>,
+
+    /// Print the effective merged configuration (secrets masked) and exit.
+    #[arg(long)]
+    pub print_config: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Subcommands; without one the server starts, preserving the historical
+/// bare invocation.
+#[derive(Debug, clap::Subcommand)]
+pub enum Command {
+    /// Start the HTTP server (the default).
+    Serve,
+    /// Run one calculation locally, printing the same JSON the HTTP API
+    /// would return; errors print the structured error body on stderr
+    /// and exit non-zero.
+    Calc {
+        /// add, sub, mul, div, mod or pow.
+        op: String,
+        x: i64,
+        y: i64,
+    },
+    /// Load and validate the merged configuration, then exit.
+    CheckConfig,
+}
+
+/// The configuration layers, consulted highest-precedence first: CLI
+/// flags, then environment variables (including .env), then the optional
+/// TOML file. File keys are the environment variable names, lowercased
+/// (`app_port = 9999`), so every knob is documented exactly once.
+struct Layers {
+    cli: BTreeMap<&'static str, String>,
+    file: BTreeMap<String, String>,
+}
+
+impl Layers {
+    fn load(cli: &Cli) -> Result<Layers> {
+        let mut overrides = BTreeMap::new();
+        if let Some(port) = cli.port {
+            overrides.insert("APP_PORT", port.to_string());
+        }
+        if let Some(host) = &cli.host {
+            overrides.insert("APP_HOST", host.clone());
+        }
+        if let Some(log_filter) = &cli.log_filter {
+            overrides.insert("APP_LOG_FILTER", log_filter.clone());
+        }
+
+        // An explicitly named file must exist; the default path is
+        // best-effort so a bare checkout still starts.
+        let (path, required) = match &cli.config {
+            Some(path) => (path.as_str(), true),
+            None => ("config.toml", false),
+        };
+        let file = match std::fs::read_to_string(path) {
+            Ok(text) => {
+                let table: toml::Table = text.parse().map_err(|err| Error::Config {
+                    var: "--config",
+                    message: format!("{path}: {err}"),
+                })?;
+                table
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let value = match value {
+                            toml::Value::String(value) => value,
+                            toml::Value::Integer(value) => value.to_string(),
+                            toml::Value::Float(value) => value.to_string(),
+                            toml::Value::Boolean(value) => value.to_string(),
+                            other => {
+                                return Err(Error::Config {
+                                    var: "--config",
+                                    message: format!(
+                                        "{path}: {key}: expected a string, number or boolean, got: {other}"
+                                    ),
+                                })
+                            }
+                        };
+                        Ok((key, value))
+                    })
+                    .collect::<Result<BTreeMap<_, _>>>()?
+            }
+            Err(err) if !required && err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => {
+                return Err(Error::Config {
+                    var: "--config",
+                    message: format!("{path}: {err}"),
+                })
+            }
+        };
+
+        Ok(Layers {
+            cli: overrides,
+            file,
+        })
+    }
+
+    fn get(&self, var: &'static str) -> Option<String> {
+        if let Some(value) = self.cli.get(var) {
+            return Some(value.clone());
+        }
+        if let Ok(value) = env::var(var) {
+            return Some(value);
+        }
+        self.file.get(&var.to_lowercase()).cloned()
+    }
+
+    /// Like get, but an empty value counts as unset, matching how the
+    /// optional string settings have always treated `FOO=`.
+    fn get_set(&self, var: &'static str) -> Option<String> {
+        self.get(var).filter(|value| !value.is_empty())
+    }
+
+    /// Parses the setting via FromStr, naming the variable and what a
+    /// valid value would be; None when unset.
+    fn parsed<T: FromStr>(&self, var: &'static str, what: &str) -> Result<Option<T>> {
+        match self.get(var) {
+            Some(value) => value.parse::<T>().map(Some).map_err(|_| Error::Config {
+                var,
+                message: format!("not a valid {what}: {value}"),
+            }),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Records the error and substitutes the fallback, so that loading can
+/// keep going and report every bad setting in one pass instead of one
+/// restart per mistake.
+fn or_record<T>(errors: &mut Vec<Error>, result: Result<T>, fallback: T) -> T {
+    match result {
+        Ok(value) => value,
+        Err(err) => {
+            errors.push(err);
+            fallback
+        }
+    }
+}
+
+/// Server configuration, merged once at startup from CLI flags, the
+/// environment (and .env) and an optional config.toml, in that order of
+/// precedence.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub host: String,
+    pub port: u16,
+    pub workers: Option<usize>,
+    /// PEM certificate chain to serve; with tls_key_path, switches the
+    /// listener from plain HTTP to rustls.
+    pub tls_cert_path: Option<String>,
+    /// PEM private key matching the certificate chain.
+    pub tls_key_path: Option<String>,
+    /// Seconds between certificate re-reads, in addition to SIGHUP;
+    /// 0 (the default) reloads on SIGHUP only.
+    pub tls_reload_secs: u64,
+    /// Unix domain socket to listen on, alongside the TCP listener; for
+    /// sidecar deployments bind the TCP side to 127.0.0.1 (or a port the
+    /// firewall blocks) and talk over the socket.
+    pub uds_path: Option<String>,
+    /// File mode for the socket, parsed as octal; defaults to 0660.
+    pub uds_mode: u32,
+    pub log_filter: String,
+    /// Emit one JSON object per log line instead of the human format.
+    pub log_format_json: bool,
+    /// Directory for rotating log files, written as JSON alongside
+    /// stdout; None keeps logging to stdout only.
+    pub log_dir: Option<String>,
+    /// How often the log file rolls; daily unless configured otherwise.
+    pub log_rotation: LogRotation,
+    /// OTLP collector endpoint for span export; None disables the
+    /// OpenTelemetry layer. Independent of sentry, which keeps errors.
+    pub otlp_endpoint: Option<String>,
+    pub shutdown_grace_secs: u64,
+    /// Keys accepted by the auth middleware; an empty list disables auth.
+    pub api_keys: Vec<ApiKey>,
+    /// Bearer token for the /admin scope; without one the scope is not
+    /// mounted at all.
+    pub admin_token: Option<String>,
+    /// Requests per second allowed per client IP; None disables limiting.
+    pub rate_limit_rps: Option<f64>,
+    /// Token bucket burst capacity; defaults to the per-second rate.
+    pub rate_limit_burst: f64,
+    /// Whether X-Forwarded-For can be trusted for the client IP.
+    pub trusted_proxy: bool,
+    /// Networks whose peers count as trusted proxies even when the
+    /// all-or-nothing switch is off.
+    pub trusted_proxy_cidrs: Vec<crate::client_ip::Cidr>,
+    /// Fall back to Cors::permissive(), for local development only.
+    pub cors_permissive: bool,
+    /// Origins allowed to make cross-origin requests; empty denies all.
+    pub cors_allowed_origins: Vec<String>,
+    /// Methods allowed in preflight responses.
+    pub cors_allowed_methods: Vec<String>,
+    /// Headers allowed in preflight responses; empty allows any header.
+    pub cors_allowed_headers: Vec<String>,
+    /// How long browsers may cache preflight responses, in seconds.
+    pub cors_max_age: Option<usize>,
+    /// How many calculations /history retains before evicting the oldest.
+    pub history_capacity: usize,
+    /// Inclusive lower bound for integer operands; None means no limit.
+    pub operand_min: Option<i32>,
+    /// Inclusive upper bound for integer operands; None means no limit.
+    pub operand_max: Option<i32>,
+    /// Reject unknown fields in request bodies instead of silently
+    /// ignoring them; off by default for compatibility.
+    pub strict_fields: bool,
+    /// Serialize i64 calculation results as JSON strings rather than
+    /// numbers, for clients that parse numbers through f64; off by
+    /// default. i128 results are always strings.
+    pub i64_as_string: bool,
+    /// Whether the LRU result cache is consulted for pure calculations;
+    /// off by default.
+    pub cache_enabled: bool,
+    /// Maximum number of cached calculation results held at once.
+    pub cache_capacity: usize,
+    /// How long a cached idempotent response can be replayed, in seconds.
+    pub idempotency_ttl_secs: u64,
+    /// Maximum number of cached idempotent responses held at once.
+    pub idempotency_capacity: usize,
+    /// The sentry DSN; None disables reporting. Kept as the raw string
+    /// (validated at load) so --print-config can mask it.
+    pub sentry_dsn: Option<String>,
+    /// Refuse to start without a DSN, for deployments where silently
+    /// losing error reporting is worse than not starting.
+    pub sentry_required: bool,
+    /// Identical error events (same code and route) within this many
+    /// seconds are sent once, with a suppressed_count on the next one
+    /// through; 0 (the default) disables dedup.
+    pub sentry_dedup_window_secs: u64,
+    /// Directory where envelopes are spooled while the DSN host is
+    /// unreachable; None (the default) drops them like stock sentry.
+    pub sentry_spool_dir: Option<String>,
+    /// Cap in bytes on the spool directory; the oldest envelopes give
+    /// way first.
+    pub sentry_spool_max_bytes: u64,
+    /// Cap on the number of spooled envelope files.
+    pub sentry_spool_max_files: usize,
+    /// Browser-project DSNs the /sentry-tunnel endpoint will forward
+    /// envelopes for; empty (the default) rejects every tunnel request.
+    pub sentry_tunnel_dsns: Vec<String>,
+    /// Cap in bytes on a tunnelled envelope body.
+    pub sentry_tunnel_max_bytes: usize,
+    /// Whether each request contributes a release-health session;
+    /// disable for deployments where the volume outweighs the signal.
+    pub sentry_session_tracking: bool,
+    /// Fingerprint overrides per error code, as semicolon-separated
+    /// `code=fragment|fragment` entries; codes without a rule group by
+    /// [code, route].
+    pub sentry_fingerprint_rules: Vec<FingerprintRule>,
+    /// Attach the masked config snapshot to the server context on every
+    /// event; opt-in, since even masked settings can be sensitive.
+    pub sentry_config_context: bool,
+    /// Cap on captured events per minute per error code; 0 (the
+    /// default) means unlimited.
+    pub sentry_events_per_minute: u32,
+    /// Initial state of the runtime reporting kill-switch; the
+    /// authenticated /admin/sentry endpoint flips it while running.
+    pub sentry_reporting_enabled: bool,
+    /// HTTP proxy for sentry delivery; falls back to HTTP_PROXY.
+    pub sentry_http_proxy: Option<String>,
+    /// HTTPS proxy for sentry delivery; falls back to HTTPS_PROXY, then
+    /// the HTTP proxy.
+    pub sentry_https_proxy: Option<String>,
+    /// PEM CA bundle additionally trusted for sentry delivery, for
+    /// TLS-intercepting proxies.
+    pub sentry_ca_bundle: Option<String>,
+    /// Whether unidentified clients get an anonymous sentry user id
+    /// hashed from their IP; disable for strict privacy.
+    pub anon_user_ids: bool,
+    /// The salt mixed into anonymous user id hashes.
+    pub anon_salt: String,
+    /// Maximum in-flight requests before the excess is shed with a 503;
+    /// None disables the limiter.
+    pub max_in_flight: Option<usize>,
+    /// How long a request may wait for a concurrency permit before it
+    /// is shed.
+    pub shed_wait_ms: u64,
+    /// Milliseconds a handler may run before the request times out with
+    /// a 504; 0 (the default) disables timeouts.
+    pub request_timeout_ms: u64,
+    /// The longer limit for routes on the slow list; defaults to four
+    /// times the regular limit.
+    pub request_timeout_slow_ms: u64,
+    /// Route patterns (as matched, e.g. "/api/v0/batch") that get the
+    /// slow limit.
+    pub slow_routes: Vec<String>,
+    /// Tracing events at this level or above become sentry events.
+    pub sentry_event_level: tracing::Level,
+    /// Tracing events at this level or above (but below the event
+    /// level) become breadcrumbs; the rest are ignored.
+    pub sentry_breadcrumb_level: tracing::Level,
+    /// Seconds between housekeeping runs; 0 disables the task.
+    pub housekeeping_interval_secs: u64,
+    /// The sentry crons monitor slug housekeeping checks in under.
+    pub housekeeping_monitor_slug: String,
+    /// Lowercased key substrings the sentry scrubber redacts.
+    pub scrub_keys: Vec<String>,
+    /// Value patterns the sentry scrubber redacts.
+    pub scrub_patterns: Vec<regex::Regex>,
+    /// Echo request bodies onto server-error events; off by default.
+    pub body_echo_enabled: bool,
+    /// Cap in bytes on the echoed body; the rest is truncated with a
+    /// marker.
+    pub body_echo_max_bytes: usize,
+    /// Route patterns whose bodies are never echoed.
+    pub body_echo_exclude_routes: Vec<String>,
+    /// Value for X-Content-Type-Options; empty disables the header.
+    pub security_content_type_options: String,
+    /// Value for X-Frame-Options; empty disables the header.
+    pub security_frame_options: String,
+    /// Value for Referrer-Policy; empty disables the header.
+    pub security_referrer_policy: String,
+    /// Value for Strict-Transport-Security, sent only when TLS is
+    /// enabled; empty disables the header.
+    pub security_hsts: String,
+    /// Content-Security-Policy for the /docs swagger page; empty
+    /// disables the header.
+    pub security_docs_csp: String,
+
+    /// Minimum response body size, in bytes, before negotiated response
+    /// compression kicks in; tiny JSON costs more to compress than it
+    /// saves on the wire.
+    pub compression_min_bytes: u64,
+
+    /// Cap on the decompressed size of a Content-Encoding request body —
+    /// the zip-bomb guard. Aligned with the 2MB extractor limits.
+    pub decompress_max_bytes: usize,
+
+    /// Signing keys for HMAC request signature verification, as
+    /// comma-separated `id:secret` entries. Empty (the default) disables
+    /// verification entirely.
+    pub hmac_keys: Vec<HmacKey>,
+
+    /// Clock-skew window, in seconds, within which a signature timestamp
+    /// is accepted.
+    pub hmac_skew_secs: u64,
+
+    /// Shared secret for verifying HS256 bearer tokens. JWT auth is
+    /// enabled when any of the three key sources is configured.
+    pub jwt_hs256_secret: Option<String>,
+
+    /// Path to a PEM RSA public key for verifying RS256 bearer tokens.
+    pub jwt_rs256_pem_path: Option<String>,
+
+    /// URL of a JWKS document to fetch RS256 verification keys from,
+    /// once, at startup.
+    pub jwt_jwks_url: Option<String>,
+
+    /// Audience a bearer token must carry; unset skips the check.
+    pub jwt_audience: Option<String>,
+
+    /// Clock-skew leeway, in seconds, for expiry validation.
+    pub jwt_skew_secs: u64,
+
+    /// Claims that must be present in every accepted token.
+    pub jwt_required_claims: Vec<String>,
+
+    /// Per-key request quotas, as comma-separated `name:limit` entries
+    /// matching API key names. Empty disables quota enforcement.
+    pub quota_limits: Vec<(String, u64)>,
+
+    /// The window quotas count against: daily or monthly.
+    pub quota_window: QuotaWindow,
+
+    /// Tenants accepted in X-Tenant-Id; a request naming any other
+    /// tenant is refused. Empty accepts no explicit tenant at all —
+    /// everything lands on "default".
+    pub tenants: Vec<String>,
+
+    /// Whether a request without X-Tenant-Id maps to the "default"
+    /// tenant; disable to force every caller to identify one.
+    pub tenant_allow_default: bool,
+
+    /// Where server-error notifications are POSTed (a Slack-style
+    /// webhook); None disables the notifier entirely.
+    pub webhook_url: Option<String>,
+
+    /// Minimum HTTP status an error needs before it is notified.
+    pub webhook_min_status: u16,
+
+    /// Seconds between stats-flusher passes (one structured log line and
+    /// a sentry breadcrumb of aggregate health); 0 disables the flusher.
+    pub stats_flush_interval_secs: u64,
+}
+
+/// How often the log file rolls over when log_dir is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogRotation {
+    Daily,
+    Hourly,
+    Never,
+}
+
+/// A named API key, so that sentry events and logs can identify the client
+/// without ever mentioning the secret itself.
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    pub name: String,
+    pub key: String,
+}
+
+/// An HMAC signing key, identified by id so rejections and logs can name
+/// the key without ever mentioning the secret itself.
+#[derive(Debug, Clone)]
+pub struct HmacKey {
+    pub id: String,
+    pub secret: String,
+}
+
+/// A grouping override: events tagged with the code are fingerprinted
+/// by these fragments instead of the default [code, route] pair. A
+/// literal `{{ default }}` fragment defers to sentry's built-in
+/// grouping for that code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FingerprintRule {
+    pub code: String,
+    pub fragments: Vec<String>,
+}
+
+/// The window per-key quotas count against; both cut over at UTC
+/// boundaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaWindow {
+    Daily,
+    Monthly,
+}
+
+impl Config {
+    /// Merges all three layers. Parse failures are collected rather than
+    /// returned eagerly, so one bad deploy surfaces every mistake at once.
+    pub fn load(cli: &Cli) -> Result<Config> {
+        let layers = Layers::load(cli)?;
+        Self::from_layers(&layers)
+    }
+
+    /// Environment-only loading; the server itself goes through load()
+    /// so the file and CLI layers participate.
+    pub fn from_env() -> Result<Config> {
+        Self::load(&Cli::default())
+    }
+
+    fn from_layers(layers: &Layers) -> Result<Config> {
+        let mut errors = Vec::new();
+
+        let host = layers
+            .get("APP_HOST")
+            .unwrap_or_else(|| "127.0.0.1".to_string());
+
+        let port =
+            or_record(&mut errors, layers.parsed("APP_PORT", "port number"), None).unwrap_or(9999);
+
+        let workers = or_record(
+            &mut errors,
+            layers.parsed("APP_WORKERS", "worker count"),
+            None,
+        );
+
+        let tls_cert_path = layers.get_set("TLS_CERT_PATH");
+        let tls_key_path = layers.get_set("TLS_KEY_PATH");
+        if tls_cert_path.is_some() != tls_key_path.is_some() {
+            errors.push(Error::Config {
+                var: "TLS_CERT_PATH",
+                message: "TLS_CERT_PATH and TLS_KEY_PATH must be set together".to_string(),
+            });
+        }
+
+        let tls_reload_secs = or_record(
+            &mut errors,
+            layers.parsed("TLS_RELOAD_SECS", "number of seconds"),
+            None,
+        )
+        .unwrap_or(0);
+
+        let uds_path = layers.get_set("APP_UDS_PATH");
+
+        let uds_mode = or_record(
+            &mut errors,
+            match layers.get("APP_UDS_MODE") {
+                Some(value) => u32::from_str_radix(&value, 8).map_err(|_| Error::Config {
+                    var: "APP_UDS_MODE",
+                    message: format!("not a valid octal mode: {value}"),
+                }),
+                None => Ok(0o660),
+            },
+            0o660,
+        );
+
+        let log_filter = layers
+            .get("APP_LOG_FILTER")
+            .unwrap_or_else(|| "INFO".to_string());
+
+        // LOG_FORMAT=json emits one JSON object per line so the whole
+        // stream (including the access records) is machine-parseable.
+        let log_format_json = layers
+            .get("LOG_FORMAT")
+            .map(|v| v == "json")
+            .unwrap_or(false);
+
+        let log_dir = layers.get_set("LOG_DIR");
+
+        let log_rotation = or_record(
+            &mut errors,
+            match layers.get("LOG_ROTATION") {
+                Some(value) => match value.to_lowercase().as_str() {
+                    "daily" => Ok(LogRotation::Daily),
+                    "hourly" => Ok(LogRotation::Hourly),
+                    "never" => Ok(LogRotation::Never),
+                    _ => Err(Error::Config {
+                        var: "LOG_ROTATION",
+                        message: format!("expected daily, hourly or never, got: {value}"),
+                    }),
+                },
+                None => Ok(LogRotation::Daily),
+            },
+            LogRotation::Daily,
+        );
+
+        let otlp_endpoint = layers.get_set("OTEL_EXPORTER_OTLP_ENDPOINT");
+
+        let shutdown_grace_secs = or_record(
+            &mut errors,
+            layers.parsed("APP_SHUTDOWN_GRACE", "number of seconds"),
+            None,
+        )
+        .unwrap_or(30);
+
+        let api_keys = or_record(
+            &mut errors,
+            match layers.get_set("APP_API_KEYS") {
+                Some(value) => value
+                    .split(',')
+                    .map(|entry| {
+                        entry
+                            .split_once(':')
+                            .filter(|(name, key)| !name.is_empty() && !key.is_empty())
+                            .map(|(name, key)| ApiKey {
+                                name: name.to_string(),
+                                key: key.to_string(),
+                            })
+                            .ok_or_else(|| Error::Config {
+                                var: "APP_API_KEYS",
+                                message: format!("expected name:key, got: {entry}"),
+                            })
+                    })
+                    .collect::<Result<Vec<_>>>(),
+                None => Ok(Vec::new()),
+            },
+            Vec::new(),
+        );
+
+        let admin_token = layers.get_set("ADMIN_TOKEN");
+
+        let rate_limit_rps = or_record(
+            &mut errors,
+            layers.parsed("APP_RATE_LIMIT_RPS", "rate"),
+            None,
+        );
+
+        let rate_limit_burst = or_record(
+            &mut errors,
+            layers.parsed("APP_RATE_LIMIT_BURST", "burst size"),
+            None,
+        )
+        .unwrap_or_else(|| rate_limit_rps.unwrap_or(0.0));
+
+        let trusted_proxy = layers
+            .get("APP_TRUSTED_PROXY")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+
+        let trusted_proxy_cidrs = or_record(
+            &mut errors,
+            match layers.get_set("APP_TRUSTED_PROXY_CIDRS") {
+                Some(value) => value
+                    .split(',')
+                    .map(|cidr| {
+                        cidr.trim()
+                            .parse::<crate::client_ip::Cidr>()
+                            .map_err(|err| Error::Config {
+                                var: "APP_TRUSTED_PROXY_CIDRS",
+                                message: format!("not a valid CIDR: {cidr}: {err}"),
+                            })
+                    })
+                    .collect::<Result<Vec<_>>>(),
+                None => Ok(Vec::new()),
+            },
+            Vec::new(),
+        );
+
+        let cors_permissive = layers
+            .get("APP_CORS_PERMISSIVE")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+
+        let cors_allowed_origins = or_record(
+            &mut errors,
+            match layers.get_set("APP_CORS_ORIGINS") {
+                Some(value) => value
+                    .split(',')
+                    .map(|origin| {
+                        let origin = origin.trim();
+                        // An origin is scheme://host[:port], nothing more; a
+                        // typo here must not silently allow everything.
+                        let uri = origin
+                            .parse::<actix_web::http::Uri>()
+                            .ok()
+                            .filter(|uri| {
+                                uri.scheme().is_some()
+                                    && uri.authority().is_some()
+                                    && (uri.path().is_empty() || uri.path() == "/")
+                                    && uri.query().is_none()
+                                    && !origin.ends_with('/')
+                            })
+                            .ok_or_else(|| Error::Config {
+                                var: "APP_CORS_ORIGINS",
+                                message: format!("not a valid origin: {origin}"),
+                            })?;
+                        Ok(uri.to_string().trim_end_matches('/').to_string())
+                    })
+                    .collect::<Result<Vec<_>>>(),
+                None => Ok(Vec::new()),
+            },
+            Vec::new(),
+        );
+
+        let split_csv = |value: String| {
+            value
+                .split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect::<Vec<_>>()
+        };
+
+        let cors_allowed_methods = layers
+            .get("APP_CORS_METHODS")
+            .map(split_csv)
+            .unwrap_or_else(|| vec!["GET".to_string(), "POST".to_string()]);
+
+        let cors_allowed_headers = layers
+            .get("APP_CORS_HEADERS")
+            .map(split_csv)
+            .unwrap_or_default();
+
+        let cors_max_age = or_record(
+            &mut errors,
+            layers.parsed("APP_CORS_MAX_AGE", "number of seconds"),
+            None,
+        );
+
+        let sentry_dsn = layers.get_set("SENTRY_DSN");
+        #[cfg(feature = "sentry")]
+        if let Some(dsn) = &sentry_dsn {
+            if dsn.parse::<sentry::types::Dsn>().is_err() {
+                errors.push(Error::Config {
+                    var: "SENTRY_DSN",
+                    message: format!("not a valid DSN: {dsn}"),
+                });
+            }
+        }
+
+        let sentry_required = layers
+            .get("SENTRY_REQUIRED")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+        if sentry_required && sentry_dsn.is_none() {
+            errors.push(Error::MissingSentryDsn);
+        }
+
+        let sentry_dedup_window_secs = or_record(
+            &mut errors,
+            layers.parsed("SENTRY_DEDUP_WINDOW_SECS", "number of seconds"),
+            None,
+        )
+        .unwrap_or(0);
+
+        let sentry_spool_dir = layers.get_set("SENTRY_SPOOL_DIR");
+
+        let sentry_spool_max_bytes = or_record(
+            &mut errors,
+            layers.parsed("SENTRY_SPOOL_MAX_BYTES", "number of bytes"),
+            None,
+        )
+        .unwrap_or(5 * 1024 * 1024);
+
+        let sentry_spool_max_files = or_record(
+            &mut errors,
+            layers.parsed("SENTRY_SPOOL_MAX_FILES", "file count"),
+            None,
+        )
+        .unwrap_or(200);
+
+        let sentry_tunnel_dsns = layers
+            .get("SENTRY_TUNNEL_DSNS")
+            .map(split_csv)
+            .unwrap_or_default();
+        #[cfg(feature = "sentry")]
+        for dsn in &sentry_tunnel_dsns {
+            if dsn.parse::<sentry::types::Dsn>().is_err() {
+                errors.push(Error::Config {
+                    var: "SENTRY_TUNNEL_DSNS",
+                    message: format!("not a valid DSN: {dsn}"),
+                });
+            }
+        }
+
+        let sentry_tunnel_max_bytes = or_record(
+            &mut errors,
+            layers.parsed("SENTRY_TUNNEL_MAX_BYTES", "number of bytes"),
+            None,
+        )
+        .unwrap_or(1024 * 1024);
+
+        let sentry_session_tracking = layers
+            .get("SENTRY_SESSION_TRACKING")
+            .map(|v| v != "false")
+            .unwrap_or(true);
+
+        let sentry_fingerprint_rules = or_record(
+            &mut errors,
+            match layers.get_set("SENTRY_FINGERPRINT_RULES") {
+                Some(value) => value
+                    .split(';')
+                    .filter(|entry| !entry.trim().is_empty())
+                    .map(|entry| {
+                        entry
+                            .split_once('=')
+                            .filter(|(code, _)| !code.trim().is_empty())
+                            .map(|(code, fragments)| FingerprintRule {
+                                code: code.trim().to_string(),
+                                fragments: fragments
+                                    .split('|')
+                                    .map(|fragment| fragment.trim().to_string())
+                                    .filter(|fragment| !fragment.is_empty())
+                                    .collect(),
+                            })
+                            .filter(|rule| !rule.fragments.is_empty())
+                            .ok_or_else(|| Error::Config {
+                                var: "SENTRY_FINGERPRINT_RULES",
+                                message: format!("expected code=fragment|fragment, got: {entry}"),
+                            })
+                    })
+                    .collect::<Result<Vec<_>>>(),
+                None => Ok(Vec::new()),
+            },
+            Vec::new(),
+        );
+        for (index, rule) in sentry_fingerprint_rules.iter().enumerate() {
+            if sentry_fingerprint_rules[..index]
+                .iter()
+                .any(|other| other.code == rule.code)
+            {
+                errors.push(Error::Config {
+                    var: "SENTRY_FINGERPRINT_RULES",
+                    message: format!("more than one rule for code: {}", rule.code),
+                });
+            }
+        }
+
+        let sentry_config_context = layers
+            .get("SENTRY_CONFIG_CONTEXT")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+
+        let sentry_events_per_minute = or_record(
+            &mut errors,
+            layers.parsed("SENTRY_EVENTS_PER_MINUTE", "events per minute"),
+            None,
+        )
+        .unwrap_or(0);
+
+        let sentry_reporting_enabled = layers
+            .get("SENTRY_REPORTING_ENABLED")
+            .map(|v| v != "false")
+            .unwrap_or(true);
+
+        let sentry_http_proxy = layers
+            .get_set("SENTRY_HTTP_PROXY")
+            .or_else(|| layers.get_set("HTTP_PROXY"));
+        let sentry_https_proxy = layers
+            .get_set("SENTRY_HTTPS_PROXY")
+            .or_else(|| layers.get_set("HTTPS_PROXY"))
+            .or_else(|| sentry_http_proxy.clone());
+        for (var, proxy) in [
+            ("SENTRY_HTTP_PROXY", &sentry_http_proxy),
+            ("SENTRY_HTTPS_PROXY", &sentry_https_proxy),
+        ] {
+            if let Some(proxy) = proxy {
+                let valid = reqwest::Url::parse(proxy)
+                    .map(|url| matches!(url.scheme(), "http" | "https"))
+                    .unwrap_or(false);
+                if !valid {
+                    errors.push(Error::Config {
+                        var,
+                        message: format!("not a valid proxy URL: {proxy}"),
+                    });
+                }
+            }
+        }
+
+        let sentry_ca_bundle = layers.get_set("SENTRY_CA_BUNDLE");
+        if let Some(path) = &sentry_ca_bundle {
+            match std::fs::read(path) {
+                Ok(pem) => {
+                    if reqwest::Certificate::from_pem(&pem).is_err() {
+                        errors.push(Error::Config {
+                            var: "SENTRY_CA_BUNDLE",
+                            message: format!("{path}: not a PEM certificate bundle"),
+                        });
+                    }
+                }
+                Err(err) => errors.push(Error::Config {
+                    var: "SENTRY_CA_BUNDLE",
+                    message: format!("{path}: {err}"),
+                }),
+            }
+        }
+
+        let anon_user_ids = layers
+            .get("SENTRY_ANON_USERS")
+            .map(|v| v != "false")
+            .unwrap_or(true);
+
+        let anon_salt = layers
+            .get("SENTRY_ANON_SALT")
+            .unwrap_or_else(|| "sentry-rs-demo".to_string());
+
+        let max_in_flight = or_record(
+            &mut errors,
+            layers.parsed("APP_MAX_IN_FLIGHT", "request count"),
+            None,
+        );
+
+        let shed_wait_ms = or_record(
+            &mut errors,
+            layers.parsed("APP_SHED_WAIT_MS", "number of milliseconds"),
+            None,
+        )
+        .unwrap_or(100);
+
+        let request_timeout_ms = or_record(
+            &mut errors,
+            layers.parsed("REQUEST_TIMEOUT_MS", "number of milliseconds"),
+            None,
+        )
+        .unwrap_or(0);
+
+        let request_timeout_slow_ms = or_record(
+            &mut errors,
+            layers.parsed("REQUEST_TIMEOUT_SLOW_MS", "number of milliseconds"),
+            None,
+        )
+        .unwrap_or(request_timeout_ms * 4);
+
+        let slow_routes = layers
+            .get("REQUEST_SLOW_ROUTES")
+            .map(split_csv)
+            .unwrap_or_else(|| {
+                ["/api/v0/batch", "/api/v0/batch/stream", "/api/v0/ws"]
+                    .iter()
+                    .map(|route| route.to_string())
+                    .collect()
+            });
+
+        let sentry_event_level = or_record(
+            &mut errors,
+            layers.parsed("SENTRY_EVENT_LEVEL", "level"),
+            None,
+        )
+        .unwrap_or(tracing::Level::ERROR);
+
+        let sentry_breadcrumb_level = or_record(
+            &mut errors,
+            layers.parsed("SENTRY_BREADCRUMB_LEVEL", "level"),
+            None,
+        )
+        .unwrap_or(tracing::Level::INFO);
+
+        let housekeeping_interval_secs = or_record(
+            &mut errors,
+            layers.parsed("APP_HOUSEKEEPING_INTERVAL", "number of seconds"),
+            None,
+        )
+        .unwrap_or(300);
+
+        let housekeeping_monitor_slug = layers
+            .get("SENTRY_MONITOR_SLUG")
+            .unwrap_or_else(|| "housekeeping".to_string());
+
+        let scrub_keys = layers
+            .get("SENTRY_SCRUB_KEYS")
+            .map(split_csv)
+            .unwrap_or_else(|| {
+                [
+                    "authorization",
+                    "api_key",
+                    "password",
+                    "secret",
+                    "token",
+                    "cookie",
+                ]
+                .iter()
+                .map(|key| key.to_string())
+                .collect()
+            })
+            .into_iter()
+            .map(|key| key.to_lowercase())
+            .collect::<Vec<_>>();
+
+        // A broken regex must not silently disable scrubbing.
+        let scrub_patterns = or_record(
+            &mut errors,
+            match layers.get_set("SENTRY_SCRUB_PATTERNS") {
+                Some(value) => value
+                    .split(',')
+                    .map(|pattern| {
+                        regex::Regex::new(pattern.trim()).map_err(|err| Error::Config {
+                            var: "SENTRY_SCRUB_PATTERNS",
+                            message: format!("not a valid regex: {pattern}: {err}"),
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>(),
+                None => Ok(Vec::new()),
+            },
+            Vec::new(),
+        );
+
+        let body_echo_enabled = layers
+            .get("BODY_ECHO_ENABLED")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+
+        let body_echo_max_bytes = or_record(
+            &mut errors,
+            layers.parsed("BODY_ECHO_MAX_BYTES", "number of bytes"),
+            None,
+        )
+        .unwrap_or(4_096);
+
+        let body_echo_exclude_routes = layers
+            .get("BODY_ECHO_EXCLUDE_ROUTES")
+            .map(split_csv)
+            .unwrap_or_default();
+
+        let security_content_type_options = layers
+            .get("SECURITY_CONTENT_TYPE_OPTIONS")
+            .unwrap_or_else(|| "nosniff".to_string());
+
+        let security_frame_options = layers
+            .get("SECURITY_FRAME_OPTIONS")
+            .unwrap_or_else(|| "DENY".to_string());
+
+        let security_referrer_policy = layers
+            .get("SECURITY_REFERRER_POLICY")
+            .unwrap_or_else(|| "no-referrer".to_string());
+
+        let security_hsts = layers
+            .get("SECURITY_HSTS")
+            .unwrap_or_else(|| "max-age=31536000; includeSubDomains".to_string());
+
+        // Swagger UI needs inline script and style to boot; everything
+        // else stays same-origin.
+        let security_docs_csp = layers.get("SECURITY_DOCS_CSP").unwrap_or_else(|| {
+            "default-src 'self'; script-src 'self' 'unsafe-inline'; \
+             style-src 'self' 'unsafe-inline'; img-src 'self' data:"
+                .to_string()
+        });
+
+        let compression_min_bytes = or_record(
+            &mut errors,
+            layers.parsed("COMPRESSION_MIN_BYTES", "number of bytes"),
+            None,
+        )
+        .unwrap_or(1_024);
+
+        let decompress_max_bytes = or_record(
+            &mut errors,
+            layers.parsed("REQUEST_DECOMPRESS_MAX_BYTES", "number of bytes"),
+            None,
+        )
+        .unwrap_or(2 * 1024 * 1024);
+
+        let hmac_keys = or_record(
+            &mut errors,
+            match layers.get_set("HMAC_KEYS") {
+                Some(value) => value
+                    .split(',')
+                    .map(|entry| {
+                        entry
+                            .split_once(':')
+                            .filter(|(id, secret)| !id.is_empty() && !secret.is_empty())
+                            .map(|(id, secret)| HmacKey {
+                                id: id.to_string(),
+                                secret: secret.to_string(),
+                            })
+                            .ok_or_else(|| Error::Config {
+                                var: "HMAC_KEYS",
+                                message: format!("expected id:secret, got: {entry}"),
+                            })
+                    })
+                    .collect::<Result<Vec<_>>>(),
+                None => Ok(Vec::new()),
+            },
+            Vec::new(),
+        );
+
+        let hmac_skew_secs = or_record(
+            &mut errors,
+            layers.parsed("HMAC_SKEW_SECS", "number of seconds"),
+            None,
+        )
+        .unwrap_or(300);
+
+        let jwt_hs256_secret = layers.get_set("JWT_HS256_SECRET");
+        let jwt_rs256_pem_path = layers.get_set("JWT_RS256_PEM_PATH");
+        let jwt_jwks_url = layers.get_set("JWT_JWKS_URL");
+        let jwt_audience = layers.get_set("JWT_AUDIENCE");
+
+        let jwt_skew_secs = or_record(
+            &mut errors,
+            layers.parsed("JWT_SKEW_SECS", "number of seconds"),
+            None,
+        )
+        .unwrap_or(60);
+
+        let jwt_required_claims = layers
+            .get("JWT_REQUIRED_CLAIMS")
+            .map(split_csv)
+            .unwrap_or_default();
+
+        let quota_limits = or_record(
+            &mut errors,
+            match layers.get("QUOTA_LIMITS") {
+                Some(value) => value
+                    .split(',')
+                    .map(|entry| {
+                        entry
+                            .split_once(':')
+                            .filter(|(name, _)| !name.is_empty())
+                            .and_then(|(name, limit)| {
+                                limit
+                                    .trim()
+                                    .parse::<u64>()
+                                    .ok()
+                                    .map(|limit| (name.to_string(), limit))
+                            })
+                            .ok_or_else(|| Error::Config {
+                                var: "QUOTA_LIMITS",
+                                message: format!("expected name:limit, got: {entry}"),
+                            })
+                    })
+                    .collect::<Result<Vec<_>>>(),
+                None => Ok(Vec::new()),
+            },
+            Vec::new(),
+        );
+
+        let quota_window = or_record(
+            &mut errors,
+            match layers.get("QUOTA_WINDOW") {
+                Some(value) => match value.to_lowercase().as_str() {
+                    "daily" => Ok(QuotaWindow::Daily),
+                    "monthly" => Ok(QuotaWindow::Monthly),
+                    _ => Err(Error::Config {
+                        var: "QUOTA_WINDOW",
+                        message: format!("expected daily or monthly, got: {value}"),
+                    }),
+                },
+                None => Ok(QuotaWindow::Daily),
+            },
+            QuotaWindow::Daily,
+        );
+
+        let tenants = layers.get("TENANTS").map(split_csv).unwrap_or_default();
+
+        let tenant_allow_default = layers
+            .get("TENANT_ALLOW_DEFAULT")
+            .map(|v| v != "false")
+            .unwrap_or(true);
+
+        let webhook_url = layers.get_set("WEBHOOK_URL");
+
+        let webhook_min_status = or_record(
+            &mut errors,
+            layers.parsed("WEBHOOK_MIN_STATUS", "HTTP status"),
+            None,
+        )
+        .unwrap_or(500);
+
+        let stats_flush_interval_secs = or_record(
+            &mut errors,
+            layers.parsed("STATS_FLUSH_INTERVAL_SECS", "number of seconds"),
+            None,
+        )
+        .unwrap_or(60);
+
+        let history_capacity = or_record(
+            &mut errors,
+            layers.parsed("APP_HISTORY_CAPACITY", "capacity"),
+            None,
+        )
+        .unwrap_or(1_000);
+
+        let operand_min = or_record(&mut errors, layers.parsed("OPERAND_MIN", "i32"), None);
+
+        let operand_max = or_record(&mut errors, layers.parsed("OPERAND_MAX", "i32"), None);
+
+        let strict_fields = layers
+            .get("APP_STRICT_FIELDS")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+
+        let i64_as_string = layers
+            .get("APP_I64_AS_STRING")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+
+        let cache_enabled = layers
+            .get("CACHE_ENABLED")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+
+        let cache_capacity = or_record(
+            &mut errors,
+            layers.parsed("CACHE_CAPACITY", "capacity"),
+            None,
+        )
+        .unwrap_or(1_024);
+
+        let idempotency_ttl_secs = or_record(
+            &mut errors,
+            layers.parsed("APP_IDEMPOTENCY_TTL_SECS", "number of seconds"),
+            None,
+        )
+        .unwrap_or(600);
+
+        let idempotency_capacity = or_record(
+            &mut errors,
+            layers.parsed("APP_IDEMPOTENCY_CAPACITY", "capacity"),
+            None,
+        )
+        .unwrap_or(1_024);
+
+        if let (Some(min), Some(max)) = (operand_min, operand_max) {
+            if min > max {
+                errors.push(Error::Config {
+                    var: "OPERAND_MIN",
+                    message: format!("lower bound {min} exceeds OPERAND_MAX {max}"),
+                });
+            }
+        }
+
+        // A single mistake keeps its precise error; several get the
+        // aggregate so none is hidden behind the first.
+        if errors.len() == 1 {
+            return Err(errors.remove(0));
+        }
+        if !errors.is_empty() {
+            return Err(Error::ConfigInvalid(errors));
+        }
+
+        Ok(Config {
+            host,
+            port,
+            workers,
+            tls_cert_path,
+            tls_key_path,
+            tls_reload_secs,
+            uds_path,
+            uds_mode,
+            log_filter,
+            log_format_json,
+            log_dir,
+            log_rotation,
+            otlp_endpoint,
+            shutdown_grace_secs,
+            api_keys,
+            admin_token,
+            rate_limit_rps,
+            rate_limit_burst,
+            trusted_proxy,
+            trusted_proxy_cidrs,
+            cors_permissive,
+            cors_allowed_origins,
+            cors_allowed_methods,
+            cors_allowed_headers,
+            cors_max_age,
+            history_capacity,
+            operand_min,
+            operand_max,
+            strict_fields,
+            i64_as_string,
+            cache_enabled,
+            cache_capacity,
+            idempotency_ttl_secs,
+            idempotency_capacity,
+            sentry_dsn,
+            sentry_required,
+            sentry_dedup_window_secs,
+            sentry_spool_dir,
+            sentry_spool_max_bytes,
+            sentry_spool_max_files,
+            sentry_tunnel_dsns,
+            sentry_tunnel_max_bytes,
+            sentry_session_tracking,
+            sentry_fingerprint_rules,
+            sentry_config_context,
+            sentry_events_per_minute,
+            sentry_reporting_enabled,
+            sentry_http_proxy,
+            sentry_https_proxy,
+            sentry_ca_bundle,
+            anon_user_ids,
+            anon_salt,
+            max_in_flight,
+            shed_wait_ms,
+            request_timeout_ms,
+            request_timeout_slow_ms,
+            slow_routes,
+            sentry_event_level,
+            sentry_breadcrumb_level,
+            housekeeping_interval_secs,
+            housekeeping_monitor_slug,
+            scrub_keys,
+            scrub_patterns,
+            body_echo_enabled,
+            body_echo_max_bytes,
+            body_echo_exclude_routes,
+            security_content_type_options,
+            security_frame_options,
+            security_referrer_policy,
+            security_hsts,
+            security_docs_csp,
+            compression_min_bytes,
+            decompress_max_bytes,
+            hmac_keys,
+            hmac_skew_secs,
+            jwt_hs256_secret,
+            jwt_rs256_pem_path,
+            jwt_jwks_url,
+            jwt_audience,
+            jwt_skew_secs,
+            jwt_required_claims,
+            quota_limits,
+            quota_window,
+            tenants,
+            tenant_allow_default,
+            webhook_url,
+            webhook_min_status,
+            stats_flush_interval_secs,
+        })
+    }
+
+    /// The effective merged configuration for --print-config. Secrets are
+    /// masked rather than omitted, so their presence is still visible.
+    pub fn render_masked(&self) -> String {
+        let mut masked = self.clone();
+        masked.admin_token = masked.admin_token.map(|_| "***".to_string());
+        masked.sentry_dsn = masked.sentry_dsn.map(|_| "***".to_string());
+        masked.anon_salt = "***".to_string();
+        for api_key in &mut masked.api_keys {
+            api_key.key = "***".to_string();
+        }
+        for hmac_key in &mut masked.hmac_keys {
+            hmac_key.secret = "***".to_string();
+        }
+        masked.jwt_hs256_secret = masked.jwt_hs256_secret.map(|_| "***".to_string());
+        // Slack-style webhook URLs embed their token in the path.
+        masked.webhook_url = masked.webhook_url.map(|_| "***".to_string());
+        format!("{masked:#?}")
+    }
+
+    /// The process-wide configuration, parsed on first access. main calls
+    /// try_global_with early (with the parsed CLI flags) so that bad
+    /// settings surface as an Error rather than a panic.
+    pub fn try_global_with(cli: &Cli) -> Result<Arc<Config>> {
+        if let Some(config) = CONFIG.get() {
+            return Ok(config.clone());
+        }
+
+        let config = Arc::new(Config::load(cli)?);
+        Ok(CONFIG.get_or_init(|| config).clone())
+    }
+
+    pub fn try_global() -> Result<Arc<Config>> {
+        Self::try_global_with(&Cli::default())
+    }
+
+    pub fn global() -> Arc<Config> {
+        Self::try_global().expect("invalid configuration")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A single test so that the env var mutations cannot race the
+    // defaults assertion in a parallel test thread.
+    #[test]
+    fn layered_loading_applies_precedence_and_aggregates_bad_settings() {
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.host, "127.0.0.1");
+        assert_eq!(config.log_filter, "INFO");
+        assert!(config.workers.is_none());
+
+        env::set_var("APP_PORT", "not-a-port");
+        let err = Config::from_env().unwrap_err();
+        env::remove_var("APP_PORT");
+
+        assert!(matches!(
+            err,
+            Error::Config {
+                var: "APP_PORT",
+                ..
+            }
+        ));
+
+        // Several mistakes are reported together, not just the first.
+        env::set_var("APP_PORT", "not-a-port");
+        env::set_var("APP_WORKERS", "many");
+        env::set_var("SENTRY_SCRUB_PATTERNS", r"\d{16},[unclosed");
+        let err = Config::from_env().unwrap_err();
+        env::remove_var("APP_PORT");
+        env::remove_var("APP_WORKERS");
+        env::remove_var("SENTRY_SCRUB_PATTERNS");
+
+        let Error::ConfigInvalid(errors) = err else {
+            panic!("expected the aggregate error, got: {err}");
+        };
+        assert_eq!(errors.len(), 3);
+        let rendered = Error::ConfigInvalid(errors).to_string();
+        assert!(rendered.contains("APP_PORT"), "missing from: {rendered}");
+        assert!(rendered.contains("APP_WORKERS"), "missing from: {rendered}");
+        assert!(
+            rendered.contains("SENTRY_SCRUB_PATTERNS"),
+            "missing from: {rendered}"
+        );
+
+        // Precedence: the file loses to the environment, which loses to
+        // the CLI flags.
+        let dir =
+            std::env::temp_dir().join(format!("sentry-rs-demo-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("config.toml");
+        std::fs::write(&file, "app_port = 1111\napp_host = \"0.0.0.0\"\n").unwrap();
+        env::set_var("APP_PORT", "2222");
+        let cli = Cli {
+            port: Some(3333),
+            config: Some(file.to_string_lossy().into_owned()),
+            ..Cli::default()
+        };
+        let config = Config::load(&cli).unwrap();
+        env::remove_var("APP_PORT");
+        assert_eq!(config.port, 3333);
+        assert_eq!(config.host, "0.0.0.0");
+
+        let cli = Cli {
+            config: Some(file.to_string_lossy().into_owned()),
+            ..Cli::default()
+        };
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.port, 1111);
+
+        // An explicitly named file must exist; the implicit default may
+        // be absent.
+        let cli = Cli {
+            config: Some("/no/such/config.toml".to_string()),
+            ..Cli::default()
+        };
+        let err = Config::load(&cli).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Config {
+                var: "--config",
+                ..
+            }
+        ));
+
+        // --print-config masks secrets but keeps their presence visible.
+        env::set_var("ADMIN_TOKEN", "hunter2");
+        let config = Config::from_env().unwrap();
+        env::remove_var("ADMIN_TOKEN");
+        let rendered = config.render_masked();
+        assert!(rendered.contains("***"), "nothing masked in: {rendered}");
+        assert!(
+            !rendered.contains("hunter2"),
+            "secret leaked into: {rendered}"
+        );
+
+        // Fingerprint rules parse into the typed form; entries without a
+        // code=fragments shape and duplicate codes are rejected.
+        env::set_var(
+            "SENTRY_FINGERPRINT_RULES",
+            "upstream_timeout=upstream|timeout; handler_panic={{ default }}",
+        );
+        let config = Config::from_env().unwrap();
+        env::remove_var("SENTRY_FINGERPRINT_RULES");
+        assert_eq!(
+            config.sentry_fingerprint_rules,
+            vec![
+                FingerprintRule {
+                    code: "upstream_timeout".to_string(),
+                    fragments: vec!["upstream".to_string(), "timeout".to_string()],
+                },
+                FingerprintRule {
+                    code: "handler_panic".to_string(),
+                    fragments: vec!["{{ default }}".to_string()],
+                },
+            ]
+        );
+
+        env::set_var("SENTRY_FINGERPRINT_RULES", "divide_by_zero");
+        let err = Config::from_env().unwrap_err();
+        env::remove_var("SENTRY_FINGERPRINT_RULES");
+        assert!(matches!(
+            err,
+            Error::Config {
+                var: "SENTRY_FINGERPRINT_RULES",
+                ..
+            }
+        ));
+
+        // Proxy URLs and the CA bundle are validated up front.
+        env::set_var("SENTRY_HTTP_PROXY", "not a proxy url");
+        let err = Config::from_env().unwrap_err();
+        env::remove_var("SENTRY_HTTP_PROXY");
+        assert!(
+            err.to_string().contains("SENTRY_HTTP_PROXY"),
+            "wrong error: {err}"
+        );
+
+        env::set_var("SENTRY_CA_BUNDLE", "/no/such/bundle.pem");
+        let err = Config::from_env().unwrap_err();
+        env::remove_var("SENTRY_CA_BUNDLE");
+        assert!(matches!(
+            err,
+            Error::Config {
+                var: "SENTRY_CA_BUNDLE",
+                ..
+            }
+        ));
+
+        env::set_var("SENTRY_FINGERPRINT_RULES", "overflow=a;overflow=b");
+        let err = Config::from_env().unwrap_err();
+        env::remove_var("SENTRY_FINGERPRINT_RULES");
+        assert!(
+            err.to_string().contains("more than one rule"),
+            "wrong error: {err}"
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,164 @@
+use std::env;
+use std::sync::{Arc, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+
+use crate::error::{Error, Result};
+
+/// Runs the embedded migrations exactly once per process.
+static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
+
+/// The async connection pool, created lazily from DATABASE_URL (default
+/// sqlite::memory:) so that building the App stays synchronous.
+pub struct Db {
+    pool: SqlitePool,
+    migrated: tokio::sync::OnceCell<()>,
+}
+
+impl Db {
+    fn connect_lazy() -> Result<Db> {
+        let url = env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite::memory:".to_string());
+
+        // An in-memory database exists per connection, so the pool must
+        // not hand out more than one.
+        let mut options = SqlitePoolOptions::new();
+        if url.contains(":memory:") {
+            options = options.max_connections(1).idle_timeout(None);
+        }
+
+        let pool = options.connect_lazy(&url).map_err(|err| Error::Config {
+            var: "DATABASE_URL",
+            message: err.to_string(),
+        })?;
+
+        Ok(Db {
+            pool,
+            migrated: tokio::sync::OnceCell::new(),
+        })
+    }
+
+    pub fn global() -> Arc<Db> {
+        static DB: OnceLock<Arc<Db>> = OnceLock::new();
+        DB.get_or_init(|| Arc::new(Db::connect_lazy().expect("invalid DATABASE_URL")))
+            .clone()
+    }
+
+    async fn ensure_migrated(&self) -> Result<()> {
+        self.migrated
+            .get_or_try_init(|| async {
+                MIGRATOR
+                    .run(&self.pool)
+                    .await
+                    .map_err(|err| Error::Database {
+                        operation: "migrate",
+                        message: err.to_string(),
+                    })
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn insert_calculation(
+        &self,
+        op: &'static str,
+        x: i32,
+        y: i32,
+        res: &Result<i32>,
+    ) -> Result<i64> {
+        self.ensure_migrated().await?;
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        let inserted = sqlx::query(
+            "INSERT INTO calculations (op, x, y, res, error, timestamp) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(op)
+        .bind(x)
+        .bind(y)
+        .bind(res.as_ref().ok().copied())
+        .bind(res.as_ref().err().map(|err| err.code()))
+        .bind(timestamp)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| Error::Database {
+            operation: "insert_calculation",
+            message: err.to_string(),
+        })?;
+
+        Ok(inserted.last_insert_rowid())
+    }
+
+    /// Adds one request to the key's window counter and returns the new
+    /// total. An upsert, so the first request of a window creates the row.
+    pub async fn increment_usage(&self, key_name: &str, window_id: i64) -> Result<i64> {
+        self.ensure_migrated().await?;
+
+        sqlx::query_scalar::<_, i64>(
+            "INSERT INTO quota_usage (key_name, window_id, used) VALUES (?, ?, 1)
+             ON CONFLICT (key_name, window_id) DO UPDATE SET used = used + 1
+             RETURNING used",
+        )
+        .bind(key_name)
+        .bind(window_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| Error::Database {
+            operation: "increment_usage",
+            message: err.to_string(),
+        })
+    }
+
+    pub async fn fetch_usage(&self, key_name: &str, window_id: i64) -> Result<i64> {
+        self.ensure_migrated().await?;
+
+        sqlx::query_scalar::<_, i64>(
+            "SELECT used FROM quota_usage WHERE key_name = ? AND window_id = ?",
+        )
+        .bind(key_name)
+        .bind(window_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map(|used| used.unwrap_or(0))
+        .map_err(|err| Error::Database {
+            operation: "fetch_usage",
+            message: err.to_string(),
+        })
+    }
+
+    pub async fn fetch_calculation(&self, id: i64) -> Result<Option<CalculationRecord>> {
+        self.ensure_migrated().await?;
+
+        sqlx::query_as::<_, CalculationRecord>(
+            "SELECT id, op, x, y, res, error, timestamp FROM calculations WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|err| Error::Database {
+            operation: "fetch_calculation",
+            message: err.to_string(),
+        })
+    }
+}
+
+/// One persisted calculation, as stored in the calculations table.
+#[derive(Debug, Serialize, sqlx::FromRow, ToSchema)]
+pub struct CalculationRecord {
+    pub id: i64,
+    pub op: String,
+    pub x: i64,
+    pub y: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub res: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Unix seconds at the time of the calculation.
+    pub timestamp: i64,
+}
@@ -0,0 +1,617 @@
+use actix_web::{http::header::ContentType, http::StatusCode, HttpResponse, ResponseError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("cannot divide by zero")]
+    DivideByZero,
+
+    #[error("unknown operation: {0}")]
+    UnknownOperation(String),
+
+    #[error("invalid request body: {0}")]
+    InvalidRequestBody(String),
+
+    #[error("unsupported content type: {0}")]
+    UnsupportedMediaType(String),
+
+    #[error("failed to encode the response body: {0}")]
+    ResponseEncoding(String),
+
+    // Operands are i128 so one variant covers every calculation width.
+    #[error("{op} overflowed with operands x = {x}, y = {y}")]
+    Overflow { op: &'static str, x: i128, y: i128 },
+
+    #[error("{field} must be between {min} and {max}, got {value}")]
+    OperandOutOfRange {
+        field: &'static str,
+        value: i128,
+        min: i128,
+        max: i128,
+    },
+
+    #[error("cannot raise {x} to the negative exponent {y}")]
+    NegativeExponent { x: i128, y: i128 },
+
+    #[error("{field} must be non-negative, got {value}")]
+    NegativeInput { field: &'static str, value: i64 },
+
+    #[error("shift amount must be between 0 and 31, got {0}")]
+    InvalidShift(i32),
+
+    #[error("base must be between 2 and 36, got {0}")]
+    UnsupportedBase(u32),
+
+    #[error("'{value}' is not a valid base-{base} number")]
+    InvalidDigit { value: String, base: u32 },
+
+    #[error("'{value}' does not fit i128")]
+    ConversionOverflow { value: String },
+
+    // Shares the "overflow" code and 422 with Overflow; decimal operands
+    // only round-trip as strings.
+    #[error("decimal {op} overflowed with operands x = {x}, y = {y}")]
+    DecimalOverflow {
+        op: &'static str,
+        x: String,
+        y: String,
+    },
+
+    // Shares the "overflow" code and 422 with Overflow: same failure
+    // class, but here the useful hint is the largest input that fits.
+    #[error("{op} result overflows u128; the largest representable input is {max}")]
+    CombinatoricOverflow {
+        op: &'static str,
+        x: i64,
+        y: i64,
+        max: i64,
+    },
+
+    #[error("batch of {size} items exceeds the maximum of {max}")]
+    BatchTooLarge { size: usize, max: usize },
+
+    #[error("decompressed request body exceeds the maximum of {max} bytes")]
+    DecompressedTooLarge { max: usize },
+
+    #[error("envelope of {len} bytes exceeds the tunnel maximum of {max}")]
+    EnvelopeTooLarge { len: usize, max: usize },
+
+    #[error("the envelope DSN is not on the tunnel allowlist")]
+    TunnelDsnRejected,
+
+    // The message carries reqwest's error text; the ingest URL holds no
+    // secret (browser DSNs are public by design).
+    #[error("could not reach the sentry ingest host: {0}")]
+    TunnelUpstream(String),
+
+    #[error("at least one value is required")]
+    EmptyInput,
+
+    #[error("this Idempotency-Key was already used with a different request body")]
+    IdempotencyMismatch,
+
+    /// A coalesced request whose leader failed: the code, message and
+    /// status are the leader's, verbatim, so followers answer exactly
+    /// as the leader did.
+    #[error("{message}")]
+    Coalesced {
+        code: &'static str,
+        message: String,
+        status: StatusCode,
+    },
+
+    #[error("syntax error at byte {offset}: {message}")]
+    ExprSyntax { offset: usize, message: String },
+
+    #[error("expression of {len} bytes exceeds the maximum of {max}")]
+    ExprTooLong { len: usize, max: usize },
+
+    #[error("expression nesting exceeds the maximum depth of {max}")]
+    ExprTooDeep { max: usize },
+
+    #[error("expression arithmetic overflowed i64")]
+    ExprOverflow,
+
+    #[error("operands must be finite: x = {x}, y = {y}")]
+    NonFiniteOperand { x: f64, y: f64 },
+
+    #[error("float {op} produced a non-finite result with operands x = {x}, y = {y}")]
+    NonFiniteResult { op: &'static str, x: f64, y: f64 },
+
+    #[error("rate limit exceeded; retry after {retry_after_secs}s")]
+    RateLimited { retry_after_secs: u64 },
+
+    #[error("quota of {limit} requests for this window is exhausted; resets at {reset}")]
+    QuotaExceeded { limit: u64, reset: i64 },
+
+    #[error("the handler exceeded the request timeout of {limit_ms}ms")]
+    Timeout { limit_ms: u64 },
+
+    #[error("too many in-flight requests; retry shortly")]
+    Overloaded,
+
+    #[error("the service is down for maintenance")]
+    Maintenance,
+
+    #[error("missing X-Signature, X-Signature-Key-Id or X-Signature-Timestamp header")]
+    MissingSignature,
+
+    #[error("unknown signature key id: {key_id}")]
+    UnknownSignatureKey { key_id: String },
+
+    #[error("signature timestamp outside the {skew_secs}s clock-skew window")]
+    StaleSignature { skew_secs: u64 },
+
+    #[error("request signature does not match")]
+    InvalidSignature,
+
+    #[error("missing Authorization: Bearer JWT")]
+    MissingJwt,
+
+    #[error("invalid bearer token: {0}")]
+    InvalidJwt(String),
+
+    #[error("missing X-Api-Key header")]
+    MissingApiKey,
+
+    #[error("unknown API key")]
+    UnknownApiKey,
+
+    #[error("missing X-Tenant-Id header")]
+    MissingTenant,
+
+    #[error("unknown tenant: {tenant}")]
+    UnknownTenant { tenant: String },
+
+    #[error("missing Authorization: Bearer token")]
+    MissingAdminToken,
+
+    #[error("invalid admin token")]
+    InvalidAdminToken,
+
+    #[error("no calculation with id {id}")]
+    HistoryNotFound { id: i64 },
+
+    #[error("no job with id {id}")]
+    JobNotFound { id: String },
+
+    #[error("no recently issued event with id {id}")]
+    UnknownEventId { id: String },
+
+    // The message carries sqlx's own error text, never a query with
+    // bound values.
+    #[error("database error during {operation}: {message}")]
+    Database {
+        operation: &'static str,
+        message: String,
+    },
+
+    #[error("invalid configuration for {var}: {message}")]
+    Config { var: &'static str, message: String },
+
+    // Every bad setting found during startup, reported in one pass so a
+    // broken deploy does not need N restarts to surface N mistakes.
+    #[error("invalid configuration:{}", .0.iter().map(|err| format!("\n  {err}")).collect::<String>())]
+    ConfigInvalid(Vec<Error>),
+
+    #[error("invalid log filter directive: {0}")]
+    InvalidLogFilter(String),
+
+    #[error("handler panicked: {0}")]
+    HandlerPanic(String),
+
+    #[error("failed to encode metrics: {0}")]
+    Metrics(String),
+
+    #[error("housekeeping run failed: {0}")]
+    Housekeeping(String),
+
+    #[error("SENTRY_REQUIRED is set but SENTRY_DSN is unset")]
+    MissingSentryDsn,
+
+    #[error(transparent)]
+    Actix(#[from] actix_web::Error),
+
+    #[error(transparent)]
+    DotEnvy(#[from] dotenvy::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl Error {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::DivideByZero => "divide_by_zero",
+            Error::UnknownOperation(_) => "unknown_operation",
+            Error::InvalidRequestBody(_) => "invalid_request_body",
+            Error::UnsupportedMediaType(_) => "unsupported_media_type",
+            Error::ResponseEncoding(_) => "response_encoding",
+            Error::Overflow { .. } => "overflow",
+            Error::OperandOutOfRange { .. } => "operand_out_of_range",
+            Error::NegativeExponent { .. } => "negative_exponent",
+            Error::NegativeInput { .. } => "negative_input",
+            Error::InvalidShift(_) => "invalid_shift",
+            Error::UnsupportedBase(_) => "unsupported_base",
+            Error::InvalidDigit { .. } => "invalid_digit",
+            Error::ConversionOverflow { .. } => "conversion_overflow",
+            Error::DecimalOverflow { .. } => "overflow",
+            Error::CombinatoricOverflow { .. } => "overflow",
+            Error::BatchTooLarge { .. } => "batch_too_large",
+            Error::DecompressedTooLarge { .. } => "decompressed_too_large",
+            Error::EnvelopeTooLarge { .. } => "envelope_too_large",
+            Error::TunnelDsnRejected => "tunnel_dsn_rejected",
+            Error::TunnelUpstream(_) => "tunnel_upstream",
+            Error::EmptyInput => "empty_input",
+            Error::IdempotencyMismatch => "idempotency_mismatch",
+            Error::Coalesced { code, .. } => code,
+            Error::ExprSyntax { .. } => "expr_syntax",
+            Error::ExprTooLong { .. } => "expr_too_long",
+            Error::ExprTooDeep { .. } => "expr_too_deep",
+            Error::ExprOverflow => "expr_overflow",
+            Error::NonFiniteOperand { .. } => "non_finite_operand",
+            Error::NonFiniteResult { .. } => "non_finite_result",
+            Error::RateLimited { .. } => "rate_limited",
+            Error::QuotaExceeded { .. } => "quota_exceeded",
+            Error::Timeout { .. } => "timeout",
+            Error::Overloaded => "overloaded",
+            Error::Maintenance => "maintenance",
+            Error::MissingSignature => "missing_signature",
+            Error::UnknownSignatureKey { .. } => "unknown_signature_key",
+            Error::StaleSignature { .. } => "stale_signature",
+            Error::InvalidSignature => "invalid_signature",
+            Error::MissingJwt => "missing_jwt",
+            Error::InvalidJwt(_) => "invalid_jwt",
+            Error::MissingApiKey => "missing_api_key",
+            Error::UnknownApiKey => "unknown_api_key",
+            Error::MissingTenant => "missing_tenant",
+            Error::UnknownTenant { .. } => "unknown_tenant",
+            Error::MissingAdminToken => "missing_admin_token",
+            Error::InvalidAdminToken => "invalid_admin_token",
+            Error::HistoryNotFound { .. } => "history_not_found",
+            Error::JobNotFound { .. } => "job_not_found",
+            Error::UnknownEventId { .. } => "unknown_event_id",
+            Error::Database { .. } => "database",
+            Error::Config { .. } => "config",
+            Error::ConfigInvalid(_) => "config",
+            Error::InvalidLogFilter(_) => "invalid_log_filter",
+            Error::HandlerPanic(_) => "handler_panic",
+            Error::Metrics(_) => "metrics",
+            Error::Housekeeping(_) => "housekeeping",
+            Error::MissingSentryDsn => "missing_sentry_dsn",
+            Error::Actix(_) => "actix",
+            Error::DotEnvy(_) => "dotenvy",
+            Error::Io(_) => "io",
+        }
+    }
+
+    /// The single place that knows which HTTP status each variant maps
+    /// to; new variants default to 500 here and nowhere else.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Error::DivideByZero
+            | Error::UnknownOperation(_)
+            | Error::InvalidRequestBody(_)
+            | Error::NegativeExponent { .. }
+            | Error::NegativeInput { .. }
+            | Error::InvalidShift(_)
+            | Error::UnsupportedBase(_)
+            | Error::InvalidDigit { .. }
+            | Error::NonFiniteOperand { .. }
+            | Error::ExprSyntax { .. }
+            | Error::ExprTooDeep { .. }
+            | Error::InvalidLogFilter(_) => StatusCode::BAD_REQUEST,
+            Error::Overflow { .. }
+            | Error::OperandOutOfRange { .. }
+            | Error::IdempotencyMismatch
+            | Error::CombinatoricOverflow { .. }
+            | Error::DecimalOverflow { .. }
+            | Error::ConversionOverflow { .. }
+            | Error::EmptyInput
+            | Error::NonFiniteResult { .. }
+            | Error::ExprOverflow => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::BatchTooLarge { .. }
+            | Error::DecompressedTooLarge { .. }
+            | Error::EnvelopeTooLarge { .. }
+            | Error::ExprTooLong { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            Error::Coalesced { status, .. } => *status,
+            Error::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Error::RateLimited { .. } | Error::QuotaExceeded { .. } => {
+                StatusCode::TOO_MANY_REQUESTS
+            }
+            Error::Timeout { .. } => StatusCode::GATEWAY_TIMEOUT,
+            Error::Overloaded | Error::Maintenance => StatusCode::SERVICE_UNAVAILABLE,
+            Error::MissingSignature
+            | Error::UnknownSignatureKey { .. }
+            | Error::StaleSignature { .. }
+            | Error::InvalidSignature
+            | Error::MissingJwt
+            | Error::InvalidJwt(_)
+            | Error::MissingApiKey
+            | Error::MissingAdminToken => StatusCode::UNAUTHORIZED,
+            Error::UnknownApiKey
+            | Error::InvalidAdminToken
+            | Error::MissingTenant
+            | Error::UnknownTenant { .. }
+            | Error::TunnelDsnRejected => StatusCode::FORBIDDEN,
+            Error::TunnelUpstream(_) => StatusCode::BAD_GATEWAY,
+            Error::HistoryNotFound { .. }
+            | Error::JobNotFound { .. }
+            | Error::UnknownEventId { .. } => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn is_client_error(&self) -> bool {
+        self.status_code().is_client_error()
+    }
+}
+
+#[derive(Debug)]
+pub struct HTTPError {
+    pub status_code: StatusCode,
+    pub code: &'static str,
+    pub source: Box<dyn std::error::Error>,
+    /// Captured at construction: errors returned as Err from the
+    /// middleware are rendered by actix outside the REQUEST_ID scope.
+    pub request_id: Option<String>,
+    /// Captured alongside request_id, for the same reason.
+    pub trace_id: Option<String>,
+    /// The sentry event id when the error was captured, so a customer
+    /// quoting a 500 can be matched to the exact event.
+    pub event_id: Option<String>,
+}
+
+impl std::error::Error for HTTPError {}
+
+impl std::fmt::Display for HTTPError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{ {}, {} }}", self.status_code, self.source)
+    }
+}
+
+impl Error {
+    /// The conversion every handler reaches through `?`. Explicit so the
+    /// capture side can be handed a RecordingReporter in tests; From
+    /// cannot take context, so it delegates here with the process
+    /// default.
+    pub fn into_http(self, reporter: &dyn crate::reporter::ErrorReporter) -> HTTPError {
+        let status_code = self.status_code();
+        // Maintenance and load-shed 503s are deliberate, not incidents;
+        // everything else on the server side is captured.
+        let capture =
+            !self.is_client_error() && !matches!(self, Error::Maintenance | Error::Overloaded);
+        // Deliberate 503s are likewise not worth a webhook ping.
+        let notify = !matches!(self, Error::Maintenance | Error::Overloaded);
+
+        let operands = match self {
+            Error::Overflow { x, y, .. } | Error::NegativeExponent { x, y } => Some((x, y)),
+            _ => None,
+        };
+
+        // The operation name only; the message never includes a query
+        // with bound values.
+        let sql_operation = match self {
+            Error::Database { operation, .. } => Some(operation),
+            _ => None,
+        };
+
+        crate::metrics::Metrics::global()
+            .calculation_errors_total
+            .with_label_values(&[self.code()])
+            .inc();
+
+        let mut http_error = HTTPError {
+            status_code,
+            code: self.code(),
+            source: self.into(),
+            request_id: crate::middleware::REQUEST_ID.try_with(|id| id.clone()).ok(),
+            trace_id: crate::middleware::TRACE_ID.try_with(|id| id.clone()).ok(),
+            event_id: None,
+        };
+
+        // Debounced and queued; delivery happens off the request path.
+        if notify {
+            crate::webhook::notify(http_error.code, status_code);
+        }
+
+        // Client errors are the caller's fault, not an incident; don't
+        // capture them at all (before_send remains as a safety net).
+        if capture {
+            let mut extras = vec![(
+                "status_code",
+                serde_json::Value::from(http_error.status_code.as_u16()),
+            )];
+            if let Some((x, y)) = operands {
+                extras.push(("x", operand_extra(x)));
+                extras.push(("y", operand_extra(y)));
+            }
+            if let Some(operation) = sql_operation {
+                extras.push(("sql_operation", operation.into()));
+            }
+            http_error.event_id = reporter.report_error(
+                &http_error,
+                &[("code", http_error.code.to_string())],
+                &extras,
+            );
+            #[cfg(feature = "sentry")]
+            if let Some(event_id) = &http_error.event_id {
+                crate::feedback::IssuedEvents::global().note(event_id);
+            }
+        }
+
+        http_error
+    }
+}
+
+impl From<Error> for HTTPError {
+    fn from(err: Error) -> Self {
+        err.into_http(crate::reporter::global())
+    }
+}
+
+/// JSON numbers cannot hold every i128; fall back to a string for values
+/// beyond the i64/u64 range rather than losing precision in the extras.
+fn operand_extra(value: i128) -> serde_json::Value {
+    i64::try_from(value)
+        .map(serde_json::Value::from)
+        .or_else(|_| u64::try_from(value).map(serde_json::Value::from))
+        .unwrap_or_else(|_| value.to_string().into())
+}
+
+/// The flat internal form of an error response; both wire formats (the
+/// classic enveloped JSON and RFC 7807 problem+json) render from it.
+struct ErrorBody {
+    code: &'static str,
+    message: String,
+    status: u16,
+    request_id: Option<String>,
+    trace_id: Option<String>,
+    event_id: Option<String>,
+}
+
+impl ErrorBody {
+    /// The classic shape: everything under an "error" envelope.
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "error": {
+                "code": self.code,
+                "message": self.message,
+                "status": self.status,
+                "request_id": self.request_id,
+                "trace_id": self.trace_id,
+                "event_id": self.event_id,
+            }
+        })
+    }
+
+    /// RFC 7807 Problem Details, with the error code and ids as
+    /// extension members.
+    fn to_problem_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": format!("/errors/{}", self.code),
+            "title": self.code.replace('_', " "),
+            "status": self.status,
+            "detail": self.message,
+            "instance": crate::middleware::REQUEST_PATH
+                .try_with(|path| path.clone())
+                .ok(),
+            "code": self.code,
+            "request_id": self.request_id,
+            "trace_id": self.trace_id,
+            "event_id": self.event_id,
+        })
+    }
+}
+
+impl ResponseError for HTTPError {
+    fn status_code(&self) -> StatusCode {
+        self.status_code
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let body = ErrorBody {
+            code: self.code,
+            message: self.source.to_string(),
+            status: self.status_code.as_u16(),
+            request_id: self
+                .request_id
+                .clone()
+                .or_else(|| crate::middleware::REQUEST_ID.try_with(|id| id.clone()).ok()),
+            trace_id: self
+                .trace_id
+                .clone()
+                .or_else(|| crate::middleware::TRACE_ID.try_with(|id| id.clone()).ok()),
+            event_id: self.event_id.clone(),
+        };
+
+        let mut builder = HttpResponse::build(self.status_code);
+        // Responses rendered by actix from an Err never pass back through
+        // the middleware, so echo the request id ourselves.
+        if let Some(request_id) = &body.request_id {
+            builder.insert_header((crate::middleware::REQUEST_ID_HEADER, request_id.as_str()));
+        }
+        // The same id as the body, for clients that only surface headers.
+        if let Some(event_id) = &body.event_id {
+            builder.insert_header(("x-sentry-event-id", event_id.as_str()));
+        }
+
+        // Honour the Accept header recorded by the middleware; the
+        // platform-wide problem+json format first, then msgpack (a failed
+        // encode falls back to JSON rather than losing the error).
+        if crate::negotiation::response_is_problem_json() {
+            return builder
+                .content_type(crate::negotiation::PROBLEM_JSON)
+                .json(body.to_problem_json());
+        }
+
+        if crate::negotiation::response_is_msgpack() {
+            if let Ok(buf) = rmp_serde::to_vec_named(&body.to_json()) {
+                return builder.content_type(crate::negotiation::MSGPACK).body(buf);
+            }
+        }
+
+        builder
+            .content_type(ContentType::json())
+            .json(body.to_json())
+    }
+}
+
+pub type HttpResult<T> = std::result::Result<T, HTTPError>;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn error_response_is_json_for_divide_by_zero() {
+        let resp = HTTPError::from(Error::DivideByZero).error_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "divide_by_zero");
+        assert_eq!(json["error"]["message"], "cannot divide by zero");
+        assert_eq!(json["error"]["status"], 400);
+    }
+
+    #[tokio::test]
+    async fn error_response_is_json_for_internal_errors() {
+        let resp = HTTPError::from(Error::MissingSentryDsn).error_response();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "missing_sentry_dsn");
+        assert_eq!(json["error"]["status"], 500);
+    }
+
+    #[test]
+    fn server_errors_reach_the_reporter_with_code_and_extras() {
+        use crate::reporter::{RecordingReporter, ReporterCall};
+
+        let reporter = RecordingReporter::default();
+        let http_error = Error::Database {
+            operation: "insert",
+            message: "database is locked".to_string(),
+        }
+        .into_http(&reporter);
+        assert_eq!(http_error.code, "database");
+
+        let calls = reporter.take();
+        let [ReporterCall::Error { tags, extras, .. }] = &calls[..] else {
+            panic!("expected exactly one error report, got {calls:?}");
+        };
+        assert!(tags.contains(&("code", "database".to_string())));
+        assert!(extras.contains(&("status_code", 500.into())));
+        assert!(extras.contains(&("sql_operation", "insert".into())));
+    }
+
+    #[test]
+    fn client_errors_are_not_reported() {
+        let reporter = crate::reporter::RecordingReporter::default();
+        let _ = Error::DivideByZero.into_http(&reporter);
+        assert!(reporter.take().is_empty());
+    }
+}
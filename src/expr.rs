@@ -0,0 +1,268 @@
+use crate::error::{Error, Result};
+
+/// The longest expression we will parse, in bytes.
+pub const MAX_EXPR_LEN: usize = 1024;
+
+/// How deep parentheses and unary minus may nest. Recursion depth is
+/// bounded by this, so hostile input cannot blow the stack.
+pub const MAX_EXPR_DEPTH: usize = 64;
+
+/// Evaluates an arithmetic expression over i64: `+ - * / %`, parentheses
+/// and unary minus, with the usual precedence and left associativity.
+/// All arithmetic is checked; syntax errors carry the byte offset of the
+/// first offending character.
+pub fn eval(input: &str) -> Result<i64> {
+    if input.len() > MAX_EXPR_LEN {
+        return Err(Error::ExprTooLong {
+            len: input.len(),
+            max: MAX_EXPR_LEN,
+        });
+    }
+
+    let mut parser = Parser {
+        input: input.as_bytes(),
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.expr()?;
+    parser.skip_whitespace();
+    if parser.pos < parser.input.len() {
+        return Err(parser.syntax_error("unexpected trailing input"));
+    }
+
+    Ok(value)
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn syntax_error(&self, message: &str) -> Error {
+        Error::ExprSyntax {
+            offset: self.pos,
+            message: message.to_owned(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.input.len() && self.input[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.input.get(self.pos).copied()
+    }
+
+    /// expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<i64> {
+        let mut acc = self.term()?;
+        while let Some(op) = self.peek() {
+            match op {
+                b'+' => {
+                    self.pos += 1;
+                    let rhs = self.term()?;
+                    acc = acc.checked_add(rhs).ok_or(Error::ExprOverflow)?;
+                }
+                b'-' => {
+                    self.pos += 1;
+                    let rhs = self.term()?;
+                    acc = acc.checked_sub(rhs).ok_or(Error::ExprOverflow)?;
+                }
+                _ => break,
+            }
+        }
+
+        Ok(acc)
+    }
+
+    /// term := unary (('*' | '/' | '%') unary)*
+    fn term(&mut self) -> Result<i64> {
+        let mut acc = self.unary()?;
+        while let Some(op) = self.peek() {
+            match op {
+                b'*' => {
+                    self.pos += 1;
+                    let rhs = self.unary()?;
+                    acc = acc.checked_mul(rhs).ok_or(Error::ExprOverflow)?;
+                }
+                b'/' => {
+                    self.pos += 1;
+                    let rhs = self.unary()?;
+                    if rhs == 0 {
+                        return Err(Error::DivideByZero);
+                    }
+                    acc = acc.checked_div(rhs).ok_or(Error::ExprOverflow)?;
+                }
+                b'%' => {
+                    self.pos += 1;
+                    let rhs = self.unary()?;
+                    if rhs == 0 {
+                        return Err(Error::DivideByZero);
+                    }
+                    acc = acc.checked_rem(rhs).ok_or(Error::ExprOverflow)?;
+                }
+                _ => break,
+            }
+        }
+
+        Ok(acc)
+    }
+
+    /// unary := '-' unary | primary
+    fn unary(&mut self) -> Result<i64> {
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+            self.descend()?;
+            let value = self.unary()?;
+            self.depth -= 1;
+            return value.checked_neg().ok_or(Error::ExprOverflow);
+        }
+
+        self.primary()
+    }
+
+    /// primary := number | '(' expr ')'
+    fn primary(&mut self) -> Result<i64> {
+        match self.peek() {
+            Some(b'(') => {
+                self.pos += 1;
+                self.descend()?;
+                let value = self.expr()?;
+                self.depth -= 1;
+                if self.peek() != Some(b')') {
+                    return Err(self.syntax_error("expected a closing parenthesis"));
+                }
+                self.pos += 1;
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() => self.number(),
+            Some(_) => Err(self.syntax_error("expected a number, '-' or '('")),
+            None => Err(self.syntax_error("unexpected end of expression")),
+        }
+    }
+
+    fn number(&mut self) -> Result<i64> {
+        let mut value: i64 = 0;
+        while let Some(&c) = self.input.get(self.pos) {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            value = value
+                .checked_mul(10)
+                .and_then(|v| v.checked_add(i64::from(c - b'0')))
+                .ok_or(Error::ExprOverflow)?;
+            self.pos += 1;
+        }
+
+        Ok(value)
+    }
+
+    fn descend(&mut self) -> Result<()> {
+        self.depth += 1;
+        if self.depth > MAX_EXPR_DEPTH {
+            return Err(Error::ExprTooDeep {
+                max: MAX_EXPR_DEPTH,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn precedence_puts_mul_before_add() {
+        assert_eq!(eval("2 + 3 * 4").unwrap(), 14);
+        assert_eq!(eval("2 * 3 + 4").unwrap(), 10);
+        assert_eq!(eval("2 * (3 + 4) - 10 / 5").unwrap(), 12);
+    }
+
+    #[test]
+    fn sub_and_div_are_left_associative() {
+        assert_eq!(eval("10 - 3 - 2").unwrap(), 5);
+        assert_eq!(eval("100 / 10 / 5").unwrap(), 2);
+        assert_eq!(eval("17 % 12 % 4").unwrap(), 1);
+    }
+
+    #[test]
+    fn unary_minus_binds_tighter_than_binary_operators() {
+        assert_eq!(eval("-2 * -3").unwrap(), 6);
+        assert_eq!(eval("2 - -3").unwrap(), 5);
+        assert_eq!(eval("--5").unwrap(), 5);
+        assert_eq!(eval("-(2 + 3)").unwrap(), -5);
+    }
+
+    #[test]
+    fn whitespace_is_insignificant() {
+        assert_eq!(eval("  1+2 *3  ").unwrap(), 7);
+    }
+
+    #[test]
+    fn syntax_errors_carry_the_byte_offset() {
+        match eval("2 + @").unwrap_err() {
+            Error::ExprSyntax { offset, .. } => assert_eq!(offset, 4),
+            err => panic!("expected a syntax error, got {err}"),
+        }
+
+        match eval("2 + 3 )").unwrap_err() {
+            Error::ExprSyntax { offset, .. } => assert_eq!(offset, 6),
+            err => panic!("expected a syntax error, got {err}"),
+        }
+
+        assert!(matches!(
+            eval("(1 + 2").unwrap_err(),
+            Error::ExprSyntax { .. }
+        ));
+        assert!(matches!(eval("").unwrap_err(), Error::ExprSyntax { .. }));
+    }
+
+    #[test]
+    fn division_and_modulo_by_zero_are_rejected() {
+        assert!(matches!(eval("1 / 0").unwrap_err(), Error::DivideByZero));
+        assert!(matches!(
+            eval("1 % (2 - 2)").unwrap_err(),
+            Error::DivideByZero
+        ));
+    }
+
+    #[test]
+    fn overflow_is_checked_everywhere() {
+        assert_eq!(eval("9223372036854775807").unwrap(), i64::MAX);
+        assert!(matches!(
+            eval("9223372036854775808").unwrap_err(),
+            Error::ExprOverflow
+        ));
+        assert!(matches!(
+            eval("9223372036854775807 + 1").unwrap_err(),
+            Error::ExprOverflow
+        ));
+        // i64::MIN / -1 overflows even though neither operand does.
+        assert!(matches!(
+            eval("(-9223372036854775807 - 1) / -1").unwrap_err(),
+            Error::ExprOverflow
+        ));
+    }
+
+    #[test]
+    fn hostile_input_is_bounded() {
+        let too_long = "1+".repeat(MAX_EXPR_LEN);
+        assert!(matches!(
+            eval(&too_long).unwrap_err(),
+            Error::ExprTooLong { .. }
+        ));
+
+        let too_deep = format!("{}1{}", "(".repeat(100), ")".repeat(100));
+        assert!(matches!(
+            eval(&too_deep).unwrap_err(),
+            Error::ExprTooDeep { .. }
+        ));
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use actix_web::{post, web, HttpResponse};
+use serde::Deserialize;
+
+use crate::error::{Error, HttpResult};
+
+/// How long after a 500 we still accept feedback quoting its event id.
+const ISSUED_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// Remembered ids; beyond this the oldest is forgotten early, which
+/// only matters during an error storm.
+const ISSUED_CAPACITY: usize = 1024;
+
+/// Cap on the comments field, so the endpoint cannot be used to pump
+/// arbitrary payloads into sentry.
+const MAX_COMMENT_BYTES: usize = 4096;
+
+/// The event ids recently handed out in 5xx responses. Feedback is only
+/// accepted for these, so the endpoint cannot be used to attach noise
+/// to arbitrary (or invented) events.
+pub struct IssuedEvents {
+    entries: Mutex<VecDeque<(Instant, String)>>,
+}
+
+impl IssuedEvents {
+    pub fn global() -> &'static IssuedEvents {
+        static ISSUED: OnceLock<IssuedEvents> = OnceLock::new();
+        ISSUED.get_or_init(|| IssuedEvents {
+            entries: Mutex::new(VecDeque::new()),
+        })
+    }
+
+    /// Records an id the error path just returned to a client.
+    pub fn note(&self, event_id: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        Self::prune(&mut entries);
+        entries.push_back((Instant::now(), event_id.to_string()));
+        if entries.len() > ISSUED_CAPACITY {
+            entries.pop_front();
+        }
+    }
+
+    /// Whether the id was issued by this process within the TTL.
+    pub fn was_issued(&self, event_id: &str) -> bool {
+        let mut entries = self.entries.lock().unwrap();
+        Self::prune(&mut entries);
+        entries.iter().any(|(_, id)| id == event_id)
+    }
+
+    fn prune(entries: &mut VecDeque<(Instant, String)>) {
+        while entries
+            .front()
+            .is_some_and(|(noted, _)| noted.elapsed() > ISSUED_TTL)
+        {
+            entries.pop_front();
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeedbackRequest {
+    /// The id quoted from a 5xx response body or X-Sentry-Event-Id.
+    pub event_id: String,
+    pub name: String,
+    pub email: String,
+    pub comments: String,
+}
+
+/// Accepts user feedback on an error the server recently reported, and
+/// forwards it to sentry as a feedback event (contexts.feedback with
+/// associated_event_id), which the ingest pipeline links to the
+/// original event.
+#[post("/feedback")]
+pub async fn submit_feedback(body: web::Json<FeedbackRequest>) -> HttpResult<HttpResponse> {
+    let body = body.into_inner();
+    if body.comments.len() > MAX_COMMENT_BYTES {
+        return Err(Error::InvalidRequestBody(format!(
+            "comments exceed the maximum of {MAX_COMMENT_BYTES} bytes"
+        ))
+        .into());
+    }
+    if !IssuedEvents::global().was_issued(&body.event_id) {
+        return Err(Error::UnknownEventId { id: body.event_id }.into());
+    }
+
+    let mut feedback = sentry::protocol::Map::new();
+    feedback.insert("associated_event_id".into(), body.event_id.clone().into());
+    feedback.insert("name".into(), body.name.into());
+    feedback.insert("contact_email".into(), body.email.into());
+    feedback.insert("message".into(), body.comments.into());
+
+    let mut contexts = sentry::protocol::Map::new();
+    contexts.insert(
+        "feedback".into(),
+        sentry::protocol::Context::Other(feedback),
+    );
+
+    sentry::capture_event(sentry::protocol::Event {
+        level: sentry::Level::Info,
+        message: Some(format!("user feedback for event {}", body.event_id)),
+        contexts,
+        ..Default::default()
+    });
+
+    Ok(HttpResponse::Accepted().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_recently_issued_ids_are_accepted() {
+        let issued = IssuedEvents {
+            entries: Mutex::new(VecDeque::new()),
+        };
+        assert!(!issued.was_issued("deadbeef"));
+        issued.note("deadbeef");
+        assert!(issued.was_issued("deadbeef"));
+        assert!(!issued.was_issued("cafebabe"));
+    }
+
+    #[test]
+    fn the_capacity_forgets_the_oldest_id() {
+        let issued = IssuedEvents {
+            entries: Mutex::new(VecDeque::new()),
+        };
+        for i in 0..=ISSUED_CAPACITY {
+            issued.note(&format!("{i:032x}"));
+        }
+        assert!(!issued.was_issued(&format!("{:032x}", 0)));
+        assert!(issued.was_issued(&format!("{ISSUED_CAPACITY:032x}")));
+    }
+}
@@ -0,0 +1,1554 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use actix_web::{get, http::header::ContentType, post, web, HttpResponse, Responder};
+#[cfg(feature = "sentry")]
+use sentry::SentryFutureExt;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::calculator::{CalcInt, Operation, OverflowMode, Width};
+use crate::error::{Error, HTTPError, HttpResult, Result};
+use crate::negotiation::Negotiated;
+
+/// Per-deployment operand policy (OPERAND_MIN/OPERAND_MAX): a 422 before
+/// the operation runs, so downstream consumers never see out-of-policy
+/// values. The policy applies at every width; unset bounds leave that
+/// side unbounded.
+fn validate_operand(field: &'static str, value: i128) -> Result<()> {
+    let config = crate::config::Config::global();
+    if config.operand_min.is_none() && config.operand_max.is_none() {
+        return Ok(());
+    }
+    let min = config.operand_min.map(i128::from).unwrap_or(i128::MIN);
+    let max = config.operand_max.map(i128::from).unwrap_or(i128::MAX);
+
+    if value < min || value > max {
+        return Err(Error::OperandOutOfRange {
+            field,
+            value,
+            min,
+            max,
+        });
+    }
+    Ok(())
+}
+
+/// Reject an operand that does not fit the selected width with the same
+/// 422 shape the operand policy produces.
+fn narrow<T: CalcInt>(field: &'static str, value: i64) -> Result<T> {
+    T::from_i128(value.into()).ok_or(Error::OperandOutOfRange {
+        field,
+        value: value.into(),
+        min: T::MIN.to_i128(),
+        max: T::MAX.to_i128(),
+    })
+}
+
+/// Thin async adapters over the pure calculator core, so behaviour is
+/// defined in exactly one place while handler code stays `await`-shaped.
+/// Every integer calculation — success or failure — lands in the history.
+/// Requests rejected by the operand policy never ran, so they are not
+/// recorded anywhere.
+pub async fn calculate(op: Operation, x: i32, y: i32) -> Result<i32> {
+    calculate_with(op, x, y, OverflowMode::default()).await
+}
+
+pub async fn calculate_with(op: Operation, x: i32, y: i32, mode: OverflowMode) -> Result<i32> {
+    validate_operand("x", x.into())?;
+    validate_operand("y", y.into())?;
+
+    // A visible record of who relies on non-default semantics.
+    if mode != OverflowMode::Error {
+        info!(
+            op = op.name(),
+            overflow_mode = mode.name(),
+            "using non-default overflow semantics"
+        );
+    }
+
+    let started = std::time::Instant::now();
+    let res = crate::calculator::calculate_with(op, x, y, mode);
+    crate::history::History::global().record(op.name(), x, y, &res);
+    crate::stats::Stats::global().record(op, &res, started.elapsed());
+
+    // Persistence is best-effort: a broken database must not fail the
+    // calculation itself. The conversion captures the 500 in sentry.
+    if let Err(err) = crate::db::Db::global()
+        .insert_calculation(op.name(), x, y, &res)
+        .await
+    {
+        warn!(%err, "failed to persist calculation");
+        let _ = HTTPError::from(err);
+    }
+
+    res
+}
+
+/// The i64/i128 twin of calculate_with. The history ring and the database
+/// both store i32 columns, so wide calculations are recorded in the stats
+/// only.
+pub async fn calculate_wide<T: CalcInt>(
+    op: Operation,
+    x: T,
+    y: T,
+    mode: OverflowMode,
+) -> Result<T> {
+    validate_operand("x", x.to_i128())?;
+    validate_operand("y", y.to_i128())?;
+
+    if mode != OverflowMode::Error {
+        info!(
+            op = op.name(),
+            overflow_mode = mode.name(),
+            "using non-default overflow semantics"
+        );
+    }
+
+    let started = std::time::Instant::now();
+    let res = crate::calculator::calculate_with(op, x, y, mode);
+    crate::stats::Stats::global().record(op, &res, started.elapsed());
+    res
+}
+
+/// Dispatch a calculation at the width the request selected, mapping the
+/// result into its wire form: i128 results always travel as strings, i64
+/// results as strings only when APP_I64_AS_STRING is set.
+pub(crate) async fn calculate_at_width(
+    op: Operation,
+    req: &CalculationRequest,
+) -> Result<CalcValue> {
+    let mode = req.overflow.unwrap_or_default();
+    match req.width.unwrap_or_default() {
+        Width::I32 => {
+            let x = narrow("x", req.x)?;
+            let y = narrow("y", req.y)?;
+            calculate_with(op, x, y, mode).await.map(CalcValue::from)
+        }
+        Width::I64 => calculate_wide(op, req.x, req.y, mode)
+            .await
+            .map(CalcValue::from_i64),
+        Width::I128 => calculate_wide(op, i128::from(req.x), i128::from(req.y), mode)
+            .await
+            .map(CalcValue::from_i128),
+    }
+}
+
+/// The CLI `calc` path: the same narrowing and calculator core as the
+/// HTTP handlers at the default width and overflow mode, minus the
+/// history, stats and database a served request records.
+pub fn calculate_local(op: Operation, x: i64, y: i64) -> Result<CalculationResponse> {
+    let x: i32 = narrow("x", x)?;
+    let y: i32 = narrow("y", y)?;
+    let res = crate::calculator::calculate(op, x, y)?;
+    Ok(CalculationResponse {
+        res: res.into(),
+        overflow: None,
+    })
+}
+
+/// calculate_at_width behind the LRU result cache: the verdict is Some
+/// HIT or MISS when the cache is enabled, None otherwise, and only
+/// successful results are stored. A hit skips the history, database and
+/// per-op stats — the cache counters on /stats record it instead.
+async fn calculate_at_width_cached(
+    op: Operation,
+    req: &CalculationRequest,
+) -> Result<(CalcValue, Option<&'static str>)> {
+    if !crate::config::Config::global().cache_enabled {
+        return Ok((calculate_at_width(op, req).await?, None));
+    }
+
+    let key = crate::cache::CacheKey {
+        op,
+        x: req.x,
+        y: req.y,
+        mode: req.overflow.unwrap_or_default(),
+        width: req.width.unwrap_or_default(),
+    };
+    let cache = crate::cache::ResultCache::global();
+    if let Some(value) = cache.get(&key) {
+        return Ok((value, Some("HIT")));
+    }
+    let value = calculate_at_width(op, req).await?;
+    cache.put(key, value.clone());
+    Ok((value, Some("MISS")))
+}
+
+/// Wraps the negotiated response, attaching the X-Cache verdict when
+/// there is one.
+fn with_cache_header(
+    req: &CalculationRequest,
+    res: CalcValue,
+    verdict: Option<&'static str>,
+) -> actix_web::CustomizeResponder<Negotiated<CalculationResponse>> {
+    let response = Negotiated(CalculationResponse {
+        res,
+        overflow: req.overflow.map(|mode| mode.name().into()),
+    })
+    .customize();
+    match verdict {
+        Some(verdict) => response.insert_header((crate::cache::CACHE_HEADER, verdict)),
+        None => response,
+    }
+}
+
+/// gcd, lcm and the bitwise family live outside the Operation enum (and
+/// therefore the stats map), but their calculations still land in the
+/// history and database like any other.
+async fn integer_utility(
+    name: &'static str,
+    op: fn(i32, i32) -> Result<i32>,
+    req: &CalculationRequest,
+) -> Result<i32> {
+    let x: i32 = narrow("x", req.x)?;
+    let y: i32 = narrow("y", req.y)?;
+    validate_operand("x", x.into())?;
+    validate_operand("y", y.into())?;
+
+    let res = op(x, y);
+    crate::history::History::global().record(name, x, y, &res);
+
+    if let Err(err) = crate::db::Db::global()
+        .insert_calculation(name, x, y, &res)
+        .await
+    {
+        warn!(%err, "failed to persist calculation");
+        let _ = HTTPError::from(err);
+    }
+
+    res
+}
+
+pub async fn calculate_float(op: Operation, x: f64, y: f64) -> Result<f64> {
+    crate::calculator::calculate_float(op, x, y)
+}
+
+#[derive(Debug, Serialize, ToSchema, utoipa::IntoParams)]
+pub struct CalculationRequest {
+    /// Operands are carried as i64 — the widest integer JSON numbers can
+    /// round-trip — and narrowed or widened to the selected width.
+    pub(crate) x: i64,
+    pub(crate) y: i64,
+    /// Overflow semantics for this request: "error" (the default),
+    /// "wrap" or "saturate".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) overflow: Option<OverflowMode>,
+    /// The arithmetic width: "i32" (the default), "i64" or "i128".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) width: Option<Width>,
+}
+
+/// Hand-written so that deny_unknown_fields semantics can be a runtime
+/// flag (APP_STRICT_FIELDS) rather than a compile-time attribute. In the
+/// default lenient mode unknown fields are ignored, exactly as the
+/// derived impl would; in strict mode they fail deserialization, which
+/// the extractors render as a structured 400 naming the field.
+impl<'de> Deserialize<'de> for CalculationRequest {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de;
+
+        const FIELDS: &[&str] = &["x", "y", "overflow", "width"];
+
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = CalculationRequest;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("a struct with integer fields x and y")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: de::MapAccess<'de>,
+            {
+                let strict = crate::config::Config::global().strict_fields;
+                let mut x = None;
+                let mut y = None;
+                let mut overflow = None;
+                let mut width = None;
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "x" => {
+                            if x.is_some() {
+                                return Err(de::Error::duplicate_field("x"));
+                            }
+                            x = Some(map.next_value()?);
+                        }
+                        "y" => {
+                            if y.is_some() {
+                                return Err(de::Error::duplicate_field("y"));
+                            }
+                            y = Some(map.next_value()?);
+                        }
+                        "overflow" => {
+                            if overflow.is_some() {
+                                return Err(de::Error::duplicate_field("overflow"));
+                            }
+                            overflow = Some(map.next_value()?);
+                        }
+                        "width" => {
+                            if width.is_some() {
+                                return Err(de::Error::duplicate_field("width"));
+                            }
+                            width = Some(map.next_value()?);
+                        }
+                        other => {
+                            if strict {
+                                return Err(de::Error::unknown_field(other, FIELDS));
+                            }
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                Ok(CalculationRequest {
+                    x: x.ok_or_else(|| de::Error::missing_field("x"))?,
+                    y: y.ok_or_else(|| de::Error::missing_field("y"))?,
+                    overflow,
+                    width,
+                })
+            }
+
+            // Positional encodings (e.g. msgpack tuples) have no field
+            // names to be strict about.
+            fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                let x = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let y = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let overflow = seq.next_element()?;
+                let width = seq.next_element()?;
+                Ok(CalculationRequest {
+                    x,
+                    y,
+                    overflow,
+                    width,
+                })
+            }
+        }
+
+        deserializer.deserialize_struct("CalculationRequest", FIELDS, Visitor)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CalcRequest {
+    pub(crate) op: String,
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+}
+
+/// A calculation result at any width. i32 and (by default) i64 results
+/// stay JSON numbers; i128 results — and i64 results when the
+/// APP_I64_AS_STRING flag is set — travel as strings, because JSON
+/// parsers that round numbers through f64 lose precision past 2^53.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum CalcValue {
+    Number(i64),
+    Text(String),
+}
+
+impl From<i32> for CalcValue {
+    fn from(value: i32) -> Self {
+        CalcValue::Number(value.into())
+    }
+}
+
+impl CalcValue {
+    fn from_i64(value: i64) -> Self {
+        if crate::config::Config::global().i64_as_string {
+            CalcValue::Text(value.to_string())
+        } else {
+            CalcValue::Number(value)
+        }
+    }
+
+    fn from_i128(value: i128) -> Self {
+        CalcValue::Text(value.to_string())
+    }
+
+    /// Combinatoric results: numbers while they fit i64, strings beyond.
+    fn from_u128(value: u128) -> Self {
+        match i64::try_from(value) {
+            Ok(value) => CalcValue::from_i64(value),
+            Err(_) => CalcValue::Text(value.to_string()),
+        }
+    }
+}
+
+/// Deserialize too, so the client module can decode what the handlers
+/// encode; Cow keeps the server side allocation-free.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CalculationResponse {
+    pub res: CalcValue,
+    /// Echoes the overflow mode when the request selected one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overflow: Option<std::borrow::Cow<'static, str>>,
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = CalcRequest,
+    responses(
+        (status = 200, description = "The computed result", body = CalculationResponse),
+        (status = 400, description = "Unknown operation or division by zero", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/calc")]
+pub async fn handle_calc(
+    body: Negotiated<CalcRequest>,
+) -> HttpResult<Negotiated<CalculationResponse>> {
+    info!(method = "handle_calc", ?body, "dispatching a calculation");
+
+    let op = body.op.parse::<Operation>()?;
+    let res = calculate(op, body.x, body.y).await?;
+    Ok(Negotiated(CalculationResponse {
+        res: res.into(),
+        overflow: None,
+    }))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "The computed result", body = CalculationResponse),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/add")]
+pub async fn handle_add(
+    body: Negotiated<CalculationRequest>,
+) -> HttpResult<impl actix_web::Responder> {
+    info!(method = "handle_add", ?body, "adding two numbers together");
+
+    let (res, cached) = calculate_at_width_cached(Operation::Add, &body).await?;
+    Ok(with_cache_header(&body, res, cached))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "The computed result", body = CalculationResponse),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/sub")]
+pub async fn handle_sub(
+    body: Negotiated<CalculationRequest>,
+) -> HttpResult<impl actix_web::Responder> {
+    info!(
+        method = "handle_sub",
+        ?body,
+        "subtracting a number from another"
+    );
+
+    let (res, cached) = calculate_at_width_cached(Operation::Sub, &body).await?;
+    Ok(with_cache_header(&body, res, cached))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "The computed result", body = CalculationResponse),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/mul")]
+pub async fn handle_mul(
+    body: Negotiated<CalculationRequest>,
+) -> HttpResult<impl actix_web::Responder> {
+    info!(method = "handle_mul", ?body, "multiplying two numbers");
+
+    let (res, cached) = calculate_at_width_cached(Operation::Mul, &body).await?;
+    Ok(with_cache_header(&body, res, cached))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "The computed result", body = CalculationResponse),
+        (status = 400, description = "y is zero", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/div")]
+pub async fn handle_div(
+    body: Negotiated<CalculationRequest>,
+) -> HttpResult<impl actix_web::Responder> {
+    info!(method = "handle_div", ?body, "Dividing a number by another");
+
+    let (res, cached) = calculate_at_width_cached(Operation::Div, &body).await?;
+    Ok(with_cache_header(&body, res, cached))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "The computed result", body = CalculationResponse),
+        (status = 400, description = "y is zero", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/mod")]
+pub async fn handle_mod(
+    body: Negotiated<CalculationRequest>,
+) -> HttpResult<impl actix_web::Responder> {
+    info!(
+        method = "handle_mod",
+        ?body,
+        "taking the remainder of a division"
+    );
+
+    let (res, cached) = calculate_at_width_cached(Operation::Mod, &body).await?;
+    Ok(with_cache_header(&body, res, cached))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "The computed result", body = CalculationResponse),
+        (status = 400, description = "The exponent is negative", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/pow")]
+pub async fn handle_pow(
+    body: Negotiated<CalculationRequest>,
+) -> HttpResult<impl actix_web::Responder> {
+    info!(
+        method = "handle_pow",
+        ?body,
+        "raising a number to an exponent"
+    );
+
+    let (res, cached) = calculate_at_width_cached(Operation::Pow, &body).await?;
+    Ok(with_cache_header(&body, res, cached))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "The greatest common divisor of |x| and |y|", body = CalculationResponse),
+        (status = 422, description = "An operand is i32::MIN", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/gcd")]
+pub async fn handle_gcd(
+    body: Negotiated<CalculationRequest>,
+) -> HttpResult<Negotiated<CalculationResponse>> {
+    info!(
+        method = "handle_gcd",
+        ?body,
+        "taking the gcd of two numbers"
+    );
+
+    let res = integer_utility("gcd", crate::calculator::gcd, &body).await?;
+    Ok(Negotiated(CalculationResponse {
+        res: res.into(),
+        overflow: None,
+    }))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "The least common multiple of |x| and |y|", body = CalculationResponse),
+        (status = 422, description = "An operand is i32::MIN or the lcm overflowed i32", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/lcm")]
+pub async fn handle_lcm(
+    body: Negotiated<CalculationRequest>,
+) -> HttpResult<Negotiated<CalculationResponse>> {
+    info!(
+        method = "handle_lcm",
+        ?body,
+        "taking the lcm of two numbers"
+    );
+
+    let res = integer_utility("lcm", crate::calculator::lcm, &body).await?;
+    Ok(Negotiated(CalculationResponse {
+        res: res.into(),
+        overflow: None,
+    }))
+}
+
+/// How long caches may hold a successful GET calculation; the operations
+/// are pure, so the only staleness risk is a redeploy changing semantics.
+const CACHE_MAX_AGE_SECS: u32 = 60;
+
+/// The GET twins share the POST code path; only the extractor (query
+/// string instead of body) and the Cache-Control header differ.
+async fn calculate_cacheable(op: Operation, query: CalculationRequest) -> HttpResult<HttpResponse> {
+    let (res, cached) = calculate_at_width_cached(op, &query).await?;
+
+    let mut builder = HttpResponse::Ok();
+    builder.insert_header((
+        actix_web::http::header::CACHE_CONTROL,
+        format!("public, max-age={CACHE_MAX_AGE_SECS}"),
+    ));
+    if let Some(verdict) = cached {
+        builder.insert_header((crate::cache::CACHE_HEADER, verdict));
+    }
+    Ok(builder.json(CalculationResponse {
+        res,
+        overflow: query.overflow.map(|mode| mode.name().into()),
+    }))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    params(CalculationRequest),
+    responses(
+        (status = 200, description = "The computed result", body = CalculationResponse),
+        (status = 400, description = "Missing or non-integer query parameters", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[get("/add")]
+pub async fn handle_add_query(query: web::Query<CalculationRequest>) -> HttpResult<HttpResponse> {
+    calculate_cacheable(Operation::Add, query.into_inner()).await
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    params(CalculationRequest),
+    responses(
+        (status = 200, description = "The computed result", body = CalculationResponse),
+        (status = 400, description = "Missing or non-integer query parameters", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[get("/sub")]
+pub async fn handle_sub_query(query: web::Query<CalculationRequest>) -> HttpResult<HttpResponse> {
+    calculate_cacheable(Operation::Sub, query.into_inner()).await
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    params(CalculationRequest),
+    responses(
+        (status = 200, description = "The computed result", body = CalculationResponse),
+        (status = 400, description = "Missing or non-integer query parameters", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[get("/mul")]
+pub async fn handle_mul_query(query: web::Query<CalculationRequest>) -> HttpResult<HttpResponse> {
+    calculate_cacheable(Operation::Mul, query.into_inner()).await
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    params(CalculationRequest),
+    responses(
+        (status = 200, description = "The computed result", body = CalculationResponse),
+        (status = 400, description = "Bad query parameters or y is zero", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[get("/div")]
+pub async fn handle_div_query(query: web::Query<CalculationRequest>) -> HttpResult<HttpResponse> {
+    calculate_cacheable(Operation::Div, query.into_inner()).await
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    params(CalculationRequest),
+    responses(
+        (status = 200, description = "The computed result", body = CalculationResponse),
+        (status = 400, description = "Bad query parameters or y is zero", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[get("/mod")]
+pub async fn handle_mod_query(query: web::Query<CalculationRequest>) -> HttpResult<HttpResponse> {
+    calculate_cacheable(Operation::Mod, query.into_inner()).await
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    params(CalculationRequest),
+    responses(
+        (status = 200, description = "The computed result", body = CalculationResponse),
+        (status = 400, description = "Bad query parameters or a negative exponent", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[get("/pow")]
+pub async fn handle_pow_query(query: web::Query<CalculationRequest>) -> HttpResult<HttpResponse> {
+    calculate_cacheable(Operation::Pow, query.into_inner()).await
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BitwiseResponse {
+    res: i32,
+    /// The same 32 bits as an unsigned hex string, e.g. "0xfffffff8".
+    hex: String,
+}
+
+impl From<i32> for BitwiseResponse {
+    fn from(res: i32) -> Self {
+        BitwiseResponse {
+            res,
+            hex: format!("{:#010x}", res as u32),
+        }
+    }
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "x & y, in decimal and hex", body = BitwiseResponse),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/and")]
+pub async fn handle_and(
+    body: Negotiated<CalculationRequest>,
+) -> HttpResult<Negotiated<BitwiseResponse>> {
+    info!(method = "handle_and", ?body, "taking a bitwise and");
+
+    let res = integer_utility("and", crate::calculator::bitwise::and, &body).await?;
+    Ok(Negotiated(res.into()))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "x | y, in decimal and hex", body = BitwiseResponse),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/or")]
+pub async fn handle_or(
+    body: Negotiated<CalculationRequest>,
+) -> HttpResult<Negotiated<BitwiseResponse>> {
+    info!(method = "handle_or", ?body, "taking a bitwise or");
+
+    let res = integer_utility("or", crate::calculator::bitwise::or, &body).await?;
+    Ok(Negotiated(res.into()))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "x ^ y, in decimal and hex", body = BitwiseResponse),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/xor")]
+pub async fn handle_xor(
+    body: Negotiated<CalculationRequest>,
+) -> HttpResult<Negotiated<BitwiseResponse>> {
+    info!(method = "handle_xor", ?body, "taking a bitwise xor");
+
+    let res = integer_utility("xor", crate::calculator::bitwise::xor, &body).await?;
+    Ok(Negotiated(res.into()))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "x << y, in decimal and hex", body = BitwiseResponse),
+        (status = 400, description = "The shift amount is outside 0..=31", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/shl")]
+pub async fn handle_shl(
+    body: Negotiated<CalculationRequest>,
+) -> HttpResult<Negotiated<BitwiseResponse>> {
+    info!(method = "handle_shl", ?body, "shifting left");
+
+    let res = integer_utility("shl", crate::calculator::bitwise::shl, &body).await?;
+    Ok(Negotiated(res.into()))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "x >> y (arithmetic), in decimal and hex", body = BitwiseResponse),
+        (status = 400, description = "The shift amount is outside 0..=31", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/shr")]
+pub async fn handle_shr(
+    body: Negotiated<CalculationRequest>,
+) -> HttpResult<Negotiated<BitwiseResponse>> {
+    info!(method = "handle_shr", ?body, "shifting right");
+
+    let res = integer_utility("shr", crate::calculator::bitwise::shr, &body).await?;
+    Ok(Negotiated(res.into()))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ConvertRequest {
+    /// The number to convert, as a string in the source base.
+    pub(crate) value: String,
+    pub(crate) from: u32,
+    pub(crate) to: u32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConvertResponse {
+    /// The value rendered in the target base.
+    result: String,
+    /// The decimal i128 value, as a string to survive JSON parsers.
+    decimal: String,
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = ConvertRequest,
+    responses(
+        (status = 200, description = "The value in the target base plus its decimal form", body = ConvertResponse),
+        (status = 400, description = "An unsupported base or an invalid digit", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The value does not fit i128", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/convert")]
+pub async fn handle_convert(
+    body: Negotiated<ConvertRequest>,
+) -> HttpResult<Negotiated<ConvertResponse>> {
+    info!(method = "handle_convert", ?body, "converting between bases");
+
+    let (result, decimal) = crate::calculator::convert_base(&body.value, body.from, body.to)?;
+    Ok(Negotiated(ConvertResponse {
+        result,
+        decimal: decimal.to_string(),
+    }))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UnaryRequest {
+    pub(crate) x: i64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ChooseRequest {
+    pub(crate) n: i64,
+    pub(crate) k: i64,
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = UnaryRequest,
+    responses(
+        (status = 200, description = "x!; results past i64 travel as strings", body = CalculationResponse),
+        (status = 400, description = "x is negative", body = crate::openapi::ErrorBody),
+        (status = 422, description = "x! overflows u128", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/factorial")]
+pub async fn handle_factorial(
+    body: Negotiated<UnaryRequest>,
+) -> HttpResult<Negotiated<CalculationResponse>> {
+    info!(method = "handle_factorial", ?body, "taking a factorial");
+
+    let res = factorial_flights()
+        .run("factorial", &body.x.to_string(), || async {
+            crate::calculator::factorial(body.x).map(CalcValue::from_u128)
+        })
+        .await?;
+    Ok(Negotiated(CalculationResponse {
+        res,
+        overflow: None,
+    }))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = ChooseRequest,
+    responses(
+        (status = 200, description = "n choose k; results past i64 travel as strings", body = CalculationResponse),
+        (status = 400, description = "n or k is negative", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The binomial overflows u128", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/choose")]
+pub async fn handle_choose(
+    body: Negotiated<ChooseRequest>,
+) -> HttpResult<Negotiated<CalculationResponse>> {
+    info!(method = "handle_choose", ?body, "taking a binomial");
+
+    let res = crate::calculator::choose(body.n, body.k)?;
+    Ok(Negotiated(CalculationResponse {
+        res: CalcValue::from_u128(res),
+        overflow: None,
+    }))
+}
+
+/// The maximum number of values accepted by /aggregate, overridable with
+/// the MAX_AGGREGATE_VALUES env var.
+fn max_aggregate_values() -> usize {
+    static MAX_AGGREGATE_VALUES: OnceLock<usize> = OnceLock::new();
+    *MAX_AGGREGATE_VALUES.get_or_init(|| {
+        std::env::var("MAX_AGGREGATE_VALUES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10_000)
+    })
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AggregateRequest {
+    pub(crate) op: crate::calculator::AggregateOp,
+    pub(crate) values: Vec<i32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AggregateResponse {
+    res: i64,
+    /// Mean only: `sum - res * count`, so exact values can be recovered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remainder: Option<i64>,
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = AggregateRequest,
+    responses(
+        (status = 200, description = "The reduction; mean rounds half to even and reports the remainder", body = AggregateResponse),
+        (status = 413, description = "The array exceeds MAX_AGGREGATE_VALUES items", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The array is empty or the accumulation overflowed i64", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument(skip(body))]
+#[post("/aggregate")]
+pub async fn handle_aggregate(
+    body: Negotiated<AggregateRequest>,
+) -> HttpResult<Negotiated<AggregateResponse>> {
+    info!(
+        method = "handle_aggregate",
+        op = body.op.name(),
+        values = body.values.len(),
+        "reducing an array of values"
+    );
+
+    // Oversized bodies never reach serde: the Negotiated extractor
+    // buffers through web::Bytes, which enforces the payload cap first.
+    // This length check bounds the work, not the allocation.
+    let max = max_aggregate_values();
+    if body.values.len() > max {
+        return Err(Error::BatchTooLarge {
+            size: body.values.len(),
+            max,
+        }
+        .into());
+    }
+
+    let aggregated = crate::calculator::aggregate(body.op, &body.values)?;
+    Ok(Negotiated(AggregateResponse {
+        res: aggregated.res,
+        remainder: aggregated.remainder,
+    }))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct EvalRequest {
+    /// An arithmetic expression, e.g. "2 * (3 + 4) - 10 / 5".
+    expr: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EvalResponse {
+    res: i64,
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = EvalRequest,
+    responses(
+        (status = 200, description = "The evaluated result", body = EvalResponse),
+        (status = 400, description = "Syntax error, excessive nesting or division by zero", body = crate::openapi::ErrorBody),
+        (status = 413, description = "The expression exceeds the maximum length", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The arithmetic overflowed i64", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/eval")]
+pub async fn handle_eval(body: Negotiated<EvalRequest>) -> HttpResult<Negotiated<EvalResponse>> {
+    info!(method = "handle_eval", ?body, "evaluating an expression");
+
+    let res = eval_flights()
+        .run("eval", &body.expr, || async {
+            crate::expr::eval(&body.expr)
+        })
+        .await?;
+    Ok(Negotiated(EvalResponse { res }))
+}
+
+/// Identical concurrent evals coalesce onto one computation; the
+/// expression itself is the key. Worthwhile only for eval and factorial,
+/// whose cost grows with the input — the arithmetic endpoints finish
+/// faster than the bookkeeping would take.
+fn eval_flights() -> &'static crate::single_flight::SingleFlight<i64> {
+    static FLIGHTS: OnceLock<crate::single_flight::SingleFlight<i64>> = OnceLock::new();
+    FLIGHTS.get_or_init(crate::single_flight::SingleFlight::new)
+}
+
+fn factorial_flights() -> &'static crate::single_flight::SingleFlight<CalcValue> {
+    static FLIGHTS: OnceLock<crate::single_flight::SingleFlight<CalcValue>> = OnceLock::new();
+    FLIGHTS.get_or_init(crate::single_flight::SingleFlight::new)
+}
+
+/// The maximum number of items accepted by /batch, overridable with the
+/// MAX_BATCH_SIZE env var.
+fn max_batch_size() -> usize {
+    static MAX_BATCH_SIZE: OnceLock<usize> = OnceLock::new();
+    *MAX_BATCH_SIZE.get_or_init(|| {
+        std::env::var("MAX_BATCH_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(100)
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BatchItemError {
+    pub code: std::borrow::Cow<'static, str>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum BatchItemResponse {
+    Ok { res: i32 },
+    Err { error: BatchItemError },
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = Vec<CalcRequest>,
+    responses(
+        (status = 200, description = "Per-item results in request order", body = Vec<BatchItemResponse>),
+        (status = 413, description = "The batch exceeds MAX_BATCH_SIZE items", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument(skip(body))]
+#[post("/batch")]
+pub async fn handle_batch(
+    body: Negotiated<Vec<CalcRequest>>,
+) -> HttpResult<Negotiated<Vec<BatchItemResponse>>> {
+    info!(
+        method = "handle_batch",
+        items = body.len(),
+        "running a batch of calculations"
+    );
+
+    let max = max_batch_size();
+    if body.len() > max {
+        return Err(Error::BatchTooLarge {
+            size: body.len(),
+            max,
+        }
+        .into());
+    }
+
+    let mut results = Vec::with_capacity(body.len());
+    let mut failure_reasons: BTreeMap<&'static str, u64> = BTreeMap::new();
+
+    for item in body.iter() {
+        let res = match item.op.parse::<Operation>() {
+            Ok(op) => calculate(op, item.x, item.y).await,
+            Err(err) => Err(err),
+        };
+
+        match res {
+            Ok(res) => results.push(BatchItemResponse::Ok { res }),
+            Err(err) => {
+                crate::metrics::Metrics::global()
+                    .calculation_errors_total
+                    .with_label_values(&[err.code()])
+                    .inc();
+                *failure_reasons.entry(err.code()).or_default() += 1;
+                results.push(BatchItemResponse::Err {
+                    error: BatchItemError {
+                        code: err.code().into(),
+                        message: err.to_string(),
+                    },
+                });
+            }
+        }
+    }
+
+    // One aggregated event per batch rather than one per failing item.
+    if !failure_reasons.is_empty() {
+        let failed: u64 = failure_reasons.values().sum();
+        let total = body.len();
+        crate::reporter::global().report_message(
+            &format!("batch calculation: {failed}/{total} items failed"),
+            crate::reporter::Level::Warning,
+            &[],
+            &[
+                ("batch_size", total.into()),
+                ("failed_items", failed.into()),
+                (
+                    "failure_reasons",
+                    serde_json::to_value(&failure_reasons).unwrap_or_default(),
+                ),
+            ],
+        );
+    }
+
+    Ok(Negotiated(results))
+}
+
+/// Seconds between SSE heartbeat comments, so idle proxies don't time
+/// the stream out. Overridable with SSE_HEARTBEAT_SECS.
+fn sse_heartbeat() -> std::time::Duration {
+    static HEARTBEAT_SECS: OnceLock<u64> = OnceLock::new();
+    std::time::Duration::from_secs(*HEARTBEAT_SECS.get_or_init(|| {
+        std::env::var("SSE_HEARTBEAT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10)
+    }))
+}
+
+fn sse_event(event: &str, data: &serde_json::Value) -> String {
+    format!("event: {event}\ndata: {data}\n\n")
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = Vec<CalcRequest>,
+    responses(
+        (status = 200, description = "text/event-stream: one `item` event per calculation, then a terminal `done` event with summary counts"),
+        (status = 413, description = "The batch exceeds MAX_BATCH_SIZE items", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument(skip(body))]
+#[post("/batch/stream")]
+pub async fn handle_batch_stream(body: Negotiated<Vec<CalcRequest>>) -> HttpResult<HttpResponse> {
+    let items = body.0;
+    info!(
+        method = "handle_batch_stream",
+        items = items.len(),
+        "streaming a batch of calculations"
+    );
+
+    let max = max_batch_size();
+    if items.len() > max {
+        return Err(Error::BatchTooLarge {
+            size: items.len(),
+            max,
+        }
+        .into());
+    }
+
+    // Capacity 1 keeps computation paced by the client: the worker only
+    // gets ahead by one item, and a disconnect surfaces as a send error
+    // that cancels the remaining work.
+    let (tx, rx) = tokio::sync::mpsc::channel::<String>(1);
+
+    // Bound to the request hub so captures keep the request_id tag even
+    // though the work outlives the handler.
+    let worker = {
+        async move {
+            let total = items.len();
+            let mut succeeded = 0_u64;
+            let mut failed = 0_u64;
+
+            for (index, item) in items.into_iter().enumerate() {
+                let res = match item.op.parse::<Operation>() {
+                    Ok(op) => calculate(op, item.x, item.y).await,
+                    Err(err) => Err(err),
+                };
+
+                let data = match res {
+                    Ok(res) => {
+                        succeeded += 1;
+                        serde_json::json!({ "index": index, "res": res })
+                    }
+                    Err(err) => {
+                        failed += 1;
+                        serde_json::json!({
+                            "index": index,
+                            "error": {
+                                "code": err.code(),
+                                "message": err.to_string(),
+                                "status": err.status_code().as_u16(),
+                            }
+                        })
+                    }
+                };
+
+                if tx.send(sse_event("item", &data)).await.is_err() {
+                    // The client went away; drop the rest of the batch.
+                    return;
+                }
+            }
+
+            let _ = tx
+                .send(sse_event(
+                    "done",
+                    &serde_json::json!({
+                        "total": total,
+                        "succeeded": succeeded,
+                        "failed": failed,
+                    }),
+                ))
+                .await;
+        }
+    };
+    #[cfg(feature = "sentry")]
+    let worker = worker.bind_hub(sentry::Hub::current());
+    actix_web::rt::spawn(worker);
+
+    let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+        match tokio::time::timeout(sse_heartbeat(), rx.recv()).await {
+            Ok(Some(event)) => Some((Ok::<_, actix_web::Error>(web::Bytes::from(event)), rx)),
+            Ok(None) => None,
+            // Comment lines are ignored by SSE clients but reset proxy
+            // idle timers.
+            Err(_) => Some((Ok(web::Bytes::from_static(b": keep-alive\n\n")), rx)),
+        }
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((actix_web::http::header::CACHE_CONTROL, "no-cache"))
+        .streaming(stream))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct FloatCalculationRequest {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FloatCalculationResponse {
+    res: f64,
+}
+
+#[utoipa::path(
+    context_path = "/api/v0/float",
+    request_body = FloatCalculationRequest,
+    responses(
+        (status = 200, description = "The computed result", body = FloatCalculationResponse),
+        (status = 400, description = "An operand is NaN or infinite", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The result is NaN or infinite", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/add")]
+pub async fn handle_float_add(
+    body: Negotiated<FloatCalculationRequest>,
+) -> HttpResult<Negotiated<FloatCalculationResponse>> {
+    info!(method = "handle_float_add", ?body, "adding two floats");
+
+    let res = calculate_float(Operation::Add, body.x, body.y).await?;
+    Ok(Negotiated(FloatCalculationResponse { res }))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0/float",
+    request_body = FloatCalculationRequest,
+    responses(
+        (status = 200, description = "The computed result", body = FloatCalculationResponse),
+        (status = 400, description = "An operand is NaN or infinite", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The result is NaN or infinite", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/sub")]
+pub async fn handle_float_sub(
+    body: Negotiated<FloatCalculationRequest>,
+) -> HttpResult<Negotiated<FloatCalculationResponse>> {
+    info!(method = "handle_float_sub", ?body, "subtracting two floats");
+
+    let res = calculate_float(Operation::Sub, body.x, body.y).await?;
+    Ok(Negotiated(FloatCalculationResponse { res }))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0/float",
+    request_body = FloatCalculationRequest,
+    responses(
+        (status = 200, description = "The computed result", body = FloatCalculationResponse),
+        (status = 400, description = "An operand is NaN or infinite", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The result is NaN or infinite", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/mul")]
+pub async fn handle_float_mul(
+    body: Negotiated<FloatCalculationRequest>,
+) -> HttpResult<Negotiated<FloatCalculationResponse>> {
+    info!(method = "handle_float_mul", ?body, "multiplying two floats");
+
+    let res = calculate_float(Operation::Mul, body.x, body.y).await?;
+    Ok(Negotiated(FloatCalculationResponse { res }))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0/float",
+    request_body = FloatCalculationRequest,
+    responses(
+        (status = 200, description = "The computed result", body = FloatCalculationResponse),
+        (status = 400, description = "An operand is non-finite or y is zero", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The result is NaN or infinite", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/div")]
+pub async fn handle_float_div(
+    body: Negotiated<FloatCalculationRequest>,
+) -> HttpResult<Negotiated<FloatCalculationResponse>> {
+    info!(method = "handle_float_div", ?body, "dividing two floats");
+
+    let res = calculate_float(Operation::Div, body.x, body.y).await?;
+    Ok(Negotiated(FloatCalculationResponse { res }))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DecimalRequest {
+    pub(crate) op: Operation,
+    /// Decimal strings, e.g. "10.25" — never binary floats.
+    pub(crate) x: String,
+    pub(crate) y: String,
+    /// Decimal places to round the result to; unset keeps the natural
+    /// scale of the operation.
+    pub(crate) scale: Option<u32>,
+    /// Rounding strategy when a scale is set; defaults to half_even.
+    pub(crate) rounding: Option<crate::calculator::DecimalRounding>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DecimalResponse {
+    /// The exact result, as a string.
+    res: String,
+}
+
+fn parse_decimal(field: &'static str, value: &str) -> Result<rust_decimal::Decimal> {
+    value
+        .parse()
+        .map_err(|err| Error::InvalidRequestBody(format!("{field} is not a valid decimal: {err}")))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0/decimal",
+    request_body = DecimalRequest,
+    responses(
+        (status = 200, description = "The exact decimal result", body = DecimalResponse),
+        (status = 400, description = "A malformed decimal, unknown operation or division by zero", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The operation overflowed Decimal's 96-bit mantissa", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator"
+)]
+#[tracing::instrument]
+#[post("/calc")]
+pub async fn handle_decimal_calc(
+    body: Negotiated<DecimalRequest>,
+) -> HttpResult<Negotiated<DecimalResponse>> {
+    info!(
+        method = "handle_decimal_calc",
+        ?body,
+        "dispatching a decimal calculation"
+    );
+
+    let x = parse_decimal("x", &body.x)?;
+    let y = parse_decimal("y", &body.y)?;
+    let res = crate::calculator::calculate_decimal(
+        body.op,
+        x,
+        y,
+        body.scale,
+        body.rounding.unwrap_or_default(),
+    )?;
+    Ok(Negotiated(DecimalResponse {
+        res: res.to_string(),
+    }))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StatusResponse {
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<&'static str>,
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    responses(
+        (status = 200, description = "The service is up", body = StatusResponse),
+        (status = 503, description = "Not ready; the body lists the failing checks", body = crate::health::ReadinessResponse),
+    ),
+    tag = "meta"
+)]
+#[get("/status")]
+pub async fn status(readiness: web::Data<crate::health::Readiness>) -> impl Responder {
+    // Kept as a readiness alias for clients that predate /readyz.
+    if !readiness.failed_checks().is_empty() {
+        return crate::health::readiness_response(&readiness);
+    }
+
+    HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(StatusResponse {
+            status: "OK".to_string(),
+            version: Some(crate::version::CARGO_VERSION),
+        })
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VersionResponse {
+    version: &'static str,
+    git_sha: &'static str,
+    build_timestamp: &'static str,
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    responses(
+        (status = 200, description = "Build metadata for this deployment", body = VersionResponse),
+    ),
+    tag = "meta"
+)]
+#[get("/version")]
+pub async fn version() -> impl Responder {
+    web::Json(VersionResponse {
+        version: crate::version::CARGO_VERSION,
+        git_sha: crate::version::GIT_SHA,
+        build_timestamp: crate::version::BUILD_TIMESTAMP,
+    })
+}
+
+/// Debug builds only (which is what integration tests compile): lets the
+/// panic-capture path be exercised end to end without faking an unwind.
+#[cfg(debug_assertions)]
+#[get("/debug/panic")]
+pub async fn debug_panic() -> HttpResponse {
+    panic!("deliberate panic from /debug/panic");
+}
+
+/// Debug builds only: a handler that sleeps for the requested number of
+/// milliseconds, as a target for the timeout tests.
+#[cfg(debug_assertions)]
+#[get("/debug/sleep/{ms}")]
+pub async fn debug_sleep(ms: web::Path<u64>) -> HttpResponse {
+    tokio::time::sleep(std::time::Duration::from_millis(ms.into_inner())).await;
+    HttpResponse::Ok().finish()
+}
+
+pub fn json_error_handler(
+    err: actix_web::error::JsonPayloadError,
+    _req: &actix_web::HttpRequest,
+) -> actix_web::Error {
+    HTTPError::from(Error::InvalidRequestBody(err.to_string())).into()
+}
+
+/// Missing or non-integer query parameters get the same structured 400
+/// as a malformed JSON body.
+pub fn query_error_handler(
+    err: actix_web::error::QueryPayloadError,
+    _req: &actix_web::HttpRequest,
+) -> actix_web::Error {
+    HTTPError::from(Error::InvalidRequestBody(err.to_string())).into()
+}
@@ -0,0 +1,151 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use actix_web::{get, http::header::ContentType, web, HttpResponse, Responder};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// What /readyz consults: each flag flips to true as the corresponding
+/// startup step completes, and shutting_down flips during graceful
+/// shutdown so load balancers drain us before connections drop.
+pub struct Readiness {
+    config_loaded: AtomicBool,
+    tracing_initialized: AtomicBool,
+    server_bound: AtomicBool,
+    shutting_down: AtomicBool,
+    /// Kept current by the telemetry transport probe; starts optimistic
+    /// so a slow first probe does not flap /readyz right after startup.
+    sentry_reachable: AtomicBool,
+}
+
+impl Readiness {
+    fn new() -> Self {
+        Readiness {
+            config_loaded: AtomicBool::new(false),
+            tracing_initialized: AtomicBool::new(false),
+            server_bound: AtomicBool::new(false),
+            shutting_down: AtomicBool::new(false),
+            sentry_reachable: AtomicBool::new(true),
+        }
+    }
+
+    pub fn global() -> Arc<Readiness> {
+        static READINESS: OnceLock<Arc<Readiness>> = OnceLock::new();
+        READINESS.get_or_init(|| Arc::new(Readiness::new())).clone()
+    }
+
+    pub fn mark_config_loaded(&self) {
+        self.config_loaded.store(true, Ordering::Relaxed);
+    }
+
+    pub fn mark_tracing_initialized(&self) {
+        self.tracing_initialized.store(true, Ordering::Relaxed);
+    }
+
+    pub fn mark_server_bound(&self) {
+        self.server_bound.store(true, Ordering::Relaxed);
+    }
+
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::Relaxed);
+    }
+
+    pub fn set_sentry_reachable(&self, reachable: bool) {
+        self.sentry_reachable.store(reachable, Ordering::Relaxed);
+    }
+
+    /// The names of the checks currently failing; empty means ready. An
+    /// unreachable sentry host only fails readiness when the deployment
+    /// declared it mandatory (SENTRY_REQUIRED) — otherwise it is a
+    /// warning, because the app serves fine without error reporting.
+    pub fn failed_checks(&self) -> Vec<&'static str> {
+        let mut failed = Vec::new();
+        if !self.config_loaded.load(Ordering::Relaxed) {
+            failed.push("config_loaded");
+        }
+        if !self.tracing_initialized.load(Ordering::Relaxed) {
+            failed.push("tracing_initialized");
+        }
+        if !self.server_bound.load(Ordering::Relaxed) {
+            failed.push("server_bound");
+        }
+        if self.shutting_down.load(Ordering::Relaxed) {
+            failed.push("shutting_down");
+        }
+        if !self.sentry_reachable.load(Ordering::Relaxed)
+            && crate::config::Config::global().sentry_required
+        {
+            failed.push("sentry");
+        }
+        failed
+    }
+
+    /// The names of the checks degraded but not blocking readiness.
+    pub fn warning_checks(&self) -> Vec<&'static str> {
+        let mut warnings = Vec::new();
+        if !self.sentry_reachable.load(Ordering::Relaxed)
+            && !crate::config::Config::global().sentry_required
+        {
+            warnings.push("sentry");
+        }
+        warnings
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadinessResponse {
+    status: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failed: Vec<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<&'static str>,
+}
+
+/// Renders the readiness state; shared by /readyz and the /status alias.
+pub fn readiness_response(readiness: &Readiness) -> HttpResponse {
+    let failed = readiness.failed_checks();
+    let warnings = readiness.warning_checks();
+    if failed.is_empty() {
+        HttpResponse::Ok()
+            .content_type(ContentType::json())
+            .json(ReadinessResponse {
+                status: "ready",
+                failed,
+                warnings,
+            })
+    } else {
+        HttpResponse::ServiceUnavailable()
+            .content_type(ContentType::json())
+            .json(ReadinessResponse {
+                status: "not_ready",
+                failed,
+                warnings,
+            })
+    }
+}
+
+/// Pure liveness: if the event loop can answer at all, we are alive.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The event loop is running"),
+    ),
+    tag = "meta"
+)]
+#[get("/healthz")]
+pub async fn healthz() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(serde_json::json!({ "status": "alive" }))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "All startup checks passed", body = ReadinessResponse),
+        (status = 503, description = "Not ready; the body lists the failing checks", body = ReadinessResponse),
+    ),
+    tag = "meta"
+)]
+#[get("/readyz")]
+pub async fn readyz(readiness: web::Data<Readiness>) -> impl Responder {
+    readiness_response(&readiness)
+}
@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix_web::{delete, get, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::calculator::Operation;
+use crate::error::{HttpResult, Result};
+
+/// One recorded calculation; failures carry the error code instead of a
+/// result so failure patterns are visible without sentry.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HistoryEntry {
+    pub id: u64,
+    pub op: &'static str,
+    pub x: i32,
+    pub y: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub res: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<&'static str>,
+    /// Unix seconds at the time of the calculation.
+    pub timestamp: u64,
+    /// The tenant the calculation belongs to; used for partitioning and
+    /// never serialized — callers only ever see their own entries.
+    #[serde(skip)]
+    pub tenant: String,
+}
+
+/// A bounded in-memory history of calculations. The ring buffer evicts
+/// the oldest entry in O(1) once capacity is reached.
+pub struct History {
+    entries: RwLock<VecDeque<HistoryEntry>>,
+    capacity: usize,
+    next_id: AtomicU64,
+}
+
+impl History {
+    fn new(capacity: usize) -> Self {
+        History {
+            entries: RwLock::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    pub fn global() -> Arc<History> {
+        static HISTORY: OnceLock<Arc<History>> = OnceLock::new();
+        HISTORY
+            .get_or_init(|| {
+                Arc::new(History::new(
+                    crate::config::Config::global().history_capacity,
+                ))
+            })
+            .clone()
+    }
+
+    pub fn record(&self, op: &'static str, x: i32, y: i32, res: &Result<i32>) {
+        let entry = HistoryEntry {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            op,
+            x,
+            y,
+            res: res.as_ref().ok().copied(),
+            error: res.as_ref().err().map(|err| err.code()),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            tenant: crate::tenant::current(),
+        };
+
+        let mut entries = self.entries.write().unwrap();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// The tenant's most recent entries first, optionally filtered by
+    /// operation and truncated to `limit`.
+    pub fn recent(
+        &self,
+        tenant: &str,
+        limit: Option<usize>,
+        op: Option<&str>,
+    ) -> Vec<HistoryEntry> {
+        self.entries
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|entry| entry.tenant == tenant)
+            .filter(|entry| op.is_none_or(|op| entry.op == op))
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    /// Evicts entries older than `max_age_secs` and returns how many
+    /// were removed; entries are timestamp-ordered, so this only pops
+    /// from the front.
+    pub fn prune_older_than(&self, max_age_secs: u64) -> usize {
+        let cutoff = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+            .saturating_sub(max_age_secs);
+
+        let mut entries = self.entries.write().unwrap();
+        let mut pruned = 0;
+        while entries
+            .front()
+            .is_some_and(|entry| entry.timestamp < cutoff)
+        {
+            entries.pop_front();
+            pruned += 1;
+        }
+        pruned
+    }
+
+    /// Drops the tenant's entries; other tenants' history is untouched.
+    pub fn clear(&self, tenant: &str) {
+        self.entries
+            .write()
+            .unwrap()
+            .retain(|entry| entry.tenant != tenant);
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct HistoryQuery {
+    /// At most this many entries, newest first.
+    limit: Option<usize>,
+    /// Only entries for this operation, e.g. "add".
+    op: Option<String>,
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    params(HistoryQuery),
+    responses(
+        (status = 200, description = "Recent calculations, newest first", body = Vec<HistoryEntry>),
+        (status = 400, description = "Unknown op filter", body = crate::openapi::ErrorBody),
+    ),
+    tag = "history"
+)]
+#[get("/history")]
+pub async fn get_history(
+    history: web::Data<History>,
+    query: web::Query<HistoryQuery>,
+) -> HttpResult<web::Json<Vec<HistoryEntry>>> {
+    // Reject typos in the filter rather than silently matching nothing.
+    let op = match &query.op {
+        Some(op) => Some(op.parse::<Operation>()?.name()),
+        None => None,
+    };
+
+    Ok(web::Json(history.recent(
+        &crate::tenant::current(),
+        query.limit,
+        op,
+    )))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    params(
+        ("id", description = "The persisted calculation id"),
+    ),
+    responses(
+        (status = 200, description = "The persisted calculation", body = crate::db::CalculationRecord),
+        (status = 404, description = "No calculation with that id", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Database error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "history"
+)]
+#[get("/history/{id}")]
+pub async fn get_history_entry(
+    db: web::Data<crate::db::Db>,
+    id: web::Path<i64>,
+) -> HttpResult<web::Json<crate::db::CalculationRecord>> {
+    let id = id.into_inner();
+    let record = db
+        .fetch_calculation(id)
+        .await?
+        .ok_or(crate::error::Error::HistoryNotFound { id })?;
+
+    Ok(web::Json(record))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    responses(
+        (status = 204, description = "History cleared"),
+    ),
+    tag = "history"
+)]
+#[delete("/history")]
+pub async fn clear_history(history: web::Data<History>) -> impl Responder {
+    history.clear(&crate::tenant::current());
+    HttpResponse::NoContent().finish()
+}
@@ -0,0 +1,137 @@
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "sentry")]
+use sentry::protocol::{
+    Envelope, MonitorCheckIn, MonitorCheckInStatus, MonitorConfig, MonitorIntervalUnit,
+    MonitorSchedule,
+};
+#[cfg(feature = "sentry")]
+use sentry::types::Uuid;
+use tracing::{error, info};
+
+use crate::error::Result;
+
+/// How long history entries live before a housekeeping pass evicts them,
+/// independent of the capacity bound.
+const HISTORY_MAX_AGE_SECS: u64 = 24 * 60 * 60;
+
+/// The body of one housekeeping run, behind a trait so tests can inject
+/// a failing implementation and observe the error check-in.
+pub trait Housekeeping: Send + Sync + 'static {
+    fn run(&self) -> Result<()>;
+}
+
+/// The real pass: evict stale history entries. Stats roll up on read,
+/// so a log line of the headline numbers is all they need here.
+pub struct DefaultHousekeeping;
+
+impl Housekeeping for DefaultHousekeeping {
+    fn run(&self) -> Result<()> {
+        let pruned = crate::history::History::global().prune_older_than(HISTORY_MAX_AGE_SECS);
+        info!(pruned, "housekeeping pass complete");
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sentry")]
+fn send_check_in(check_in: MonitorCheckIn) {
+    if let Some(client) = sentry::Hub::current().client() {
+        let mut envelope = Envelope::new();
+        envelope.add_item(check_in);
+        client.send_envelope(envelope);
+    }
+}
+
+/// One run bracketed by monitor check-ins: in_progress before, then
+/// ok/error with the duration. Failures are also captured as regular
+/// events tagged task=housekeeping. Public so tests can drive a run
+/// without the timer.
+pub fn run_once(task: &dyn Housekeeping, slug: &str, interval: Duration) {
+    #[cfg(not(feature = "sentry"))]
+    let _ = (slug, interval);
+    #[cfg(feature = "sentry")]
+    let check_in_id = Uuid::new_v4();
+    #[cfg(feature = "sentry")]
+    let environment = sentry::Hub::current()
+        .client()
+        .and_then(|client| client.options().environment.clone())
+        .map(|env| env.into_owned());
+
+    #[cfg(feature = "sentry")]
+    send_check_in(MonitorCheckIn {
+        check_in_id,
+        monitor_slug: slug.to_string(),
+        status: MonitorCheckInStatus::InProgress,
+        environment: environment.clone(),
+        duration: None,
+        // Upserts the monitor, so the slug needs no dashboard setup.
+        monitor_config: Some(MonitorConfig {
+            schedule: MonitorSchedule::Interval {
+                value: (interval.as_secs() / 60).max(1),
+                unit: MonitorIntervalUnit::Minute,
+            },
+            checkin_margin: Some(5),
+            max_runtime: None,
+            timezone: None,
+            failure_issue_threshold: None,
+            recovery_threshold: None,
+        }),
+    });
+
+    let started = Instant::now();
+    let result = task.run();
+
+    if let Err(err) = &result {
+        error!(error = %err, "housekeeping run failed");
+        crate::reporter::global().report_error(err, &[("task", "housekeeping".to_string())], &[]);
+    }
+
+    #[cfg(feature = "sentry")]
+    send_check_in(MonitorCheckIn {
+        check_in_id,
+        monitor_slug: slug.to_string(),
+        status: match result {
+            Ok(()) => MonitorCheckInStatus::Ok,
+            Err(_) => MonitorCheckInStatus::Error,
+        },
+        environment,
+        duration: Some(started.elapsed().as_secs_f64()),
+        monitor_config: None,
+    });
+    #[cfg(not(feature = "sentry"))]
+    info!(
+        duration_secs = started.elapsed().as_secs_f64(),
+        "housekeeping run finished"
+    );
+}
+
+/// Spawns the periodic task. Firing (or dropping) the returned sender
+/// stops the loop; the handle resolves once any in-flight run finishes.
+pub fn spawn(
+    task: std::sync::Arc<dyn Housekeeping>,
+    interval_secs: u64,
+    slug: String,
+) -> (
+    tokio::task::JoinHandle<()>,
+    tokio::sync::oneshot::Sender<()>,
+) {
+    let (stop_tx, mut stop_rx) = tokio::sync::oneshot::channel::<()>();
+
+    let handle = tokio::spawn(async move {
+        let period = Duration::from_secs(interval_secs);
+        let mut ticker = tokio::time::interval(period);
+        // The first tick fires immediately; skip it so startup does not
+        // count as a scheduled run.
+        ticker.tick().await;
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => run_once(task.as_ref(), &slug, period),
+                _ = &mut stop_rx => break,
+            }
+        }
+        info!("housekeeping task stopped");
+    });
+
+    (handle, stop_tx)
+}
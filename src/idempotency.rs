@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use actix_web::{
+    body::{BoxBody, EitherBody, MessageBody},
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{header, Method, StatusCode},
+    web, Error, FromRequest, HttpResponse, ResponseError,
+};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+
+/// The request header that opts a POST into idempotent handling.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Set on responses served from the cache rather than recomputed.
+pub const IDEMPOTENCY_REPLAYED_HEADER: &str = "idempotency-replayed";
+
+/// A cached response: enough to replay it byte for byte, plus the hash
+/// of the request body it was computed from so key reuse is detectable.
+struct Stored {
+    body_hash: u64,
+    status: StatusCode,
+    content_type: Option<header::HeaderValue>,
+    body: web::Bytes,
+    stored_at: Instant,
+}
+
+enum Entry {
+    /// A request with this key is computing right now; the mutex is held
+    /// for the duration, so waiters queue on it instead of recomputing.
+    InProgress(Arc<tokio::sync::Mutex<()>>),
+    Done(Stored),
+}
+
+/// What the store decided for an incoming (key, body) pair.
+enum Claim {
+    /// Nobody holds this key: compute, then fulfil or abandon. The guard
+    /// is what waiters block on.
+    Compute(tokio::sync::OwnedMutexGuard<()>),
+    /// Another request with this key is in flight; wait on its lock and
+    /// claim again.
+    Wait(Arc<tokio::sync::Mutex<()>>),
+    /// A fresh cached response for the identical body.
+    Replay(StatusCode, Option<header::HeaderValue>, web::Bytes),
+    /// The key was already used with a different body.
+    Mismatch,
+    /// The cache is full of in-flight entries; compute without caching
+    /// rather than grow unboundedly.
+    Bypass,
+}
+
+/// A bounded TTL cache of responses keyed by Idempotency-Key, shared
+/// app-wide via web::Data.
+pub struct IdempotencyStore {
+    ttl: Duration,
+    capacity: usize,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl IdempotencyStore {
+    fn new(ttl_secs: u64, capacity: usize) -> Self {
+        IdempotencyStore {
+            ttl: Duration::from_secs(ttl_secs),
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn global() -> Arc<IdempotencyStore> {
+        static STORE: OnceLock<Arc<IdempotencyStore>> = OnceLock::new();
+        STORE
+            .get_or_init(|| {
+                let config = crate::config::Config::global();
+                Arc::new(IdempotencyStore::new(
+                    config.idempotency_ttl_secs,
+                    config.idempotency_capacity,
+                ))
+            })
+            .clone()
+    }
+
+    fn claim(&self, key: &str, body_hash: u64) -> Claim {
+        let mut entries = self.entries.lock().unwrap();
+
+        match entries.get(key) {
+            Some(Entry::InProgress(lock)) => return Claim::Wait(lock.clone()),
+            Some(Entry::Done(stored)) if stored.stored_at.elapsed() <= self.ttl => {
+                if stored.body_hash != body_hash {
+                    return Claim::Mismatch;
+                }
+                return Claim::Replay(
+                    stored.status,
+                    stored.content_type.clone(),
+                    stored.body.clone(),
+                );
+            }
+            // Expired or absent: this request computes.
+            _ => {}
+        }
+
+        if !entries.contains_key(key) && entries.len() >= self.capacity {
+            // Expired entries first, then the oldest completed one; only
+            // a cache full of in-flight computations cannot make room.
+            entries.retain(|_, entry| match entry {
+                Entry::Done(stored) => stored.stored_at.elapsed() <= self.ttl,
+                Entry::InProgress(_) => true,
+            });
+            if entries.len() >= self.capacity {
+                let oldest = entries
+                    .iter()
+                    .filter_map(|(key, entry)| match entry {
+                        Entry::Done(stored) => Some((key.clone(), stored.stored_at)),
+                        Entry::InProgress(_) => None,
+                    })
+                    .min_by_key(|(_, stored_at)| *stored_at)
+                    .map(|(key, _)| key);
+                match oldest {
+                    Some(oldest) => {
+                        entries.remove(&oldest);
+                    }
+                    None => return Claim::Bypass,
+                }
+            }
+        }
+
+        let lock = Arc::new(tokio::sync::Mutex::new(()));
+        // Cannot fail: the mutex was created on the previous line.
+        let guard = lock.clone().try_lock_owned().expect("freshly created lock");
+        entries.insert(key.to_string(), Entry::InProgress(lock));
+        Claim::Compute(guard)
+    }
+
+    fn fulfil(&self, key: &str, stored: Stored) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), Entry::Done(stored));
+    }
+
+    /// The computation failed before producing a response; let the next
+    /// request with this key try again.
+    fn abandon(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+}
+
+fn hash_body(bytes: &[u8]) -> u64 {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Whether this request opts into idempotent handling. Streaming
+/// responses are exempt: buffering them for replay would defeat the
+/// point of streaming.
+fn applies(req: &ServiceRequest) -> bool {
+    req.method() == Method::POST
+        && req.path().starts_with("/api/")
+        && !req.path().ends_with("/batch/stream")
+        && req.headers().contains_key(IDEMPOTENCY_KEY_HEADER)
+}
+
+/// Replays cached responses for retried POSTs carrying an
+/// Idempotency-Key header, so flaky-network retries neither recompute
+/// nor duplicate history entries. Requests without the header pass
+/// through untouched.
+pub struct Idempotency;
+
+impl<S, B> Transform<S, ServiceRequest> for Idempotency
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = IdempotencyService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(IdempotencyService {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct IdempotencyService<S> {
+    // Rc because the cache has to be consulted before the inner call,
+    // inside the boxed future.
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for IdempotencyService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            if !applies(&req) {
+                return service.call(req).await.map(|res| res.map_into_left_body());
+            }
+
+            let key = req
+                .headers()
+                .get(IDEMPOTENCY_KEY_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+
+            // Buffer the request body: its hash is what distinguishes a
+            // legitimate retry from key reuse.
+            let (http_req, mut payload) = req.into_parts();
+            let bytes = web::Bytes::from_request(&http_req, &mut payload).await?;
+            let body_hash = hash_body(&bytes);
+
+            let store = match http_req.app_data::<web::Data<IdempotencyStore>>() {
+                Some(store) => Arc::clone(store),
+                None => IdempotencyStore::global(),
+            };
+
+            loop {
+                match store.claim(&key, body_hash) {
+                    Claim::Wait(lock) => {
+                        // Held by the computing request until it fulfils
+                        // or abandons the entry; then claim again.
+                        drop(lock.lock().await);
+                    }
+                    Claim::Replay(status, content_type, body) => {
+                        let mut builder = HttpResponse::build(status);
+                        builder.insert_header((IDEMPOTENCY_REPLAYED_HEADER, "true"));
+                        if let Some(content_type) = content_type {
+                            builder.insert_header((header::CONTENT_TYPE, content_type));
+                        }
+                        let response = builder.body(body);
+
+                        let req =
+                            ServiceRequest::from_parts(http_req, actix_web::dev::Payload::None);
+                        return Ok(req.into_response(response).map_into_right_body());
+                    }
+                    Claim::Mismatch => {
+                        let response =
+                            crate::error::HTTPError::from(crate::error::Error::IdempotencyMismatch)
+                                .error_response();
+                        let req =
+                            ServiceRequest::from_parts(http_req, actix_web::dev::Payload::None);
+                        return Ok(req.into_response(response).map_into_right_body());
+                    }
+                    Claim::Bypass => {
+                        let req = reassemble(http_req, bytes);
+                        return service.call(req).await.map(|res| res.map_into_left_body());
+                    }
+                    Claim::Compute(guard) => {
+                        let req = reassemble(http_req, bytes);
+                        let res = match service.call(req).await {
+                            Ok(res) => res,
+                            Err(err) => {
+                                store.abandon(&key);
+                                drop(guard);
+                                return Err(err);
+                            }
+                        };
+
+                        let (http_req, response) = res.into_parts();
+                        let status = response.status();
+                        let content_type = response.headers().get(header::CONTENT_TYPE).cloned();
+                        let (response, body) = response.into_parts();
+
+                        let body = match actix_web::body::to_bytes(body).await {
+                            Ok(body) => body,
+                            Err(err) => {
+                                store.abandon(&key);
+                                drop(guard);
+                                let err: Box<dyn std::error::Error> = err.into();
+                                return Err(crate::error::HTTPError::from(
+                                    crate::error::Error::ResponseEncoding(err.to_string()),
+                                )
+                                .into());
+                            }
+                        };
+
+                        store.fulfil(
+                            &key,
+                            Stored {
+                                body_hash,
+                                status,
+                                content_type,
+                                body: body.clone(),
+                                stored_at: Instant::now(),
+                            },
+                        );
+                        drop(guard);
+
+                        let response = response.set_body(BoxBody::new(body));
+                        return Ok(ServiceResponse::new(http_req, response).map_into_right_body());
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Puts the buffered body back so the handler's extractor sees it.
+pub(crate) fn reassemble(http_req: actix_web::HttpRequest, bytes: web::Bytes) -> ServiceRequest {
+    let stream = futures_util::stream::once(async move {
+        Ok::<web::Bytes, actix_web::error::PayloadError>(bytes)
+    });
+    let boxed: std::pin::Pin<
+        Box<dyn futures_util::Stream<Item = Result<web::Bytes, actix_web::error::PayloadError>>>,
+    > = Box::pin(stream);
+    ServiceRequest::from_parts(http_req, actix_web::dev::Payload::from(boxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(body_hash: u64, at: Instant) -> Stored {
+        Stored {
+            body_hash,
+            status: StatusCode::OK,
+            content_type: None,
+            body: web::Bytes::from_static(b"{}"),
+            stored_at: at,
+        }
+    }
+
+    #[tokio::test]
+    async fn claims_replay_only_fresh_identical_bodies() {
+        let store = IdempotencyStore::new(60, 10);
+
+        let Claim::Compute(guard) = store.claim("k", 1) else {
+            panic!("first claim should compute");
+        };
+        assert!(matches!(store.claim("k", 1), Claim::Wait(_)));
+
+        store.fulfil("k", stored(1, Instant::now()));
+        drop(guard);
+
+        assert!(matches!(store.claim("k", 1), Claim::Replay(..)));
+        assert!(matches!(store.claim("k", 2), Claim::Mismatch));
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_recomputed_and_capacity_is_bounded() {
+        let store = IdempotencyStore::new(0, 2);
+
+        // TTL 0: immediately stale, so the same key computes again.
+        let Claim::Compute(guard) = store.claim("a", 1) else {
+            panic!("first claim should compute");
+        };
+        store.fulfil("a", stored(1, Instant::now()));
+        drop(guard);
+        assert!(matches!(store.claim("a", 1), Claim::Compute(_)));
+
+        // At capacity with only in-flight entries, new keys bypass the
+        // cache instead of evicting someone mid-computation.
+        let store = IdempotencyStore::new(60, 2);
+        let _a = store.claim("a", 1);
+        let _b = store.claim("b", 1);
+        assert!(matches!(store.claim("c", 1), Claim::Bypass));
+    }
+}
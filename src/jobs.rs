@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use actix_web::{delete, get, post, web, HttpResponse};
+#[cfg(feature = "sentry")]
+use sentry::SentryFutureExt;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+use tracing::info;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::error::{Error, HttpResult};
+use crate::handlers::{BatchItemError, BatchItemResponse, CalcRequest};
+use crate::negotiation::Negotiated;
+
+/// How long finished jobs remain pollable, overridable with the
+/// JOBS_TTL_SECS env var. Queued and running jobs never expire.
+fn jobs_ttl_secs() -> u64 {
+    static JOBS_TTL_SECS: OnceLock<u64> = OnceLock::new();
+    *JOBS_TTL_SECS.get_or_init(|| {
+        std::env::var("JOBS_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300)
+    })
+}
+
+/// The maximum number of jobs the store holds at once, overridable with
+/// the JOBS_CAPACITY env var.
+fn jobs_capacity() -> usize {
+    static JOBS_CAPACITY: OnceLock<usize> = OnceLock::new();
+    *JOBS_CAPACITY.get_or_init(|| {
+        std::env::var("JOBS_CAPACITY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(256)
+    })
+}
+
+/// How many jobs run concurrently, overridable with the JOBS_CONCURRENCY
+/// env var. Submissions past this limit queue rather than fail.
+fn jobs_concurrency() -> usize {
+    static JOBS_CONCURRENCY: OnceLock<usize> = OnceLock::new();
+    *JOBS_CONCURRENCY.get_or_init(|| {
+        std::env::var("JOBS_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(4)
+    })
+}
+
+/// The permits that bound how many jobs compute at once; a queued job
+/// holds its task alive but does no work until a permit frees up.
+fn pool() -> Arc<Semaphore> {
+    static POOL: OnceLock<Arc<Semaphore>> = OnceLock::new();
+    POOL.get_or_init(|| Arc::new(Semaphore::new(jobs_concurrency())))
+        .clone()
+}
+
+/// The work a job carries, tagged by operation kind. Both variants map
+/// onto existing synchronous endpoints; the job API exists for payloads
+/// large enough that holding an HTTP connection open is unreasonable.
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum JobRequest {
+    /// Evaluate an arithmetic expression, as POST /eval does.
+    Eval { expr: String },
+    /// Run a batch of calculations, as POST /batch does. Items fail
+    /// individually without failing the job, mirroring /batch.
+    Batch { items: Vec<CalcRequest> },
+}
+
+/// A job's lifecycle. Terminal states keep what the poller needs; the
+/// JoinHandle lives alongside in the store entry, not here, so a
+/// snapshot can be cloned out without touching the task.
+#[derive(Debug, Clone)]
+enum JobState {
+    Queued,
+    Running,
+    Done(serde_json::Value),
+    Failed { code: &'static str, message: String },
+    Cancelled,
+}
+
+impl JobState {
+    fn name(&self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Done(_) => "done",
+            JobState::Failed { .. } => "failed",
+            JobState::Cancelled => "cancelled",
+        }
+    }
+
+    fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            JobState::Done(_) | JobState::Failed { .. } | JobState::Cancelled
+        )
+    }
+}
+
+struct Job {
+    state: JobState,
+    handle: Option<tokio::task::JoinHandle<()>>,
+    finished_at: Option<Instant>,
+}
+
+/// A bounded in-memory job table. Finished jobs are swept once their TTL
+/// lapses, on the next submission — the same opportunistic cleanup the
+/// idempotency cache uses, so an idle server holds entries a little
+/// longer but never grows past capacity.
+pub struct JobStore {
+    ttl: Duration,
+    capacity: usize,
+    jobs: Mutex<HashMap<String, Job>>,
+}
+
+impl JobStore {
+    fn new(ttl_secs: u64, capacity: usize) -> Self {
+        JobStore {
+            ttl: Duration::from_secs(ttl_secs),
+            capacity,
+            jobs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn global() -> Arc<JobStore> {
+        static STORE: OnceLock<Arc<JobStore>> = OnceLock::new();
+        STORE
+            .get_or_init(|| Arc::new(JobStore::new(jobs_ttl_secs(), jobs_capacity())))
+            .clone()
+    }
+
+    /// Registers a queued job, sweeping expired entries first. Fails
+    /// with Overloaded when the table is full of live entries.
+    fn submit(&self, id: &str) -> crate::error::Result<()> {
+        let mut jobs = self.jobs.lock().unwrap();
+        if jobs.len() >= self.capacity {
+            let ttl = self.ttl;
+            jobs.retain(|_, job| match job.finished_at {
+                Some(finished_at) => finished_at.elapsed() <= ttl,
+                None => true,
+            });
+            if jobs.len() >= self.capacity {
+                return Err(Error::Overloaded);
+            }
+        }
+        jobs.insert(
+            id.to_string(),
+            Job {
+                state: JobState::Queued,
+                handle: None,
+                finished_at: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Stores the task handle after spawning. The task may already have
+    /// finished; that's fine, aborting a finished handle is a no-op.
+    fn attach(&self, id: &str, handle: tokio::task::JoinHandle<()>) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(id) {
+            job.handle = Some(handle);
+        }
+    }
+
+    fn mark_running(&self, id: &str) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(job) = jobs.get_mut(id) {
+            if matches!(job.state, JobState::Queued) {
+                job.state = JobState::Running;
+            }
+        }
+    }
+
+    /// Records a terminal state, unless cancellation got there first —
+    /// a cancel that raced the final write must win, or the poller
+    /// would see a job it cancelled report success.
+    fn finish(&self, id: &str, state: JobState) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(job) = jobs.get_mut(id) {
+            if !job.state.is_terminal() {
+                job.state = state;
+                job.finished_at = Some(Instant::now());
+            }
+        }
+    }
+
+    fn snapshot(&self, id: &str) -> Option<JobResponse> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.get(id).map(|job| JobResponse::new(id, &job.state))
+    }
+
+    /// Aborts the task and marks the job cancelled, if it has not
+    /// already finished; either way returns the resulting snapshot.
+    fn cancel(&self, id: &str) -> Option<JobResponse> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get_mut(id)?;
+        if !job.state.is_terminal() {
+            if let Some(handle) = job.handle.take() {
+                handle.abort();
+            }
+            job.state = JobState::Cancelled;
+            job.finished_at = Some(Instant::now());
+        }
+        Some(JobResponse::new(id, &job.state))
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct JobError {
+    code: String,
+    message: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct JobResponse {
+    id: String,
+    /// One of "queued", "running", "done", "failed" or "cancelled".
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<JobError>,
+}
+
+impl JobResponse {
+    fn new(id: &str, state: &JobState) -> Self {
+        JobResponse {
+            id: id.to_string(),
+            status: state.name(),
+            result: match state {
+                JobState::Done(result) => Some(result.clone()),
+                _ => None,
+            },
+            error: match state {
+                JobState::Failed { code, message } => Some(JobError {
+                    code: code.to_string(),
+                    message: message.clone(),
+                }),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// The job body itself. Batch item failures are folded into the result,
+/// as /batch does; only errors that would have failed the synchronous
+/// endpoint outright fail the job.
+async fn run(request: JobRequest) -> Result<serde_json::Value, Error> {
+    match request {
+        JobRequest::Eval { expr } => {
+            let res = crate::expr::eval(&expr)?;
+            Ok(serde_json::json!({ "res": res }))
+        }
+        JobRequest::Batch { items } => {
+            let mut results = Vec::with_capacity(items.len());
+            for item in &items {
+                let res = match item.op.parse::<crate::calculator::Operation>() {
+                    Ok(op) => crate::handlers::calculate(op, item.x, item.y).await,
+                    Err(err) => Err(err),
+                };
+                results.push(match res {
+                    Ok(res) => BatchItemResponse::Ok { res },
+                    Err(err) => BatchItemResponse::Err {
+                        error: BatchItemError {
+                            code: err.code().into(),
+                            message: err.to_string(),
+                        },
+                    },
+                });
+            }
+            Ok(serde_json::to_value(results).unwrap_or_default())
+        }
+    }
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    request_body = JobRequest,
+    responses(
+        (status = 202, description = "The job was enqueued; poll the Location header", body = JobResponse),
+        (status = 503, description = "The job store is full", body = crate::openapi::ErrorBody),
+    ),
+    tag = "jobs"
+)]
+#[tracing::instrument(skip(body))]
+#[post("/jobs")]
+pub async fn submit_job(body: Negotiated<JobRequest>) -> HttpResult<HttpResponse> {
+    let store = JobStore::global();
+    let id = Uuid::new_v4().to_string();
+    info!(method = "submit_job", job_id = %id, "enqueuing a job");
+
+    store.submit(&id)?;
+
+    // A dedicated hub tagged with the job id: the work outlives the
+    // request, and unlike a failing synchronous request — where the
+    // client sees the error — a failed job's only trace is the capture.
+    #[cfg(feature = "sentry")]
+    let hub = Arc::new(sentry::Hub::new_from_top(sentry::Hub::current()));
+    #[cfg(feature = "sentry")]
+    hub.configure_scope(|scope| scope.set_tag("job_id", &id));
+
+    let task_store = Arc::clone(&store);
+    let task_id = id.clone();
+    let request = body.0;
+    let task = async move {
+        // Queued until a pool permit frees up; the permit rides the
+        // task, so an abort releases it.
+        let _permit = pool().acquire_owned().await.ok();
+        task_store.mark_running(&task_id);
+        match run(request).await {
+            Ok(result) => task_store.finish(&task_id, JobState::Done(result)),
+            Err(err) => {
+                crate::reporter::global().report_error(&err, &[], &[]);
+                task_store.finish(
+                    &task_id,
+                    JobState::Failed {
+                        code: err.code(),
+                        message: err.to_string(),
+                    },
+                );
+            }
+        }
+    };
+    #[cfg(feature = "sentry")]
+    let task = task.bind_hub(hub);
+    let handle = actix_web::rt::spawn(task);
+    store.attach(&id, handle);
+
+    // Snapshot rather than a hand-built body: the job may legitimately
+    // already be past "queued" by now.
+    let snapshot = store
+        .snapshot(&id)
+        .unwrap_or_else(|| JobResponse::new(&id, &JobState::Queued));
+    Ok(HttpResponse::Accepted()
+        .insert_header(("location", format!("/api/v0/jobs/{id}")))
+        .json(snapshot))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    responses(
+        (status = 200, description = "The job's current status", body = JobResponse),
+        (status = 404, description = "No such job, or its TTL lapsed", body = crate::openapi::ErrorBody),
+    ),
+    tag = "jobs"
+)]
+#[tracing::instrument]
+#[get("/jobs/{id}")]
+pub async fn get_job(id: web::Path<String>) -> HttpResult<web::Json<JobResponse>> {
+    let id = id.into_inner();
+    let snapshot = JobStore::global()
+        .snapshot(&id)
+        .ok_or(Error::JobNotFound { id })?;
+    Ok(web::Json(snapshot))
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    responses(
+        (status = 200, description = "The job after cancellation; already-finished jobs keep their state", body = JobResponse),
+        (status = 404, description = "No such job, or its TTL lapsed", body = crate::openapi::ErrorBody),
+    ),
+    tag = "jobs"
+)]
+#[tracing::instrument]
+#[delete("/jobs/{id}")]
+pub async fn cancel_job(id: web::Path<String>) -> HttpResult<web::Json<JobResponse>> {
+    let id = id.into_inner();
+    info!(method = "cancel_job", job_id = %id, "cancelling a job");
+    let snapshot = JobStore::global()
+        .cancel(&id)
+        .ok_or(Error::JobNotFound { id })?;
+    Ok(web::Json(snapshot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cancel_aborts_the_task_and_wins_races_with_finish() {
+        let store = JobStore::new(60, 10);
+        store.submit("a").unwrap();
+
+        // A task that would never finish on its own; the sender drops
+        // only when the task ends, so the receiver erroring out proves
+        // the abort actually happened.
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let handle = tokio::spawn(async move {
+            let _tx = tx;
+            std::future::pending::<()>().await
+        });
+        store.attach("a", handle);
+        store.mark_running("a");
+
+        let snapshot = store.cancel("a").unwrap();
+        assert_eq!(snapshot.status, "cancelled");
+        assert!(rx.await.is_err());
+
+        // A finish arriving after cancellation must not resurrect the job.
+        store.finish("a", JobState::Done(serde_json::json!({ "res": 1 })));
+        assert_eq!(store.snapshot("a").unwrap().status, "cancelled");
+
+        // Cancelling a done job leaves it done.
+        store.submit("b").unwrap();
+        store.finish("b", JobState::Done(serde_json::json!({ "res": 2 })));
+        assert_eq!(store.cancel("b").unwrap().status, "done");
+    }
+
+    #[tokio::test]
+    async fn capacity_sweeps_expired_jobs_but_never_live_ones() {
+        let store = JobStore::new(0, 2);
+        store.submit("a").unwrap();
+        store.finish("a", JobState::Done(serde_json::Value::Null));
+        store.submit("b").unwrap();
+
+        // TTL 0: "a" is finished and immediately stale, so it makes room.
+        store.submit("c").unwrap();
+        assert!(store.snapshot("a").is_none());
+
+        // "b" and "c" are unfinished; a full table of live jobs rejects.
+        assert!(matches!(store.submit("d"), Err(Error::Overloaded)));
+    }
+}
@@ -0,0 +1,363 @@
+use std::sync::OnceLock;
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header,
+    Error, FromRequest, HttpMessage, HttpRequest, ResponseError,
+};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+
+use crate::config::Config;
+use crate::error::HTTPError;
+
+/// The validated claims of a bearer token, inserted by JwtAuth and
+/// available to handlers as an extractor. Requests that never passed JWT
+/// auth (it is disabled, or the route is public) have no user to
+/// extract, which surfaces as the same 401 an unauthenticated request
+/// gets.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    /// The subject claim, when the token carries one.
+    pub subject: Option<String>,
+    /// Every claim from the validated payload.
+    pub claims: serde_json::Map<String, serde_json::Value>,
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<AuthenticatedUser>()
+                .cloned()
+                .ok_or_else(|| HTTPError::from(crate::error::Error::MissingJwt).into()),
+        )
+    }
+}
+
+/// A verification key plus the metadata needed to pick it for a token.
+struct Key {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    key: DecodingKey,
+}
+
+pub(crate) struct Verifier {
+    keys: Vec<Key>,
+}
+
+static VERIFIER: OnceLock<Verifier> = OnceLock::new();
+
+/// Loads the verification key set once at startup, so a bad PEM or an
+/// unreachable JWKS URL fails the deploy instead of every request.
+pub fn init(config: &Config) -> crate::error::Result<()> {
+    if VERIFIER.get().is_none() {
+        let _ = VERIFIER.set(Verifier::from_config(config)?);
+    }
+    Ok(())
+}
+
+fn verifier() -> &'static Verifier {
+    // main calls init and surfaces errors properly; the lazy fallback is
+    // for tests, whose HS256-only configs cannot fail to load.
+    VERIFIER.get_or_init(|| {
+        Verifier::from_config(&Config::global()).expect("JWT verification keys failed to load")
+    })
+}
+
+impl Verifier {
+    fn from_config(config: &Config) -> crate::error::Result<Verifier> {
+        use crate::error::Error;
+
+        let mut keys = Vec::new();
+        if let Some(secret) = &config.jwt_hs256_secret {
+            keys.push(Key {
+                kid: None,
+                algorithm: Algorithm::HS256,
+                key: DecodingKey::from_secret(secret.as_bytes()),
+            });
+        }
+        if let Some(path) = &config.jwt_rs256_pem_path {
+            let pem = std::fs::read(path).map_err(|err| Error::Config {
+                var: "JWT_RS256_PEM_PATH",
+                message: format!("{path}: {err}"),
+            })?;
+            let key = DecodingKey::from_rsa_pem(&pem).map_err(|err| Error::Config {
+                var: "JWT_RS256_PEM_PATH",
+                message: format!("{path}: {err}"),
+            })?;
+            keys.push(Key {
+                kid: None,
+                algorithm: Algorithm::RS256,
+                key,
+            });
+        }
+        if let Some(url) = &config.jwt_jwks_url {
+            // On its own thread: reqwest's blocking client refuses to run
+            // on a tokio worker, and init is called from async main.
+            let fetch_url = url.clone();
+            let jwks = std::thread::spawn(move || {
+                reqwest::blocking::get(&fetch_url)
+                    .and_then(reqwest::blocking::Response::error_for_status)
+                    .and_then(|resp| resp.json::<jsonwebtoken::jwk::JwkSet>())
+            })
+            .join()
+            .map_err(|_| Error::Config {
+                var: "JWT_JWKS_URL",
+                message: "the JWKS fetch thread panicked".to_string(),
+            })?
+            .map_err(|err| Error::Config {
+                var: "JWT_JWKS_URL",
+                message: format!("{url}: {err}"),
+            })?;
+            for jwk in &jwks.keys {
+                let key = DecodingKey::from_jwk(jwk).map_err(|err| Error::Config {
+                    var: "JWT_JWKS_URL",
+                    message: format!("unusable JWK in {url}: {err}"),
+                })?;
+                keys.push(Key {
+                    kid: jwk.common.key_id.clone(),
+                    algorithm: jwk
+                        .common
+                        .key_algorithm
+                        .and_then(|alg| alg.to_string().parse().ok())
+                        .unwrap_or(Algorithm::RS256),
+                    key,
+                });
+            }
+        }
+        Ok(Verifier { keys })
+    }
+
+    /// Validates signature, expiry and audience, then the configured
+    /// required-claims list, returning the decoded payload.
+    fn verify(
+        &self,
+        token: &str,
+        config: &Config,
+    ) -> crate::error::Result<serde_json::Map<String, serde_json::Value>> {
+        use crate::error::Error;
+
+        let token_header =
+            jsonwebtoken::decode_header(token).map_err(|err| Error::InvalidJwt(err.to_string()))?;
+        // A kid narrows the candidates; keys without one (HS256, PEM) are
+        // always tried for their algorithm.
+        let candidates: Vec<&Key> = self
+            .keys
+            .iter()
+            .filter(|key| key.algorithm == token_header.alg)
+            .filter(|key| match (&key.kid, &token_header.kid) {
+                (Some(kid), Some(wanted)) => kid == wanted,
+                _ => true,
+            })
+            .collect();
+
+        let mut validation = Validation::new(token_header.alg);
+        validation.leeway = config.jwt_skew_secs;
+        match &config.jwt_audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+
+        let mut last = Error::InvalidJwt(format!(
+            "no verification key for algorithm {:?}",
+            token_header.alg
+        ));
+        for key in candidates {
+            match jsonwebtoken::decode::<serde_json::Map<String, serde_json::Value>>(
+                token,
+                &key.key,
+                &validation,
+            ) {
+                Ok(data) => {
+                    for claim in &config.jwt_required_claims {
+                        if !data.claims.contains_key(claim) {
+                            return Err(Error::InvalidJwt(format!(
+                                "missing required claim: {claim}"
+                            )));
+                        }
+                    }
+                    return Ok(data.claims);
+                }
+                Err(err) => last = Error::InvalidJwt(err.to_string()),
+            }
+        }
+        Err(last)
+    }
+}
+
+/// Bearer-token authentication, the alternative to static API keys:
+/// enabled when any verification key source is configured, with public
+/// paths staying open exactly as they do for Auth. A deployment
+/// typically configures one of the two schemes, not both.
+pub struct JwtAuth;
+
+impl<S, B> Transform<S, ServiceRequest> for JwtAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = JwtAuthService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(JwtAuthService { service }))
+    }
+}
+
+pub struct JwtAuthService<S> {
+    service: S,
+}
+
+/// Renders the structured JSON error body directly rather than returning
+/// Err, mirroring Auth's rejections.
+fn reject<B>(req: ServiceRequest, err: crate::error::Error) -> ServiceResponse<EitherBody<B>> {
+    let response = HTTPError::from(err).error_response();
+    req.into_response(response).map_into_right_body()
+}
+
+impl<S, B> Service<ServiceRequest> for JwtAuthService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let config = Config::global();
+        let enabled = config.jwt_hs256_secret.is_some()
+            || config.jwt_rs256_pem_path.is_some()
+            || config.jwt_jwks_url.is_some();
+
+        let mut subject = None;
+        if enabled && !crate::middleware::is_public_path(req.path()) {
+            let token = req
+                .headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .map(str::to_owned);
+            let Some(token) = token else {
+                return Box::pin(ready(Ok(reject(req, crate::error::Error::MissingJwt))));
+            };
+
+            let claims = match verifier().verify(&token, &config) {
+                Ok(claims) => claims,
+                Err(err) => return Box::pin(ready(Ok(reject(req, err)))),
+            };
+
+            subject = claims
+                .get("sub")
+                .and_then(|value| value.as_str())
+                .map(str::to_owned);
+            // Identify the caller (by subject, never the token) on the
+            // request-scoped hub set up by Middleware.
+            #[cfg(feature = "sentry")]
+            if let Some(hub) = req.extensions().get::<std::sync::Arc<sentry::Hub>>() {
+                if let Some(subject) = &subject {
+                    hub.configure_scope(|scope| {
+                        scope.set_user(Some(sentry::User {
+                            id: Some(subject.clone()),
+                            ..Default::default()
+                        }));
+                    });
+                }
+            }
+            req.extensions_mut().insert(AuthenticatedUser {
+                subject: subject.clone(),
+                claims,
+            });
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            // Inside the future, where Middleware's request span is
+            // entered; recording from the synchronous path would miss it.
+            if let Some(subject) = &subject {
+                tracing::Span::current().record("jwt_sub", subject.as_str());
+            }
+            fut.await.map(|res| res.map_into_left_body())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn the_extractor_requires_a_validated_user() {
+        let (req, mut payload) = TestRequest::default().to_http_parts();
+        assert!(AuthenticatedUser::from_request(&req, &mut payload)
+            .await
+            .is_err());
+
+        req.extensions_mut().insert(AuthenticatedUser {
+            subject: Some("robot-7".to_string()),
+            claims: serde_json::Map::new(),
+        });
+        let user = AuthenticatedUser::from_request(&req, &mut payload)
+            .await
+            .unwrap();
+        assert_eq!(user.subject.as_deref(), Some("robot-7"));
+    }
+
+    #[test]
+    fn hs256_tokens_verify_and_enforce_required_claims() {
+        let mut config = Config::from_env().unwrap();
+        config.jwt_skew_secs = 60;
+        config.jwt_audience = Some("calc-clients".to_string());
+        config.jwt_required_claims = vec!["sub".to_string()];
+
+        let verifier = Verifier {
+            keys: vec![Key {
+                kid: None,
+                algorithm: Algorithm::HS256,
+                key: DecodingKey::from_secret(b"unit-secret"),
+            }],
+        };
+        let encode = |claims: &serde_json::Value| {
+            jsonwebtoken::encode(
+                &jsonwebtoken::Header::default(),
+                claims,
+                &jsonwebtoken::EncodingKey::from_secret(b"unit-secret"),
+            )
+            .unwrap()
+        };
+        let exp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 600;
+
+        let good =
+            encode(&serde_json::json!({"sub": "robot-7", "aud": "calc-clients", "exp": exp}));
+        let claims = verifier.verify(&good, &config).unwrap();
+        assert_eq!(claims["sub"], "robot-7");
+
+        let no_sub = encode(&serde_json::json!({"aud": "calc-clients", "exp": exp}));
+        let err = verifier.verify(&no_sub, &config).unwrap_err();
+        assert!(
+            err.to_string().contains("missing required claim: sub"),
+            "{err}"
+        );
+
+        let wrong_aud = encode(&serde_json::json!({"sub": "robot-7", "aud": "other", "exp": exp}));
+        assert!(verifier.verify(&wrong_aud, &config).is_err());
+    }
+}
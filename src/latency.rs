@@ -0,0 +1,248 @@
+//! Per-route request-latency histograms, HDR-style: power-of-two
+//! magnitude buckets split into linear sub-buckets, so percentiles come
+//! out with ~6% relative error over 1µs..~67s from a fixed array of
+//! atomics. Recording is lock-free and sits on the request path; reads
+//! merge the per-thread stripes.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Linear sub-buckets per power of two; 16 gives 1/16 resolution.
+const SUB_BUCKETS: usize = 16;
+const SUB_SHIFT: usize = 4;
+
+/// Slots cover 1µs up to ~67s; anything slower lands in the last slot,
+/// which only flattens the tail of a request that already timed out.
+const SLOTS: usize = 384;
+
+/// Stripes per route, indexed by a hash of the recording thread, so
+/// workers rarely touch the same cache lines.
+const STRIPES: usize = 4;
+
+/// A fixed-layout histogram of microsecond latencies. All atomics, so
+/// recording never blocks and a reader merging mid-record at worst
+/// misses the sample it raced with.
+struct Histogram {
+    counts: Vec<AtomicU64>,
+    max_micros: AtomicU64,
+}
+
+/// The slot a value falls in: values below 16µs map linearly, above
+/// that the top four value bits select the sub-bucket within the
+/// value's power of two.
+fn slot(micros: u64) -> usize {
+    if micros < SUB_BUCKETS as u64 {
+        return micros as usize;
+    }
+    let magnitude = 63 - micros.leading_zeros() as usize;
+    let sub = (micros >> (magnitude - SUB_SHIFT)) as usize & (SUB_BUCKETS - 1);
+    ((magnitude - SUB_SHIFT + 1) * SUB_BUCKETS + sub).min(SLOTS - 1)
+}
+
+/// The upper edge of a slot, the value reported for percentiles that
+/// fall in it.
+fn slot_upper_micros(slot: usize) -> u64 {
+    if slot < SUB_BUCKETS {
+        return slot as u64 + 1;
+    }
+    let magnitude = slot / SUB_BUCKETS - 1 + SUB_SHIFT;
+    let sub = (slot % SUB_BUCKETS) as u64;
+    (SUB_BUCKETS as u64 + sub + 1) << (magnitude - SUB_SHIFT)
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Histogram {
+            counts: (0..SLOTS).map(|_| AtomicU64::new(0)).collect(),
+            max_micros: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, micros: u64) {
+        self.counts[slot(micros)].fetch_add(1, Ordering::Relaxed);
+        self.max_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+}
+
+/// One histogram per stripe; the recording thread picks its stripe by
+/// identity, readers fold all of them.
+struct RouteHistograms {
+    stripes: Vec<Histogram>,
+}
+
+impl RouteHistograms {
+    fn new() -> Self {
+        RouteHistograms {
+            stripes: (0..STRIPES).map(|_| Histogram::new()).collect(),
+        }
+    }
+
+    fn record(&self, micros: u64) {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        std::thread::current().id().hash(&mut hasher);
+        self.stripes[hasher.finish() as usize % STRIPES].record(micros);
+    }
+
+    /// Merges the stripes and reads the percentiles off the fold.
+    fn summary(&self) -> Option<RouteLatency> {
+        let mut counts = [0u64; SLOTS];
+        let mut max_micros = 0;
+        for stripe in &self.stripes {
+            for (slot, count) in stripe.counts.iter().enumerate() {
+                counts[slot] += count.load(Ordering::Relaxed);
+            }
+            max_micros = max_micros.max(stripe.max_micros.load(Ordering::Relaxed));
+        }
+        let samples: u64 = counts.iter().sum();
+        if samples == 0 {
+            return None;
+        }
+
+        let percentile = |q: f64| {
+            let rank = ((samples as f64) * q).ceil() as u64;
+            let mut seen = 0;
+            for (slot, count) in counts.iter().enumerate() {
+                seen += count;
+                if seen >= rank {
+                    return slot_upper_micros(slot) as f64 / 1_000.0;
+                }
+            }
+            max_micros as f64 / 1_000.0
+        };
+
+        Some(RouteLatency {
+            samples,
+            p50_ms: percentile(0.50),
+            p90_ms: percentile(0.90),
+            p99_ms: percentile(0.99),
+            p999_ms: percentile(0.999),
+            max_ms: max_micros as f64 / 1_000.0,
+        })
+    }
+}
+
+/// The per-route latency percentiles surfaced on /stats and /metrics.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RouteLatency {
+    pub samples: u64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub p999_ms: f64,
+    /// Exact, not bucketed: the slowest request seen.
+    pub max_ms: f64,
+}
+
+/// The process-wide registry, one striped histogram per route pattern.
+/// Like Stats, the map only grows on a route's first request, so the
+/// hot path is a read lock around lock-free recording.
+pub struct Latency {
+    routes: RwLock<BTreeMap<String, Arc<RouteHistograms>>>,
+}
+
+impl Latency {
+    fn new() -> Self {
+        Latency {
+            routes: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn global() -> &'static Latency {
+        static LATENCY: OnceLock<Latency> = OnceLock::new();
+        LATENCY.get_or_init(Latency::new)
+    }
+
+    pub fn record(&self, route: &str, latency: Duration) {
+        let micros = latency.as_micros() as u64;
+        if let Some(histograms) = self.routes.read().unwrap().get(route) {
+            histograms.record(micros);
+            return;
+        }
+        self.routes
+            .write()
+            .unwrap()
+            .entry(route.to_owned())
+            .or_insert_with(|| Arc::new(RouteHistograms::new()))
+            .record(micros);
+    }
+
+    /// Percentiles per route, merged across the stripes at read time.
+    pub fn snapshot(&self) -> BTreeMap<String, RouteLatency> {
+        self.routes
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|(route, histograms)| histograms.summary().map(|s| (route.clone(), s)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_land_within_the_bucket_resolution() {
+        let latency = Latency::new();
+        for micros in 1..=10_000u64 {
+            latency.record("/api/v0/add", Duration::from_micros(micros));
+        }
+
+        let snapshot = latency.snapshot();
+        let route = &snapshot["/api/v0/add"];
+        assert_eq!(route.samples, 10_000);
+        // Upper bucket edges over-report by at most one sub-bucket: ~6%.
+        assert!((5.0..=5.4).contains(&route.p50_ms), "p50: {}", route.p50_ms);
+        assert!((9.0..=9.6).contains(&route.p90_ms), "p90: {}", route.p90_ms);
+        assert!(
+            (9.9..=10.7).contains(&route.p99_ms),
+            "p99: {}",
+            route.p99_ms
+        );
+        assert_eq!(route.max_ms, 10.0);
+
+        // Routes never hit are absent rather than all-zero.
+        assert!(!snapshot.contains_key("/api/v0/div"));
+    }
+
+    #[test]
+    fn slots_are_contiguous_and_ordered() {
+        let mut previous = 0;
+        for micros in 1..1_000_000u64 {
+            let index = slot(micros);
+            assert!(
+                index == previous || index == previous + 1,
+                "gap at {micros}: {previous} -> {index}"
+            );
+            assert!(slot_upper_micros(index) >= micros, "edge below {micros}");
+            previous = index;
+        }
+    }
+
+    /// Benchmark-shaped guard rather than a benchmark: recording must
+    /// stay in the nanoseconds so it never shows up in request latency.
+    /// The bound is loose enough not to flake on a busy CI runner.
+    #[test]
+    fn recording_overhead_stays_below_a_few_microseconds() {
+        let latency = Latency::new();
+        latency.record("/api/v0/add", Duration::from_micros(100));
+
+        let samples = 100_000u32;
+        let started = std::time::Instant::now();
+        for i in 0..samples {
+            latency.record("/api/v0/add", Duration::from_micros(u64::from(i % 1000)));
+        }
+        let per_record = started.elapsed() / samples;
+        assert!(
+            per_record < Duration::from_micros(3),
+            "recording took {per_record:?} per sample"
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use std::sync::Arc;
+
+use actix_cors::Cors;
+use actix_web::{
+    body::MessageBody,
+    dev::{Server, ServiceFactory, ServiceRequest, ServiceResponse},
+    web, App, HttpServer,
+};
+
+pub mod access_log;
+pub mod admin;
+pub mod body_echo;
+pub mod bootstrap;
+pub mod cache;
+pub mod calculator;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod client_ip;
+pub mod compression;
+pub mod config;
+pub mod db;
+pub mod error;
+pub mod expr;
+#[cfg(feature = "sentry")]
+pub mod feedback;
+pub mod handlers;
+pub mod health;
+pub mod history;
+pub mod housekeeping;
+pub mod idempotency;
+pub mod jobs;
+pub mod jwt;
+pub mod latency;
+pub mod load_shed;
+pub mod log_level;
+pub mod maintenance;
+pub mod metrics;
+pub mod middleware;
+pub mod negotiation;
+pub mod openapi;
+pub mod otlp;
+pub mod quota;
+pub mod rate_limit;
+pub mod reporter;
+#[cfg(feature = "sentry")]
+pub mod reporting;
+pub mod security_headers;
+pub mod signature;
+pub mod single_flight;
+#[cfg(feature = "sentry")]
+pub mod spool;
+pub mod stats;
+#[cfg(feature = "sentry")]
+pub mod telemetry;
+pub mod tenant;
+pub mod timeout;
+pub mod tls;
+#[cfg(feature = "sentry")]
+pub mod tunnel;
+pub mod v1;
+pub mod version;
+pub mod webhook;
+pub mod ws;
+
+pub use error::{Error, HTTPError, HttpResult, Result};
+
+/// Binds the HttpServer according to the Config, with signal handling left
+/// to the caller so that shutdown can drain in-flight requests and flush
+/// sentry. Returns the running server and the addresses actually bound
+/// (which matter when the configured port is 0).
+pub fn build_server(config: &config::Config) -> Result<(Server, Vec<std::net::SocketAddr>)> {
+    let mut server = HttpServer::new(create_app);
+    if let Some(workers) = config.workers {
+        server = server.workers(workers);
+    }
+    let server = server
+        .disable_signals()
+        .shutdown_timeout(config.shutdown_grace_secs);
+
+    // With a configured cert/key pair the listener speaks TLS directly
+    // (no reverse proxy required); otherwise plain HTTP as before.
+    let mut server = match (&config.tls_cert_path, &config.tls_key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            let resolver = tls::ReloadingCertResolver::from_files(cert_path, key_path)?;
+            tls::spawn_reload(Arc::clone(&resolver), config.tls_reload_secs);
+            server.bind_rustls_0_23(
+                (config.host.as_str(), config.port),
+                tls::server_config(resolver)?,
+            )?
+        }
+        _ => server.bind((config.host.as_str(), config.port))?,
+    };
+
+    // The unix socket listener, alongside TCP. The file must not exist
+    // when we bind, so a socket left behind by an unclean exit is swept
+    // first; main removes it again on graceful shutdown.
+    if let Some(uds_path) = &config.uds_path {
+        let path = std::path::Path::new(uds_path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|err| error::Error::Config {
+                var: "APP_UDS_PATH",
+                message: format!("{uds_path}: {err}"),
+            })?;
+        }
+        if path.exists() {
+            std::fs::remove_file(path).map_err(|err| error::Error::Config {
+                var: "APP_UDS_PATH",
+                message: format!("{uds_path}: stale socket could not be removed: {err}"),
+            })?;
+        }
+        server = server.bind_uds(path)?;
+        // After the bind, so the mode applies to the socket actually
+        // being served rather than racing its creation.
+        std::fs::set_permissions(
+            path,
+            std::os::unix::fs::PermissionsExt::from_mode(config.uds_mode),
+        )?;
+    }
+    let addrs = server.addrs();
+
+    Ok((server.run(), addrs))
+}
+
+/// When /api/v0 goes away; surfaced to clients in the Sunset header so
+/// they can plan the migration to /api/v1.
+const V0_SUNSET: &str = "Wed, 01 Sep 2027 00:00:00 GMT";
+
+/// Registers the /api/v0 and /api/v1 scopes with all their routes,
+/// exactly as the server runs them, so that tests can mount the same
+/// services.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    let v0 = web::scope("/api/v0")
+        // v0 is deprecated in favour of the enveloped v1 responses.
+        .wrap(
+            actix_web::middleware::DefaultHeaders::new()
+                .add(("deprecation", "true"))
+                .add(("sunset", V0_SUNSET)),
+        )
+        .app_data(web::JsonConfig::default().error_handler(handlers::json_error_handler))
+        .app_data(web::QueryConfig::default().error_handler(handlers::query_error_handler))
+        // Raw-body extractors (Negotiated) default to 256KB; keep the
+        // limit aligned with JsonConfig's 2MB.
+        .app_data(web::PayloadConfig::new(2 * 1024 * 1024))
+        .service(handlers::status)
+        .service(handlers::version)
+        .service(handlers::handle_add)
+        .service(handlers::handle_sub)
+        .service(handlers::handle_mul)
+        .service(handlers::handle_div)
+        .service(handlers::handle_mod)
+        .service(handlers::handle_pow)
+        .service(handlers::handle_add_query)
+        .service(handlers::handle_sub_query)
+        .service(handlers::handle_mul_query)
+        .service(handlers::handle_div_query)
+        .service(handlers::handle_mod_query)
+        .service(handlers::handle_pow_query)
+        .service(handlers::handle_gcd)
+        .service(handlers::handle_lcm)
+        .service(handlers::handle_and)
+        .service(handlers::handle_or)
+        .service(handlers::handle_xor)
+        .service(handlers::handle_shl)
+        .service(handlers::handle_shr)
+        .service(handlers::handle_convert)
+        .service(handlers::handle_factorial)
+        .service(handlers::handle_choose)
+        .service(handlers::handle_aggregate)
+        .service(handlers::handle_calc)
+        .service(handlers::handle_eval)
+        .service(handlers::handle_batch)
+        .service(handlers::handle_batch_stream)
+        .service(jobs::submit_job)
+        .service(jobs::get_job)
+        .service(jobs::cancel_job)
+        .service(ws::connect)
+        .service(history::get_history)
+        .service(history::get_history_entry)
+        .service(history::clear_history)
+        .service(stats::get_stats)
+        .service(quota::get_usage)
+        .service(
+            web::scope("/float")
+                .service(handlers::handle_float_add)
+                .service(handlers::handle_float_sub)
+                .service(handlers::handle_float_mul)
+                .service(handlers::handle_float_div),
+        )
+        .service(web::scope("/decimal").service(handlers::handle_decimal_calc));
+    // The tunnel only exists when sentry is compiled in; the browser
+    // SDK retries through it like any other ingest endpoint.
+    #[cfg(feature = "sentry")]
+    let v0 = v0
+        .service(tunnel::tunnel)
+        .service(feedback::submit_feedback);
+    cfg.service(v0);
+
+    #[cfg(debug_assertions)]
+    {
+        cfg.service(handlers::debug_panic);
+        cfg.service(handlers::debug_sleep);
+    }
+
+    cfg.service(
+        web::scope("/api/v1")
+            .app_data(web::JsonConfig::default().error_handler(handlers::json_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(handlers::query_error_handler))
+            .app_data(web::PayloadConfig::new(2 * 1024 * 1024))
+            .service(v1::add)
+            .service(v1::sub)
+            .service(v1::mul)
+            .service(v1::div)
+            .service(v1::modulo)
+            .service(v1::pow)
+            .service(v1::calc),
+    );
+}
+
+/// CORS policy from config: explicit allow-lists by default, with an
+/// opt-in permissive fallback for local development.
+fn build_cors(config: &config::Config) -> Cors {
+    if config.cors_permissive {
+        return Cors::permissive();
+    }
+
+    let mut cors = Cors::default().allowed_methods(
+        config
+            .cors_allowed_methods
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>(),
+    );
+
+    for origin in &config.cors_allowed_origins {
+        cors = cors.allowed_origin(origin);
+    }
+
+    if config.cors_allowed_headers.is_empty() {
+        cors = cors.allow_any_header();
+    } else {
+        cors = cors.allowed_headers(
+            config
+                .cors_allowed_headers
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    if let Some(max_age) = config.cors_max_age {
+        cors = cors.max_age(max_age);
+    }
+
+    cors
+}
+
+/// Builds the actix App with CORS, the logging middleware and all routes.
+/// Used both by main's HttpServer::new and by actix_web::test::init_service.
+pub fn create_app() -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let config = config::Config::global();
+    let cors = build_cors(&config);
+    let app = App::new()
+        // wrap() runs in reverse registration order: Middleware first (it
+        // sets up the per-request hub), then CORS, then Auth, then the
+        // tenant gate (so the rate limiter and everything inside it sees
+        // the resolved tenant), then the rate limiter, then the
+        // maintenance gate, then the timeout, the
+        // concurrency limiter (so the timeout budget covers any wait for
+        // a permit), and innermost the idempotency cache — replays still
+        // count against rate limits but skip the handler entirely. The
+        // body echo buffer sits inside even that, so only requests that
+        // actually reach a handler are buffered.
+        .wrap(body_echo::BodyEcho)
+        .wrap(idempotency::Idempotency)
+        .wrap(load_shed::LoadShed)
+        .wrap(timeout::Timeout)
+        .wrap(maintenance::MaintenanceGate)
+        .wrap(quota::Quota)
+        .wrap(rate_limit::RateLimit)
+        .wrap(tenant::TenantGate)
+        .wrap(signature::Signature)
+        .wrap(jwt::JwtAuth)
+        .wrap(middleware::Auth)
+        .wrap(cors)
+        .wrap(middleware::Middleware)
+        // Compression sits outside the access log so encoded requests
+        // are inflated before anything inside reads the body, and
+        // security headers sit outermost so even middleware-generated
+        // responses carry them.
+        .wrap(compression::Compression)
+        .wrap(security_headers::SecurityHeaders)
+        .app_data(web::Data::from(reporter::shared()))
+        .app_data(web::Data::from(config::Config::global()))
+        .app_data(web::Data::from(metrics::Metrics::global()))
+        .app_data(web::Data::from(rate_limit::RateLimiterState::global()))
+        .app_data(web::Data::from(health::Readiness::global()))
+        .app_data(web::Data::from(cache::ResultCache::global()))
+        .app_data(web::Data::from(history::History::global()))
+        .app_data(web::Data::from(idempotency::IdempotencyStore::global()))
+        .app_data(web::Data::from(load_shed::LoadShedState::global()))
+        .app_data(web::Data::from(log_level::LogLevel::global()))
+        .app_data(web::Data::from(maintenance::Maintenance::global()))
+        .app_data(web::Data::from(stats::Stats::global()))
+        .app_data(web::Data::from(db::Db::global()))
+        .service(health::healthz)
+        .service(health::readyz)
+        .service(metrics::scrape)
+        .service(openapi::spec)
+        .service(openapi::docs)
+        .configure(configure);
+
+    // Without a configured token the admin routes do not exist at all
+    // (404), rather than being present but open.
+    if config.admin_token.is_some() {
+        app.service(admin::admin_scope())
+    } else {
+        app
+    }
+}
@@ -0,0 +1,176 @@
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header,
+    Error, ResponseError,
+};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::warn;
+
+use crate::config::Config;
+use crate::metrics::Metrics;
+
+/// In-flight accounting and the semaphore that bounds it, shared
+/// app-wide via web::Data.
+pub struct LoadShedState {
+    semaphore: Option<Arc<Semaphore>>,
+    in_flight: AtomicU64,
+    shed_total: AtomicU64,
+}
+
+impl LoadShedState {
+    fn new(max_in_flight: Option<usize>) -> Self {
+        LoadShedState {
+            semaphore: max_in_flight.map(|max| Arc::new(Semaphore::new(max))),
+            in_flight: AtomicU64::new(0),
+            shed_total: AtomicU64::new(0),
+        }
+    }
+
+    pub fn global() -> Arc<LoadShedState> {
+        static STATE: OnceLock<Arc<LoadShedState>> = OnceLock::new();
+        STATE
+            .get_or_init(|| Arc::new(LoadShedState::new(Config::global().max_in_flight)))
+            .clone()
+    }
+
+    pub fn in_flight(&self) -> u64 {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    pub fn shed_total(&self) -> u64 {
+        self.shed_total.load(Ordering::Relaxed)
+    }
+
+    /// A permit within the wait budget, None when limiting is disabled,
+    /// or Err when the request should be shed.
+    async fn acquire(
+        &self,
+        wait: Duration,
+    ) -> std::result::Result<Option<OwnedSemaphorePermit>, ()> {
+        let Some(semaphore) = &self.semaphore else {
+            return Ok(None);
+        };
+
+        match tokio::time::timeout(wait, semaphore.clone().acquire_owned()).await {
+            Ok(Ok(permit)) => Ok(Some(permit)),
+            // The semaphore is never closed; a timeout is the only miss.
+            _ => Err(()),
+        }
+    }
+}
+
+/// Decrements the in-flight accounting even if the handler unwinds.
+struct InFlightGuard {
+    state: Arc<LoadShedState>,
+    metrics: Arc<Metrics>,
+}
+
+impl InFlightGuard {
+    fn enter(state: Arc<LoadShedState>, metrics: Arc<Metrics>) -> Self {
+        state.in_flight.fetch_add(1, Ordering::Relaxed);
+        metrics.http_in_flight_requests.inc();
+        InFlightGuard { state, metrics }
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.state.in_flight.fetch_sub(1, Ordering::Relaxed);
+        self.metrics.http_in_flight_requests.dec();
+    }
+}
+
+/// Bounds the number of in-flight requests and sheds the excess with a
+/// 503 instead of queueing unboundedly. Disabled unless
+/// APP_MAX_IN_FLIGHT is configured; probe endpoints are exempt so
+/// health checks succeed during overload.
+pub struct LoadShed;
+
+impl<S, B> Transform<S, ServiceRequest> for LoadShed
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = LoadShedService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(LoadShedService {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct LoadShedService<S> {
+    // Rc because the permit has to be acquired before the inner call,
+    // inside the boxed future.
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for LoadShedService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            if crate::middleware::is_public_path(req.path()) {
+                return service.call(req).await.map(|res| res.map_into_left_body());
+            }
+
+            let config = Config::global();
+            let state = LoadShedState::global();
+            let metrics = Metrics::global();
+
+            let permit = match state
+                .acquire(Duration::from_millis(config.shed_wait_ms))
+                .await
+            {
+                Ok(permit) => permit,
+                Err(()) => {
+                    let route = req
+                        .match_pattern()
+                        .unwrap_or_else(|| req.path().to_string());
+                    state.shed_total.fetch_add(1, Ordering::Relaxed);
+                    metrics
+                        .http_requests_shed_total
+                        .with_label_values(&[&route])
+                        .inc();
+                    warn!(route, "shedding load: too many in-flight requests");
+
+                    let mut response =
+                        crate::error::HTTPError::from(crate::error::Error::Overloaded)
+                            .error_response();
+                    response.headers_mut().insert(header::RETRY_AFTER, 1.into());
+                    return Ok(req.into_response(response).map_into_right_body());
+                }
+            };
+
+            let guard = InFlightGuard::enter(state, metrics);
+            let res = service.call(req).await;
+            drop(guard);
+            drop(permit);
+            res.map(|res| res.map_into_left_body())
+        })
+    }
+}
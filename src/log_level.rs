@@ -0,0 +1,112 @@
+use std::sync::{Arc, Mutex, OnceLock};
+
+use actix_web::{get, put, web, Responder};
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::filter::EnvFilter;
+use utoipa::ToSchema;
+
+use crate::error::{Error, HttpResult};
+
+/// Hooks the running subscriber's reload handle; boxed because the
+/// handle's type names the whole layer stack beneath it.
+type ReloadFn = Box<dyn Fn(EnvFilter) -> std::result::Result<(), String> + Send + Sync>;
+
+/// The runtime-adjustable log filter. main installs the reload handle
+/// after the subscriber is built; until then (and in tests) changes are
+/// validated and recorded without touching a subscriber.
+pub struct LogLevel {
+    current: Mutex<String>,
+    reload: OnceLock<ReloadFn>,
+}
+
+impl LogLevel {
+    fn new() -> Self {
+        LogLevel {
+            current: Mutex::new(crate::config::Config::global().log_filter.clone()),
+            reload: OnceLock::new(),
+        }
+    }
+
+    pub fn global() -> Arc<LogLevel> {
+        static LOG_LEVEL: OnceLock<Arc<LogLevel>> = OnceLock::new();
+        LOG_LEVEL.get_or_init(|| Arc::new(LogLevel::new())).clone()
+    }
+
+    /// Called once from main with a closure over the subscriber's reload
+    /// handle.
+    pub fn install(&self, reload: ReloadFn) {
+        let _ = self.reload.set(reload);
+    }
+
+    pub fn current(&self) -> String {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Validates and applies the directive, returning the one it
+    /// replaced.
+    pub fn set(&self, directive: &str) -> crate::error::Result<String> {
+        let filter = EnvFilter::try_new(directive)
+            .map_err(|err| Error::InvalidLogFilter(err.to_string()))?;
+
+        if let Some(reload) = self.reload.get() {
+            reload(filter).map_err(Error::InvalidLogFilter)?;
+        }
+
+        let mut current = self.current.lock().unwrap();
+        let previous = std::mem::replace(&mut *current, directive.to_owned());
+        Ok(previous)
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LogLevelResponse {
+    pub filter: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LogLevelRequest {
+    /// An EnvFilter directive string, e.g. "debug" or
+    /// "sentry_rs_demo=trace,actix_web=info".
+    pub filter: String,
+}
+
+#[utoipa::path(
+    context_path = "/admin",
+    responses(
+        (status = 200, description = "The filter currently applied", body = LogLevelResponse),
+    ),
+    tag = "admin"
+)]
+#[get("/log-level")]
+pub async fn get_log_level(log_level: web::Data<LogLevel>) -> impl Responder {
+    web::Json(LogLevelResponse {
+        filter: log_level.current(),
+        previous: None,
+    })
+}
+
+#[utoipa::path(
+    context_path = "/admin",
+    request_body = LogLevelRequest,
+    responses(
+        (status = 200, description = "Filter applied; the body carries the one it replaced", body = LogLevelResponse),
+        (status = 400, description = "The directive did not parse", body = crate::openapi::ErrorBody),
+    ),
+    tag = "admin"
+)]
+#[put("/log-level")]
+pub async fn set_log_level(
+    req: actix_web::HttpRequest,
+    log_level: web::Data<LogLevel>,
+    body: web::Json<LogLevelRequest>,
+) -> HttpResult<web::Json<LogLevelResponse>> {
+    let previous = log_level.set(&body.filter)?;
+    crate::admin::audit(&req, "log-level", &previous, &body.filter);
+
+    Ok(web::Json(LogLevelResponse {
+        filter: body.filter.clone(),
+        previous: Some(previous),
+    }))
+}
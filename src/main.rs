@@ -1,244 +1,311 @@
-use std::{env, sync::Arc};
-
-use actix_cors::Cors;
-use actix_web::{
-    get,
-    http::{header::ContentType, StatusCode},
-    post, web, App, HttpResponse, HttpServer, Responder, ResponseError,
-};
-use middleware::Middleware;
+use clap::Parser;
+#[cfg(feature = "sentry")]
 use sentry::ClientInitGuard;
-use sentry_tracing::EventFilter;
-use serde::{Deserialize, Serialize};
-use tracing::{error, info};
+use sentry_rs_demo::{
+    build_server,
+    config::{Cli, Command, Config, LogRotation},
+    Error, Result,
+};
+use tracing::{info, warn};
 use tracing_subscriber::{filter::EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
-mod middleware;
-
-#[derive(Debug, thiserror::Error)]
-pub enum Error {
-    #[error("cannot divide by zero")]
-    DivideByZero,
-
-    #[error("SENRTY_DSN is unset")]
-    MissingSentryDsn,
-
-    #[error(transparent)]
-    Actix(#[from] actix_web::Error),
-
-    #[error(transparent)]
-    DotEnvy(#[from] dotenvy::Error),
-
-    #[error(transparent)]
-    Io(#[from] std::io::Error),
+/// Everything init_tracing hands back that must stay alive (and be
+/// flushed) until the end of main.
+struct TelemetryGuards {
+    #[cfg(feature = "sentry")]
+    sentry: Option<ClientInitGuard>,
+    /// Held only for its Drop, which flushes the non-blocking writer.
+    _log: Option<tracing_appender::non_blocking::WorkerGuard>,
+    otlp: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
 }
 
-#[derive(Debug)]
-pub struct HTTPError {
-    pub status_code: StatusCode,
-    pub source: Box<dyn std::error::Error>,
-}
-
-impl std::error::Error for HTTPError {}
-
-impl std::fmt::Display for HTTPError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{{ {}, {} }}", self.status_code, self.source)
+async fn init_tracing(config: &Config) -> Result<TelemetryGuards> {
+    // Validation (including SENTRY_REQUIRED) happened in Config::load;
+    // here an absent DSN just means reporting stays off.
+    #[cfg(feature = "sentry")]
+    let guard = match &config.sentry_dsn {
+        None => None,
+        Some(sentry_dsn) => {
+            let dsn = sentry_dsn.parse().map_err(|_| Error::Config {
+                var: "SENTRY_DSN",
+                message: format!("not a valid DSN: {sentry_dsn}"),
+            })?;
+            Some(sentry::init(
+                sentry_rs_demo::telemetry::build_sentry_options(config, Some(dsn))?,
+            ))
+        }
+    };
+
+    #[cfg(feature = "sentry")]
+    let sentry_layer = sentry_tracing::layer()
+        .event_filter(sentry_rs_demo::telemetry::tracing_event_filter)
+        // #[tracing::instrument] handler spans become children of the
+        // per-request transaction started by the middleware.
+        .span_filter(|md| md.is_span());
+
+    // Behind a reload layer so PUT /admin/log-level can swap the filter
+    // without a restart.
+    let (log_level_filter, reload_handle) =
+        tracing_subscriber::reload::Layer::new(EnvFilter::new(&config.log_filter));
+    sentry_rs_demo::log_level::LogLevel::global().install(Box::new(move |filter| {
+        reload_handle.reload(filter).map_err(|err| err.to_string())
+    }));
+    // Optional rotating file output: always JSON, through a non-blocking
+    // writer so a slow disk cannot stall request handling. A directory
+    // that cannot take the first file fails startup here rather than
+    // silently logging nowhere.
+    let (file_layer, file_guard) = match &config.log_dir {
+        Some(dir) => {
+            let rotation = match config.log_rotation {
+                LogRotation::Daily => tracing_appender::rolling::Rotation::DAILY,
+                LogRotation::Hourly => tracing_appender::rolling::Rotation::HOURLY,
+                LogRotation::Never => tracing_appender::rolling::Rotation::NEVER,
+            };
+            let appender = tracing_appender::rolling::RollingFileAppender::builder()
+                .rotation(rotation)
+                .filename_prefix("sentry-rs-demo")
+                .filename_suffix("log")
+                .build(dir)
+                .map_err(|err| Error::Config {
+                    var: "LOG_DIR",
+                    message: format!("{dir}: {err}"),
+                })?;
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            (
+                Some(
+                    tracing_subscriber::fmt::layer()
+                        .json()
+                        .with_ansi(false)
+                        .with_writer(writer),
+                ),
+                Some(guard),
+            )
+        }
+        None => (None, None),
+    };
+
+    // The OTLP layer exports the same handler spans to the collector
+    // that sentry sees; the two coexist in the registry, sentry keeping
+    // the errors.
+    let (otel_layer, otlp_provider) = match &config.otlp_endpoint {
+        Some(endpoint) => {
+            use opentelemetry::trace::TracerProvider as _;
+            let provider = sentry_rs_demo::otlp::build_otlp_provider(endpoint)?;
+            let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+            (
+                Some(tracing_opentelemetry::layer().with_tracer(tracer)),
+                Some(provider),
+            )
+        }
+        None => (None, None),
+    };
+
+    let registry = tracing_subscriber::registry()
+        .with(log_level_filter)
+        .with(file_layer)
+        .with(otel_layer);
+    // The field layer records span state so captures can attach it as
+    // the event's "tracing" context.
+    #[cfg(feature = "sentry")]
+    let registry = registry
+        .with(sentry_rs_demo::telemetry::SpanFieldsLayer)
+        .with(sentry_layer);
+
+    if config.log_format_json {
+        registry
+            .with(tracing_subscriber::fmt::layer().json())
+            .init();
+    } else {
+        registry.with(tracing_subscriber::fmt::layer()).init();
     }
-}
 
-impl From<Error> for HTTPError {
-    fn from(err: Error) -> Self {
-        let http_error = match err {
-            Error::DivideByZero => HTTPError {
-                status_code: StatusCode::BAD_REQUEST,
-                source: err.into(),
-            },
-            _ => HTTPError {
-                status_code: StatusCode::INTERNAL_SERVER_ERROR,
-                source: err.into(),
-            },
-        };
-
-        sentry::with_scope(
-            |scope| {
-                scope.set_extra("status_code", http_error.status_code.as_u16().into());
-            },
-            || sentry::capture_error(&http_error),
-        );
-
-        http_error
+    #[cfg(feature = "sentry")]
+    if guard.is_none() {
+        warn!("SENTRY_DSN is unset; error reporting to sentry is disabled");
+    } else {
+        // On the main hub before workers spawn, so every derived hub (and
+        // thus every event) carries the build metadata.
+        sentry::configure_scope(|scope| {
+            scope.set_context("runtime", sentry_rs_demo::version::runtime_context());
+        });
+        sentry_rs_demo::version::ServerContext::gather(config).install();
     }
+    #[cfg(not(feature = "sentry"))]
+    info!("built without the sentry feature; error reporting is compiled out");
+
+    Ok(TelemetryGuards {
+        #[cfg(feature = "sentry")]
+        sentry: guard,
+        _log: file_guard,
+        otlp: otlp_provider,
+    })
 }
 
-impl ResponseError for HTTPError {}
-
-pub type HttpResult<T> = std::result::Result<T, HTTPError>;
-
-pub type Result<T> = std::result::Result<T, Error>;
-
-async fn add(x: i32, y: i32) -> Result<i32> {
-    Ok(x + y)
-}
-
-async fn sub(x: i32, y: i32) -> Result<i32> {
-    Ok(x - y)
-}
-
-async fn mul(x: i32, y: i32) -> Result<i32> {
-    Ok(x * y)
-}
+/// Resolves when either SIGINT (ctrl-C) or SIGTERM arrives.
+async fn shutdown_signal() {
+    let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        .expect("failed to install SIGTERM handler");
 
-async fn div(x: i32, y: i32) -> Result<i32> {
-    if y == 0 {
-        Err(Error::DivideByZero)
-    } else {
-        Ok(x + y)
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {},
+        _ = sigterm.recv() => {},
     }
 }
 
-async fn init_tracing() -> Result<ClientInitGuard> {
-    let sentry_dsn = env::var("SENTRY_DSN").map_err(|_| Error::MissingSentryDsn)?;
-    let _guard = sentry::init((
-        sentry_dsn,
-        sentry::ClientOptions {
-            release: sentry::release_name!(),
-            before_send: Some(Arc::new(|event| {
-                if let Some(status_code) = event.extra.get("status_code") {
-                    let status_code = status_code.as_u64().unwrap_or(200);
-
-                    if (400..500).contains(&status_code) {
-                        println!("Intercepted a 4xx notification");
-                        return None;
-                    }
+/// The `calc` subcommand: the handlers' own code path, no server. The
+/// success JSON matches the HTTP response body; errors render the same
+/// enveloped body the API would, on stderr, with the ids a served
+/// request would have carried left null.
+fn run_calc(op: &str, x: i64, y: i64) -> Result<()> {
+    use sentry_rs_demo::calculator::Operation;
+
+    match op
+        .parse::<Operation>()
+        .and_then(|op| sentry_rs_demo::handlers::calculate_local(op, x, y))
+    {
+        Ok(response) => {
+            println!(
+                "{}",
+                serde_json::to_string(&response).expect("the response type serializes")
+            );
+            Ok(())
+        }
+        Err(err) => {
+            let body = serde_json::json!({
+                "error": {
+                    "code": err.code(),
+                    "message": err.to_string(),
+                    "status": err.status_code().as_u16(),
+                    "request_id": null,
+                    "trace_id": null,
+                    "event_id": null,
                 }
-                Some(event)
-            })),
-            ..Default::default()
-        },
-    ));
-
-    let sentry_layer = sentry_tracing::layer().event_filter(|md| match md.level() {
-        &tracing::Level::ERROR => EventFilter::Event,
-        _ => EventFilter::Ignore,
-    });
-
-    let log_level_filter = EnvFilter::new("INFO");
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
-        .with(sentry_layer)
-        .with(log_level_filter)
-        .init();
-
-    Ok(_guard)
+            });
+            eprintln!("{body}");
+            std::process::exit(1);
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
-pub struct CalculationRequest {
-    x: i32,
-    y: i32,
-}
+#[tokio::main]
+async fn main() -> Result<()> {
+    sentry_rs_demo::bootstrap::load_env_files()?;
+    let cli = Cli::parse();
+
+    // Subcommands that do their work and exit, before any server
+    // machinery spins up.
+    match &cli.command {
+        Some(Command::Calc { op, x, y }) => return run_calc(op, *x, *y),
+        Some(Command::CheckConfig) => {
+            // A config error propagates as the usual non-zero exit.
+            let _ = Config::try_global_with(&cli)?;
+            println!("configuration OK");
+            return Ok(());
+        }
+        Some(Command::Serve) | None => {}
+    }
 
-#[derive(Debug, Serialize)]
-pub struct CalculationResponse {
-    res: i32,
-}
+    let readiness = sentry_rs_demo::health::Readiness::global();
 
-#[tracing::instrument]
-#[post("/add")]
-async fn handle_add(
-    body: web::Json<CalculationRequest>,
-) -> HttpResult<web::Json<CalculationResponse>> {
-    info!(method = "handle_add", ?body, "adding two numbers together");
-    error!("add");
+    let config = Config::try_global_with(&cli)?;
+    if cli.print_config {
+        println!("{}", config.render_masked());
+        return Ok(());
+    }
+    readiness.mark_config_loaded();
 
-    let x = body.x;
-    let y = body.y;
+    let guards = init_tracing(&config).await?;
+    readiness.mark_tracing_initialized();
 
-    let sum = add(x, y).await?;
-    Ok(web::Json(CalculationResponse { res: sum }))
-}
+    // Loads PEM/JWKS verification keys up front, so a bad key source
+    // fails the deploy here rather than on the first bearer token.
+    sentry_rs_demo::jwt::init(&config)?;
 
-#[tracing::instrument]
-#[post("/sub")]
-async fn handle_sub(
-    body: web::Json<CalculationRequest>,
-) -> HttpResult<web::Json<CalculationResponse>> {
-    info!(
-        method = "handle_sub",
-        ?body,
-        "subtracting a number from another"
-    );
-
-    let x = body.x;
-    let y = body.y;
-
-    let diff = sub(x, y).await?;
-    Ok(web::Json(CalculationResponse { res: diff }))
-}
+    let (server, addrs) = build_server(&config)?;
+    readiness.mark_server_bound();
 
-#[tracing::instrument]
-#[post("/mul")]
-async fn handle_mul(
-    body: web::Json<CalculationRequest>,
-) -> HttpResult<web::Json<CalculationResponse>> {
-    info!(method = "handle_mul", ?body, "multiplying two numbers");
+    // With APP_PORT=0 the OS picks a free port; log the real one so
+    // tests (and humans) can find the server.
+    for addr in &addrs {
+        info!(%addr, "server listening");
+    }
 
-    let x = body.x;
-    let y = body.y;
+    // Keeps /readyz and the sentry_transport_up gauge tracking whether
+    // the DSN host is actually reachable; a no-op without a DSN.
+    #[cfg(feature = "sentry")]
+    sentry_rs_demo::telemetry::spawn_transport_probe(&config);
+
+    let housekeeping = if config.housekeeping_interval_secs > 0 {
+        Some(sentry_rs_demo::housekeeping::spawn(
+            std::sync::Arc::new(sentry_rs_demo::housekeeping::DefaultHousekeeping),
+            config.housekeeping_interval_secs,
+            config.housekeeping_monitor_slug.clone(),
+        ))
+    } else {
+        None
+    };
 
-    let prod = mul(x, y).await?;
-    Ok(web::Json(CalculationResponse { res: prod }))
-}
+    let stats_flusher = if config.stats_flush_interval_secs > 0 {
+        Some(sentry_rs_demo::stats::spawn_flusher(
+            config.stats_flush_interval_secs,
+        ))
+    } else {
+        None
+    };
+
+    let handle = server.handle();
+    let grace_secs = config.shutdown_grace_secs;
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        info!(
+            grace_secs,
+            "shutdown signal received; draining in-flight requests"
+        );
+        // Fail /readyz first so load balancers stop routing to us while
+        // in-flight requests drain.
+        sentry_rs_demo::health::Readiness::global().begin_shutdown();
+        handle.stop(true).await;
+    });
 
-#[tracing::instrument]
-#[post("/div")]
-async fn handle_div(
-    body: web::Json<CalculationRequest>,
-) -> HttpResult<web::Json<CalculationResponse>> {
-    info!(method = "handle_div", ?body, "Dividing a number by another");
+    server.await?;
 
-    let x = body.x;
-    let y = body.y;
+    // The socket file outlives the listener; leaving it around would
+    // make the next startup's stale-socket sweep do the honours instead.
+    if let Some(uds_path) = &config.uds_path {
+        let _ = std::fs::remove_file(uds_path);
+    }
 
-    let quot = div(x, y).await?;
-    Ok(web::Json(CalculationResponse { res: quot }))
-}
+    // Stop the housekeeping loop before flushing, so a final check-in
+    // is not lost in the transport buffer.
+    if let Some((task_handle, stop)) = housekeeping {
+        let _ = stop.send(());
+        let _ = task_handle.await;
+    }
 
-#[derive(Debug, Serialize)]
-pub struct StatusResponse {
-    status: String,
-}
+    if let Some((task_handle, stop)) = stats_flusher {
+        let _ = stop.send(());
+        let _ = task_handle.await;
+    }
 
-#[get("/status")]
-async fn status() -> impl Responder {
-    HttpResponse::Ok()
-        .content_type(ContentType::json())
-        .json(StatusResponse {
-            status: "OK".to_string(),
-        })
-}
+    #[cfg(feature = "sentry")]
+    if let Some(guard) = guards.sentry.as_ref() {
+        let started = std::time::Instant::now();
+        let flushed = guard.flush(Some(std::time::Duration::from_secs(grace_secs)));
+        info!(
+            waited_secs = started.elapsed().as_secs(),
+            flushed, "flushed buffered sentry events before exit"
+        );
+    }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    dotenvy::dotenv()?;
-
-    let _guard = init_tracing().await?;
-
-    HttpServer::new(|| {
-        let cors = Cors::permissive();
-        App::new().wrap(cors).wrap(Middleware).service(
-            web::scope("/api/v0")
-                .service(status)
-                .service(handle_add)
-                .service(handle_sub)
-                .service(handle_mul)
-                .service(handle_div),
-        )
-    })
-    .bind(("127.0.0.1", 9999))?
-    .run()
-    .await?;
+    // Shutting the provider down flushes batched OTLP spans; dropping
+    // the guards then flushes the non-blocking log writer, so neither
+    // loses its tail on exit.
+    if let Some(provider) = guards.otlp.as_ref() {
+        if let Err(err) = provider.shutdown() {
+            warn!(%err, "failed to flush OTLP spans before exit");
+        }
+    }
+    drop(guards);
 
     Ok(())
 }
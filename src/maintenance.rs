@@ -0,0 +1,171 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    get,
+    http::header,
+    post, web, Error, Responder, ResponseError,
+};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// What a 503 tells clients to wait before retrying; deploys are short.
+const RETRY_AFTER_SECS: u64 = 30;
+
+/// The deploy-time brown-out switch: while enabled, the API routes answer
+/// 503 and everything operational (health, status, metrics, admin) keeps
+/// working. Starts enabled when MAINTENANCE_MODE=true.
+pub struct Maintenance {
+    enabled: AtomicBool,
+}
+
+impl Maintenance {
+    fn new() -> Self {
+        let from_env = std::env::var("MAINTENANCE_MODE")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+        Maintenance {
+            enabled: AtomicBool::new(from_env),
+        }
+    }
+
+    pub fn global() -> Arc<Maintenance> {
+        static MAINTENANCE: OnceLock<Arc<Maintenance>> = OnceLock::new();
+        MAINTENANCE
+            .get_or_init(|| Arc::new(Maintenance::new()))
+            .clone()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+}
+
+/// Only the API itself is browned out; health checks, status, metrics
+/// and the admin routes must stay reachable to end the maintenance.
+fn is_gated_path(path: &str) -> bool {
+    path.starts_with("/api/") && !crate::middleware::is_public_path(path)
+}
+
+/// Short-circuits gated routes with a structured 503 while maintenance
+/// mode is on. Registered innermost so the access log, metrics and auth
+/// still apply to the 503s.
+pub struct MaintenanceGate;
+
+impl<S, B> Transform<S, ServiceRequest> for MaintenanceGate
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = MaintenanceGateService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(MaintenanceGateService { service }))
+    }
+}
+
+pub struct MaintenanceGateService<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for MaintenanceGateService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if Maintenance::global().is_enabled() && is_gated_path(req.path()) {
+            let mut response =
+                crate::error::HTTPError::from(crate::error::Error::Maintenance).error_response();
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, RETRY_AFTER_SECS.into());
+
+            return Box::pin(ready(Ok(req.into_response(response).map_into_right_body())));
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(|res| res.map_into_left_body()) })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MaintenanceState {
+    pub enabled: bool,
+}
+
+#[utoipa::path(
+    context_path = "/admin",
+    responses(
+        (status = 200, description = "Whether maintenance mode is on", body = MaintenanceState),
+    ),
+    tag = "admin"
+)]
+#[get("/maintenance")]
+pub async fn get_maintenance(maintenance: web::Data<Maintenance>) -> impl Responder {
+    web::Json(MaintenanceState {
+        enabled: maintenance.is_enabled(),
+    })
+}
+
+#[utoipa::path(
+    context_path = "/admin",
+    request_body = MaintenanceState,
+    responses(
+        (status = 200, description = "The new maintenance state", body = MaintenanceState),
+    ),
+    tag = "admin"
+)]
+#[post("/maintenance")]
+pub async fn set_maintenance(
+    req: actix_web::HttpRequest,
+    maintenance: web::Data<Maintenance>,
+    body: web::Json<MaintenanceState>,
+) -> impl Responder {
+    let previous = maintenance.is_enabled();
+    maintenance.set_enabled(body.enabled);
+    crate::admin::audit(
+        &req,
+        "maintenance",
+        &previous.to_string(),
+        &body.enabled.to_string(),
+    );
+    web::Json(MaintenanceState {
+        enabled: maintenance.is_enabled(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_api_routes_are_gated() {
+        assert!(is_gated_path("/api/v0/add"));
+        assert!(is_gated_path("/api/v1/calc"));
+        assert!(!is_gated_path("/api/v0/status"));
+        assert!(!is_gated_path("/healthz"));
+        assert!(!is_gated_path("/readyz"));
+        assert!(!is_gated_path("/metrics"));
+        assert!(!is_gated_path("/admin/maintenance"));
+    }
+}
@@ -0,0 +1,192 @@
+use std::sync::{Arc, OnceLock};
+
+use actix_web::{get, HttpResponse};
+#[cfg(feature = "sentry")]
+use prometheus::IntCounter;
+use prometheus::{GaugeVec, HistogramVec, IntCounterVec, IntGauge, Registry, TextEncoder};
+
+use crate::error::HttpResult;
+
+/// App-wide metrics registry, shared by every worker via web::Data.
+pub struct Metrics {
+    pub registry: Registry,
+    pub http_requests_total: IntCounterVec,
+    pub http_request_duration_seconds: HistogramVec,
+    pub calculation_errors_total: IntCounterVec,
+    pub http_in_flight_requests: IntGauge,
+    pub http_requests_shed_total: IntCounterVec,
+    pub coalesced_requests_total: IntCounterVec,
+    /// Percentiles from the per-route latency histograms, refreshed at
+    /// scrape time — summary-style series the fixed prometheus histogram
+    /// buckets cannot provide.
+    pub http_request_latency_quantile_seconds: GaugeVec,
+    /// 1 while the sentry transport probe can reach the DSN host.
+    #[cfg(feature = "sentry")]
+    pub sentry_transport_up: IntGauge,
+    /// Events dropped by the runtime reporting kill-switch.
+    #[cfg(feature = "sentry")]
+    pub sentry_events_suppressed_total: IntCounter,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+
+        let http_requests_total = IntCounterVec::new(
+            prometheus::opts!("http_requests_total", "Requests served, by route"),
+            &["route", "method", "status_class"],
+        )
+        .expect("invalid counter definition");
+
+        let http_request_duration_seconds = HistogramVec::new(
+            prometheus::histogram_opts!(
+                "http_request_duration_seconds",
+                "Request latency, by route"
+            ),
+            &["route", "method"],
+        )
+        .expect("invalid histogram definition");
+
+        let calculation_errors_total = IntCounterVec::new(
+            prometheus::opts!(
+                "calculation_errors_total",
+                "Calculation failures, by error code"
+            ),
+            &["code"],
+        )
+        .expect("invalid counter definition");
+
+        let http_in_flight_requests =
+            IntGauge::new("http_in_flight_requests", "Requests currently being served")
+                .expect("invalid gauge definition");
+
+        let http_requests_shed_total = IntCounterVec::new(
+            prometheus::opts!(
+                "http_requests_shed_total",
+                "Requests shed by the concurrency limiter, by route"
+            ),
+            &["route"],
+        )
+        .expect("invalid counter definition");
+
+        registry
+            .register(Box::new(http_requests_total.clone()))
+            .expect("failed to register http_requests_total");
+        registry
+            .register(Box::new(http_request_duration_seconds.clone()))
+            .expect("failed to register http_request_duration_seconds");
+        registry
+            .register(Box::new(calculation_errors_total.clone()))
+            .expect("failed to register calculation_errors_total");
+        registry
+            .register(Box::new(http_in_flight_requests.clone()))
+            .expect("failed to register http_in_flight_requests");
+        let coalesced_requests_total = IntCounterVec::new(
+            prometheus::opts!(
+                "coalesced_requests_total",
+                "Requests that awaited an identical in-flight computation, by operation"
+            ),
+            &["op"],
+        )
+        .expect("invalid counter definition");
+
+        let http_request_latency_quantile_seconds = GaugeVec::new(
+            prometheus::opts!(
+                "http_request_latency_quantile_seconds",
+                "Request latency percentiles from the merged per-route histograms"
+            ),
+            &["route", "quantile"],
+        )
+        .expect("invalid gauge definition");
+
+        registry
+            .register(Box::new(http_requests_shed_total.clone()))
+            .expect("failed to register http_requests_shed_total");
+        registry
+            .register(Box::new(coalesced_requests_total.clone()))
+            .expect("failed to register coalesced_requests_total");
+        registry
+            .register(Box::new(http_request_latency_quantile_seconds.clone()))
+            .expect("failed to register http_request_latency_quantile_seconds");
+
+        // Optimistic until the first probe reports, matching /readyz.
+        #[cfg(feature = "sentry")]
+        let sentry_transport_up = IntGauge::new(
+            "sentry_transport_up",
+            "Whether the sentry DSN host answered the last connectivity probe",
+        )
+        .expect("invalid gauge definition");
+        #[cfg(feature = "sentry")]
+        {
+            sentry_transport_up.set(1);
+            registry
+                .register(Box::new(sentry_transport_up.clone()))
+                .expect("failed to register sentry_transport_up");
+        }
+
+        #[cfg(feature = "sentry")]
+        let sentry_events_suppressed_total = IntCounter::new(
+            "sentry_events_suppressed_total",
+            "Events dropped by the runtime reporting kill-switch",
+        )
+        .expect("invalid counter definition");
+        #[cfg(feature = "sentry")]
+        registry
+            .register(Box::new(sentry_events_suppressed_total.clone()))
+            .expect("failed to register sentry_events_suppressed_total");
+
+        Metrics {
+            registry,
+            http_requests_total,
+            http_request_duration_seconds,
+            calculation_errors_total,
+            http_in_flight_requests,
+            http_requests_shed_total,
+            coalesced_requests_total,
+            http_request_latency_quantile_seconds,
+            #[cfg(feature = "sentry")]
+            sentry_transport_up,
+            #[cfg(feature = "sentry")]
+            sentry_events_suppressed_total,
+        }
+    }
+
+    pub fn global() -> Arc<Metrics> {
+        static METRICS: OnceLock<Arc<Metrics>> = OnceLock::new();
+        METRICS.get_or_init(|| Arc::new(Metrics::new())).clone()
+    }
+
+    /// /metrics and /status are scrape/probe noise; keep them out of the
+    /// request counters.
+    pub fn is_excluded_path(path: &str) -> bool {
+        path == "/metrics" || path == "/healthz" || path == "/readyz" || path.ends_with("/status")
+    }
+}
+
+#[get("/metrics")]
+pub async fn scrape() -> HttpResult<HttpResponse> {
+    let metrics = Metrics::global();
+    // The quantile gauges are point-in-time reads of the latency
+    // histograms, so they are only refreshed when someone scrapes.
+    for (route, latency) in crate::latency::Latency::global().snapshot() {
+        for (quantile, ms) in [
+            ("0.5", latency.p50_ms),
+            ("0.9", latency.p90_ms),
+            ("0.99", latency.p99_ms),
+            ("0.999", latency.p999_ms),
+        ] {
+            metrics
+                .http_request_latency_quantile_seconds
+                .with_label_values(&[&route, quantile])
+                .set(ms / 1_000.0);
+        }
+    }
+
+    let metrics = TextEncoder::new()
+        .encode_to_string(&metrics.registry.gather())
+        .map_err(|err| crate::error::Error::Metrics(err.to_string()))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics))
+}
@@ -1,9 +1,256 @@
+use std::sync::{Arc, OnceLock};
+
 use actix_web::{
+    body::{BodySize, EitherBody, MessageBody},
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-    Error,
+    http::header::{HeaderName, HeaderValue},
+    web, Error, HttpMessage,
 };
 use futures_util::future::{ready, LocalBoxFuture, Ready};
-use tracing::error;
+use futures_util::FutureExt;
+#[cfg(feature = "sentry")]
+use sentry::SentryFutureExt;
+use tracing::{error, info, warn, Instrument};
+use uuid::Uuid;
+
+use crate::metrics::Metrics;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    /// The id of the request currently being served, so that code without
+    /// access to the HttpRequest (e.g. HTTPError::error_response) can still
+    /// report it.
+    pub static REQUEST_ID: String;
+
+    /// The distributed trace id for the current request, inbound or
+    /// freshly started; surfaced in error bodies so a client error can be
+    /// matched to the frontend trace it belongs to.
+    pub static TRACE_ID: String;
+
+    /// The request path (no query string), for the `instance` member of
+    /// problem+json error bodies.
+    pub static REQUEST_PATH: String;
+}
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+pub const USER_ID_HEADER: &str = "x-user-id";
+
+pub const RESPONSE_TIME_HEADER: &str = "x-response-time-ms";
+
+/// Requests slower than this many milliseconds get a warn! and a sentry
+/// message; 0 disables the reporting. Overridable with SLOW_REQUEST_MS.
+fn slow_request_ms() -> u64 {
+    static SLOW_REQUEST_MS: OnceLock<u64> = OnceLock::new();
+    *SLOW_REQUEST_MS.get_or_init(|| {
+        std::env::var("SLOW_REQUEST_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1_000)
+    })
+}
+
+/// Paths load balancers and scrapers may hit without credentials (and
+/// without counting against rate limits).
+pub(crate) fn is_public_path(path: &str) -> bool {
+    path == "/metrics" || path == "/healthz" || path == "/readyz" || path.ends_with("/status")
+}
+
+/// Authentication via X-Api-Key, validated against the configured key set.
+/// Auth is disabled entirely when no keys are configured.
+pub struct Auth;
+
+impl<S, B> Transform<S, ServiceRequest> for Auth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = AuthService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AuthService { service }))
+    }
+}
+
+pub struct AuthService<S> {
+    service: S,
+}
+
+impl<S> AuthService<S> {
+    /// Renders the structured JSON error body directly rather than
+    /// returning Err, so the response shape is identical in tests and
+    /// production.
+    fn reject<B>(req: ServiceRequest, err: crate::error::Error) -> ServiceResponse<EitherBody<B>> {
+        use actix_web::ResponseError;
+
+        let response = crate::error::HTTPError::from(err).error_response();
+        req.into_response(response).map_into_right_body()
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for AuthService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let api_keys = &crate::config::Config::global().api_keys;
+
+        if !api_keys.is_empty() && !is_public_path(req.path()) {
+            let presented = req
+                .headers()
+                .get(API_KEY_HEADER)
+                .and_then(|value| value.to_str().ok());
+
+            let presented = match presented {
+                Some(presented) => presented.to_owned(),
+                None => {
+                    return Box::pin(ready(Ok(Self::reject(
+                        req,
+                        crate::error::Error::MissingApiKey,
+                    ))))
+                }
+            };
+
+            let known = api_keys.iter().find(|api_key| api_key.key == presented);
+            let name = match known {
+                Some(api_key) => api_key.name.clone(),
+                None => {
+                    return Box::pin(ready(Ok(Self::reject(
+                        req,
+                        crate::error::Error::UnknownApiKey,
+                    ))))
+                }
+            };
+
+            // Identify the client (by name, never the secret) on the
+            // request-scoped hub set up by Middleware.
+            #[cfg(feature = "sentry")]
+            if let Some(hub) = req.extensions().get::<Arc<sentry::Hub>>() {
+                hub.configure_scope(|scope| {
+                    scope.set_tag("api_key", &name);
+                    scope.set_user(Some(sentry::User {
+                        username: Some(name.clone()),
+                        ..Default::default()
+                    }));
+                });
+            }
+            #[cfg(not(feature = "sentry"))]
+            let _ = name;
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(|res| res.map_into_left_body()) })
+    }
+}
+
+#[cfg(feature = "sentry")]
+fn span_status(status: actix_web::http::StatusCode) -> sentry::protocol::SpanStatus {
+    use sentry::protocol::SpanStatus;
+
+    match status.as_u16() {
+        200..=399 => SpanStatus::Ok,
+        404 => SpanStatus::NotFound,
+        429 => SpanStatus::ResourceExhausted,
+        400..=499 => SpanStatus::InvalidArgument,
+        _ => SpanStatus::InternalError,
+    }
+}
+
+/// The inbound sentry-trace value, accepting the W3C traceparent form
+/// ("00-<trace>-<span>-<flags>") from clients that speak OpenTelemetry
+/// rather than sentry. sentry-trace wins when both are present.
+fn incoming_sentry_trace(headers: &actix_web::http::header::HeaderMap) -> Option<String> {
+    if let Some(value) = headers.get("sentry-trace").and_then(|v| v.to_str().ok()) {
+        return Some(value.to_owned());
+    }
+
+    let traceparent = headers.get("traceparent")?.to_str().ok()?;
+    let mut parts = traceparent.split('-');
+    let _version = parts.next()?;
+    let trace_id = parts.next()?;
+    let span_id = parts.next()?;
+    let sampled = if parts.next()?.ends_with('1') {
+        "1"
+    } else {
+        "0"
+    };
+    Some(format!("{trace_id}-{span_id}-{sampled}"))
+}
+
+/// The request id as stored in request extensions by the middleware.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// The hub the request-scoped hub was derived from. Breadcrumbs meant to
+/// survive into later requests (the admin audit trail) go here, since
+/// per-request hubs snapshot it at creation.
+#[cfg(feature = "sentry")]
+#[derive(Clone)]
+pub(crate) struct ParentHub(pub(crate) Arc<sentry::Hub>);
+
+/// The identity for sentry's "affected users": an explicit X-User-Id
+/// when the client sends one, otherwise (unless disabled for privacy) an
+/// anonymous id from a salted hash of the client IP, so unique-user
+/// counts stay meaningful without storing addresses. Never the API key
+/// itself — Auth overwrites this with the key's configured name.
+#[cfg(feature = "sentry")]
+fn identity(req: &ServiceRequest) -> Option<sentry::User> {
+    if let Some(user_id) = req
+        .headers()
+        .get(USER_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        return Some(sentry::User {
+            id: Some(user_id.to_owned()),
+            ..Default::default()
+        });
+    }
+
+    let config = crate::config::Config::global();
+    if !config.anon_user_ids {
+        return None;
+    }
+
+    use std::hash::{Hash, Hasher};
+    let ip = req
+        .extensions()
+        .get::<crate::client_ip::ClientIp>()
+        .map(|ip| ip.0.clone())
+        .unwrap_or_else(|| crate::client_ip::resolve(req, &config));
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    config.anon_salt.hash(&mut hasher);
+    ip.hash(&mut hasher);
+    Some(sentry::User {
+        id: Some(format!("anon:{:016x}", hasher.finish())),
+        ..Default::default()
+    })
+}
+
+/// Best-effort extraction of the panic payload; panic! with a literal or
+/// a format string covers nearly everything in practice.
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(s) = panic.downcast_ref::<&str>() {
+        (*s).to_owned()
+    } else if let Some(s) = panic.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "opaque panic payload".to_owned()
+    }
+}
 
 pub struct Middleware;
 
@@ -11,7 +258,7 @@ impl<S, B> Transform<S, ServiceRequest> for Middleware
 where
     S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
     S::Future: 'static,
-    B: 'static,
+    B: MessageBody + 'static,
 {
     type Response = ServiceResponse<B>;
     type Error = Error;
@@ -32,7 +279,7 @@ impl<S, B> Service<ServiceRequest> for MiddlewareService<S>
 where
     S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
     S::Future: 'static,
-    B: 'static,
+    B: MessageBody + 'static,
 {
     type Response = ServiceResponse<B>;
     type Error = Error;
@@ -41,22 +288,486 @@ where
     forward_ready!(service);
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
+        let started = std::time::Instant::now();
+        // req.path() carries no query string, so breadcrumbs and logs can't
+        // leak secrets passed as query parameters.
         let path = req.path().to_owned();
-        let fut = self.service.call(req);
+        let method = req.method().to_string();
+        let remote_addr = req
+            .peer_addr()
+            .map(|addr| addr.to_string())
+            .unwrap_or_default();
+        // The raw header; the gate resolves (and may refuse) it later,
+        // so the error arm of the access record can still name it.
+        let tenant_header = req
+            .headers()
+            .get(crate::tenant::TENANT_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+        // Resolved once per request; rate limiting, identity and the
+        // access record all read this extension.
+        let client_ip = crate::client_ip::resolve(&req, &crate::config::Config::global());
+        req.extensions_mut()
+            .insert(crate::client_ip::ClientIp(client_ip.clone()));
+        // Breadcrumbs are recorded on the parent hub: per-request hubs
+        // snapshot it at creation, so an error in a later request carries
+        // the trail of earlier ones.
+        #[cfg(feature = "sentry")]
+        let parent_hub = sentry::Hub::current();
 
-        Box::pin(async move {
-            match fut.await {
-                Ok(res) => {
-                    if let Some(err) = res.response().error() {
-                        error!(path, ?err)
-                    }
-                    Ok(res)
-                }
-                Err(err) => {
-                    error!(path, ?err, "Unhandled server error");
-                    Err(err)
-                }
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|id| id.to_owned())
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        // Injected app data in production, a recorder in tests; requests
+        // built without one fall back to the process default.
+        let reporter: Arc<dyn crate::reporter::ErrorReporter> = req
+            .app_data::<web::Data<dyn crate::reporter::ErrorReporter>>()
+            .map(|data| data.clone().into_inner())
+            .unwrap_or_else(crate::reporter::shared);
+
+        req.extensions_mut().insert(RequestId(request_id.clone()));
+        #[cfg(feature = "sentry")]
+        req.extensions_mut().insert(ParentHub(parent_hub.clone()));
+
+        // The route pattern (not the raw path, so parameterised routes
+        // group) and the handler fn name, resolved from the resource map.
+        let route_pattern = req.match_pattern().unwrap_or_else(|| path.clone());
+        let handler_name = req.match_name().map(|name| name.to_owned());
+
+        // A fresh hub per request, so scope data (tags, extras) set while
+        // handling one request cannot bleed into events captured for another.
+        #[cfg(feature = "sentry")]
+        let hub = Arc::new(sentry::Hub::new_from_top(sentry::Hub::current()));
+        #[cfg(feature = "sentry")]
+        hub.configure_scope(|scope| {
+            scope.set_tag("request_id", &request_id);
+            // Set before the handler runs, so every event captured during
+            // the request can be grouped and filtered by route.
+            scope.set_tag("http.route", &route_pattern);
+            scope.set_tag("http.method", &method);
+            if let Some(handler_name) = &handler_name {
+                scope.set_tag("handler", handler_name);
+            }
+            scope.set_user(identity(&req));
+        });
+        #[cfg(not(feature = "sentry"))]
+        let _ = (&route_pattern, &handler_name);
+        #[cfg(feature = "sentry")]
+        req.extensions_mut().insert(hub.clone());
+        // Each request contributes a release-health session; a capture
+        // during the request marks it errored automatically because it
+        // runs bound to this hub.
+        #[cfg(feature = "sentry")]
+        if crate::config::Config::global().sentry_session_tracking {
+            hub.start_session();
+        }
+        #[cfg(feature = "sentry")]
+        let session_hub = hub.clone();
+
+        // Continue the caller's trace (frontend SDKs send sentry-trace)
+        // so both ends land in one trace; otherwise start a fresh one.
+        let sentry_trace = incoming_sentry_trace(req.headers());
+        #[cfg(feature = "sentry")]
+        let (trace_id, transaction) = {
+            let transaction_name = format!("{method} {route_pattern}");
+            let baggage = req
+                .headers()
+                .get("baggage")
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_owned());
+            let mut trace_headers = Vec::new();
+            if let Some(sentry_trace) = &sentry_trace {
+                trace_headers.push(("sentry-trace", sentry_trace.as_str()));
             }
-        })
+            if let Some(baggage) = &baggage {
+                trace_headers.push(("baggage", baggage.as_str()));
+            }
+            let transaction_ctx = sentry::TransactionContext::continue_from_headers(
+                &transaction_name,
+                "http.server",
+                trace_headers,
+            );
+            let trace_id = transaction_ctx.trace_id().to_string();
+            let transaction = hub.start_transaction(transaction_ctx);
+            hub.configure_scope(|scope| scope.set_span(Some(transaction.clone().into())));
+            (trace_id, transaction)
+        };
+        // Without sentry there is no transaction, but error bodies still
+        // carry a trace id: the caller's if one came in, otherwise fresh.
+        #[cfg(not(feature = "sentry"))]
+        let trace_id = sentry_trace
+            .as_deref()
+            .and_then(|value| value.split('-').next())
+            .map(|id| id.to_owned())
+            .unwrap_or_else(|| Uuid::new_v4().simple().to_string());
+
+        let span = tracing::info_span!(
+            "request",
+            request_id = %request_id,
+            trace_id = %trace_id,
+            // Filled in by JwtAuth once the bearer token is validated.
+            jwt_sub = tracing::field::Empty,
+        );
+        // Recorded as a task-local so HTTPError::error_response can encode
+        // error bodies in the format the client asked for.
+        let accepts_msgpack = crate::negotiation::accepts_msgpack(req.headers());
+        let accepts_problem = crate::negotiation::accepts_problem_json(req.headers());
+        // AssertUnwindSafe is fine here: a panicked request is answered
+        // with a 500 and never touched again.
+        let fut = std::panic::AssertUnwindSafe(self.service.call(req)).catch_unwind();
+
+        let fut = crate::negotiation::ACCEPTS_PROBLEM
+                .scope(
+                    accepts_problem,
+                    REQUEST_PATH.scope(
+                        path.clone(),
+                        crate::negotiation::ACCEPTS_MSGPACK.scope(
+                    accepts_msgpack,
+                    REQUEST_ID.scope(
+                        request_id.clone(),
+                        TRACE_ID.scope(trace_id.clone(), async move {
+                            let result = match fut.await {
+                                Ok(result) => result,
+                                // A panicking handler becomes a structured 500
+                                // and a sentry event; the worker stays up.
+                                Err(panic) => {
+                                    let message = panic_message(panic);
+                                    error!(path, message, "handler panicked");
+                                    // On the request scope, so the capture in
+                                    // into_http carries it.
+                                    reporter
+                                        .set_tag("handler_panic", "true".to_string());
+                                    let http_error =
+                                        crate::error::Error::HandlerPanic(message)
+                                            .into_http(reporter.as_ref());
+                                    Err(http_error.into())
+                                }
+                            };
+
+                            match result {
+                                Ok(mut res) => {
+                                    if let Some(err) = res.response().error() {
+                                        error!(path, ?err)
+                                    }
+                                    let crumb = [
+                                        ("method", serde_json::Value::from(method.as_str())),
+                                        ("path", path.as_str().into()),
+                                        ("status_code", res.status().as_u16().into()),
+                                        (
+                                            "duration_ms",
+                                            (started.elapsed().as_millis() as u64).into(),
+                                        ),
+                                    ];
+                                    // On the parent hub, which later requests'
+                                    // hubs snapshot, so the trail survives into
+                                    // their events.
+                                    #[cfg(feature = "sentry")]
+                                    sentry::Hub::run(parent_hub.clone(), || {
+                                        reporter.add_breadcrumb("http", String::new(), &crumb)
+                                    });
+                                    #[cfg(not(feature = "sentry"))]
+                                    reporter.add_breadcrumb("http", String::new(), &crumb);
+
+                                    let route = res
+                                        .request()
+                                        .match_pattern()
+                                        .unwrap_or_else(|| path.clone());
+                                    let bytes = match res.response().body().size() {
+                                        BodySize::Sized(n) => n,
+                                        _ => 0,
+                                    };
+
+                                    // The access record: exactly one line per request,
+                                    // with a stable field set that log pipelines depend
+                                    // on. Add fields if you must, never rename or drop:
+                                    //   method, path, route, status, latency_ms, bytes,
+                                    //   request_id, remote_addr, client_ip, tenant, error.
+                                    let tenant = res
+                                        .request()
+                                        .extensions()
+                                        .get::<crate::tenant::Tenant>()
+                                        .map(|tenant| tenant.0.clone())
+                                        .or(tenant_header);
+                                    info!(
+                                        target: "access",
+                                        method,
+                                        path,
+                                        route,
+                                        status = res.status().as_u16(),
+                                        latency_ms = started.elapsed().as_millis() as u64,
+                                        bytes,
+                                        request_id,
+                                        remote_addr,
+                                        client_ip,
+                                        tenant = tenant.as_deref(),
+                                        error = res.response().error().map(tracing::field::display),
+                                        "request"
+                                    );
+
+                                    if !Metrics::is_excluded_path(&path) {
+                                        let status_class =
+                                            format!("{}xx", res.status().as_u16() / 100);
+
+                                        let metrics = Metrics::global();
+                                        metrics
+                                            .http_requests_total
+                                            .with_label_values(&[&route, &method, &status_class])
+                                            .inc();
+                                        metrics
+                                            .http_request_duration_seconds
+                                            .with_label_values(&[&route, &method])
+                                            .observe(started.elapsed().as_secs_f64());
+                                        crate::latency::Latency::global()
+                                            .record(&route, started.elapsed());
+                                    }
+                                    if let Ok(value) = HeaderValue::from_str(&request_id) {
+                                        res.headers_mut().insert(
+                                            HeaderName::from_static(REQUEST_ID_HEADER),
+                                            value,
+                                        );
+                                    }
+
+                                    let elapsed_ms = started.elapsed().as_millis() as u64;
+                                    res.headers_mut().insert(
+                                        HeaderName::from_static(RESPONSE_TIME_HEADER),
+                                        elapsed_ms.into(),
+                                    );
+
+                                    let threshold_ms = slow_request_ms();
+                                    if threshold_ms > 0 && elapsed_ms > threshold_ms {
+                                        warn!(path, elapsed_ms, threshold_ms, "slow request");
+                                        // Captured on the request hub (we are bound to
+                                        // it), so the event carries the request_id tag.
+                                        reporter.report_message(
+                                            &format!(
+                                                "slow request: {method} {path} took {elapsed_ms}ms"
+                                            ),
+                                            crate::reporter::Level::Warning,
+                                            &[("slow_request", "true".to_string())],
+                                            &[
+                                                ("path", path.clone().into()),
+                                                ("duration_ms", elapsed_ms.into()),
+                                                ("status", res.status().as_u16().into()),
+                                            ],
+                                        );
+                                    }
+
+                                    #[cfg(feature = "sentry")]
+                                    {
+                                        let status = res.status();
+                                        transaction.set_data(
+                                            "http.response.status_code",
+                                            status.as_u16().into(),
+                                        );
+                                        transaction.set_status(span_status(status));
+                                        transaction.finish();
+                                        // Folds the session into the aggregate
+                                        // the client flushes; a no-op when
+                                        // session tracking is off.
+                                        session_hub.end_session();
+                                    }
+
+                                    Ok(res)
+                                }
+                                Err(err) => {
+                                    error!(path, ?err, "Unhandled server error");
+                                    // Same stable field set as the success path; the
+                                    // match pattern is gone with the request, so route
+                                    // falls back to the raw path.
+                                    info!(
+                                        target: "access",
+                                        method,
+                                        path,
+                                        route = path.as_str(),
+                                        status = err.as_response_error().status_code().as_u16(),
+                                        latency_ms = started.elapsed().as_millis() as u64,
+                                        bytes = 0_u64,
+                                        request_id,
+                                        remote_addr,
+                                        client_ip,
+                                        tenant = tenant_header.as_deref(),
+                                        error = %err,
+                                        "request"
+                                    );
+                                    let crumb = [
+                                        ("method", serde_json::Value::from(method.as_str())),
+                                        ("path", path.as_str().into()),
+                                        (
+                                            "duration_ms",
+                                            (started.elapsed().as_millis() as u64).into(),
+                                        ),
+                                    ];
+                                    #[cfg(feature = "sentry")]
+                                    sentry::Hub::run(parent_hub.clone(), || {
+                                        reporter.add_breadcrumb("http", String::new(), &crumb)
+                                    });
+                                    #[cfg(not(feature = "sentry"))]
+                                    reporter.add_breadcrumb("http", String::new(), &crumb);
+                                    #[cfg(feature = "sentry")]
+                                    {
+                                        transaction
+                                            .set_status(sentry::protocol::SpanStatus::InternalError);
+                                        transaction.finish();
+                                        session_hub.end_session();
+                                    }
+                                    Err(err)
+                                }
+                            }
+                        }),
+                    ),
+                        ),
+                    ),
+                )
+                .instrument(span);
+        #[cfg(feature = "sentry")]
+        let fut = fut.bind_hub(hub);
+        Box::pin(fut)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix_web::{get, test, App, HttpResponse, Responder};
+
+    use super::*;
+
+    /// Deliberately slow, registered only for these tests.
+    #[get("/slow")]
+    async fn slow() -> impl Responder {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        HttpResponse::Ok().finish()
+    }
+
+    #[cfg(feature = "sentry")]
+    #[actix_web::test]
+    async fn slow_requests_get_a_timing_header_and_a_sentry_event() {
+        // Before the first slow_request_ms() call, which caches the value.
+        std::env::set_var("SLOW_REQUEST_MS", "10");
+
+        let transport = sentry::test::TestTransport::new();
+        let options = sentry::ClientOptions {
+            dsn: Some("https://public@example.com/1".parse().unwrap()),
+            transport: Some(Arc::new(transport.clone())),
+            ..Default::default()
+        };
+        sentry::Hub::current().bind_client(Some(Arc::new(sentry::Client::from(options))));
+
+        let app = test::init_service(App::new().wrap(Middleware).service(slow)).await;
+        let resp =
+            test::call_service(&app, test::TestRequest::get().uri("/slow").to_request()).await;
+        assert!(resp.status().is_success());
+
+        let elapsed_ms: u64 = resp
+            .headers()
+            .get(RESPONSE_TIME_HEADER)
+            .expect("missing x-response-time-ms header")
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(elapsed_ms >= 50, "reported {elapsed_ms}ms for a 50ms sleep");
+
+        let events = transport.fetch_and_clear_events();
+        let slow_event = events
+            .iter()
+            .find(|e| e.tags.get("slow_request").map(String::as_str) == Some("true"))
+            .expect("no slow_request event captured");
+        assert!(slow_event
+            .message
+            .as_deref()
+            .is_some_and(|m| m.contains("/slow")));
+    }
+
+    #[get("/explode")]
+    async fn explode() -> HttpResponse {
+        panic!("deliberate test panic");
+    }
+
+    #[actix_web::test]
+    async fn panics_are_reported_through_the_injected_reporter() {
+        use crate::reporter::{ErrorReporter, RecordingReporter, ReporterCall};
+
+        let reporter = Arc::new(RecordingReporter::default());
+        let data: Arc<dyn ErrorReporter> = reporter.clone();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::from(data))
+                .wrap(Middleware)
+                .service(explode),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/explode").to_request();
+        let err = test::try_call_service(&app, req)
+            .await
+            .expect_err("a panicking handler surfaces as an error");
+        assert_eq!(
+            err.as_response_error().status_code(),
+            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR
+        );
+
+        let calls = reporter.take();
+        assert!(calls.iter().any(|call| matches!(
+            call,
+            ReporterCall::Tag {
+                key: "handler_panic",
+                value,
+            } if value == "true"
+        )));
+        assert!(calls.iter().any(|call| matches!(
+            call,
+            ReporterCall::Error { message, .. } if message.contains("deliberate test panic")
+        )));
+    }
+
+    #[actix_web::test]
+    async fn requests_leave_breadcrumbs_on_the_injected_reporter() {
+        use crate::reporter::{ErrorReporter, RecordingReporter, ReporterCall};
+
+        // slow_request_ms() caches on first use, process-wide; pin the
+        // same value the slow-request test relies on so test order
+        // cannot change what either observes.
+        std::env::set_var("SLOW_REQUEST_MS", "10");
+
+        let reporter = Arc::new(RecordingReporter::default());
+        let data: Arc<dyn ErrorReporter> = reporter.clone();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::from(data))
+                .wrap(Middleware)
+                .service(slow),
+        )
+        .await;
+
+        let resp =
+            test::call_service(&app, test::TestRequest::get().uri("/slow").to_request()).await;
+        assert!(resp.status().is_success());
+
+        let calls = reporter.take();
+        assert!(calls.iter().any(|call| matches!(
+            call,
+            ReporterCall::Breadcrumb { category: "http", data, .. }
+                if data.contains(&("path", "/slow".into()))
+                    && data.contains(&("status_code", 200.into()))
+        )));
+    }
+
+    /// The no-sentry build still has to serve requests through the full
+    /// middleware: request id stamped, timing header present.
+    #[cfg(not(feature = "sentry"))]
+    #[actix_web::test]
+    async fn requests_are_served_without_sentry_compiled_in() {
+        let app = test::init_service(App::new().wrap(Middleware).service(slow)).await;
+        let resp =
+            test::call_service(&app, test::TestRequest::get().uri("/slow").to_request()).await;
+        assert!(resp.status().is_success());
+        assert!(resp.headers().get(REQUEST_ID_HEADER).is_some());
+        assert!(resp.headers().get(RESPONSE_TIME_HEADER).is_some());
     }
 }
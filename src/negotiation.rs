@@ -0,0 +1,162 @@
+use actix_web::body::BoxBody;
+use actix_web::http::header::{self, HeaderMap};
+use actix_web::{web, FromRequest, HttpMessage, HttpRequest, HttpResponse, Responder};
+use futures_util::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::{Error, HTTPError};
+
+/// The canonical msgpack media type; `application/x-msgpack` is accepted
+/// as a legacy alias on the way in.
+pub const MSGPACK: &str = "application/msgpack";
+
+/// RFC 7807 Problem Details, the platform-wide error format; opt-in via
+/// the Accept header.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+tokio::task_local! {
+    /// Whether the current request asked for msgpack responses, so the
+    /// error path (which has no access to the HttpRequest) can honour the
+    /// Accept header too.
+    pub static ACCEPTS_MSGPACK: bool;
+
+    /// Whether the current request asked for problem+json error bodies.
+    pub static ACCEPTS_PROBLEM: bool;
+}
+
+fn is_msgpack(media_type: &str) -> bool {
+    media_type == MSGPACK || media_type == "application/x-msgpack"
+}
+
+/// Whether the Accept header asks for msgpack. Anything else — including
+/// no Accept header at all — falls back to JSON.
+pub(crate) fn accepts_msgpack(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| {
+            accept
+                .split(',')
+                .map(|part| part.split(';').next().unwrap_or("").trim())
+                .any(is_msgpack)
+        })
+}
+
+/// Reads the task-local set by the middleware; false outside a request.
+pub(crate) fn response_is_msgpack() -> bool {
+    ACCEPTS_MSGPACK
+        .try_with(|accepts| *accepts)
+        .unwrap_or(false)
+}
+
+/// Whether the Accept header asks for RFC 7807 problem details.
+pub(crate) fn accepts_problem_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| {
+            accept
+                .split(',')
+                .map(|part| part.split(';').next().unwrap_or("").trim())
+                .any(|media_type| media_type == PROBLEM_JSON)
+        })
+}
+
+/// Reads the task-local set by the middleware; false outside a request.
+pub(crate) fn response_is_problem_json() -> bool {
+    ACCEPTS_PROBLEM
+        .try_with(|accepts| *accepts)
+        .unwrap_or(false)
+}
+
+/// A request/response body negotiated between JSON (the default),
+/// msgpack and form-urlencoded input. As an extractor it decodes by
+/// Content-Type and rejects anything else with a structured 415; as a
+/// responder it encodes by Accept header. Handlers using this pair get
+/// every format for free.
+#[derive(Debug)]
+pub struct Negotiated<T>(pub T);
+
+impl<T> std::ops::Deref for Negotiated<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned> FromRequest for Negotiated<T> {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut actix_web::dev::Payload) -> Self::Future {
+        let content_type = req.content_type().to_owned();
+        let bytes = web::Bytes::from_request(req, payload);
+
+        Box::pin(async move {
+            let bytes = bytes.await?;
+            let decoded = match content_type.as_str() {
+                // No Content-Type keeps working the way it always has.
+                "" | "application/json" => serde_json::from_slice(&bytes)
+                    .map_err(|err| Error::InvalidRequestBody(err.to_string())),
+                media_type if is_msgpack(media_type) => rmp_serde::from_slice(&bytes)
+                    .map_err(|err| Error::InvalidRequestBody(err.to_string())),
+                // HTML forms and curl's --data default; responses stay JSON.
+                "application/x-www-form-urlencoded" => serde_urlencoded::from_bytes(&bytes)
+                    .map_err(|err| Error::InvalidRequestBody(err.to_string())),
+                media_type => Err(Error::UnsupportedMediaType(media_type.to_owned())),
+            };
+
+            match decoded {
+                Ok(value) => Ok(Negotiated(value)),
+                Err(err) => Err(HTTPError::from(err).into()),
+            }
+        })
+    }
+}
+
+impl<T: Serialize> Responder for Negotiated<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse {
+        if accepts_msgpack(req.headers()) {
+            // Named serialization keeps struct fields as map keys, so
+            // msgpack bodies have the same shape as their JSON twins.
+            return match rmp_serde::to_vec_named(&self.0) {
+                Ok(buf) => HttpResponse::Ok().content_type(MSGPACK).body(buf),
+                Err(err) => {
+                    use actix_web::ResponseError;
+                    HTTPError::from(Error::ResponseEncoding(err.to_string())).error_response()
+                }
+            };
+        }
+
+        HttpResponse::Ok().json(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_header_variants() {
+        let mut headers = HeaderMap::new();
+        assert!(!accepts_msgpack(&headers));
+
+        headers.insert(header::ACCEPT, "application/json".parse().unwrap());
+        assert!(!accepts_msgpack(&headers));
+
+        headers.insert(header::ACCEPT, "application/msgpack".parse().unwrap());
+        assert!(accepts_msgpack(&headers));
+
+        headers.insert(
+            header::ACCEPT,
+            "application/json, application/x-msgpack;q=0.9"
+                .parse()
+                .unwrap(),
+        );
+        assert!(accepts_msgpack(&headers));
+    }
+}
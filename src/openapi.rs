@@ -0,0 +1,141 @@
+use actix_web::{get, http::header::ContentType, web, HttpResponse, Responder};
+use utoipa::OpenApi;
+
+/// The envelope every error response carries; mirrors
+/// HTTPError::error_response, which builds the body by hand. These types
+/// exist only so the spec documents the error shape — they are never
+/// constructed.
+#[allow(dead_code)]
+#[derive(utoipa::ToSchema)]
+pub struct ErrorBody {
+    error: ErrorDetail,
+}
+
+#[allow(dead_code)]
+#[derive(utoipa::ToSchema)]
+pub struct ErrorDetail {
+    /// Stable machine-readable code, e.g. "divide_by_zero".
+    code: String,
+    message: String,
+    /// The HTTP status, repeated in the body.
+    status: u16,
+    /// The X-Request-Id of the failing request, when known.
+    request_id: Option<String>,
+    /// The distributed trace id of the failing request, when known.
+    trace_id: Option<String>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "sentry-rs-demo calculator API",
+        description = "A small calculator service instrumented with sentry."
+    ),
+    paths(
+        crate::health::healthz,
+        crate::health::readyz,
+        crate::handlers::status,
+        crate::handlers::version,
+        crate::handlers::handle_calc,
+        crate::handlers::handle_add,
+        crate::handlers::handle_sub,
+        crate::handlers::handle_mul,
+        crate::handlers::handle_div,
+        crate::handlers::handle_mod,
+        crate::handlers::handle_pow,
+        crate::handlers::handle_gcd,
+        crate::handlers::handle_lcm,
+        crate::handlers::handle_and,
+        crate::handlers::handle_or,
+        crate::handlers::handle_xor,
+        crate::handlers::handle_shl,
+        crate::handlers::handle_shr,
+        crate::handlers::handle_convert,
+        crate::handlers::handle_decimal_calc,
+        crate::handlers::handle_factorial,
+        crate::handlers::handle_choose,
+        crate::handlers::handle_aggregate,
+        crate::handlers::handle_eval,
+        crate::handlers::handle_batch,
+        crate::handlers::handle_batch_stream,
+        crate::jobs::submit_job,
+        crate::jobs::get_job,
+        crate::jobs::cancel_job,
+        crate::handlers::handle_add_query,
+        crate::handlers::handle_sub_query,
+        crate::handlers::handle_mul_query,
+        crate::handlers::handle_div_query,
+        crate::handlers::handle_mod_query,
+        crate::handlers::handle_pow_query,
+        crate::history::get_history,
+        crate::history::get_history_entry,
+        crate::history::clear_history,
+        crate::stats::get_stats,
+        crate::quota::get_usage,
+        crate::stats::reset_stats,
+        crate::handlers::handle_float_add,
+        crate::handlers::handle_float_sub,
+        crate::handlers::handle_float_mul,
+        crate::handlers::handle_float_div,
+        crate::v1::add,
+        crate::v1::sub,
+        crate::v1::mul,
+        crate::v1::div,
+        crate::v1::modulo,
+        crate::v1::pow,
+        crate::v1::calc,
+        crate::maintenance::get_maintenance,
+        crate::maintenance::set_maintenance,
+        crate::log_level::get_log_level,
+        crate::log_level::set_log_level,
+    ),
+    components(schemas(
+        ErrorBody,
+        ErrorDetail,
+        crate::health::ReadinessResponse,
+        crate::stats::StatsResponse,
+        crate::stats::OpStatsSnapshot,
+        crate::stats::LatencyStats,
+        crate::stats::FlushSnapshot,
+        crate::latency::RouteLatency,
+        crate::quota::UsageResponse,
+        crate::v1::Envelope,
+        crate::v1::Meta,
+        crate::maintenance::MaintenanceState,
+        crate::log_level::LogLevelRequest,
+        crate::log_level::LogLevelResponse,
+    ))
+)]
+pub struct ApiDoc;
+
+#[get("/api/v0/openapi.json")]
+pub async fn spec() -> impl Responder {
+    web::Json(ApiDoc::openapi())
+}
+
+/// Swagger UI loaded from the official CDN, pointed at our spec. Serving
+/// the two static assets from elsewhere keeps the binary (and the build)
+/// free of a bundled UI distribution.
+const DOCS_HTML: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>sentry-rs-demo API docs</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    SwaggerUIBundle({ url: "/api/v0/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
+
+#[get("/docs")]
+pub async fn docs() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(DOCS_HTML)
+}
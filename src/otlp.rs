@@ -0,0 +1,30 @@
+use crate::error::{Error, Result};
+/// Builds the OTLP tracer provider for the configured collector
+/// endpoint: batched export over HTTP, with the service identity from
+/// the build info so the collector can tell releases apart. The caller
+/// owns the provider and must shut it down on exit to flush the batch.
+pub fn build_otlp_provider(endpoint: &str) -> Result<opentelemetry_sdk::trace::SdkTracerProvider> {
+    use opentelemetry_otlp::WithExportConfig;
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|err| Error::Config {
+            var: "OTEL_EXPORTER_OTLP_ENDPOINT",
+            message: format!("{endpoint}: {err}"),
+        })?;
+
+    let resource = opentelemetry_sdk::Resource::builder()
+        .with_service_name(env!("CARGO_PKG_NAME"))
+        .with_attribute(opentelemetry::KeyValue::new(
+            "service.version",
+            crate::version::release(),
+        ))
+        .build();
+
+    Ok(opentelemetry_sdk::trace::SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource)
+        .build())
+}
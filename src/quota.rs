@@ -0,0 +1,296 @@
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    get,
+    http::header::{HeaderMap, HeaderName, HeaderValue},
+    web, Error, HttpRequest, ResponseError,
+};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use serde::Serialize;
+use tracing::warn;
+use utoipa::ToSchema;
+
+use crate::config::{Config, QuotaWindow};
+use crate::error::{HTTPError, HttpResult};
+
+pub const REMAINING_HEADER: &str = "x-quota-remaining";
+pub const RESET_HEADER: &str = "x-quota-reset";
+
+/// Per-API-key request quotas over daily or monthly UTC windows, on top
+/// of (and much coarser than) the per-IP rate limiter. Counters live in
+/// the quota_usage table, so a file-backed DATABASE_URL carries them
+/// across restarts; the default in-memory database degrades to
+/// per-process counting.
+pub struct Quota;
+
+impl<S, B> Transform<S, ServiceRequest> for Quota
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = QuotaService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(QuotaService {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct QuotaService<S> {
+    service: Rc<S>,
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Civil-date helpers (Howard Hinnant's algorithms): just enough
+/// calendar to cut monthly windows without pulling in a date crate.
+fn civil_from_days(days: i64) -> (i64, u32) {
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m)
+}
+
+fn days_from_civil(y: i64, m: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = i64::from(if m > 2 { m - 3 } else { m + 9 });
+    let doy = (153 * mp + 2) / 5;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// The current window's identifier (stable for its whole span) and the
+/// unix timestamp at which it rolls over.
+pub(crate) fn window(now_secs: i64, window: QuotaWindow) -> (i64, i64) {
+    let days = now_secs.div_euclid(86_400);
+    match window {
+        QuotaWindow::Daily => (days, (days + 1) * 86_400),
+        QuotaWindow::Monthly => {
+            let (year, month) = civil_from_days(days);
+            let (next_year, next_month) = if month == 12 {
+                (year + 1, 1)
+            } else {
+                (year, month + 1)
+            };
+            (
+                year * 12 + i64::from(month),
+                days_from_civil(next_year, next_month) * 86_400,
+            )
+        }
+    }
+}
+
+/// The API key name presented on the request, resolved exactly as Auth
+/// resolves it.
+fn key_name(headers: &HeaderMap, config: &Config) -> Option<String> {
+    let presented = headers
+        .get(crate::middleware::API_KEY_HEADER)?
+        .to_str()
+        .ok()?;
+    config
+        .api_keys
+        .iter()
+        .find(|api_key| api_key.key == presented)
+        .map(|api_key| api_key.name.clone())
+}
+
+fn limit_for(name: &str, config: &Config) -> Option<u64> {
+    config
+        .quota_limits
+        .iter()
+        .find(|(quota_name, _)| quota_name == name)
+        .map(|(_, limit)| *limit)
+}
+
+/// Whether a successful response to this path consumes quota; the usage
+/// endpoint itself is free to poll.
+fn counted(path: &str) -> bool {
+    path.starts_with("/api/") && !path.ends_with("/usage")
+}
+
+fn set_quota_headers<B>(res: &mut ServiceResponse<B>, remaining: u64, reset: i64) {
+    res.headers_mut().insert(
+        HeaderName::from_static(REMAINING_HEADER),
+        HeaderValue::from(remaining),
+    );
+    res.headers_mut().insert(
+        HeaderName::from_static(RESET_HEADER),
+        HeaderValue::from(reset),
+    );
+}
+
+impl<S, B> Service<ServiceRequest> for QuotaService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            let config = Config::global();
+            // Keys without a configured limit (and anonymous requests,
+            // which Auth handles) pass through uncounted, as do paths
+            // outside the quota: the usage endpoint stays reachable even
+            // once the window is exhausted.
+            let quota = (!config.quota_limits.is_empty()
+                && !crate::middleware::is_public_path(req.path())
+                && counted(req.path()))
+            .then(|| key_name(req.headers(), &config))
+            .flatten()
+            .and_then(|name| limit_for(&name, &config).map(|limit| (name, limit)));
+            let Some((name, limit)) = quota else {
+                return Ok(service.call(req).await?.map_into_left_body());
+            };
+
+            let (window_id, reset) = window(now_secs(), config.quota_window);
+            let db = crate::db::Db::global();
+            // A quota store that cannot answer fails open: the request is
+            // served uncounted rather than refused for our own outage.
+            let mut used = match db.fetch_usage(&name, window_id).await {
+                Ok(used) => used as u64,
+                Err(err) => {
+                    warn!(%err, key = %name, "quota lookup failed; serving the request uncounted");
+                    return Ok(service.call(req).await?.map_into_left_body());
+                }
+            };
+
+            if used >= limit {
+                let mut response =
+                    HTTPError::from(crate::error::Error::QuotaExceeded { limit, reset })
+                        .error_response();
+                response.headers_mut().insert(
+                    HeaderName::from_static(REMAINING_HEADER),
+                    HeaderValue::from(0u64),
+                );
+                response.headers_mut().insert(
+                    HeaderName::from_static(RESET_HEADER),
+                    HeaderValue::from(reset),
+                );
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+
+            let res = service.call(req).await?;
+
+            // Only successful requests consume quota; a 400 or a shed
+            // request costs the caller nothing.
+            if res.status().is_success() {
+                match db.increment_usage(&name, window_id).await {
+                    Ok(new_used) => used = new_used as u64,
+                    Err(err) => {
+                        warn!(%err, key = %name, "failed to record quota usage");
+                        used += 1;
+                    }
+                }
+            }
+
+            let mut res = res.map_into_left_body();
+            set_quota_headers(&mut res, limit.saturating_sub(used), reset);
+            Ok(res)
+        })
+    }
+}
+
+/// The caller's consumption for the current window.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UsageResponse {
+    pub key: String,
+    pub window: String,
+    /// Absent when the key has no quota configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    pub used: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<u64>,
+    /// Unix timestamp at which the current window rolls over.
+    pub reset: i64,
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    responses(
+        (status = 200, description = "The caller's quota consumption for the current window", body = UsageResponse),
+        (status = 401, description = "No API key presented", body = crate::openapi::ErrorBody),
+    ),
+    tag = "meta"
+)]
+#[get("/usage")]
+pub async fn get_usage(req: HttpRequest) -> HttpResult<web::Json<UsageResponse>> {
+    let config = Config::global();
+    let Some(name) = key_name(req.headers(), &config) else {
+        return Err(crate::error::Error::MissingApiKey.into());
+    };
+
+    let (window_id, reset) = window(now_secs(), config.quota_window);
+    let used = crate::db::Db::global()
+        .fetch_usage(&name, window_id)
+        .await? as u64;
+    let limit = limit_for(&name, &config);
+
+    Ok(web::Json(UsageResponse {
+        key: name,
+        window: match config.quota_window {
+            QuotaWindow::Daily => "daily".to_string(),
+            QuotaWindow::Monthly => "monthly".to_string(),
+        },
+        limit,
+        used,
+        remaining: limit.map(|limit| limit.saturating_sub(used)),
+        reset,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn daily_windows_roll_over_at_utc_midnight() {
+        // 2024-01-31 23:59:59 and 2024-02-01 00:00:00.
+        let (id_before, reset) = window(1_706_745_599, QuotaWindow::Daily);
+        let (id_after, _) = window(1_706_745_600, QuotaWindow::Daily);
+        assert_eq!(id_after, id_before + 1);
+        assert_eq!(reset, 1_706_745_600);
+    }
+
+    #[test]
+    fn monthly_windows_roll_over_on_the_first() {
+        let (id_jan, reset) = window(1_706_745_599, QuotaWindow::Monthly);
+        let (id_feb, _) = window(1_706_745_600, QuotaWindow::Monthly);
+        assert_eq!(id_feb, id_jan + 1);
+        assert_eq!(reset, 1_706_745_600);
+
+        // December wraps into January of the next year.
+        // 2024-12-31 23:59:59 -> resets at 2025-01-01 00:00:00.
+        let (_, reset) = window(1_735_689_599, QuotaWindow::Monthly);
+        assert_eq!(reset, 1_735_689_600);
+    }
+}
@@ -0,0 +1,298 @@
+/// Severity for reported messages; the service only ever emits these two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Warning,
+    Error,
+}
+
+/// The capture path, abstracted away from sentry so air-gapped builds
+/// compiled without the "sentry" feature still type-check every call
+/// site. Tags and extras land on the event scope; the per-request hub
+/// bound by the middleware supplies the rest (request_id, route, user).
+pub trait ErrorReporter: Send + Sync {
+    /// Returns the id of the captured event when one was actually sent,
+    /// so callers can hand it to the user (and accept feedback on it).
+    fn report_error(
+        &self,
+        err: &(dyn std::error::Error + 'static),
+        tags: &[(&'static str, String)],
+        extras: &[(&'static str, serde_json::Value)],
+    ) -> Option<String>;
+
+    fn report_message(
+        &self,
+        message: &str,
+        level: Level,
+        tags: &[(&'static str, String)],
+        extras: &[(&'static str, serde_json::Value)],
+    );
+
+    /// Records a breadcrumb on whatever scope is current; an empty
+    /// message means the data map carries the whole story.
+    fn add_breadcrumb(
+        &self,
+        category: &'static str,
+        message: String,
+        data: &[(&'static str, serde_json::Value)],
+    );
+
+    /// Sets a tag on the current scope, so it rides along on every
+    /// subsequent capture for the same request.
+    fn set_tag(&self, key: &'static str, value: String);
+}
+
+/// Captures through whatever hub is current, exactly as the direct
+/// sentry calls it replaced did.
+#[cfg(feature = "sentry")]
+pub struct SentryReporter;
+
+#[cfg(feature = "sentry")]
+impl ErrorReporter for SentryReporter {
+    fn report_error(
+        &self,
+        err: &(dyn std::error::Error + 'static),
+        tags: &[(&'static str, String)],
+        extras: &[(&'static str, serde_json::Value)],
+    ) -> Option<String> {
+        // The fields of whatever spans are entered right now (already
+        // scrubbed), so the event shows the state the handler logged.
+        let span_fields = crate::telemetry::current_span_fields();
+        let uuid = sentry::with_scope(
+            |scope| {
+                if !span_fields.is_empty() {
+                    scope.set_context(
+                        "tracing",
+                        sentry::protocol::Context::Other(span_fields.clone()),
+                    );
+                }
+                for (key, value) in tags {
+                    scope.set_tag(key, value);
+                }
+                for (key, value) in extras {
+                    scope.set_extra(key, value.clone());
+                }
+            },
+            || sentry::capture_error(err),
+        );
+        // A nil uuid means no client is bound (or the event was
+        // dropped); there is nothing for the caller to quote.
+        (uuid != sentry::types::Uuid::nil()).then(|| uuid.simple().to_string())
+    }
+
+    fn report_message(
+        &self,
+        message: &str,
+        level: Level,
+        tags: &[(&'static str, String)],
+        extras: &[(&'static str, serde_json::Value)],
+    ) {
+        let level = match level {
+            Level::Warning => sentry::Level::Warning,
+            Level::Error => sentry::Level::Error,
+        };
+        let span_fields = crate::telemetry::current_span_fields();
+        sentry::with_scope(
+            |scope| {
+                if !span_fields.is_empty() {
+                    scope.set_context(
+                        "tracing",
+                        sentry::protocol::Context::Other(span_fields.clone()),
+                    );
+                }
+                for (key, value) in tags {
+                    scope.set_tag(key, value);
+                }
+                for (key, value) in extras {
+                    scope.set_extra(key, value.clone());
+                }
+            },
+            || {
+                sentry::capture_message(message, level);
+            },
+        );
+    }
+
+    fn add_breadcrumb(
+        &self,
+        category: &'static str,
+        message: String,
+        data: &[(&'static str, serde_json::Value)],
+    ) {
+        let mut map = sentry::protocol::Map::new();
+        for (key, value) in data {
+            map.insert((*key).to_owned(), value.clone());
+        }
+        sentry::add_breadcrumb(sentry::Breadcrumb {
+            // The http type renders as a request line in the UI;
+            // everything else is a plain default crumb.
+            ty: if category == "http" {
+                "http"
+            } else {
+                "default"
+            }
+            .into(),
+            category: Some(category.into()),
+            message: (!message.is_empty()).then_some(message),
+            data: map,
+            ..Default::default()
+        });
+    }
+
+    fn set_tag(&self, key: &'static str, value: String) {
+        sentry::configure_scope(|scope| scope.set_tag(key, value));
+    }
+}
+
+/// The implementation air-gapped builds get: errors still reach the
+/// structured logs and metrics through their usual paths, they just have
+/// nowhere else to go.
+pub struct NoopReporter;
+
+impl ErrorReporter for NoopReporter {
+    fn report_error(
+        &self,
+        _err: &(dyn std::error::Error + 'static),
+        _tags: &[(&'static str, String)],
+        _extras: &[(&'static str, serde_json::Value)],
+    ) -> Option<String> {
+        None
+    }
+
+    fn report_message(
+        &self,
+        _message: &str,
+        _level: Level,
+        _tags: &[(&'static str, String)],
+        _extras: &[(&'static str, serde_json::Value)],
+    ) {
+    }
+
+    fn add_breadcrumb(
+        &self,
+        _category: &'static str,
+        _message: String,
+        _data: &[(&'static str, serde_json::Value)],
+    ) {
+    }
+
+    fn set_tag(&self, _key: &'static str, _value: String) {}
+}
+
+/// One recorded reporter call, in order; tests pattern-match on these.
+#[derive(Debug)]
+pub enum ReporterCall {
+    Error {
+        message: String,
+        tags: Vec<(&'static str, String)>,
+        extras: Vec<(&'static str, serde_json::Value)>,
+    },
+    Message {
+        message: String,
+        level: Level,
+        tags: Vec<(&'static str, String)>,
+        extras: Vec<(&'static str, serde_json::Value)>,
+    },
+    Breadcrumb {
+        category: &'static str,
+        message: String,
+        data: Vec<(&'static str, serde_json::Value)>,
+    },
+    Tag {
+        key: &'static str,
+        value: String,
+    },
+}
+
+/// Records every call instead of capturing, so tests can assert on what
+/// would have been sent without a client or transport anywhere.
+#[derive(Default)]
+pub struct RecordingReporter {
+    calls: std::sync::Mutex<Vec<ReporterCall>>,
+}
+
+impl RecordingReporter {
+    /// Drains and returns everything recorded so far.
+    pub fn take(&self) -> Vec<ReporterCall> {
+        std::mem::take(&mut self.calls.lock().unwrap())
+    }
+
+    fn record(&self, call: ReporterCall) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+impl ErrorReporter for RecordingReporter {
+    fn report_error(
+        &self,
+        err: &(dyn std::error::Error + 'static),
+        tags: &[(&'static str, String)],
+        extras: &[(&'static str, serde_json::Value)],
+    ) -> Option<String> {
+        self.record(ReporterCall::Error {
+            message: err.to_string(),
+            tags: tags.to_vec(),
+            extras: extras.to_vec(),
+        });
+        // A stable fake id per call, so tests can assert the plumbing.
+        let count = self.calls.lock().unwrap().len();
+        Some(format!("{count:032x}"))
+    }
+
+    fn report_message(
+        &self,
+        message: &str,
+        level: Level,
+        tags: &[(&'static str, String)],
+        extras: &[(&'static str, serde_json::Value)],
+    ) {
+        self.record(ReporterCall::Message {
+            message: message.to_owned(),
+            level,
+            tags: tags.to_vec(),
+            extras: extras.to_vec(),
+        });
+    }
+
+    fn add_breadcrumb(
+        &self,
+        category: &'static str,
+        message: String,
+        data: &[(&'static str, serde_json::Value)],
+    ) {
+        self.record(ReporterCall::Breadcrumb {
+            category,
+            message,
+            data: data.to_vec(),
+        });
+    }
+
+    fn set_tag(&self, key: &'static str, value: String) {
+        self.record(ReporterCall::Tag { key, value });
+    }
+}
+
+/// The default reporter as shareable app data, for injection into the
+/// request path via web::Data.
+pub fn shared() -> std::sync::Arc<dyn ErrorReporter> {
+    #[cfg(feature = "sentry")]
+    {
+        std::sync::Arc::new(SentryReporter)
+    }
+    #[cfg(not(feature = "sentry"))]
+    {
+        std::sync::Arc::new(NoopReporter)
+    }
+}
+
+/// The process-wide reporter for capture sites with no request (and so
+/// no app data) in reach.
+pub fn global() -> &'static dyn ErrorReporter {
+    #[cfg(feature = "sentry")]
+    {
+        &SentryReporter
+    }
+    #[cfg(not(feature = "sentry"))]
+    {
+        &NoopReporter
+    }
+}
@@ -0,0 +1,129 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use actix_web::{get, post, web, Responder};
+use serde::{Deserialize, Serialize};
+
+/// The sentry kill-switch: while disabled, before_send drops every
+/// event, so reporting can be stopped immediately (quota emergency,
+/// privacy incident) without a redeploy. Suppressions still count in
+/// the local metrics, so nothing disappears without trace.
+pub struct Reporting {
+    enabled: AtomicBool,
+    sent: AtomicU64,
+    suppressed: AtomicU64,
+}
+
+impl Reporting {
+    fn new() -> Self {
+        Reporting {
+            enabled: AtomicBool::new(crate::config::Config::global().sentry_reporting_enabled),
+            sent: AtomicU64::new(0),
+            suppressed: AtomicU64::new(0),
+        }
+    }
+
+    pub fn global() -> Arc<Reporting> {
+        static REPORTING: OnceLock<Arc<Reporting>> = OnceLock::new();
+        REPORTING.get_or_init(|| Arc::new(Reporting::new())).clone()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Flips the switch, returning the previous state. The counters
+    /// reset on an actual change, so they always read "since the flag
+    /// was last flipped".
+    pub fn set_enabled(&self, enabled: bool) -> bool {
+        let previous = self.enabled.swap(enabled, Ordering::Relaxed);
+        if previous != enabled {
+            self.sent.store(0, Ordering::Relaxed);
+            self.suppressed.store(0, Ordering::Relaxed);
+        }
+        previous
+    }
+
+    pub(crate) fn note_sent(&self) {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn note_suppressed(&self) {
+        self.suppressed.fetch_add(1, Ordering::Relaxed);
+        crate::metrics::Metrics::global()
+            .sentry_events_suppressed_total
+            .inc();
+    }
+
+    fn state(&self) -> ReportingState {
+        ReportingState {
+            enabled: self.is_enabled(),
+            events_sent: self.sent.load(Ordering::Relaxed),
+            events_suppressed: self.suppressed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportingState {
+    pub enabled: bool,
+    /// Events that went out since the flag last changed.
+    pub events_sent: u64,
+    /// Events the kill-switch dropped since the flag last changed.
+    pub events_suppressed: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportingToggle {
+    pub enabled: bool,
+}
+
+#[get("/sentry")]
+pub async fn get_reporting() -> impl Responder {
+    web::Json(Reporting::global().state())
+}
+
+#[post("/sentry")]
+pub async fn set_reporting(
+    req: actix_web::HttpRequest,
+    body: web::Json<ReportingToggle>,
+) -> impl Responder {
+    let reporting = Reporting::global();
+    let previous = reporting.set_enabled(body.enabled);
+    crate::admin::audit(
+        &req,
+        "sentry-reporting",
+        &previous.to_string(),
+        &body.enabled.to_string(),
+    );
+    web::Json(reporting.state())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_accumulate_and_reset_when_the_flag_flips() {
+        let reporting = Reporting::new();
+        assert!(reporting.is_enabled());
+
+        reporting.note_sent();
+        reporting.note_sent();
+        let state = reporting.state();
+        assert_eq!(state.events_sent, 2);
+        assert_eq!(state.events_suppressed, 0);
+
+        // Flipping resets; setting the same value again does not.
+        assert!(reporting.set_enabled(false));
+        reporting.note_suppressed();
+        assert!(!reporting.set_enabled(false));
+        let state = reporting.state();
+        assert!(!state.enabled);
+        assert_eq!(state.events_sent, 0);
+        assert_eq!(state.events_suppressed, 1);
+
+        assert!(!reporting.set_enabled(true));
+        assert_eq!(reporting.state().events_suppressed, 0);
+    }
+}
@@ -0,0 +1,129 @@
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    Error,
+};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+
+/// Sets the standard security response headers, each configurable (and
+/// disablable) through Config. Strict-Transport-Security only goes out
+/// when TLS is actually enabled, and the Content-Security-Policy is
+/// restricted to the /docs swagger page, the only HTML we serve.
+/// Headers a handler already set are left alone.
+pub struct SecurityHeaders;
+
+impl<S, B> Transform<S, ServiceRequest> for SecurityHeaders
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = SecurityHeadersService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(SecurityHeadersService { service }))
+    }
+}
+
+pub struct SecurityHeadersService<S> {
+    service: S,
+}
+
+/// Inserts only when absent and only when the configured value is both
+/// non-empty and a legal header value.
+fn set_if_absent<B>(res: &mut ServiceResponse<B>, name: HeaderName, value: &str) {
+    if value.is_empty() || res.headers().contains_key(&name) {
+        return;
+    }
+    if let Ok(value) = HeaderValue::from_str(value) {
+        res.headers_mut().insert(name, value);
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for SecurityHeadersService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let docs = req.path() == "/docs";
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+            let config = crate::config::Config::global();
+
+            set_if_absent(
+                &mut res,
+                HeaderName::from_static("x-content-type-options"),
+                &config.security_content_type_options,
+            );
+            set_if_absent(
+                &mut res,
+                HeaderName::from_static("x-frame-options"),
+                &config.security_frame_options,
+            );
+            set_if_absent(
+                &mut res,
+                HeaderName::from_static("referrer-policy"),
+                &config.security_referrer_policy,
+            );
+            if config.tls_cert_path.is_some() && config.tls_key_path.is_some() {
+                set_if_absent(
+                    &mut res,
+                    HeaderName::from_static("strict-transport-security"),
+                    &config.security_hsts,
+                );
+            }
+            if docs {
+                set_if_absent(
+                    &mut res,
+                    HeaderName::from_static("content-security-policy"),
+                    &config.security_docs_csp,
+                );
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{get, test, App, HttpResponse, Responder};
+
+    use super::*;
+
+    #[get("/sameorigin")]
+    async fn sameorigin() -> impl Responder {
+        HttpResponse::Ok()
+            .insert_header(("x-frame-options", "SAMEORIGIN"))
+            .finish()
+    }
+
+    #[actix_web::test]
+    async fn handler_set_headers_are_not_clobbered() {
+        let app = test::init_service(App::new().wrap(SecurityHeaders).service(sameorigin)).await;
+        let resp = test::call_service(
+            &app,
+            test::TestRequest::get().uri("/sameorigin").to_request(),
+        )
+        .await;
+        assert_eq!(resp.headers().get("x-frame-options").unwrap(), "SAMEORIGIN");
+        assert_eq!(
+            resp.headers().get("x-content-type-options").unwrap(),
+            "nosniff"
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+use tokio::sync::broadcast;
+
+use crate::error::{Error, Result};
+
+/// What a leader broadcasts to its followers. Error is not Clone (some
+/// variants wrap io/actix errors), so failures travel as the triple the
+/// wire format needs and are rebuilt into Error::Coalesced on arrival.
+type Outcome<V> = std::result::Result<V, (&'static str, String, actix_web::http::StatusCode)>;
+
+/// Coalesces identical concurrent requests: the first caller for a key
+/// computes, everyone else who arrives before it finishes awaits the
+/// same result. Distinct from the result cache — nothing is retained
+/// once the flight lands, so this helps only while requests overlap.
+pub struct SingleFlight<V> {
+    flights: Mutex<HashMap<String, broadcast::Sender<Outcome<V>>>>,
+}
+
+/// Removes the flight entry when the leader is done — including when it
+/// panics or is cancelled mid-compute, in which case dropping the map's
+/// sender wakes every follower with a closed-channel error instead of
+/// leaving them hanging.
+struct Cleanup<'a, V> {
+    flights: &'a Mutex<HashMap<String, broadcast::Sender<Outcome<V>>>>,
+    key: &'a str,
+}
+
+impl<V> Cleanup<'_, V> {
+    fn take(&self) -> Option<broadcast::Sender<Outcome<V>>> {
+        self.flights.lock().unwrap().remove(self.key)
+    }
+}
+
+impl<V> Drop for Cleanup<'_, V> {
+    fn drop(&mut self) {
+        self.take();
+    }
+}
+
+impl<V: Clone> SingleFlight<V> {
+    pub fn new() -> Self {
+        SingleFlight {
+            flights: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Runs `compute` unless an identical call is already in flight, in
+    /// which case its result is awaited instead. `op` labels the
+    /// coalesced-request counter.
+    pub async fn run<F, Fut>(&self, op: &'static str, key: &str, compute: F) -> Result<V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V>>,
+    {
+        let rx = {
+            let mut flights = self.flights.lock().unwrap();
+            match flights.get(key) {
+                Some(tx) => Some(tx.subscribe()),
+                None => {
+                    // Capacity 1 suffices: each sender carries exactly
+                    // one message, the outcome.
+                    let (tx, _) = broadcast::channel(1);
+                    flights.insert(key.to_string(), tx);
+                    None
+                }
+            }
+        };
+
+        if let Some(mut rx) = rx {
+            crate::metrics::Metrics::global()
+                .coalesced_requests_total
+                .with_label_values(&[op])
+                .inc();
+            return match rx.recv().await {
+                Ok(Ok(value)) => Ok(value),
+                Ok(Err((code, message, status))) => Err(Error::Coalesced {
+                    code,
+                    message,
+                    status,
+                }),
+                // The leader panicked or was cancelled before sending.
+                Err(_) => Err(Error::HandlerPanic(format!(
+                    "coalesced {op} leader vanished before producing a result"
+                ))),
+            };
+        }
+
+        let cleanup = Cleanup {
+            flights: &self.flights,
+            key,
+        };
+        let res = compute().await;
+
+        // Remove the entry before sending: a request arriving after the
+        // send must start a fresh flight, not subscribe to a landed one.
+        if let Some(tx) = cleanup.take() {
+            let outcome = match &res {
+                Ok(value) => Ok(value.clone()),
+                Err(err) => Err((err.code(), err.to_string(), err.status_code())),
+            };
+            let _ = tx.send(outcome);
+        }
+        res
+    }
+}
+
+impl<V: Clone> Default for SingleFlight<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn many_identical_slow_requests_compute_once() {
+        let flight = Arc::new(SingleFlight::<i64>::new());
+        let computed = Arc::new(AtomicUsize::new(0));
+
+        let calls = (0..32).map(|_| {
+            let flight = Arc::clone(&flight);
+            let computed = Arc::clone(&computed);
+            async move {
+                flight
+                    .run("eval", "6*7", || async {
+                        computed.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                        Ok(42)
+                    })
+                    .await
+            }
+        });
+
+        for res in futures_util::future::join_all(calls).await {
+            assert_eq!(res.unwrap(), 42);
+        }
+        assert_eq!(computed.load(Ordering::SeqCst), 1);
+        assert!(flight.flights.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn followers_receive_the_leaders_error() {
+        let flight = Arc::new(SingleFlight::<i64>::new());
+
+        let calls = (0..4).map(|_| {
+            let flight = Arc::clone(&flight);
+            async move {
+                flight
+                    .run("eval", "1/0", || async {
+                        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                        Err(Error::DivideByZero)
+                    })
+                    .await
+            }
+        });
+
+        let results = futures_util::future::join_all(calls).await;
+        let mut leaders = 0;
+        for res in results {
+            let err = res.unwrap_err();
+            assert_eq!(err.code(), "divide_by_zero");
+            assert_eq!(err.status_code(), Error::DivideByZero.status_code());
+            if matches!(err, Error::DivideByZero) {
+                leaders += 1;
+            }
+        }
+        assert_eq!(leaders, 1, "exactly one caller computed");
+        assert!(flight.flights.lock().unwrap().is_empty());
+    }
+
+    // The key includes the operands: different work never coalesces.
+    #[tokio::test]
+    async fn distinct_keys_do_not_coalesce() {
+        let flight = Arc::new(SingleFlight::<i64>::new());
+        let computed = Arc::new(AtomicUsize::new(0));
+
+        let calls = (0..4).map(|i| {
+            let flight = Arc::clone(&flight);
+            let computed = Arc::clone(&computed);
+            async move {
+                flight
+                    .run("eval", &format!("{i}+1"), || async {
+                        computed.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                        Ok(i + 1)
+                    })
+                    .await
+                    .unwrap()
+            }
+        });
+
+        let results = futures_util::future::join_all(calls).await;
+        assert_eq!(results, vec![1, 2, 3, 4]);
+        assert_eq!(computed.load(Ordering::SeqCst), 4);
+    }
+}
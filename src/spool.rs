@@ -0,0 +1,453 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use sentry::protocol::Envelope;
+use tracing::{info, warn};
+
+/// How long the worker waits for the next envelope before checking
+/// whether a replay is due.
+const REPLAY_POLL: Duration = Duration::from_millis(500);
+
+/// Replay backoff bounds: doubling from the initial value on every
+/// failed attempt, capped so a long outage still retries about once a
+/// minute.
+const INITIAL_REPLAY_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_REPLAY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Envelopes waiting for the worker; beyond this the newest is dropped,
+/// same trade as the webhook queue.
+const QUEUE_CAPACITY: usize = 100;
+
+/// How a spooled or fresh envelope reaches the DSN host. The transport
+/// is generic over this so tests can stand in a fake that fails on
+/// command.
+pub trait EnvelopeSender: Send + Sync + 'static {
+    /// Whether the envelope is done with — delivered, or rejected in a
+    /// way a retry cannot fix. False means spool it and try again.
+    fn try_send(&self, envelope: &Envelope) -> bool;
+}
+
+/// The production sender: POSTs envelopes to the DSN's envelope
+/// endpoint, authenticated the same way the stock transport does.
+pub struct HttpSender {
+    client: reqwest::blocking::Client,
+    url: String,
+    auth: String,
+}
+
+impl HttpSender {
+    pub fn new(dsn: &sentry::types::Dsn, config: &crate::config::Config) -> Self {
+        let mut builder = reqwest::blocking::Client::builder().timeout(Duration::from_secs(5));
+        // All validated at config load; a failure here means the bundle
+        // changed on disk since.
+        if let Some(path) = &config.sentry_ca_bundle {
+            let pem = std::fs::read(path).expect("failed to read SENTRY_CA_BUNDLE");
+            builder = builder.add_root_certificate(
+                reqwest::Certificate::from_pem(&pem).expect("invalid SENTRY_CA_BUNDLE"),
+            );
+        }
+        if let Some(proxy) = &config.sentry_http_proxy {
+            builder =
+                builder.proxy(reqwest::Proxy::http(proxy).expect("invalid SENTRY_HTTP_PROXY"));
+        }
+        if let Some(proxy) = &config.sentry_https_proxy {
+            builder =
+                builder.proxy(reqwest::Proxy::https(proxy).expect("invalid SENTRY_HTTPS_PROXY"));
+        }
+        HttpSender {
+            client: builder
+                .build()
+                .expect("failed to build the sentry spool http client"),
+            url: dsn.envelope_api_url().to_string(),
+            auth: dsn
+                .to_auth(Some(concat!("sentry-rs-demo/", env!("CARGO_PKG_VERSION"))))
+                .to_string(),
+        }
+    }
+}
+
+impl EnvelopeSender for HttpSender {
+    fn try_send(&self, envelope: &Envelope) -> bool {
+        let mut body = Vec::new();
+        if envelope.to_writer(&mut body).is_err() {
+            // Unserializable now means unserializable on replay too;
+            // treat it as consumed rather than spooling it forever.
+            return true;
+        }
+        match self
+            .client
+            .post(&self.url)
+            .header("x-sentry-auth", &self.auth)
+            .body(body)
+            .send()
+        {
+            // 429 and 5xx are worth a retry; other rejections (bad
+            // envelope, wrong key) will not improve with age.
+            Ok(response) => {
+                !(response.status().is_server_error() || response.status().as_u16() == 429)
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+/// The bounded on-disk envelope buffer. File names sort chronologically
+/// (microsecond timestamp plus a sequence number), so lexicographic
+/// order is replay order.
+pub struct Spool {
+    dir: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    seq: AtomicU64,
+}
+
+impl Spool {
+    pub fn new(dir: PathBuf, max_bytes: u64, max_files: usize) -> std::io::Result<Spool> {
+        fs::create_dir_all(&dir)?;
+        Ok(Spool {
+            dir,
+            max_bytes,
+            max_files,
+            seq: AtomicU64::new(0),
+        })
+    }
+
+    /// The spooled files, oldest first. Non-envelope files (editors,
+    /// filesystems) are left alone.
+    fn files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = fs::read_dir(&self.dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "envelope"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.sort();
+        files
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files().is_empty()
+    }
+
+    /// Writes the envelope to the spool and evicts the oldest entries
+    /// if that pushed the directory over its bounds.
+    pub fn spill(&self, envelope: &Envelope) {
+        let mut body = Vec::new();
+        if envelope.to_writer(&mut body).is_err() {
+            return;
+        }
+        let micros = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_micros() as u64)
+            .unwrap_or(0);
+        let seq = self.seq.fetch_add(1, Ordering::Relaxed);
+        let path = self.dir.join(format!("{micros:020}-{seq:06}.envelope"));
+        if let Err(err) = fs::write(&path, &body) {
+            warn!(%err, path = %path.display(), "could not spool a sentry envelope");
+            return;
+        }
+        self.enforce_bounds();
+    }
+
+    fn enforce_bounds(&self) {
+        let files = self.files();
+        let sizes: Vec<u64> = files
+            .iter()
+            .map(|path| fs::metadata(path).map(|meta| meta.len()).unwrap_or(0))
+            .collect();
+        let mut total: u64 = sizes.iter().sum();
+        let mut count = files.len();
+        for (path, size) in files.iter().zip(&sizes) {
+            if count <= self.max_files && total <= self.max_bytes {
+                break;
+            }
+            if fs::remove_file(path).is_ok() {
+                warn!(
+                    path = %path.display(),
+                    "sentry spool over its bounds; dropped the oldest envelope"
+                );
+                count -= 1;
+                total -= size;
+            }
+        }
+    }
+
+    /// Resends the spooled envelopes oldest-first, deleting each on
+    /// success. Stops at the first failure (the host is down again);
+    /// corrupt files are deleted and skipped so one bad write cannot
+    /// wedge the queue. True means the spool is drained.
+    pub fn replay(&self, sender: &dyn EnvelopeSender) -> bool {
+        for path in self.files() {
+            let envelope = match Envelope::from_path(&path) {
+                Ok(envelope) => envelope,
+                Err(err) => {
+                    warn!(%err, path = %path.display(), "corrupt spool file; deleting it");
+                    let _ = fs::remove_file(&path);
+                    continue;
+                }
+            };
+            if !sender.try_send(&envelope) {
+                return false;
+            }
+            let _ = fs::remove_file(&path);
+        }
+        true
+    }
+}
+
+enum Task {
+    Send(Envelope),
+    Flush(mpsc::SyncSender<()>),
+}
+
+/// A sentry Transport that falls back to the on-disk spool when the
+/// host is unreachable, replaying in order once it answers again. The
+/// actual I/O happens on a dedicated worker thread, like the stock
+/// transports, so capture never blocks on the network.
+pub struct SpoolingTransport {
+    queue: Mutex<Option<mpsc::SyncSender<Task>>>,
+    worker: Mutex<Option<std::thread::JoinHandle<()>>>,
+}
+
+impl SpoolingTransport {
+    pub fn start<S: EnvelopeSender>(sender: S, spool: Spool) -> SpoolingTransport {
+        let (queue, tasks) = mpsc::sync_channel(QUEUE_CAPACITY);
+        let worker = std::thread::Builder::new()
+            .name("sentry-spool".into())
+            .spawn(move || run(sender, spool, tasks))
+            .expect("failed to spawn the sentry spool worker");
+        SpoolingTransport {
+            queue: Mutex::new(Some(queue)),
+            worker: Mutex::new(Some(worker)),
+        }
+    }
+}
+
+impl sentry::Transport for SpoolingTransport {
+    fn send_envelope(&self, envelope: Envelope) {
+        let queue = self.queue.lock().unwrap();
+        let Some(queue) = queue.as_ref() else {
+            return;
+        };
+        if queue.try_send(Task::Send(envelope)).is_err() {
+            warn!("sentry spool queue full; envelope dropped");
+        }
+    }
+
+    fn flush(&self, timeout: Duration) -> bool {
+        let queue = match self.queue.lock().unwrap().clone() {
+            Some(queue) => queue,
+            None => return true,
+        };
+        let (done, ack) = mpsc::sync_channel(1);
+        if queue.try_send(Task::Flush(done)).is_err() {
+            return false;
+        }
+        ack.recv_timeout(timeout).is_ok()
+    }
+
+    fn shutdown(&self, timeout: Duration) -> bool {
+        let flushed = self.flush(timeout);
+        // Dropping the queue ends the worker loop; join so the final
+        // replay attempt finishes before the process exits.
+        drop(self.queue.lock().unwrap().take());
+        if let Some(worker) = self.worker.lock().unwrap().take() {
+            let _ = worker.join();
+        }
+        flushed
+    }
+}
+
+fn run<S: EnvelopeSender>(sender: S, spool: Spool, tasks: mpsc::Receiver<Task>) {
+    let mut backoff = INITIAL_REPLAY_BACKOFF;
+    let mut next_replay = Instant::now();
+    loop {
+        match tasks.recv_timeout(REPLAY_POLL) {
+            Ok(Task::Send(envelope)) => {
+                // Anything already spooled goes first, or replay would
+                // deliver out of order.
+                if !spool.is_empty() {
+                    spool.spill(&envelope);
+                } else if !sender.try_send(&envelope) {
+                    spool.spill(&envelope);
+                    next_replay = Instant::now() + jittered(backoff);
+                }
+            }
+            Ok(Task::Flush(done)) => {
+                // An explicit flush is shutdown trying to get the tail
+                // out; it overrides the backoff.
+                attempt_replay(&sender, &spool, &mut backoff, &mut next_replay);
+                let _ = done.send(());
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if Instant::now() >= next_replay {
+                    attempt_replay(&sender, &spool, &mut backoff, &mut next_replay);
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+fn attempt_replay<S: EnvelopeSender>(
+    sender: &S,
+    spool: &Spool,
+    backoff: &mut Duration,
+    next_replay: &mut Instant,
+) {
+    if spool.is_empty() {
+        return;
+    }
+    if spool.replay(sender) {
+        info!("sentry spool drained; live delivery resumes");
+        *backoff = INITIAL_REPLAY_BACKOFF;
+    } else {
+        *backoff = (*backoff * 2).min(MAX_REPLAY_BACKOFF);
+        *next_replay = Instant::now() + jittered(*backoff);
+    }
+}
+
+/// The backoff plus up to half of itself again, from the clock's
+/// sub-second noise, so a fleet of replicas does not hammer a
+/// recovering host in lockstep.
+fn jittered(backoff: Duration) -> Duration {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0);
+    backoff + Duration::from_millis(nanos % (backoff.as_millis() as u64 / 2).max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    use sentry::types::Uuid;
+    use sentry::Transport as _;
+
+    use super::*;
+
+    struct FakeSender {
+        up: AtomicBool,
+        sent: Mutex<Vec<Uuid>>,
+    }
+
+    impl FakeSender {
+        fn new(up: bool) -> Arc<FakeSender> {
+            Arc::new(FakeSender {
+                up: AtomicBool::new(up),
+                sent: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn sent(&self) -> Vec<Uuid> {
+            self.sent.lock().unwrap().clone()
+        }
+    }
+
+    impl EnvelopeSender for Arc<FakeSender> {
+        fn try_send(&self, envelope: &Envelope) -> bool {
+            if !self.up.load(Ordering::Relaxed) {
+                return false;
+            }
+            self.sent.lock().unwrap().push(*envelope.uuid().unwrap());
+            true
+        }
+    }
+
+    fn temp_spool(name: &str, max_bytes: u64, max_files: usize) -> Spool {
+        let dir = std::env::temp_dir().join(format!(
+            "sentry-rs-demo-spool-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        Spool::new(dir, max_bytes, max_files).unwrap()
+    }
+
+    fn envelope() -> (Uuid, Envelope) {
+        let event_id = Uuid::new_v4();
+        let event = sentry::protocol::Event {
+            event_id,
+            ..Default::default()
+        };
+        (event_id, Envelope::from(event))
+    }
+
+    #[test]
+    fn spilled_envelopes_replay_in_order() {
+        let spool = temp_spool("order", u64::MAX, usize::MAX);
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let (id, envelope) = envelope();
+            spool.spill(&envelope);
+            ids.push(id);
+        }
+
+        let sender = FakeSender::new(true);
+        assert!(spool.replay(&sender));
+        assert_eq!(sender.sent(), ids);
+        assert!(spool.is_empty());
+    }
+
+    #[test]
+    fn the_bounds_drop_the_oldest_first() {
+        let spool = temp_spool("bounds", u64::MAX, 2);
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let (id, envelope) = envelope();
+            spool.spill(&envelope);
+            ids.push(id);
+        }
+        assert_eq!(spool.files().len(), 2);
+
+        let sender = FakeSender::new(true);
+        assert!(spool.replay(&sender));
+        assert_eq!(sender.sent(), ids[1..]);
+    }
+
+    #[test]
+    fn corrupt_spool_files_are_deleted_and_skipped() {
+        let spool = temp_spool("corrupt", u64::MAX, usize::MAX);
+        fs::write(
+            spool.dir.join("00000000-000000.envelope"),
+            b"not an envelope",
+        )
+        .unwrap();
+        let (id, envelope) = envelope();
+        spool.spill(&envelope);
+
+        let sender = FakeSender::new(true);
+        assert!(spool.replay(&sender));
+        assert_eq!(sender.sent(), vec![id]);
+        assert!(spool.is_empty());
+    }
+
+    #[test]
+    fn the_transport_spills_on_failure_and_replays_on_recovery() {
+        let spool = temp_spool("transport", u64::MAX, usize::MAX);
+        let sender = FakeSender::new(false);
+        let transport = SpoolingTransport::start(Arc::clone(&sender), spool);
+
+        let (first, envelope_one) = envelope();
+        let (second, envelope_two) = envelope();
+        transport.send_envelope(envelope_one);
+        transport.send_envelope(envelope_two);
+
+        // With the host down both envelopes land on disk.
+        assert!(transport.flush(Duration::from_secs(5)));
+        assert!(sender.sent().is_empty());
+
+        // Once it answers again, a flush drains the spool in order.
+        sender.up.store(true, Ordering::Relaxed);
+        assert!(transport.flush(Duration::from_secs(5)));
+        assert_eq!(sender.sent(), vec![first, second]);
+        assert!(transport.shutdown(Duration::from_secs(5)));
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use actix_web::{delete, get, web, HttpResponse, Responder};
+use serde::Serialize;
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::calculator::Operation;
+use crate::error::Result;
+
+/// Per-operation counters. The success hot path touches only atomics;
+/// the error map takes a lock, but errors are not the hot path.
+struct OpStats {
+    requests: AtomicU64,
+    total_micros: AtomicU64,
+    /// u64::MAX until the first sample lands.
+    min_micros: AtomicU64,
+    max_micros: AtomicU64,
+    errors: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl OpStats {
+    fn new() -> Self {
+        OpStats {
+            requests: AtomicU64::new(0),
+            total_micros: AtomicU64::new(0),
+            min_micros: AtomicU64::new(u64::MAX),
+            max_micros: AtomicU64::new(0),
+            errors: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    // Generic over the result width; only the error code is recorded.
+    fn record<T>(&self, res: &Result<T>, latency: Duration) {
+        let micros = latency.as_micros() as u64;
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.total_micros.fetch_add(micros, Ordering::Relaxed);
+        self.min_micros.fetch_min(micros, Ordering::Relaxed);
+        self.max_micros.fetch_max(micros, Ordering::Relaxed);
+
+        if let Err(err) = res {
+            *self.errors.lock().unwrap().entry(err.code()).or_default() += 1;
+        }
+    }
+
+    fn snapshot(&self) -> OpStatsSnapshot {
+        let requests = self.requests.load(Ordering::Relaxed);
+        let latency = if requests > 0 {
+            Some(LatencyStats {
+                min_ms: self.min_micros.load(Ordering::Relaxed) as f64 / 1_000.0,
+                max_ms: self.max_micros.load(Ordering::Relaxed) as f64 / 1_000.0,
+                avg_ms: self.total_micros.load(Ordering::Relaxed) as f64
+                    / requests as f64
+                    / 1_000.0,
+            })
+        } else {
+            None
+        };
+
+        OpStatsSnapshot {
+            requests,
+            errors: self.errors.lock().unwrap().clone(),
+            latency,
+        }
+    }
+}
+
+/// Aggregate statistics since process start, one bucket per operation
+/// per tenant. The tenant map only grows on a tenant's first request,
+/// so the hot path is a read lock.
+pub struct Stats {
+    started: Instant,
+    tenants: RwLock<BTreeMap<String, BTreeMap<&'static str, OpStats>>>,
+    /// What the flusher last recorded; surfaced on /stats.
+    last_flush: Mutex<Option<FlushSnapshot>>,
+}
+
+const ALL_OPERATIONS: [Operation; 6] = [
+    Operation::Add,
+    Operation::Sub,
+    Operation::Mul,
+    Operation::Div,
+    Operation::Mod,
+    Operation::Pow,
+];
+
+fn per_op_buckets() -> BTreeMap<&'static str, OpStats> {
+    ALL_OPERATIONS
+        .iter()
+        .map(|op| (op.name(), OpStats::new()))
+        .collect()
+}
+
+impl Stats {
+    fn new() -> Self {
+        Stats {
+            started: Instant::now(),
+            tenants: RwLock::new(BTreeMap::new()),
+            last_flush: Mutex::new(None),
+        }
+    }
+
+    pub fn global() -> Arc<Stats> {
+        static STATS: OnceLock<Arc<Stats>> = OnceLock::new();
+        STATS.get_or_init(|| Arc::new(Stats::new())).clone()
+    }
+
+    pub fn record<T>(&self, op: Operation, res: &Result<T>, latency: Duration) {
+        let tenant = crate::tenant::current();
+        if let Some(ops) = self.tenants.read().unwrap().get(&tenant) {
+            ops[op.name()].record(res, latency);
+            return;
+        }
+        self.tenants
+            .write()
+            .unwrap()
+            .entry(tenant)
+            .or_insert_with(per_op_buckets)[op.name()]
+        .record(res, latency);
+    }
+
+    pub fn reset(&self) {
+        self.tenants.write().unwrap().clear();
+    }
+
+    /// Requests recorded across every tenant, for the reset audit line.
+    fn served(&self) -> u64 {
+        self.tenants
+            .read()
+            .unwrap()
+            .values()
+            .flat_map(|ops| ops.values())
+            .map(|op_stats| op_stats.requests.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Errors recorded across every tenant since process start.
+    fn errors_total(&self) -> u64 {
+        self.tenants
+            .read()
+            .unwrap()
+            .values()
+            .flat_map(|ops| ops.values())
+            .map(|op_stats| op_stats.errors.lock().unwrap().values().sum::<u64>())
+            .sum()
+    }
+
+    /// Takes the aggregate health picture — all tenants folded together —
+    /// and remembers it as the last flush.
+    pub fn flush(&self) -> FlushSnapshot {
+        let snapshot = FlushSnapshot {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            requests_total: self.served(),
+            errors_total: self.errors_total(),
+            p95_latency_ms: p95_latency_ms(),
+        };
+        *self.last_flush.lock().unwrap() = Some(snapshot.clone());
+        snapshot
+    }
+
+    /// The caller's tenant only; a tenant yet to make a request sees
+    /// every operation at zero.
+    fn snapshot(&self, tenant: &str, load: &crate::load_shed::LoadShedState) -> StatsResponse {
+        let cache = crate::cache::ResultCache::global();
+        let webhook = crate::webhook::Notifier::global();
+        StatsResponse {
+            uptime_secs: self.started.elapsed().as_secs(),
+            in_flight: load.in_flight(),
+            shed_total: load.shed_total(),
+            cache_hits: cache.hits(),
+            cache_misses: cache.misses(),
+            webhook_delivered: webhook.delivered(),
+            webhook_failed: webhook.failed(),
+            last_flush: self.last_flush.lock().unwrap().clone(),
+            routes: crate::latency::Latency::global().snapshot(),
+            operations: match self.tenants.read().unwrap().get(tenant) {
+                Some(ops) => ops
+                    .iter()
+                    .map(|(name, op_stats)| (*name, op_stats.snapshot()))
+                    .collect(),
+                None => ALL_OPERATIONS
+                    .iter()
+                    .map(|op| (op.name(), OpStats::new().snapshot()))
+                    .collect(),
+            },
+        }
+    }
+}
+
+/// The aggregate health picture one flusher pass records: totals across
+/// every tenant plus the latency tail.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FlushSnapshot {
+    /// Unix seconds when the snapshot was taken.
+    pub timestamp: u64,
+    /// Requests recorded since process start.
+    pub requests_total: u64,
+    /// Failed calculations since process start.
+    pub errors_total: u64,
+    /// Estimated p95 request latency, interpolated from the prometheus
+    /// histogram; absent before the first request lands.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p95_latency_ms: Option<f64>,
+}
+
+/// The p95 across every route and method, interpolated within the
+/// histogram bucket the 95th-percentile sample falls in. An estimate by
+/// construction, which is all a health breadcrumb needs.
+fn p95_latency_ms() -> Option<f64> {
+    let families = crate::metrics::Metrics::global().registry.gather();
+    let family = families
+        .iter()
+        .find(|family| family.name() == "http_request_duration_seconds")?;
+
+    // Every child shares the histogram's bucket layout, so the
+    // cumulative counts sum index-wise.
+    let mut total = 0u64;
+    let mut buckets: Vec<(f64, u64)> = Vec::new();
+    for metric in family.get_metric() {
+        let histogram = metric.get_histogram();
+        total += histogram.get_sample_count();
+        for (i, bucket) in histogram.get_bucket().iter().enumerate() {
+            if i == buckets.len() {
+                buckets.push((bucket.upper_bound(), 0));
+            }
+            buckets[i].1 += bucket.cumulative_count();
+        }
+    }
+    if total == 0 {
+        return None;
+    }
+
+    let rank = ((total as f64) * 0.95).ceil() as u64;
+    let mut lower_bound = 0.0;
+    let mut lower_count = 0;
+    for (upper_bound, cumulative) in buckets {
+        if cumulative >= rank {
+            let fraction = (rank - lower_count) as f64 / (cumulative - lower_count) as f64;
+            return Some((lower_bound + fraction * (upper_bound - lower_bound)) * 1_000.0);
+        }
+        lower_bound = upper_bound;
+        lower_count = cumulative;
+    }
+    // The sample sits past the last finite bucket; its lower edge is the
+    // best bound available.
+    Some(lower_bound * 1_000.0)
+}
+
+/// One flusher pass: snapshot, a single structured INFO line, and a
+/// breadcrumb on the global scope so the next error event carries a
+/// picture of recent traffic.
+pub fn flush_once() {
+    let snapshot = Stats::global().flush();
+    info!(
+        target: "stats",
+        requests_total = snapshot.requests_total,
+        errors_total = snapshot.errors_total,
+        p95_latency_ms = snapshot.p95_latency_ms,
+        "stats flush"
+    );
+
+    let mut crumb = vec![
+        (
+            "requests_total",
+            serde_json::Value::from(snapshot.requests_total),
+        ),
+        ("errors_total", snapshot.errors_total.into()),
+    ];
+    if let Some(p95_latency_ms) = snapshot.p95_latency_ms {
+        crumb.push(("p95_latency_ms", p95_latency_ms.into()));
+    }
+    crate::reporter::global().add_breadcrumb("stats", String::new(), &crumb);
+}
+
+/// Spawns the periodic flusher. Firing (or dropping) the returned sender
+/// stops the loop; the handle resolves once any in-flight pass finishes.
+pub fn spawn_flusher(
+    interval_secs: u64,
+) -> (
+    tokio::task::JoinHandle<()>,
+    tokio::sync::oneshot::Sender<()>,
+) {
+    let (stop_tx, mut stop_rx) = tokio::sync::oneshot::channel::<()>();
+
+    let handle = tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+        // The first tick fires immediately; skip it so startup does not
+        // log an all-zero snapshot.
+        ticker.tick().await;
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => flush_once(),
+                _ = &mut stop_rx => break,
+            }
+        }
+        info!("stats flusher stopped");
+    });
+
+    (handle, stop_tx)
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LatencyStats {
+    min_ms: f64,
+    max_ms: f64,
+    avg_ms: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OpStatsSnapshot {
+    requests: u64,
+    /// Error counts by error code, e.g. {"divide_by_zero": 3}.
+    errors: BTreeMap<&'static str, u64>,
+    /// None until the operation has served at least one request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latency: Option<LatencyStats>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StatsResponse {
+    uptime_secs: u64,
+    /// Requests currently being served.
+    in_flight: u64,
+    /// Requests shed by the concurrency limiter since process start.
+    shed_total: u64,
+    /// Result-cache lookups answered from the cache; 0 when disabled.
+    cache_hits: u64,
+    /// Result-cache lookups that fell through to computation.
+    cache_misses: u64,
+    /// Webhook notifications delivered since process start; 0 while the
+    /// notifier is disabled.
+    webhook_delivered: u64,
+    /// Webhook notifications that exhausted their retries or were
+    /// dropped on a full queue.
+    webhook_failed: u64,
+    /// What the periodic flusher last recorded; absent until its first
+    /// pass (or while STATS_FLUSH_INTERVAL_SECS disables it).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_flush: Option<FlushSnapshot>,
+    /// Latency percentiles per route pattern, across every tenant.
+    /// Routes yet to serve a request are absent.
+    routes: BTreeMap<String, crate::latency::RouteLatency>,
+    operations: BTreeMap<&'static str, OpStatsSnapshot>,
+}
+
+#[utoipa::path(
+    context_path = "/api/v0",
+    responses(
+        (status = 200, description = "Per-operation counters and latency since process start", body = StatsResponse),
+    ),
+    tag = "meta"
+)]
+#[get("/stats")]
+pub async fn get_stats(
+    stats: web::Data<Stats>,
+    load: web::Data<crate::load_shed::LoadShedState>,
+) -> impl Responder {
+    web::Json(stats.snapshot(&crate::tenant::current(), &load))
+}
+
+#[utoipa::path(
+    context_path = "/admin",
+    responses(
+        (status = 204, description = "Counters reset; uptime is unaffected"),
+    ),
+    tag = "admin"
+)]
+#[delete("/stats")]
+pub async fn reset_stats(req: actix_web::HttpRequest, stats: web::Data<Stats>) -> impl Responder {
+    let served = stats.served();
+    stats.reset();
+    crate::admin::audit(&req, "stats-reset", &format!("{served} requests"), "0");
+    HttpResponse::NoContent().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flush_folds_every_tenant_and_remembers_the_snapshot() {
+        let stats = Stats::new();
+        stats.record(Operation::Add, &Ok(3), Duration::from_millis(2));
+        stats.record::<i32>(
+            Operation::Div,
+            &Err(crate::error::Error::DivideByZero),
+            Duration::from_millis(5),
+        );
+
+        let snapshot = stats.flush();
+        assert_eq!(snapshot.requests_total, 2);
+        assert_eq!(snapshot.errors_total, 1);
+        assert!(snapshot.timestamp > 0);
+        assert!(stats.last_flush.lock().unwrap().is_some());
+    }
+}
@@ -0,0 +1,1083 @@
+use std::env;
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+
+/// The production sentry ClientOptions used by main, with every knob
+/// that comes from the environment parsed and validated here so a typo
+/// fails startup rather than silently disabling sampling.
+pub fn build_sentry_options(
+    config: &crate::config::Config,
+    dsn: Option<sentry::types::Dsn>,
+) -> Result<sentry::ClientOptions> {
+    let sample_rate = sample_rate_from("SENTRY_SAMPLE_RATE", 1.0)?;
+    let traces_sample_rate = sample_rate_from("SENTRY_TRACES_SAMPLE_RATE", 0.0)?;
+
+    let max_breadcrumbs = match env::var("SENTRY_MAX_BREADCRUMBS") {
+        Ok(value) => value.parse().map_err(|_| Error::Config {
+            var: "SENTRY_MAX_BREADCRUMBS",
+            message: format!("not a valid count: {value}"),
+        })?,
+        Err(_) => 100,
+    };
+
+    // With a spool directory configured, delivery goes through the
+    // spooling transport so envelopes survive DSN-host outages.
+    let transport = match (&dsn, &config.sentry_spool_dir) {
+        (Some(dsn), Some(spool_dir)) => {
+            let spool = crate::spool::Spool::new(
+                std::path::PathBuf::from(spool_dir),
+                config.sentry_spool_max_bytes,
+                config.sentry_spool_max_files,
+            )
+            .map_err(|err| Error::Config {
+                var: "SENTRY_SPOOL_DIR",
+                message: format!("{spool_dir}: {err}"),
+            })?;
+            Some(Arc::new(crate::spool::SpoolingTransport::start(
+                crate::spool::HttpSender::new(dsn, config),
+                spool,
+            )))
+        }
+        _ => None,
+    };
+
+    let mut options = client_options(dsn, traces_sample_rate);
+    // Egress through a corporate proxy; the stock transport reads these
+    // from the options when it builds its client.
+    options.http_proxy = config.sentry_http_proxy.clone().map(Into::into);
+    options.https_proxy = config.sentry_https_proxy.clone().map(Into::into);
+    if let Some(transport) = transport {
+        options.transport = Some(Arc::new(transport));
+    } else if config.sentry_ca_bundle.is_some() {
+        // The stock transport cannot take an extra CA, so delivery goes
+        // through a client we build ourselves; the spooling sender above
+        // already applies the bundle to its own client.
+        let client = proxied_client(config)?;
+        options.transport = Some(Arc::new(move |opts: &sentry::ClientOptions| {
+            Arc::new(sentry::transports::ReqwestHttpTransport::with_client(
+                opts,
+                client.clone(),
+            )) as Arc<dyn sentry::Transport>
+        }));
+    }
+    options.sample_rate = sample_rate;
+    options.max_breadcrumbs = max_breadcrumbs;
+    // The SDK's own debug logging, for diagnosing delivery problems.
+    options.debug = env::var("SENTRY_DEBUG")
+        .map(|v| v == "true")
+        .unwrap_or(false);
+    // The configured bind host stands in for a hostname; multi-host
+    // deployments set SENTRY_ENVIRONMENT per fleet anyway.
+    options.server_name = Some(config.host.clone().into());
+    Ok(options)
+}
+
+/// A reqwest client with the configured CA bundle and proxies applied,
+/// for the transport path the stock client cannot cover. The settings
+/// were validated at config load; failures here mean the file changed
+/// underneath us since.
+fn proxied_client(config: &crate::config::Config) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(path) = &config.sentry_ca_bundle {
+        let pem = std::fs::read(path).map_err(|err| Error::Config {
+            var: "SENTRY_CA_BUNDLE",
+            message: format!("{path}: {err}"),
+        })?;
+        let cert = reqwest::Certificate::from_pem(&pem).map_err(|_| Error::Config {
+            var: "SENTRY_CA_BUNDLE",
+            message: format!("{path}: not a PEM certificate bundle"),
+        })?;
+        builder = builder.add_root_certificate(cert);
+    }
+    if let Some(proxy) = &config.sentry_http_proxy {
+        builder = builder.proxy(reqwest::Proxy::http(proxy).map_err(|_| Error::Config {
+            var: "SENTRY_HTTP_PROXY",
+            message: format!("not a valid proxy URL: {proxy}"),
+        })?);
+    }
+    if let Some(proxy) = &config.sentry_https_proxy {
+        builder = builder.proxy(reqwest::Proxy::https(proxy).map_err(|_| Error::Config {
+            var: "SENTRY_HTTPS_PROXY",
+            message: format!("not a valid proxy URL: {proxy}"),
+        })?);
+    }
+    builder.build().map_err(|err| Error::Config {
+        var: "SENTRY_CA_BUNDLE",
+        message: format!("failed to build the transport client: {err}"),
+    })
+}
+
+/// An event/trace sampling rate from the named env var; must lie within
+/// 0.0..=1.0.
+fn sample_rate_from(var: &'static str, default: f32) -> Result<f32> {
+    match env::var(var) {
+        Ok(value) => {
+            let rate = value.parse::<f32>().map_err(|_| Error::Config {
+                var,
+                message: format!("not a valid rate: {value}"),
+            })?;
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(Error::Config {
+                    var,
+                    message: format!("rate must be within 0.0..=1.0, got {rate}"),
+                });
+            }
+            Ok(rate)
+        }
+        Err(_) => Ok(default),
+    }
+}
+
+/// Decides what sentry does with a tracing event, from the configured
+/// levels: SENTRY_EVENT_LEVEL and above become events (ERROR by
+/// default), SENTRY_BREADCRUMB_LEVEL and above become breadcrumbs (INFO
+/// by default), the rest are ignored.
+pub fn tracing_event_filter(metadata: &tracing::Metadata<'_>) -> sentry_tracing::EventFilter {
+    let config = crate::config::Config::global();
+    level_filter(
+        *metadata.level(),
+        config.sentry_event_level,
+        config.sentry_breadcrumb_level,
+    )
+}
+
+// tracing orders levels by severity: ERROR < WARN < INFO < DEBUG < TRACE.
+fn level_filter(
+    level: tracing::Level,
+    event_level: tracing::Level,
+    breadcrumb_level: tracing::Level,
+) -> sentry_tracing::EventFilter {
+    if level <= event_level {
+        sentry_tracing::EventFilter::Event
+    } else if level <= breadcrumb_level {
+        sentry_tracing::EventFilter::Breadcrumb
+    } else {
+        sentry_tracing::EventFilter::Ignore
+    }
+}
+
+/// The core ClientOptions: release, environment, sampling and the
+/// before_send filter. Tests build their client from this too (swapping
+/// only the transport), so the filtering they assert on is the code that
+/// ships rather than a copy.
+pub fn client_options(
+    dsn: Option<sentry::types::Dsn>,
+    traces_sample_rate: f32,
+) -> sentry::ClientOptions {
+    sentry::ClientOptions {
+        dsn,
+        // name@version+sha, so issues group per deployment.
+        release: Some(crate::version::release().into()),
+        environment: Some(
+            env::var("SENTRY_ENVIRONMENT")
+                .unwrap_or_else(|_| "development".to_string())
+                .into(),
+        ),
+        server_name: Some(crate::config::Config::global().host.clone().into()),
+        max_breadcrumbs: 100,
+        traces_sample_rate,
+        // Health checks and scrapes would otherwise eat the performance
+        // quota.
+        traces_sampler: Some(Arc::new(move |ctx| {
+            if ctx.name().ends_with("/status") || ctx.name().ends_with("/metrics") {
+                0.0
+            } else {
+                traces_sample_rate
+            }
+        })),
+        before_send: Some(Arc::new(before_send)),
+        // Release-health sessions aggregate per request rather than one
+        // long-lived application session; the middleware starts and ends
+        // them explicitly, so automatic tracking stays off.
+        session_mode: sentry::SessionMode::Request,
+        ..Default::default()
+    }
+}
+
+/// The before_send filter as shipped: scrub PII first, then drop 4xx
+/// events that slipped past the capture-time filtering, then dedup.
+pub fn before_send(
+    mut event: sentry::protocol::Event<'static>,
+) -> Option<sentry::protocol::Event<'static>> {
+    // The kill-switch short-circuits everything else; the suppression
+    // still counts locally so nothing is lost silently.
+    let reporting = crate::reporting::Reporting::global();
+    if !reporting.is_enabled() {
+        reporting.note_suppressed();
+        return None;
+    }
+
+    let config = crate::config::Config::global();
+    scrub_event(&mut event, &config.scrub_keys, &config.scrub_patterns);
+
+    if let Some(status_code) = event.extra.get("status_code") {
+        let status_code = status_code.as_u64().unwrap_or(200);
+
+        if (400..500).contains(&status_code) {
+            tracing::debug!(status_code, "dropping a 4xx event before send");
+            return None;
+        }
+    }
+
+    apply_fingerprint(&mut event, &config.sentry_fingerprint_rules);
+
+    // A stuck client repeating the same failure would otherwise flood
+    // the project; identical (code, route) events within the window are
+    // sent once.
+    if config.sentry_dedup_window_secs > 0 {
+        if let Some(code) = event.tags.get("code") {
+            let route = event
+                .tags
+                .get("http.route")
+                .map(String::as_str)
+                .unwrap_or("-");
+            let fingerprint = format!("{code}:{route}");
+            let window = std::time::Duration::from_secs(config.sentry_dedup_window_secs);
+
+            match Dedup::global().check(&fingerprint, window, std::time::Instant::now()) {
+                None => {
+                    tracing::debug!(fingerprint, "suppressing a duplicate event before send");
+                    return None;
+                }
+                Some(suppressed) if suppressed > 0 => {
+                    event
+                        .extra
+                        .insert("suppressed_count".into(), suppressed.into());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Last, so events the dedup already suppressed do not spend tokens.
+    if config.sentry_events_per_minute > 0 {
+        if let Some(code) = event.tags.get("code") {
+            match EventLimiter::global().check(
+                code,
+                config.sentry_events_per_minute,
+                std::time::Instant::now(),
+            ) {
+                None => {
+                    tracing::debug!(code, "dropping an event over the per-minute cap");
+                    return None;
+                }
+                Some(dropped) if dropped > 0 => {
+                    event
+                        .extra
+                        .insert("client_sample_dropped".into(), dropped.into());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    reporting.note_sent();
+    Some(event)
+}
+
+/// Groups events by [code, route], since the Display-driven messages
+/// all share one shape and would otherwise collapse distinct routes
+/// into one issue. Rules from SENTRY_FINGERPRINT_RULES override per
+/// code; a rule of `{{ default }}` hands grouping back to sentry.
+/// Events without a code tag (not ours) keep their fingerprint.
+fn apply_fingerprint(
+    event: &mut sentry::protocol::Event<'static>,
+    rules: &[crate::config::FingerprintRule],
+) {
+    let Some(code) = event.tags.get("code") else {
+        return;
+    };
+    let fragments = match rules.iter().find(|rule| &rule.code == code) {
+        Some(rule) => rule.fragments.clone(),
+        None => {
+            let route = event
+                .tags
+                .get("http.route")
+                .cloned()
+                .unwrap_or_else(|| "-".to_string());
+            vec![code.clone(), route]
+        }
+    };
+    event.fingerprint =
+        std::borrow::Cow::Owned(fragments.into_iter().map(std::borrow::Cow::Owned).collect());
+}
+
+/// The dedup map stays small even under a storm of distinct failures.
+const MAX_FINGERPRINTS: usize = 256;
+
+struct DedupEntry {
+    last_sent: std::time::Instant,
+    suppressed: u64,
+}
+
+/// Time-windowed suppression of repeated identical events, keyed by
+/// fingerprint. Purely in-memory and client-side, so it also protects
+/// the sentry quota when the project is otherwise healthy.
+pub(crate) struct Dedup {
+    entries: std::sync::Mutex<std::collections::HashMap<String, DedupEntry>>,
+}
+
+impl Dedup {
+    fn new() -> Self {
+        Dedup {
+            entries: std::sync::Mutex::new(std::collections::HashMap::new()),
+        }
+    }
+
+    pub(crate) fn global() -> &'static Dedup {
+        static DEDUP: std::sync::OnceLock<Dedup> = std::sync::OnceLock::new();
+        DEDUP.get_or_init(Dedup::new)
+    }
+
+    /// None means the event is a duplicate and should be dropped;
+    /// Some(n) means send it, with n duplicates suppressed since the
+    /// last one that went through.
+    fn check(
+        &self,
+        fingerprint: &str,
+        window: std::time::Duration,
+        now: std::time::Instant,
+    ) -> Option<u64> {
+        let mut entries = self.entries.lock().unwrap();
+
+        if let Some(entry) = entries.get_mut(fingerprint) {
+            if now.duration_since(entry.last_sent) < window {
+                entry.suppressed += 1;
+                return None;
+            }
+            let suppressed = entry.suppressed;
+            entry.suppressed = 0;
+            entry.last_sent = now;
+            return Some(suppressed);
+        }
+
+        if entries.len() >= MAX_FINGERPRINTS {
+            entries.retain(|_, entry| now.duration_since(entry.last_sent) < window);
+            if entries.len() >= MAX_FINGERPRINTS {
+                if let Some(oldest) = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_sent)
+                    .map(|(key, _)| key.clone())
+                {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+
+        entries.insert(
+            fingerprint.to_owned(),
+            DedupEntry {
+                last_sent: now,
+                suppressed: 0,
+            },
+        );
+        Some(0)
+    }
+}
+
+/// Shards, so concurrent failing requests rarely contend on one lock;
+/// the limiter sits on the error path.
+const LIMITER_SHARDS: usize = 8;
+
+/// Per shard; with the shards this bounds the limiter at 2048 codes,
+/// far more than the error enum can produce.
+const MAX_BUCKETS: usize = 256;
+
+struct Bucket {
+    tokens: f64,
+    last_refill: std::time::Instant,
+    dropped: u64,
+}
+
+/// A client-side token bucket per error code, so a bug that turns every
+/// request into the same 500 cannot exhaust the sentry quota in
+/// minutes. Distinct from Dedup: that suppresses identical events
+/// within a window, this caps the overall rate per code.
+pub(crate) struct EventLimiter {
+    shards: Vec<std::sync::Mutex<std::collections::HashMap<String, Bucket>>>,
+}
+
+impl EventLimiter {
+    fn new() -> Self {
+        EventLimiter {
+            shards: (0..LIMITER_SHARDS)
+                .map(|_| std::sync::Mutex::new(std::collections::HashMap::new()))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn global() -> &'static EventLimiter {
+        static LIMITER: std::sync::OnceLock<EventLimiter> = std::sync::OnceLock::new();
+        LIMITER.get_or_init(EventLimiter::new)
+    }
+
+    /// None means the code is over its per-minute cap and the event
+    /// should be dropped; Some(n) means send it, with n events dropped
+    /// for the code since the last one that went through.
+    fn check(&self, code: &str, per_minute: u32, now: std::time::Instant) -> Option<u64> {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        code.hash(&mut hasher);
+        let mut shard = self.shards[hasher.finish() as usize % LIMITER_SHARDS]
+            .lock()
+            .unwrap();
+
+        let burst = per_minute as f64;
+        let bucket = if let Some(bucket) = shard.get_mut(code) {
+            let refill = now.duration_since(bucket.last_refill).as_secs_f64() * burst / 60.0;
+            bucket.tokens = (bucket.tokens + refill).min(burst);
+            bucket.last_refill = now;
+            bucket
+        } else {
+            if shard.len() >= MAX_BUCKETS {
+                // Codes idle long enough to be full again lose nothing
+                // by eviction; a fresh bucket grants the same burst.
+                shard.retain(|_, bucket| {
+                    let refill =
+                        now.duration_since(bucket.last_refill).as_secs_f64() * burst / 60.0;
+                    bucket.tokens + refill < burst
+                });
+                if shard.len() >= MAX_BUCKETS {
+                    if let Some(oldest) = shard
+                        .iter()
+                        .min_by_key(|(_, bucket)| bucket.last_refill)
+                        .map(|(key, _)| key.clone())
+                    {
+                        shard.remove(&oldest);
+                    }
+                }
+            }
+            shard.entry(code.to_owned()).or_insert(Bucket {
+                tokens: burst,
+                last_refill: now,
+                dropped: 0,
+            })
+        };
+
+        if bucket.tokens < 1.0 {
+            bucket.dropped += 1;
+            return None;
+        }
+        bucket.tokens -= 1.0;
+        Some(std::mem::take(&mut bucket.dropped))
+    }
+}
+
+const REDACTED: &str = "[redacted]";
+
+// Hyphens normalise to underscores so "api_key" covers "X-Api-Key".
+fn key_is_sensitive(key: &str, keys: &[String]) -> bool {
+    let key = key.to_lowercase().replace('-', "_");
+    keys.iter()
+        .any(|sensitive| key.contains(&sensitive.replace('-', "_")))
+}
+
+fn value_matches(value: &str, patterns: &[regex::Regex]) -> bool {
+    patterns.iter().any(|pattern| pattern.is_match(value))
+}
+
+pub(crate) fn scrub_string(value: &mut String, patterns: &[regex::Regex]) {
+    if value_matches(value, patterns) {
+        *value = REDACTED.to_owned();
+    }
+}
+
+pub(crate) fn scrub_json(
+    value: &mut serde_json::Value,
+    keys: &[String],
+    patterns: &[regex::Regex],
+) {
+    match value {
+        serde_json::Value::String(s) => scrub_string(s, patterns),
+        serde_json::Value::Array(items) => {
+            for item in items {
+                scrub_json(item, keys, patterns);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if key_is_sensitive(key, keys) {
+                    *value = REDACTED.into();
+                } else {
+                    scrub_json(value, keys, patterns);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn scrub_map(
+    map: &mut sentry::protocol::Map<String, serde_json::Value>,
+    keys: &[String],
+    patterns: &[regex::Regex],
+) {
+    for (key, value) in map.iter_mut() {
+        if key_is_sensitive(key, keys) {
+            *value = REDACTED.into();
+        } else {
+            scrub_json(value, keys, patterns);
+        }
+    }
+}
+
+/// Redacts sensitive keys and matching values across the parts of an
+/// event that can carry request-derived data: extras, the request
+/// interface and breadcrumbs. Keys match by lowercased substring so
+/// "api_key" also covers "x-api-key".
+pub fn scrub_event(
+    event: &mut sentry::protocol::Event<'static>,
+    keys: &[String],
+    patterns: &[regex::Regex],
+) {
+    scrub_map(&mut event.extra, keys, patterns);
+
+    if let Some(request) = event.request.as_mut() {
+        for (key, value) in request.headers.iter_mut() {
+            if key_is_sensitive(key, keys) {
+                *value = REDACTED.to_owned();
+            } else {
+                scrub_string(value, patterns);
+            }
+        }
+        if let Some(cookies) = request.cookies.as_mut() {
+            if key_is_sensitive("cookie", keys) {
+                *cookies = REDACTED.to_owned();
+            } else {
+                scrub_string(cookies, patterns);
+            }
+        }
+        if let Some(data) = request.data.as_mut() {
+            scrub_string(data, patterns);
+        }
+        if let Some(query_string) = request.query_string.as_mut() {
+            scrub_string(query_string, patterns);
+        }
+    }
+
+    for breadcrumb in event.breadcrumbs.values.iter_mut() {
+        if let Some(message) = breadcrumb.message.as_mut() {
+            scrub_string(message, patterns);
+        }
+        for (key, value) in breadcrumb.data.iter_mut() {
+            if key_is_sensitive(key, keys) {
+                *value = REDACTED.into();
+            } else {
+                scrub_json(value, keys, patterns);
+            }
+        }
+    }
+}
+
+/// The fields of one span, recorded at creation and kept in the span's
+/// extensions so they can be read back at capture time.
+struct SpanFields(std::collections::BTreeMap<String, serde_json::Value>);
+
+struct FieldVisitor<'a>(&'a mut std::collections::BTreeMap<String, serde_json::Value>);
+
+impl tracing::field::Visit for FieldVisitor<'_> {
+    fn record_i64(&mut self, field: &tracing::field::Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &tracing::field::Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &tracing::field::Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}").into());
+    }
+}
+
+/// Remembers every span's fields so capture sites can attach the state
+/// of the spans they sit inside (handler name, parsed operands, ids) to
+/// the event. The sentry layer only forwards span timing; this keeps
+/// the data the #[tracing::instrument] attributes already record.
+pub struct SpanFieldsLayer;
+
+impl<S> tracing_subscriber::Layer<S> for SpanFieldsLayer
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    fn on_new_span(
+        &self,
+        attrs: &tracing::span::Attributes<'_>,
+        id: &tracing::span::Id,
+        ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut fields = std::collections::BTreeMap::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+        span.extensions_mut().insert(SpanFields(fields));
+    }
+
+    fn on_record(
+        &self,
+        id: &tracing::span::Id,
+        values: &tracing::span::Record<'_>,
+        ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanFields(fields)) = extensions.get_mut() {
+            values.record(&mut FieldVisitor(fields));
+        }
+    }
+}
+
+/// The merged fields of the current span hierarchy, outermost first so
+/// an inner span's value wins on a name collision. Fields whose name
+/// matches the scrub config never leave this function; events must not
+/// regain through span state what the scrubber strips elsewhere.
+pub fn current_span_fields() -> std::collections::BTreeMap<String, serde_json::Value> {
+    use tracing_subscriber::registry::LookupSpan;
+
+    let Some(id) = tracing::Span::current().id() else {
+        return Default::default();
+    };
+    tracing::dispatcher::get_default(|dispatch| {
+        let Some(registry) = dispatch.downcast_ref::<tracing_subscriber::Registry>() else {
+            return Default::default();
+        };
+        let Some(span) = registry.span(&id) else {
+            return Default::default();
+        };
+        let mut merged = std::collections::BTreeMap::new();
+        for span in span.scope().from_root() {
+            if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>() {
+                for (key, value) in fields {
+                    merged.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        let config = crate::config::Config::global();
+        merged.retain(|key, _| !key_is_sensitive(key, &config.scrub_keys));
+        merged
+    })
+}
+
+/// How often the transport probe re-checks the DSN host, and how long
+/// each attempt waits — short enough that a blackholed route cannot pin
+/// the probe task for a full interval.
+const PROBE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+const PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// One reachability check against the envelope endpoint. Any HTTP answer
+/// counts as reachable — sentry responds 405 to a HEAD, and that still
+/// proves the host resolves and accepts connections; only a transport
+/// error (DNS, refused, timeout) means events would be going nowhere.
+async fn probe(client: &reqwest::Client, url: &str) -> bool {
+    client.head(url).send().await.is_ok()
+}
+
+/// Spawns the background probe that keeps /readyz and the
+/// sentry_transport_up gauge honest about whether events can actually
+/// leave the box. A no-op without a DSN; the loop runs for the life of
+/// the process, so there is no stop handle — nothing needs flushing.
+pub fn spawn_transport_probe(config: &crate::config::Config) {
+    let Some(sentry_dsn) = &config.sentry_dsn else {
+        return;
+    };
+    // main() already validated the DSN when initialising sentry.
+    let Ok(dsn) = sentry_dsn.parse::<sentry::types::Dsn>() else {
+        return;
+    };
+    // The same URL the transport posts envelopes to, straight from the
+    // parsed DSN rather than reassembled by hand.
+    let url = dsn.envelope_api_url().to_string();
+    let host = dsn.host().to_string();
+
+    tokio::spawn(async move {
+        let client = match reqwest::Client::builder().timeout(PROBE_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(err) => {
+                tracing::warn!(%err, "could not build the sentry probe client");
+                return;
+            }
+        };
+        let mut was_reachable = true;
+        let mut ticker = tokio::time::interval(PROBE_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let reachable = probe(&client, &url).await;
+            if reachable != was_reachable {
+                // Only transitions are logged; a dead host already costs
+                // one warning per minute of somebody's attention.
+                if reachable {
+                    tracing::info!(host = %host, "sentry transport recovered");
+                } else {
+                    tracing::warn!(
+                        host = %host,
+                        "sentry DSN host is unreachable; events will buffer and may be dropped"
+                    );
+                }
+                was_reachable = reachable;
+            }
+            crate::health::Readiness::global().set_sentry_reachable(reachable);
+            crate::metrics::Metrics::global()
+                .sentry_transport_up
+                .set(reachable as i64);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys() -> Vec<String> {
+        ["authorization", "api_key", "password", "cookie"]
+            .iter()
+            .map(|key| key.to_string())
+            .collect()
+    }
+
+    fn patterns() -> Vec<regex::Regex> {
+        vec![regex::Regex::new(r"\b\d{16}\b").unwrap()]
+    }
+
+    #[test]
+    fn levels_map_to_events_breadcrumbs_and_ignores() {
+        use sentry_tracing::EventFilter;
+        use tracing::Level;
+
+        // The defaults: ERROR → event, WARN/INFO → breadcrumb.
+        assert!(matches!(
+            level_filter(Level::ERROR, Level::ERROR, Level::INFO),
+            EventFilter::Event
+        ));
+        assert!(matches!(
+            level_filter(Level::WARN, Level::ERROR, Level::INFO),
+            EventFilter::Breadcrumb
+        ));
+        assert!(matches!(
+            level_filter(Level::INFO, Level::ERROR, Level::INFO),
+            EventFilter::Breadcrumb
+        ));
+        assert!(matches!(
+            level_filter(Level::DEBUG, Level::ERROR, Level::INFO),
+            EventFilter::Ignore
+        ));
+
+        // A stricter breadcrumb level drops INFO entirely.
+        assert!(matches!(
+            level_filter(Level::INFO, Level::ERROR, Level::WARN),
+            EventFilter::Ignore
+        ));
+
+        // Promoting WARN to events.
+        assert!(matches!(
+            level_filter(Level::WARN, Level::WARN, Level::INFO),
+            EventFilter::Event
+        ));
+    }
+
+    fn tagged_event(tags: &[(&str, &str)]) -> sentry::protocol::Event<'static> {
+        let mut event = sentry::protocol::Event::default();
+        for (key, value) in tags {
+            event.tags.insert(key.to_string(), value.to_string());
+        }
+        event
+    }
+
+    #[test]
+    fn fingerprints_group_by_code_and_route_unless_a_rule_overrides() {
+        let rules = vec![
+            crate::config::FingerprintRule {
+                code: "upstream_timeout".to_string(),
+                fragments: vec!["upstream".to_string(), "timeout".to_string()],
+            },
+            crate::config::FingerprintRule {
+                code: "handler_panic".to_string(),
+                fragments: vec!["{{ default }}".to_string()],
+            },
+        ];
+
+        // No rule: the default [code, route] pair, so the same code on
+        // different routes stays distinct.
+        let mut event = tagged_event(&[("code", "divide_by_zero"), ("http.route", "/api/v0/div")]);
+        apply_fingerprint(&mut event, &rules);
+        assert_eq!(*event.fingerprint, ["divide_by_zero", "/api/v0/div"]);
+
+        // An override replaces the pair entirely.
+        let mut event = tagged_event(&[("code", "upstream_timeout"), ("http.route", "/api/v0/a")]);
+        apply_fingerprint(&mut event, &rules);
+        assert_eq!(*event.fingerprint, ["upstream", "timeout"]);
+
+        // The passthrough rule defers to sentry's own grouping.
+        let mut event = tagged_event(&[("code", "handler_panic"), ("http.route", "/api/v0/div")]);
+        apply_fingerprint(&mut event, &rules);
+        assert_eq!(*event.fingerprint, ["{{ default }}"]);
+
+        // Captures outside the request path have no route tag.
+        let mut event = tagged_event(&[("code", "metrics")]);
+        apply_fingerprint(&mut event, &rules);
+        assert_eq!(*event.fingerprint, ["metrics", "-"]);
+
+        // Events that are not ours keep whatever sentry assigned.
+        let mut event = tagged_event(&[]);
+        apply_fingerprint(&mut event, &rules);
+        assert_eq!(*event.fingerprint, ["{{ default }}"]);
+    }
+
+    #[test]
+    fn dedup_suppresses_within_the_window_and_reports_the_count() {
+        use std::time::{Duration, Instant};
+
+        let dedup = Dedup::new();
+        let window = Duration::from_secs(30);
+        let start = Instant::now();
+        let key = "divide_by_zero:/api/v0/div";
+
+        assert_eq!(dedup.check(key, window, start), Some(0));
+        assert_eq!(
+            dedup.check(key, window, start + Duration::from_secs(1)),
+            None
+        );
+        assert_eq!(
+            dedup.check(key, window, start + Duration::from_secs(2)),
+            None
+        );
+
+        // A different fingerprint is unaffected.
+        assert_eq!(
+            dedup.check(
+                "overflow:/api/v0/pow",
+                window,
+                start + Duration::from_secs(2)
+            ),
+            Some(0)
+        );
+
+        // Past the window the next event goes through carrying the count,
+        // and the counter resets.
+        assert_eq!(
+            dedup.check(key, window, start + Duration::from_secs(33)),
+            Some(2)
+        );
+        assert_eq!(
+            dedup.check(key, window, start + Duration::from_secs(64)),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn the_event_limiter_caps_per_code_and_reports_the_dropped_count() {
+        use std::time::{Duration, Instant};
+
+        let limiter = EventLimiter::new();
+        let now = Instant::now();
+
+        // The burst equals the per-minute cap; past it, events drop.
+        for _ in 0..3 {
+            assert_eq!(limiter.check("metrics", 3, now), Some(0));
+        }
+        assert_eq!(limiter.check("metrics", 3, now), None);
+        assert_eq!(limiter.check("metrics", 3, now), None);
+
+        // Another code has its own bucket.
+        assert_eq!(limiter.check("db_timeout", 3, now), Some(0));
+
+        // Twenty seconds refill one token at 3/minute, and the event
+        // that spends it carries the count dropped in the meantime.
+        assert_eq!(
+            limiter.check("metrics", 3, now + Duration::from_secs(20)),
+            Some(2)
+        );
+        assert_eq!(
+            limiter.check("metrics", 3, now + Duration::from_secs(21)),
+            None
+        );
+    }
+
+    #[test]
+    fn the_event_limiter_stays_bounded() {
+        use std::time::Instant;
+
+        let limiter = EventLimiter::new();
+        let now = Instant::now();
+
+        for i in 0..(LIMITER_SHARDS * MAX_BUCKETS * 2) {
+            limiter.check(&format!("code{i}"), 10, now);
+        }
+        for shard in &limiter.shards {
+            assert!(shard.lock().unwrap().len() <= MAX_BUCKETS);
+        }
+    }
+
+    #[test]
+    fn dedup_map_stays_bounded() {
+        use std::time::{Duration, Instant};
+
+        let dedup = Dedup::new();
+        let window = Duration::from_secs(30);
+        let now = Instant::now();
+
+        for i in 0..(MAX_FINGERPRINTS + 10) {
+            dedup.check(&format!("code{i}:/route"), window, now);
+        }
+        assert!(dedup.entries.lock().unwrap().len() <= MAX_FINGERPRINTS);
+    }
+
+    // A single test so that the env mutations cannot race each other in
+    // parallel test threads.
+    #[test]
+    fn sentry_option_parsing_applies_defaults_and_validates_rates() {
+        let config = crate::config::Config::from_env().unwrap();
+
+        let options = build_sentry_options(&config, None).unwrap();
+        assert_eq!(options.sample_rate, 1.0);
+        assert_eq!(options.traces_sample_rate, 0.0);
+        assert_eq!(options.max_breadcrumbs, 100);
+        assert!(!options.debug);
+
+        env::set_var("SENTRY_SAMPLE_RATE", "0.5");
+        env::set_var("SENTRY_TRACES_SAMPLE_RATE", "0.25");
+        env::set_var("SENTRY_MAX_BREADCRUMBS", "7");
+        env::set_var("SENTRY_DEBUG", "true");
+        let options = build_sentry_options(&config, None).unwrap();
+        assert_eq!(options.sample_rate, 0.5);
+        assert_eq!(options.traces_sample_rate, 0.25);
+        assert_eq!(options.max_breadcrumbs, 7);
+        assert!(options.debug);
+        env::remove_var("SENTRY_TRACES_SAMPLE_RATE");
+        env::remove_var("SENTRY_MAX_BREADCRUMBS");
+        env::remove_var("SENTRY_DEBUG");
+
+        // Proxy settings reach the options the transport is built from.
+        env::set_var("SENTRY_HTTP_PROXY", "http://proxy.internal:3128");
+        env::set_var("SENTRY_HTTPS_PROXY", "http://tls-proxy.internal:3128");
+        let proxied = crate::config::Config::from_env().unwrap();
+        let options = build_sentry_options(&proxied, None).unwrap();
+        assert_eq!(
+            options.http_proxy.as_deref(),
+            Some("http://proxy.internal:3128")
+        );
+        assert_eq!(
+            options.https_proxy.as_deref(),
+            Some("http://tls-proxy.internal:3128")
+        );
+
+        // Without a dedicated HTTPS proxy the HTTP one covers both.
+        env::remove_var("SENTRY_HTTPS_PROXY");
+        let proxied = crate::config::Config::from_env().unwrap();
+        let options = build_sentry_options(&proxied, None).unwrap();
+        assert_eq!(
+            options.https_proxy.as_deref(),
+            Some("http://proxy.internal:3128")
+        );
+        env::remove_var("SENTRY_HTTP_PROXY");
+
+        env::set_var("SENTRY_SAMPLE_RATE", "1.5");
+        let err = build_sentry_options(&config, None).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Config {
+                var: "SENTRY_SAMPLE_RATE",
+                ..
+            }
+        ));
+
+        env::set_var("SENTRY_SAMPLE_RATE", "not-a-rate");
+        let err = build_sentry_options(&config, None).unwrap_err();
+        env::remove_var("SENTRY_SAMPLE_RATE");
+        assert!(matches!(
+            err,
+            Error::Config {
+                var: "SENTRY_SAMPLE_RATE",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn sensitive_keys_are_redacted_wherever_they_appear() {
+        let mut event = sentry::protocol::Event {
+            extra: [
+                ("api_key".to_string(), "hunter2".into()),
+                ("x".to_string(), 1.into()),
+                (
+                    "nested".to_string(),
+                    serde_json::json!({ "password": "hunter2", "kept": "ok" }),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            request: Some(sentry::protocol::Request {
+                headers: [
+                    ("Authorization".to_string(), "Bearer abc".to_string()),
+                    ("X-Api-Key".to_string(), "hunter2".to_string()),
+                    ("Accept".to_string(), "application/json".to_string()),
+                ]
+                .into_iter()
+                .collect(),
+                cookies: Some("session=abc".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        event.breadcrumbs.values.push(sentry::Breadcrumb {
+            data: [("authorization".to_string(), "Bearer abc".into())]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        });
+
+        scrub_event(&mut event, &keys(), &patterns());
+
+        assert_eq!(event.extra["api_key"], REDACTED);
+        assert_eq!(event.extra["x"], 1);
+        assert_eq!(event.extra["nested"]["password"], REDACTED);
+        assert_eq!(event.extra["nested"]["kept"], "ok");
+
+        let request = event.request.as_ref().unwrap();
+        assert_eq!(request.headers["Authorization"], REDACTED);
+        // "api_key" matches "x-api-key" by substring.
+        assert_eq!(request.headers["X-Api-Key"], REDACTED);
+        assert_eq!(request.headers["Accept"], "application/json");
+        assert_eq!(request.cookies.as_deref(), Some(REDACTED));
+
+        assert_eq!(event.breadcrumbs.values[0].data["authorization"], REDACTED);
+    }
+
+    #[test]
+    fn matching_values_are_redacted_and_others_kept() {
+        let mut event = sentry::protocol::Event {
+            extra: [
+                ("card".to_string(), "pan 4111111111111111 ok".into()),
+                ("note".to_string(), "only 1234 digits".into()),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        event.breadcrumbs.values.push(sentry::Breadcrumb {
+            message: Some("charged 4111111111111111".to_string()),
+            ..Default::default()
+        });
+
+        scrub_event(&mut event, &keys(), &patterns());
+
+        assert_eq!(event.extra["card"], REDACTED);
+        assert_eq!(event.extra["note"], "only 1234 digits");
+        assert_eq!(
+            event.breadcrumbs.values[0].message.as_deref(),
+            Some(REDACTED)
+        );
+    }
+}
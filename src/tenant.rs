@@ -0,0 +1,137 @@
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error, HttpMessage,
+};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+
+pub const TENANT_HEADER: &str = "x-tenant-id";
+
+/// The tenant every request lands on when no allowlist entry claims it.
+pub const DEFAULT_TENANT: &str = "default";
+
+tokio::task_local! {
+    /// The tenant of the request currently being served, so the history
+    /// ring and stats counters can partition without threading it
+    /// through every call site.
+    pub static TENANT: String;
+}
+
+/// The current request's tenant; code running outside a request (tests,
+/// background jobs) sees the default tenant.
+pub fn current() -> String {
+    TENANT
+        .try_with(|tenant| tenant.clone())
+        .unwrap_or_else(|_| DEFAULT_TENANT.to_owned())
+}
+
+/// The resolved tenant as stored in request extensions by the gate, for
+/// middleware running inside it (the rate limiter partitions on this).
+#[derive(Debug, Clone)]
+pub struct Tenant(pub String);
+
+/// Multi-tenancy via X-Tenant-Id: the header is validated against the
+/// configured allowlist (403 otherwise) and the resolved tenant is
+/// threaded to everything downstream — request extensions, a task-local,
+/// the sentry scope and the access log. A request without the header
+/// maps to "default" only while TENANT_ALLOW_DEFAULT permits it. Public
+/// paths (health, metrics, /status) are never gated.
+pub struct TenantGate;
+
+impl<S, B> Transform<S, ServiceRequest> for TenantGate
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = TenantGateService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(TenantGateService { service }))
+    }
+}
+
+pub struct TenantGateService<S> {
+    service: S,
+}
+
+/// Renders the structured JSON error body directly, like Auth's
+/// rejections, so the response shape is identical in tests and
+/// production.
+fn reject<B>(req: ServiceRequest, err: crate::error::Error) -> ServiceResponse<EitherBody<B>> {
+    use actix_web::ResponseError;
+
+    let response = crate::error::HTTPError::from(err).error_response();
+    req.into_response(response).map_into_right_body()
+}
+
+/// The tenant for this request, or the error to refuse it with.
+fn resolve(req: &ServiceRequest) -> crate::error::Result<String> {
+    let config = crate::config::Config::global();
+
+    let presented = req
+        .headers()
+        .get(TENANT_HEADER)
+        .and_then(|value| value.to_str().ok());
+
+    match presented {
+        Some(tenant) if config.tenants.iter().any(|known| known == tenant) => Ok(tenant.to_owned()),
+        Some(tenant) => Err(crate::error::Error::UnknownTenant {
+            tenant: tenant.to_owned(),
+        }),
+        None if config.tenant_allow_default => Ok(DEFAULT_TENANT.to_owned()),
+        None => Err(crate::error::Error::MissingTenant),
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for TenantGateService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        // With no tenants configured the gate only labels: every request
+        // is the default tenant, and the header is ignored rather than
+        // refused so single-tenant deployments need no configuration.
+        // Public paths are likewise never gated.
+        let tenant = if crate::config::Config::global().tenants.is_empty()
+            || crate::middleware::is_public_path(req.path())
+        {
+            DEFAULT_TENANT.to_owned()
+        } else {
+            match resolve(&req) {
+                Ok(tenant) => tenant,
+                Err(err) => return Box::pin(ready(Ok(reject(req, err)))),
+            }
+        };
+
+        req.extensions_mut().insert(Tenant(tenant.clone()));
+
+        // On the request-scoped hub set up by Middleware, so every event
+        // captured during the request can be filtered by tenant.
+        #[cfg(feature = "sentry")]
+        if let Some(hub) = req
+            .extensions()
+            .get::<std::sync::Arc<sentry::Hub>>()
+            .cloned()
+        {
+            hub.configure_scope(|scope| scope.set_tag("tenant", &tenant));
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(TENANT.scope(tenant, async move {
+            fut.await.map(|res| res.map_into_left_body())
+        }))
+    }
+}
@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use tracing::error;
+
+use crate::config::Config;
+
+/// The timeout that applies to a request: the slow limit for routes on
+/// the slow list (batch and streaming work), the default otherwise, or
+/// None when timeouts are disabled.
+fn limit_for(route: &str, config: &Config) -> Option<Duration> {
+    if config.request_timeout_ms == 0 {
+        return None;
+    }
+
+    let limit_ms = if config.slow_routes.iter().any(|slow| slow == route) {
+        config.request_timeout_slow_ms
+    } else {
+        config.request_timeout_ms
+    };
+    Some(Duration::from_millis(limit_ms))
+}
+
+/// Races every handler against a deadline and turns expiry into a
+/// structured 504. Disabled unless REQUEST_TIMEOUT_MS is configured;
+/// slow routes (batch, streaming) get REQUEST_TIMEOUT_SLOW_MS instead.
+pub struct Timeout;
+
+impl<S, B> Transform<S, ServiceRequest> for Timeout
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = TimeoutService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(TimeoutService { service }))
+    }
+}
+
+pub struct TimeoutService<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for TimeoutService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let config = Config::global();
+        let route = req
+            .match_pattern()
+            .unwrap_or_else(|| req.path().to_string());
+        let limit = limit_for(&route, &config);
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let Some(limit) = limit else {
+                return fut.await;
+            };
+
+            match tokio::time::timeout(limit, fut).await {
+                Ok(res) => res,
+                // The handler future is dropped (and thus cancelled) by
+                // the expired timeout.
+                Err(_) => {
+                    let limit_ms = limit.as_millis() as u64;
+                    error!(route, limit_ms, "handler exceeded the request timeout");
+                    #[cfg(feature = "sentry")]
+                    let http_error = sentry::with_scope(
+                        |scope| {
+                            scope.set_tag("timeout", "true");
+                            scope.set_tag("http.route", &route);
+                            scope.set_extra("timeout_limit_ms", limit_ms.into());
+                        },
+                        || crate::error::HTTPError::from(crate::error::Error::Timeout { limit_ms }),
+                    );
+                    #[cfg(not(feature = "sentry"))]
+                    let http_error =
+                        crate::error::HTTPError::from(crate::error::Error::Timeout { limit_ms });
+                    Err(http_error.into())
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_routes_get_the_longer_limit() {
+        let mut config = Config::from_env().unwrap();
+        config.request_timeout_ms = 100;
+        config.request_timeout_slow_ms = 400;
+        config.slow_routes = vec!["/api/v0/batch".to_string()];
+
+        assert_eq!(
+            limit_for("/api/v0/add", &config),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            limit_for("/api/v0/batch", &config),
+            Some(Duration::from_millis(400))
+        );
+
+        config.request_timeout_ms = 0;
+        assert_eq!(limit_for("/api/v0/add", &config), None);
+    }
+}
@@ -0,0 +1,195 @@
+use std::sync::{Arc, RwLock};
+
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use tracing::{error, info};
+
+use crate::error::{Error, Result};
+
+/// Parses the PEM pair into what rustls serves. Failures name the file,
+/// because "invalid certificate" without a path is useless at 3am when
+/// the rotation cron half-wrote one of them.
+fn load_certified_key(cert_path: &str, key_path: &str) -> Result<CertifiedKey> {
+    let pem = std::fs::read(cert_path).map_err(|err| Error::Config {
+        var: "TLS_CERT_PATH",
+        message: format!("{cert_path}: {err}"),
+    })?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|err| Error::Config {
+            var: "TLS_CERT_PATH",
+            message: format!("{cert_path}: {err}"),
+        })?;
+    if certs.is_empty() {
+        return Err(Error::Config {
+            var: "TLS_CERT_PATH",
+            message: format!("{cert_path}: no certificates found"),
+        });
+    }
+
+    let pem = std::fs::read(key_path).map_err(|err| Error::Config {
+        var: "TLS_KEY_PATH",
+        message: format!("{key_path}: {err}"),
+    })?;
+    let key = rustls_pemfile::private_key(&mut pem.as_slice())
+        .map_err(|err| Error::Config {
+            var: "TLS_KEY_PATH",
+            message: format!("{key_path}: {err}"),
+        })?
+        .ok_or_else(|| Error::Config {
+            var: "TLS_KEY_PATH",
+            message: format!("{key_path}: no private key found"),
+        })?;
+    let key =
+        rustls::crypto::ring::sign::any_supported_type(&key).map_err(|err| Error::Config {
+            var: "TLS_KEY_PATH",
+            message: format!("{key_path}: {err}"),
+        })?;
+
+    Ok(CertifiedKey::new(certs, key))
+}
+
+/// Serves whichever certificate was most recently loaded from the
+/// configured paths, so Let's Encrypt rotation needs a reload, not a
+/// restart. A failed reload keeps the old certificate: serving a stale
+/// cert beats serving none.
+#[derive(Debug)]
+pub struct ReloadingCertResolver {
+    cert_path: String,
+    key_path: String,
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl ReloadingCertResolver {
+    /// Loads the initial pair; startup fails loudly on a bad config.
+    pub fn from_files(cert_path: &str, key_path: &str) -> Result<Arc<Self>> {
+        let key = load_certified_key(cert_path, key_path)?;
+        Ok(Arc::new(ReloadingCertResolver {
+            cert_path: cert_path.to_string(),
+            key_path: key_path.to_string(),
+            current: RwLock::new(Arc::new(key)),
+        }))
+    }
+
+    pub fn reload(&self) -> Result<()> {
+        let key = load_certified_key(&self.cert_path, &self.key_path)?;
+        *self.current.write().unwrap() = Arc::new(key);
+        Ok(())
+    }
+}
+
+impl ResolvesServerCert for ReloadingCertResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.read().unwrap().clone())
+    }
+}
+
+/// The rustls server config actix binds with: safe defaults, no client
+/// auth, certificates answered by the reloading resolver.
+pub fn server_config(resolver: Arc<ReloadingCertResolver>) -> Result<rustls::ServerConfig> {
+    let config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .map_err(|err| Error::Config {
+        var: "TLS_CERT_PATH",
+        message: format!("unusable protocol versions: {err}"),
+    })?
+    .with_no_client_auth()
+    .with_cert_resolver(resolver);
+    Ok(config)
+}
+
+/// Reloads the certificate on SIGHUP, and every `interval_secs` when
+/// non-zero — belt and braces for deployments where the rotation job
+/// cannot signal the process.
+pub fn spawn_reload(resolver: Arc<ReloadingCertResolver>, interval_secs: u64) {
+    tokio::spawn(async move {
+        let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+            .expect("failed to install SIGHUP handler");
+        // A zero interval still needs a future that never resolves; a
+        // ~68-year sleep inside the loop serves as "never".
+        let period = if interval_secs > 0 {
+            std::time::Duration::from_secs(interval_secs)
+        } else {
+            std::time::Duration::from_secs(u32::MAX.into())
+        };
+
+        loop {
+            let trigger = tokio::select! {
+                _ = hangup.recv() => "SIGHUP",
+                _ = tokio::time::sleep(period) => "interval",
+            };
+            match resolver.reload() {
+                Ok(()) => info!(trigger, "TLS certificate reloaded"),
+                Err(err) => {
+                    error!(trigger, %err, "TLS certificate reload failed; keeping the previous certificate")
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_pair(dir: &std::path::Path, name: &str) -> (String, String, Vec<u8>) {
+        let generated = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])
+            .expect("failed to generate certificate");
+        let cert_path = dir.join(format!("{name}.crt"));
+        let key_path = dir.join(format!("{name}.key"));
+        std::fs::write(&cert_path, generated.cert.pem()).unwrap();
+        std::fs::write(&key_path, generated.signing_key.serialize_pem()).unwrap();
+        (
+            cert_path.to_string_lossy().into_owned(),
+            key_path.to_string_lossy().into_owned(),
+            generated.cert.der().to_vec(),
+        )
+    }
+
+    #[test]
+    fn reload_swaps_the_certificate_and_failures_keep_the_old_one() {
+        let dir = std::env::temp_dir().join(format!("sentry-rs-demo-tls-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let (cert_path, key_path, first_der) = write_pair(&dir, "first");
+        let resolver = ReloadingCertResolver::from_files(&cert_path, &key_path).unwrap();
+        let served = resolver.current.read().unwrap().cert[0].clone();
+        assert_eq!(served.as_ref(), first_der.as_slice());
+
+        // Rotate the files in place, as certbot would.
+        let (new_cert, new_key, second_der) = write_pair(&dir, "second");
+        std::fs::copy(new_cert, &cert_path).unwrap();
+        std::fs::copy(new_key, &key_path).unwrap();
+        resolver.reload().unwrap();
+        let served = resolver.current.read().unwrap().cert[0].clone();
+        assert_eq!(served.as_ref(), second_der.as_slice());
+
+        // A half-written rotation must not take the listener down.
+        std::fs::write(&cert_path, "not a pem").unwrap();
+        let err = resolver.reload().unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Config {
+                var: "TLS_CERT_PATH",
+                ..
+            }
+        ));
+        let served = resolver.current.read().unwrap().cert[0].clone();
+        assert_eq!(served.as_ref(), second_der.as_slice());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn missing_files_name_the_path() {
+        let err =
+            ReloadingCertResolver::from_files("/no/such/cert.pem", "/no/such/key.pem").unwrap_err();
+        let Error::Config { var, message } = err else {
+            panic!("expected a config error");
+        };
+        assert_eq!(var, "TLS_CERT_PATH");
+        assert!(message.contains("/no/such/cert.pem"));
+    }
+}
@@ -0,0 +1,80 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use actix_web::{http::StatusCode, post, web, HttpResponse};
+
+use crate::error::{Error, HttpResult};
+
+/// How long a forward to the ingest host may take end to end.
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Idle connections kept per ingest host between forwards.
+const POOL_SIZE: usize = 4;
+
+/// One shared client for every tunnelled envelope, so forwards reuse
+/// connections instead of handshaking per event.
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(UPSTREAM_TIMEOUT)
+            .pool_max_idle_per_host(POOL_SIZE)
+            .build()
+            .expect("failed to build the sentry tunnel client")
+    })
+}
+
+/// Same-origin tunnel for the browser SDK, so ad blockers that eat
+/// requests to *.sentry.io see only first-party traffic. The envelope
+/// names its own DSN in the header line; only DSNs on the configured
+/// allowlist are forwarded, and the body passes through untouched and
+/// unlogged — envelopes carry user data.
+#[post("/sentry-tunnel")]
+pub async fn tunnel(body: web::Bytes) -> HttpResult<HttpResponse> {
+    let config = crate::config::Config::global();
+    if body.len() > config.sentry_tunnel_max_bytes {
+        return Err(Error::EnvelopeTooLarge {
+            len: body.len(),
+            max: config.sentry_tunnel_max_bytes,
+        }
+        .into());
+    }
+
+    // The first line of an envelope is a JSON header carrying the DSN.
+    let header_line = body.split(|&b| b == b'\n').next().unwrap_or_default();
+    let header: serde_json::Value = serde_json::from_slice(header_line)
+        .map_err(|_| Error::InvalidRequestBody("envelope header is not valid JSON".to_string()))?;
+    let dsn = header
+        .get("dsn")
+        .and_then(|dsn| dsn.as_str())
+        .ok_or_else(|| Error::InvalidRequestBody("envelope header carries no dsn".to_string()))?
+        .parse::<sentry::types::Dsn>()
+        .map_err(|_| Error::InvalidRequestBody("envelope dsn is not valid".to_string()))?;
+
+    // The allowlist entries were validated at config load, so a parse
+    // failure here can only mean no match.
+    if !config
+        .sentry_tunnel_dsns
+        .iter()
+        .any(|allowed| allowed.parse::<sentry::types::Dsn>().ok() == Some(dsn.clone()))
+    {
+        return Err(Error::TunnelDsnRejected.into());
+    }
+
+    let response = client()
+        .post(dsn.envelope_api_url().to_string())
+        .header("content-type", "application/x-sentry-envelope")
+        .body(body)
+        .send()
+        .await
+        .map_err(|err| Error::TunnelUpstream(err.to_string()))?;
+
+    // Sentry's verdict (200, 429, ...) goes back to the browser SDK
+    // verbatim; it knows how to back off from a 429.
+    let status =
+        StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let body = response.bytes().await.unwrap_or_default();
+    Ok(HttpResponse::build(status)
+        .content_type("application/json")
+        .body(body))
+}
@@ -0,0 +1,173 @@
+use actix_web::post;
+use serde::Serialize;
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::calculator::Operation;
+use crate::error::HttpResult;
+use crate::handlers::{CalcRequest, CalculationRequest, CalculationResponse};
+use crate::negotiation::Negotiated;
+
+/// The v1 response envelope: the v0 payload under `data`, plus request
+/// metadata clients kept reconstructing from headers and logs.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Envelope {
+    data: CalculationResponse,
+    meta: Meta,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Meta {
+    /// The X-Request-Id of this request, when known.
+    request_id: Option<String>,
+    /// Server-side handling time for the calculation itself.
+    duration_ms: u64,
+    op: &'static str,
+}
+
+/// The shared core is handlers::calculate_at_width — v1 only changes the
+/// response mapping, never the behaviour.
+async fn enveloped(op: Operation, req: &CalculationRequest) -> HttpResult<Negotiated<Envelope>> {
+    let started = std::time::Instant::now();
+    let res = crate::handlers::calculate_at_width(op, req).await?;
+
+    Ok(Negotiated(Envelope {
+        data: CalculationResponse {
+            res,
+            overflow: req.overflow.map(|mode| mode.name().into()),
+        },
+        meta: Meta {
+            request_id: crate::middleware::REQUEST_ID.try_with(|id| id.clone()).ok(),
+            duration_ms: started.elapsed().as_millis() as u64,
+            op: op.name(),
+        },
+    }))
+}
+
+#[utoipa::path(
+    context_path = "/api/v1",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "The enveloped result", body = Envelope),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator-v1"
+)]
+#[tracing::instrument]
+#[post("/add")]
+pub async fn add(body: Negotiated<CalculationRequest>) -> HttpResult<Negotiated<Envelope>> {
+    enveloped(Operation::Add, &body).await
+}
+
+#[utoipa::path(
+    context_path = "/api/v1",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "The enveloped result", body = Envelope),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator-v1"
+)]
+#[tracing::instrument]
+#[post("/sub")]
+pub async fn sub(body: Negotiated<CalculationRequest>) -> HttpResult<Negotiated<Envelope>> {
+    enveloped(Operation::Sub, &body).await
+}
+
+#[utoipa::path(
+    context_path = "/api/v1",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "The enveloped result", body = Envelope),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator-v1"
+)]
+#[tracing::instrument]
+#[post("/mul")]
+pub async fn mul(body: Negotiated<CalculationRequest>) -> HttpResult<Negotiated<Envelope>> {
+    enveloped(Operation::Mul, &body).await
+}
+
+#[utoipa::path(
+    context_path = "/api/v1",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "The enveloped result", body = Envelope),
+        (status = 400, description = "y is zero", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator-v1"
+)]
+#[tracing::instrument]
+#[post("/div")]
+pub async fn div(body: Negotiated<CalculationRequest>) -> HttpResult<Negotiated<Envelope>> {
+    enveloped(Operation::Div, &body).await
+}
+
+#[utoipa::path(
+    context_path = "/api/v1",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "The enveloped result", body = Envelope),
+        (status = 400, description = "y is zero", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator-v1"
+)]
+#[tracing::instrument]
+#[post("/mod")]
+pub async fn modulo(body: Negotiated<CalculationRequest>) -> HttpResult<Negotiated<Envelope>> {
+    enveloped(Operation::Mod, &body).await
+}
+
+#[utoipa::path(
+    context_path = "/api/v1",
+    request_body = CalculationRequest,
+    responses(
+        (status = 200, description = "The enveloped result", body = Envelope),
+        (status = 400, description = "The exponent is negative", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator-v1"
+)]
+#[tracing::instrument]
+#[post("/pow")]
+pub async fn pow(body: Negotiated<CalculationRequest>) -> HttpResult<Negotiated<Envelope>> {
+    enveloped(Operation::Pow, &body).await
+}
+
+#[utoipa::path(
+    context_path = "/api/v1",
+    request_body = CalcRequest,
+    responses(
+        (status = 200, description = "The enveloped result", body = Envelope),
+        (status = 400, description = "Unknown operation or invalid input", body = crate::openapi::ErrorBody),
+        (status = 422, description = "The operation overflowed i32", body = crate::openapi::ErrorBody),
+        (status = 500, description = "Internal server error", body = crate::openapi::ErrorBody),
+    ),
+    tag = "calculator-v1"
+)]
+#[tracing::instrument]
+#[post("/calc")]
+pub async fn calc(body: Negotiated<CalcRequest>) -> HttpResult<Negotiated<Envelope>> {
+    info!(method = "v1::calc", ?body, "dispatching a calculation");
+
+    let op = body.op.parse::<Operation>()?;
+    enveloped(
+        op,
+        &CalculationRequest {
+            x: body.x.into(),
+            y: body.y.into(),
+            overflow: None,
+            width: None,
+        },
+    )
+    .await
+}
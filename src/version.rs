@@ -0,0 +1,156 @@
+//! Build metadata embedded by build.rs, shared by the /version endpoint
+//! and the sentry release / runtime context.
+
+pub const CARGO_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const GIT_SHA: &str = env!("GIT_SHA");
+pub const BUILD_TIMESTAMP: &str = env!("BUILD_TIMESTAMP");
+pub const RUSTC_VERSION: &str = env!("RUSTC_VERSION");
+
+/// The sentry release in the conventional `name@version+build` form, so
+/// issues group per deployment rather than per cargo version.
+pub fn release() -> String {
+    format!("{}@{}+{}", env!("CARGO_PKG_NAME"), CARGO_VERSION, GIT_SHA)
+}
+
+/// The same metadata as a sentry context, attached to every event.
+#[cfg(feature = "sentry")]
+pub fn runtime_context() -> sentry::protocol::Context {
+    let mut map = sentry::protocol::Map::new();
+    map.insert("cargo_version".into(), CARGO_VERSION.into());
+    map.insert("git_sha".into(), GIT_SHA.into());
+    map.insert("build_timestamp".into(), BUILD_TIMESTAMP.into());
+    sentry::protocol::Context::Other(map)
+}
+
+/// Instance metadata gathered once at startup and attached to every
+/// event as the "server" context, so behaviour can be compared between
+/// instances of the same release.
+#[cfg(feature = "sentry")]
+pub struct ServerContext {
+    hostname: String,
+    os: String,
+    workers: usize,
+    started_unix: u64,
+    started: std::time::Instant,
+    /// The masked config snapshot; only with SENTRY_CONFIG_CONTEXT=true,
+    /// since even masked settings may be sensitive.
+    config_snapshot: Option<String>,
+}
+
+#[cfg(feature = "sentry")]
+impl ServerContext {
+    pub fn gather(config: &crate::config::Config) -> ServerContext {
+        ServerContext {
+            hostname: hostname(),
+            os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            // The count actix will actually use when APP_WORKERS is
+            // unset: one per available core.
+            workers: config.workers.unwrap_or_else(|| {
+                std::thread::available_parallelism()
+                    .map(|n| n.get())
+                    .unwrap_or(1)
+            }),
+            started_unix: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|since| since.as_secs())
+                .unwrap_or(0),
+            started: std::time::Instant::now(),
+            config_snapshot: config.sentry_config_context.then(|| config.render_masked()),
+        }
+    }
+
+    /// Registers the context on the current scope, with an event
+    /// processor filling in the uptime at capture time rather than the
+    /// uptime at startup (which would always be zero).
+    pub fn install(self) {
+        let mut map = sentry::protocol::Map::new();
+        map.insert("hostname".into(), self.hostname.into());
+        map.insert("os".into(), self.os.into());
+        map.insert("workers".into(), (self.workers as u64).into());
+        map.insert("rustc_version".into(), RUSTC_VERSION.into());
+        map.insert("start_time_unix".into(), self.started_unix.into());
+        if let Some(snapshot) = self.config_snapshot {
+            map.insert("config".into(), snapshot.into());
+        }
+        let started = self.started;
+        sentry::configure_scope(move |scope| {
+            scope.set_context("server", sentry::protocol::Context::Other(map));
+            scope.add_event_processor(move |mut event| {
+                if let Some(sentry::protocol::Context::Other(server)) =
+                    event.contexts.get_mut("server")
+                {
+                    server.insert("uptime_secs".into(), started.elapsed().as_secs().into());
+                }
+                Some(event)
+            });
+        });
+    }
+}
+
+/// Best-effort: the environment variable most inits set, then the
+/// kernel's record, then a marker; no extra dependency for a label.
+#[cfg(feature = "sentry")]
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .filter(|name| !name.is_empty())
+        .or_else(|| {
+            std::fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(all(test, feature = "sentry"))]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn events_carry_the_server_context_and_a_capture_time_uptime() {
+        let transport = sentry::test::TestTransport::new();
+        let options = sentry::ClientOptions {
+            dsn: Some("https://public@example.com/1".parse().unwrap()),
+            transport: Some(Arc::new(transport.clone())),
+            ..Default::default()
+        };
+        sentry::Hub::current().bind_client(Some(Arc::new(options.into())));
+
+        let mut config = crate::config::Config::from_env().unwrap();
+        ServerContext::gather(&config).install();
+        sentry::capture_message("ping", sentry::Level::Info);
+
+        let events = transport.fetch_and_clear_events();
+        let Some(sentry::protocol::Context::Other(server)) = events[0].contexts.get("server")
+        else {
+            panic!("no server context on the event: {:?}", events[0].contexts);
+        };
+        for key in [
+            "hostname",
+            "os",
+            "workers",
+            "rustc_version",
+            "start_time_unix",
+            "uptime_secs",
+        ] {
+            assert!(server.contains_key(key), "missing {key}: {server:?}");
+        }
+        // The config snapshot is opt-in.
+        assert!(!server.contains_key("config"));
+
+        config.sentry_config_context = true;
+        ServerContext::gather(&config).install();
+        sentry::capture_message("ping", sentry::Level::Info);
+
+        let events = transport.fetch_and_clear_events();
+        let Some(sentry::protocol::Context::Other(server)) = events[0].contexts.get("server")
+        else {
+            panic!("no server context on the event: {:?}", events[0].contexts);
+        };
+        let snapshot = server["config"].as_str().unwrap();
+        assert!(snapshot.contains("port:"), "not a snapshot: {snapshot}");
+    }
+}
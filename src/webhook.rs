@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tracing::warn;
+
+/// How long one notification suppresses the next for the same code.
+const DEBOUNCE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Delivery attempts per notification, with exponential backoff between.
+const MAX_ATTEMPTS: u32 = 3;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Notifications waiting for the delivery task; beyond this the newest
+/// is dropped — losing a ping beats stalling the error path.
+const QUEUE_CAPACITY: usize = 64;
+
+/// The JSON POSTed to the configured webhook for a server error.
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    /// The error code, e.g. "handler_panic".
+    pub code: &'static str,
+    /// The path the error occurred on.
+    pub route: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Unix seconds when the notification was enqueued.
+    pub timestamp: u64,
+    /// Occurrences of this code within the last minute, including the
+    /// ones a previous notification's debounce window swallowed.
+    pub count: u64,
+}
+
+struct Debounce {
+    last_sent: Instant,
+    /// Occurrences since last_sent that did not get their own ping.
+    suppressed: u64,
+}
+
+/// Fans server errors out to the configured webhook URL. notify() only
+/// debounces and enqueues; a background task owns the HTTP delivery, so
+/// a slow or dead webhook endpoint never adds to request latency.
+pub struct Notifier {
+    sender: Option<tokio::sync::mpsc::Sender<Notification>>,
+    debounce: Mutex<HashMap<&'static str, Debounce>>,
+    delivered: AtomicU64,
+    failed: AtomicU64,
+}
+
+impl Notifier {
+    fn disabled() -> Self {
+        Notifier {
+            sender: None,
+            debounce: Mutex::new(HashMap::new()),
+            delivered: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+        }
+    }
+
+    /// Lazily spawns the delivery task on first use, which always
+    /// happens inside the server runtime (the error path or /stats).
+    pub fn global() -> Arc<Notifier> {
+        static NOTIFIER: OnceLock<Arc<Notifier>> = OnceLock::new();
+        NOTIFIER
+            .get_or_init(|| {
+                let Some(url) = crate::config::Config::global().webhook_url.clone() else {
+                    return Arc::new(Notifier::disabled());
+                };
+                let (sender, receiver) = tokio::sync::mpsc::channel(QUEUE_CAPACITY);
+                let notifier = Arc::new(Notifier {
+                    sender: Some(sender),
+                    ..Notifier::disabled()
+                });
+                tokio::spawn(deliver(url, receiver, Arc::clone(&notifier)));
+                notifier
+            })
+            .clone()
+    }
+
+    pub fn delivered(&self) -> u64 {
+        self.delivered.load(Ordering::Relaxed)
+    }
+
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Whether a notification for the code is due now, and with what
+    /// occurrence count; None while the previous one's window holds.
+    fn should_send(&self, code: &'static str, window: Duration) -> Option<u64> {
+        let mut debounce = self.debounce.lock().unwrap();
+        match debounce.get_mut(code) {
+            Some(entry) if entry.last_sent.elapsed() < window => {
+                entry.suppressed += 1;
+                None
+            }
+            Some(entry) => {
+                let count = entry.suppressed + 1;
+                entry.last_sent = Instant::now();
+                entry.suppressed = 0;
+                Some(count)
+            }
+            None => {
+                debounce.insert(
+                    code,
+                    Debounce {
+                        last_sent: Instant::now(),
+                        suppressed: 0,
+                    },
+                );
+                Some(1)
+            }
+        }
+    }
+}
+
+/// Called from the error conversion path: enqueues at most one
+/// notification per error code per minute, never blocking. Does nothing
+/// unless WEBHOOK_URL is configured and the status clears the floor.
+pub fn notify(code: &'static str, status: actix_web::http::StatusCode) {
+    let config = crate::config::Config::global();
+    if config.webhook_url.is_none() || status.as_u16() < config.webhook_min_status {
+        return;
+    }
+
+    let notifier = Notifier::global();
+    let Some(sender) = &notifier.sender else {
+        return;
+    };
+    let Some(count) = notifier.should_send(code, DEBOUNCE_WINDOW) else {
+        return;
+    };
+
+    let notification = Notification {
+        code,
+        route: crate::middleware::REQUEST_PATH
+            .try_with(|path| path.clone())
+            .unwrap_or_default(),
+        request_id: crate::middleware::REQUEST_ID.try_with(|id| id.clone()).ok(),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        count,
+    };
+
+    if sender.try_send(notification).is_err() {
+        notifier.failed.fetch_add(1, Ordering::Relaxed);
+        warn!(code, "webhook queue full; notification dropped");
+    }
+}
+
+/// The delivery loop: POSTs each notification with retries and
+/// exponential backoff, counting the outcome either way.
+async fn deliver(
+    url: String,
+    mut receiver: tokio::sync::mpsc::Receiver<Notification>,
+    notifier: Arc<Notifier>,
+) {
+    let client = reqwest::Client::new();
+    while let Some(notification) = receiver.recv().await {
+        let mut backoff = INITIAL_BACKOFF;
+        let mut delivered = false;
+        for attempt in 1..=MAX_ATTEMPTS {
+            match client.post(&url).json(&notification).send().await {
+                Ok(response) if response.status().is_success() => {
+                    delivered = true;
+                    break;
+                }
+                Ok(response) => warn!(
+                    status = response.status().as_u16(),
+                    attempt,
+                    code = notification.code,
+                    "webhook refused the notification"
+                ),
+                Err(err) => warn!(
+                    %err,
+                    attempt,
+                    code = notification.code,
+                    "webhook delivery failed"
+                ),
+            }
+            if attempt < MAX_ATTEMPTS {
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+        }
+        if delivered {
+            notifier.delivered.fetch_add(1, Ordering::Relaxed);
+        } else {
+            notifier.failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_code_is_debounced_within_its_window() {
+        let notifier = Notifier::disabled();
+        assert_eq!(notifier.should_send("timeout", DEBOUNCE_WINDOW), Some(1));
+        assert_eq!(notifier.should_send("timeout", DEBOUNCE_WINDOW), None);
+        assert_eq!(notifier.should_send("timeout", DEBOUNCE_WINDOW), None);
+        // A different code has its own window.
+        assert_eq!(notifier.should_send("database", DEBOUNCE_WINDOW), Some(1));
+    }
+
+    #[test]
+    fn suppressed_occurrences_are_counted_into_the_next_send() {
+        let notifier = Notifier::disabled();
+        assert_eq!(notifier.should_send("timeout", DEBOUNCE_WINDOW), Some(1));
+        assert_eq!(notifier.should_send("timeout", DEBOUNCE_WINDOW), None);
+        assert_eq!(notifier.should_send("timeout", DEBOUNCE_WINDOW), None);
+        // With the window already elapsed, the next occurrence reports
+        // itself plus the two that were swallowed.
+        assert_eq!(notifier.should_send("timeout", Duration::ZERO), Some(3));
+    }
+}
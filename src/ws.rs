@@ -0,0 +1,201 @@
+#[cfg(feature = "sentry")]
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use actix_web::{get, web, HttpRequest, HttpResponse};
+use actix_ws::{CloseCode, CloseReason, Message, MessageStream, Session};
+use futures_util::StreamExt;
+#[cfg(feature = "sentry")]
+use sentry::SentryFutureExt;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::calculator::Operation;
+use crate::error::Error;
+use crate::handlers::CalcRequest;
+
+/// Seconds without a frame before the server closes the session.
+/// Overridable with WS_IDLE_TIMEOUT_SECS.
+fn idle_timeout() -> Duration {
+    static IDLE_TIMEOUT_SECS: OnceLock<u64> = OnceLock::new();
+    Duration::from_secs(*IDLE_TIMEOUT_SECS.get_or_init(|| {
+        std::env::var("WS_IDLE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60)
+    }))
+}
+
+/// Messages per second one connection may send; 0 disables the limit.
+/// Overridable with WS_RATE_LIMIT_RPS.
+fn messages_per_sec() -> f64 {
+    static RPS: OnceLock<f64> = OnceLock::new();
+    *RPS.get_or_init(|| {
+        std::env::var("WS_RATE_LIMIT_RPS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10.0)
+    })
+}
+
+/// A per-connection token bucket; no sharing, so no locks. The bucket
+/// starts full, which permits a small initial burst.
+struct Throttle {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl Throttle {
+    fn new() -> Self {
+        Throttle {
+            tokens: messages_per_sec(),
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn try_acquire(&mut self) -> bool {
+        let rps = messages_per_sec();
+        if rps <= 0.0 {
+            return true;
+        }
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rps).min(rps);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Upgrades to a WebSocket session where every text frame is a JSON
+/// {op, x, y} calculation and the reply is either {"res": n} or the
+/// structured error object, over as many frames as the client likes.
+#[get("/ws")]
+pub async fn connect(req: HttpRequest, body: web::Payload) -> actix_web::Result<HttpResponse> {
+    let (response, session, stream) = actix_ws::handle(&req, body)?;
+
+    let session_id = Uuid::new_v4().to_string();
+    // A dedicated hub for the whole session: frame handling happens in a
+    // spawned task, so request-scoped tags would otherwise be lost.
+    #[cfg(feature = "sentry")]
+    let hub = Arc::new(sentry::Hub::new_from_top(sentry::Hub::current()));
+    #[cfg(feature = "sentry")]
+    hub.configure_scope(|scope| {
+        scope.set_tag("websocket", true);
+        scope.set_tag("session_id", &session_id);
+    });
+
+    info!(session_id, "websocket session opened");
+    let task = run_session(session, stream, session_id);
+    #[cfg(feature = "sentry")]
+    let task = task.bind_hub(hub);
+    actix_web::rt::spawn(task);
+
+    Ok(response)
+}
+
+async fn run_session(mut session: Session, mut stream: MessageStream, session_id: String) {
+    let mut throttle = Throttle::new();
+
+    loop {
+        let msg = match tokio::time::timeout(idle_timeout(), stream.next()).await {
+            // Idle sessions are closed server-side so they can't pile up.
+            Err(_) => {
+                info!(session_id, "closing idle websocket session");
+                let _ = session
+                    .close(Some(CloseReason {
+                        code: CloseCode::Policy,
+                        description: Some("idle timeout".to_owned()),
+                    }))
+                    .await;
+                return;
+            }
+            Ok(None) => return,
+            Ok(Some(Err(err))) => {
+                warn!(session_id, %err, "websocket protocol error");
+                crate::reporter::global().report_message(
+                    &format!("websocket protocol error: {err}"),
+                    crate::reporter::Level::Warning,
+                    &[],
+                    &[],
+                );
+                return;
+            }
+            Ok(Some(Ok(msg))) => msg,
+        };
+
+        match msg {
+            Message::Text(text) => {
+                let reply = if throttle.try_acquire() {
+                    handle_frame(&text).await
+                } else {
+                    error_frame(&Error::RateLimited {
+                        retry_after_secs: 1,
+                    })
+                };
+
+                if session.text(reply.to_string()).await.is_err() {
+                    return;
+                }
+            }
+            Message::Ping(bytes) => {
+                // A failed pong means the session is gone; the next recv
+                // ends the loop.
+                let _ = session.pong(&bytes).await;
+            }
+            Message::Close(reason) => {
+                info!(session_id, ?reason, "websocket session closed by client");
+                let _ = session.close(reason).await;
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// One calculation per text frame; the same core and error codes as the
+/// HTTP endpoints. Server errors are captured on the session hub, which
+/// carries the websocket and session_id tags.
+async fn handle_frame(text: &str) -> serde_json::Value {
+    let request: CalcRequest = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(err) => return error_frame(&Error::InvalidRequestBody(err.to_string())),
+    };
+
+    let res = match request.op.parse::<Operation>() {
+        Ok(op) => crate::handlers::calculate(op, request.x, request.y).await,
+        Err(err) => Err(err),
+    };
+
+    match res {
+        Ok(res) => serde_json::json!({ "res": res }),
+        Err(err) => {
+            if !err.is_client_error() {
+                crate::reporter::global().report_message(
+                    &err.to_string(),
+                    crate::reporter::Level::Error,
+                    &[],
+                    &[],
+                );
+            }
+            error_frame(&err)
+        }
+    }
+}
+
+fn error_frame(err: &Error) -> serde_json::Value {
+    serde_json::json!({
+        "error": {
+            "code": err.code(),
+            "message": err.to_string(),
+            "status": err.status_code().as_u16(),
+        }
+    })
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "sentry")]
+
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+mod common;
+
+// One sequential test: ADMIN_TOKEN is read once into the process-global
+// Config.
+#[actix_web::test]
+async fn admin_routes_require_the_bearer_token_and_audit_actions() {
+    // Before the first Config::global() call, which reads it.
+    std::env::set_var("ADMIN_TOKEN", "admin-test-token");
+
+    let events = common::bind_recording_client();
+    let app = test::init_service(create_app()).await;
+
+    // No credential: 401 with the structured body.
+    let req = test::TestRequest::get()
+        .uri("/admin/maintenance")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "missing_admin_token");
+
+    // A wrong token: 403.
+    let req = test::TestRequest::get()
+        .uri("/admin/maintenance")
+        .insert_header(("authorization", "Bearer wrong"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "invalid_admin_token");
+
+    // The right one works, and the action is audited.
+    let req = test::TestRequest::post()
+        .uri("/admin/maintenance")
+        .insert_header(("authorization", "Bearer admin-test-token"))
+        .set_json(serde_json::json!({ "enabled": false }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // Auth failures are client errors, never sentry events; the audit
+    // trail shows up as an admin breadcrumb on the next captured event.
+    let req = test::TestRequest::get().uri("/debug/panic").to_request();
+    let _ = test::try_call_service(&app, req).await;
+
+    let captured = common::recorded_events(&events);
+    assert_eq!(captured.len(), 1);
+    assert!(captured[0]
+        .breadcrumbs
+        .values
+        .iter()
+        .any(|breadcrumb| breadcrumb.category.as_deref() == Some("admin")
+            && breadcrumb
+                .message
+                .as_deref()
+                .is_some_and(|message| message.contains("maintenance"))));
+}
@@ -0,0 +1,727 @@
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+mod common;
+
+#[actix_web::test]
+async fn status_reports_ok() {
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::get().uri("/api/v0/status").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["status"], "OK");
+}
+
+#[actix_web::test]
+async fn add_returns_the_sum() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": 2, "y": 3 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 5);
+}
+
+#[actix_web::test]
+async fn version_reports_build_metadata() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::get().uri("/api/v0/version").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+    assert!(body["git_sha"].as_str().is_some_and(|s| !s.is_empty()));
+    assert!(body["build_timestamp"]
+        .as_str()
+        .is_some_and(|s| !s.is_empty()));
+}
+
+// Regression: /div used to return x + y.
+#[actix_web::test]
+async fn div_returns_the_quotient() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/div")
+        .set_json(serde_json::json!({ "x": 84, "y": 2 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 42);
+}
+
+#[actix_web::test]
+async fn mod_returns_the_remainder() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/mod")
+        .set_json(serde_json::json!({ "x": 7, "y": 3 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 1);
+}
+
+#[actix_web::test]
+async fn mod_by_zero_is_a_structured_400() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/mod")
+        .set_json(serde_json::json!({ "x": 7, "y": 0 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "divide_by_zero");
+}
+
+#[actix_web::test]
+async fn mod_overflow_is_a_structured_422() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/mod")
+        .set_json(serde_json::json!({ "x": i32::MIN, "y": -1 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "overflow");
+}
+
+#[actix_web::test]
+async fn float_div_returns_a_decimal_quotient() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/float/div")
+        .set_json(serde_json::json!({ "x": 1.0, "y": 4.0 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 0.25);
+}
+
+#[actix_web::test]
+async fn float_div_by_zero_is_a_structured_400() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/float/div")
+        .set_json(serde_json::json!({ "x": 1.0, "y": 0.0 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "divide_by_zero");
+}
+
+#[actix_web::test]
+async fn float_overflow_to_infinity_is_a_structured_422() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/float/mul")
+        .set_json(serde_json::json!({ "x": 1e308, "y": 1e308 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "non_finite_result");
+}
+
+#[actix_web::test]
+async fn batch_reports_errors_per_item() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/batch")
+        .set_json(serde_json::json!([
+            { "op": "add", "x": 1, "y": 2 },
+            { "op": "div", "x": 1, "y": 0 },
+            { "op": "nope", "x": 1, "y": 1 },
+        ]))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body[0]["res"], 3);
+    assert_eq!(body[1]["error"]["code"], "divide_by_zero");
+    assert_eq!(body[2]["error"]["code"], "unknown_operation");
+}
+
+#[actix_web::test]
+async fn oversized_batches_are_rejected() {
+    let app = test::init_service(create_app()).await;
+
+    let items: Vec<_> = (0..101)
+        .map(|i| serde_json::json!({ "op": "add", "x": i, "y": 1 }))
+        .collect();
+    let req = test::TestRequest::post()
+        .uri("/api/v0/batch")
+        .set_json(serde_json::json!(items))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "batch_too_large");
+}
+
+#[actix_web::test]
+async fn metrics_expose_request_and_error_counters() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": 1, "y": 1 }))
+        .to_request();
+    test::call_service(&app, req).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/div")
+        .set_json(serde_json::json!({ "x": 1, "y": 0 }))
+        .to_request();
+    test::call_service(&app, req).await;
+
+    let req = test::TestRequest::get().uri("/metrics").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("http_requests_total"));
+    assert!(body.contains(r#"calculation_errors_total{code="divide_by_zero"}"#));
+    assert!(!body.contains(r#"route="/metrics""#));
+}
+
+#[actix_web::test]
+async fn client_supplied_request_id_is_echoed_back() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::get()
+        .uri("/api/v0/status")
+        .insert_header(("x-request-id", "my-request-id"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers().get("x-request-id").unwrap(), "my-request-id");
+}
+
+#[actix_web::test]
+async fn server_generates_a_request_id_when_none_is_supplied() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::get().uri("/api/v0/status").to_request();
+    let resp = test::call_service(&app, req).await;
+
+    let request_id = resp
+        .headers()
+        .get("x-request-id")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert!(uuid::Uuid::parse_str(request_id).is_ok());
+}
+
+#[actix_web::test]
+async fn error_bodies_carry_the_request_id() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/div")
+        .insert_header(("x-request-id", "my-request-id"))
+        .set_json(serde_json::json!({ "x": 1, "y": 0 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["request_id"], "my-request-id");
+}
+
+#[actix_web::test]
+async fn div_by_zero_is_a_structured_400() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/div")
+        .set_json(serde_json::json!({ "x": 1, "y": 0 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "divide_by_zero");
+}
+
+#[actix_web::test]
+async fn get_variants_compute_and_are_cacheable() {
+    let app = test::init_service(create_app()).await;
+
+    for (path, expected) in [
+        ("/api/v0/add?x=3&y=4", 7),
+        ("/api/v0/sub?x=3&y=4", -1),
+        ("/api/v0/mul?x=3&y=4", 12),
+        ("/api/v0/div?x=12&y=4", 3),
+        ("/api/v0/mod?x=14&y=4", 2),
+        ("/api/v0/pow?x=3&y=4", 81),
+    ] {
+        let req = test::TestRequest::get().uri(path).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK, "{path}");
+        let cache_control = resp
+            .headers()
+            .get("cache-control")
+            .expect("missing cache-control header")
+            .to_str()
+            .unwrap()
+            .to_owned();
+        assert!(cache_control.starts_with("public, max-age="), "{path}");
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["res"], expected, "{path}");
+    }
+}
+
+#[actix_web::test]
+async fn bad_query_parameters_are_a_structured_400() {
+    let app = test::init_service(create_app()).await;
+
+    for path in ["/api/v0/add?x=3", "/api/v0/add?x=three&y=4"] {
+        let req = test::TestRequest::get().uri(path).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{path}");
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "invalid_request_body", "{path}");
+    }
+}
+
+#[actix_web::test]
+async fn eval_computes_an_expression() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/eval")
+        .set_json(serde_json::json!({ "expr": "2 * (3 + 4) - 10 / 5" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 12);
+}
+
+#[actix_web::test]
+async fn eval_syntax_errors_report_the_offset() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/eval")
+        .set_json(serde_json::json!({ "expr": "2 + @" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "expr_syntax");
+    assert!(body["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("byte 4"));
+}
+
+// This binary never sets ADMIN_TOKEN, so the admin scope must not be
+// mounted at all.
+#[actix_web::test]
+async fn admin_routes_do_not_exist_without_a_token() {
+    let app = test::init_service(create_app()).await;
+
+    for uri in ["/admin/maintenance", "/admin/log-level", "/admin/stats"] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND, "{uri}");
+    }
+}
+
+// The default lenient mode: unknown fields are ignored, as they always
+// have been.
+#[actix_web::test]
+async fn unknown_fields_are_ignored_by_default() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": 1, "y": 2, "z": 3 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 3);
+}
+
+#[actix_web::test]
+async fn errors_negotiate_problem_json() {
+    let app = test::init_service(create_app()).await;
+
+    // The default shape is unchanged.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/div")
+        .set_json(serde_json::json!({ "x": 1, "y": 0 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/json"
+    );
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "divide_by_zero");
+
+    // Accept: application/problem+json switches to RFC 7807.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/div")
+        .insert_header(("accept", "application/problem+json"))
+        .set_json(serde_json::json!({ "x": 1, "y": 0 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/problem+json"
+    );
+    let body = test::read_body(resp).await;
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["type"], "/errors/divide_by_zero");
+    assert_eq!(body["title"], "divide by zero");
+    assert_eq!(body["status"], 400);
+    assert_eq!(body["detail"], "cannot divide by zero");
+    assert_eq!(body["instance"], "/api/v0/div");
+    assert_eq!(body["code"], "divide_by_zero");
+    assert!(body["request_id"].is_string());
+}
+
+#[actix_web::test]
+async fn overflow_mode_is_selectable_per_request() {
+    let app = test::init_service(create_app()).await;
+
+    // The default stays a 422.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": i32::MAX, "y": 1 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Wrap: two's-complement wraparound, with the mode echoed back.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": i32::MAX, "y": 1, "overflow": "wrap" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], i32::MIN);
+    assert_eq!(body["overflow"], "wrap");
+
+    // Saturate, via the query twins.
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v0/add?x={}&y=1&overflow=saturate", i32::MAX))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], i32::MAX);
+    assert_eq!(body["overflow"], "saturate");
+
+    // An unknown mode is a 400, not a silent fallback.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": 1, "y": 2, "overflow": "explode" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "invalid_request_body");
+}
+
+#[actix_web::test]
+async fn width_is_selectable_per_request() {
+    let app = test::init_service(create_app()).await;
+
+    // i64: operands beyond the i32 range compute instead of 422ing, and
+    // the in-range result stays a JSON number.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(
+            serde_json::json!({ "x": 3_000_000_000_i64, "y": 3_000_000_000_i64, "width": "i64" }),
+        )
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 6_000_000_000_i64);
+
+    // i128: the result travels as a string, so nothing is rounded
+    // through f64 on the way to the client.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/mul")
+        .set_json(serde_json::json!({ "x": i64::MAX, "y": 2, "width": "i128" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], (i128::from(i64::MAX) * 2).to_string());
+
+    // Overflow semantics are the same at every width.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": i64::MAX, "y": 1, "width": "i64" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "overflow");
+
+    // At the default width an operand that doesn't fit i32 is a 422
+    // naming the range, via the query twins.
+    let req = test::TestRequest::get()
+        .uri("/api/v0/add?x=3000000000&y=1")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "operand_out_of_range");
+
+    // An unknown width is a 400, not a silent fallback.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": 1, "y": 2, "width": "i256" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "invalid_request_body");
+}
+
+#[actix_web::test]
+async fn gcd_and_lcm_endpoints() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/gcd")
+        .set_json(serde_json::json!({ "x": -12, "y": 18 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 6);
+
+    // gcd(0, 0) is defined, not an error.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/gcd")
+        .set_json(serde_json::json!({ "x": 0, "y": 0 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 0);
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/lcm")
+        .set_json(serde_json::json!({ "x": 4, "y": 6 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 12);
+
+    // The checked product: lcm(i32::MAX, 2) is a 422, not a wrap.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/lcm")
+        .set_json(serde_json::json!({ "x": i32::MAX, "y": 2 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "overflow");
+}
+
+#[actix_web::test]
+async fn aggregate_reduces_arrays() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/aggregate")
+        .set_json(serde_json::json!({ "op": "sum", "values": [i32::MAX, i32::MAX] }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 2 * i64::from(i32::MAX));
+
+    // Mean comes with its remainder, so the exact sum is recoverable.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/aggregate")
+        .set_json(serde_json::json!({ "op": "mean", "values": [2, 3] }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 2);
+    assert_eq!(body["remainder"], 1);
+
+    // An empty array is a 422, not a panic or a silent zero.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/aggregate")
+        .set_json(serde_json::json!({ "op": "min", "values": [] }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "empty_input");
+}
+
+#[actix_web::test]
+async fn bitwise_endpoints_validate_shifts() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/xor")
+        .set_json(serde_json::json!({ "x": 12, "y": 10 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 6);
+    assert_eq!(body["hex"], "0x00000006");
+
+    // Negative results show the full two's-complement bit pattern.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/shr")
+        .set_json(serde_json::json!({ "x": -8, "y": 1 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], -4);
+    assert_eq!(body["hex"], "0xfffffffc");
+
+    // An out-of-range shift is a 400, not masked to y % 32.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/shl")
+        .set_json(serde_json::json!({ "x": 1, "y": 32 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "invalid_shift");
+    assert_eq!(
+        body["error"]["message"],
+        "shift amount must be between 0 and 31, got 32"
+    );
+}
+
+#[actix_web::test]
+async fn convert_translates_between_bases() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/convert")
+        .set_json(serde_json::json!({ "value": "ff", "from": 16, "to": 2 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["result"], "11111111");
+    assert_eq!(body["decimal"], "255");
+
+    // Each failure mode has its own code: bad base (400), bad digit
+    // (400), too big for i128 (422).
+    let req = test::TestRequest::post()
+        .uri("/api/v0/convert")
+        .set_json(serde_json::json!({ "value": "1", "from": 37, "to": 2 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "unsupported_base");
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/convert")
+        .set_json(serde_json::json!({ "value": "12", "from": 2, "to": 10 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "invalid_digit");
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/convert")
+        .set_json(serde_json::json!({
+            "value": "170141183460469231731687303715884105728",
+            "from": 10,
+            "to": 16
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "conversion_overflow");
+}
+
+#[actix_web::test]
+async fn decimal_calc_is_exact() {
+    let app = test::init_service(create_app()).await;
+
+    // 0.1 + 0.2 is exactly 0.3, which is the whole point.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/decimal/calc")
+        .set_json(serde_json::json!({ "op": "add", "x": "0.1", "y": "0.2" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], "0.3");
+
+    // Division honours the requested scale and rounding strategy.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/decimal/calc")
+        .set_json(serde_json::json!({
+            "op": "div", "x": "10", "y": "3", "scale": 2, "rounding": "half_up"
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], "3.33");
+
+    // A malformed decimal names the field in a structured 400.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/decimal/calc")
+        .set_json(serde_json::json!({ "op": "add", "x": "ten", "y": "2" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "invalid_request_body");
+}
@@ -0,0 +1,71 @@
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+mod common;
+
+// Every test in this binary shares the process-wide Config, so the keys
+// are set once before the first create_app() call.
+fn set_test_keys() {
+    std::env::set_var("APP_API_KEYS", "testclient:sekrit,other:hunter2");
+}
+
+#[actix_web::test]
+async fn missing_api_key_is_a_401() {
+    set_test_keys();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": 1, "y": 2 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "missing_api_key");
+}
+
+#[actix_web::test]
+async fn unknown_api_key_is_a_403() {
+    set_test_keys();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .insert_header(("x-api-key", "wrong"))
+        .set_json(serde_json::json!({ "x": 1, "y": 2 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "unknown_api_key");
+}
+
+#[actix_web::test]
+async fn valid_api_key_is_accepted() {
+    set_test_keys();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .insert_header(("x-api-key", "sekrit"))
+        .set_json(serde_json::json!({ "x": 1, "y": 2 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 3);
+}
+
+#[actix_web::test]
+async fn status_stays_unauthenticated() {
+    set_test_keys();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::get().uri("/api/v0/status").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "sentry")]
+
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+mod common;
+
+#[actix_web::test]
+async fn bodies_reach_server_error_events_scrubbed_and_capped() {
+    std::env::set_var("BODY_ECHO_ENABLED", "true");
+    std::env::set_var("BODY_ECHO_MAX_BYTES", "96");
+    let events = common::bind_recording_client();
+    let app = test::init_service(create_app()).await;
+
+    // A client error first: those are never captured, so no event may
+    // leak this body.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/div")
+        .set_json(serde_json::json!({ "x": 1, "y": 0, "marker": "client-error-body" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert!(common::recorded_events(&events).is_empty());
+
+    // A forced 500 with a body: the event carries the echo, with the
+    // sensitive key redacted.
+    let req = test::TestRequest::get()
+        .uri("/debug/panic")
+        .set_payload(r#"{ "password": "hunter2", "note": "from the body echo test" }"#)
+        .to_request();
+    let _ = test::try_call_service(&app, req).await;
+
+    let captured = common::recorded_events(&events);
+    let event = captured
+        .iter()
+        .find(|event| event.tags.get("code").map(String::as_str) == Some("handler_panic"))
+        .expect("no handler_panic event captured");
+    let body = event
+        .extra
+        .get("request_body")
+        .and_then(|value| value.as_str())
+        .expect("no request_body extra on the event");
+    assert!(body.contains("from the body echo test"), "echo: {body}");
+    assert!(!body.contains("hunter2"), "password not scrubbed: {body}");
+    assert!(body.contains("[redacted]"), "echo: {body}");
+}
+
+#[actix_web::test]
+async fn oversized_bodies_are_truncated_with_a_marker() {
+    std::env::set_var("BODY_ECHO_ENABLED", "true");
+    std::env::set_var("BODY_ECHO_MAX_BYTES", "96");
+    let events = common::bind_recording_client();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::get()
+        .uri("/debug/panic")
+        .set_payload("x".repeat(500))
+        .to_request();
+    let _ = test::try_call_service(&app, req).await;
+
+    let captured = common::recorded_events(&events);
+    let event = captured
+        .iter()
+        .find(|event| event.tags.get("code").map(String::as_str) == Some("handler_panic"))
+        .expect("no handler_panic event captured");
+    let body = event
+        .extra
+        .get("request_body")
+        .and_then(|value| value.as_str())
+        .expect("no request_body extra on the event");
+    assert!(
+        body.contains("[truncated to 96 of 500 bytes]"),
+        "echo: {body}"
+    );
+}
@@ -0,0 +1,86 @@
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+fn x_cache(resp: &actix_web::dev::ServiceResponse<impl actix_web::body::MessageBody>) -> String {
+    resp.headers()
+        .get("x-cache")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+// One sequential test: CACHE_ENABLED is read once into the
+// process-global Config.
+#[actix_web::test]
+async fn cache_serves_repeats_skips_errors_and_survives_races() {
+    // Before the first Config::global() call, which reads it.
+    std::env::set_var("CACHE_ENABLED", "true");
+
+    let app = test::init_service(create_app()).await;
+    let add = |x: i64, y: i64| {
+        test::TestRequest::post()
+            .uri("/api/v0/add")
+            .set_json(serde_json::json!({ "x": x, "y": y }))
+            .to_request()
+    };
+
+    // First sight of the operands computes, the repeat is served back.
+    let resp = test::call_service(&app, add(2, 3)).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(x_cache(&resp), "MISS");
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 5);
+
+    let resp = test::call_service(&app, add(2, 3)).await;
+    assert_eq!(x_cache(&resp), "HIT");
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 5);
+
+    // Different operands are a different key.
+    let resp = test::call_service(&app, add(2, 4)).await;
+    assert_eq!(x_cache(&resp), "MISS");
+
+    // The GET twins share the cache, so this is already a hit.
+    let req = test::TestRequest::get()
+        .uri("/api/v0/add?x=2&y=3")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(x_cache(&resp), "HIT");
+
+    // Errors never land in the cache: the retry recomputes (and fails)
+    // rather than replaying, and carries no verdict header.
+    for _ in 0..2 {
+        let req = test::TestRequest::post()
+            .uri("/api/v0/div")
+            .set_json(serde_json::json!({ "x": 1, "y": 0 }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert!(resp.headers().get("x-cache").is_none());
+    }
+
+    // Two simultaneous identical requests: both answer correctly, and
+    // whatever interleaving happened, the cache still holds the right
+    // value afterwards.
+    let (first, second) = futures_util::future::join(
+        test::call_service(&app, add(6, 7)),
+        test::call_service(&app, add(6, 7)),
+    )
+    .await;
+    for resp in [first, second] {
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["res"], 13);
+    }
+    let resp = test::call_service(&app, add(6, 7)).await;
+    assert_eq!(x_cache(&resp), "HIT");
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 13);
+
+    // The counters surface on /stats.
+    let req = test::TestRequest::get().uri("/api/v0/stats").to_request();
+    let resp = test::call_service(&app, req).await;
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body["cache_hits"].as_u64().unwrap() >= 2);
+    assert!(body["cache_misses"].as_u64().unwrap() >= 2);
+}
@@ -0,0 +1,36 @@
+use std::process::Command;
+
+fn run(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_sentry-rs-demo"))
+        .args(args)
+        .output()
+        .expect("the server binary runs")
+}
+
+#[test]
+fn calc_prints_the_http_shapes_with_matching_exit_codes() {
+    let out = run(&["calc", "add", "3", "4"]);
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), r#"{"res":7}"#);
+
+    // Failures go to stderr as the enveloped error body, non-zero exit.
+    let out = run(&["calc", "div", "1", "0"]);
+    assert!(!out.status.success());
+    let body: serde_json::Value = serde_json::from_slice(&out.stderr).expect("structured stderr");
+    assert_eq!(body["error"]["code"], "divide_by_zero");
+    assert_eq!(body["error"]["status"], 400);
+
+    // Operands are narrowed exactly as the HTTP default width narrows.
+    let out = run(&["calc", "add", "3000000000", "4"]);
+    assert!(!out.status.success());
+    let body: serde_json::Value = serde_json::from_slice(&out.stderr).expect("structured stderr");
+    assert_eq!(body["error"]["code"], "operand_out_of_range");
+    assert_eq!(body["error"]["status"], 422);
+}
+
+#[test]
+fn check_config_validates_and_exits() {
+    let out = run(&["check-config"]);
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("configuration OK"));
+}
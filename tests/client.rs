@@ -0,0 +1,75 @@
+#![cfg(feature = "client")]
+
+use std::time::Duration;
+
+use sentry_rs_demo::calculator::{Operation, OverflowMode};
+use sentry_rs_demo::client::{CalculatorClient, ClientError};
+use sentry_rs_demo::create_app;
+use sentry_rs_demo::handlers::{BatchItemResponse, CalcValue};
+
+// One sequential test: APP_API_KEYS must be set before the first
+// Config::global() call, so this binary owns its server configuration.
+#[actix_web::test]
+async fn the_client_round_trips_results_errors_and_batches() {
+    std::env::set_var("APP_API_KEYS", "tests:client-test-key");
+
+    let server = actix_test::start(create_app);
+    let base = format!("http://{}", server.addr());
+
+    let client = CalculatorClient::new(&base)
+        .api_key("client-test-key")
+        .timeout(Duration::from_secs(5));
+
+    // Plain success through a convenience method.
+    let res = client.add(2, 3).await.unwrap();
+    assert!(matches!(res.res, CalcValue::Number(5)), "res: {res:?}");
+    assert!(res.overflow.is_none());
+
+    // Per-request semantics travel and are echoed back.
+    let res = client
+        .calculate_with(
+            Operation::Add,
+            i64::from(i32::MAX),
+            1,
+            Some(OverflowMode::Wrap),
+            None,
+        )
+        .await
+        .unwrap();
+    assert!(matches!(res.res, CalcValue::Number(n) if n == i64::from(i32::MIN)));
+    assert_eq!(res.overflow.as_deref(), Some("wrap"));
+
+    // A calculation failure decodes into the typed error, request id
+    // included.
+    let err = client.div(1, 0).await.unwrap_err();
+    match err {
+        ClientError::Api(api) => {
+            assert_eq!(api.code, "divide_by_zero");
+            assert_eq!(api.status, 400);
+            assert!(api.request_id.is_some(), "no request id in: {api:?}");
+        }
+        other => panic!("expected an api error, got: {other}"),
+    }
+
+    // So does an auth failure from a client without the key.
+    let anonymous = CalculatorClient::new(&base);
+    match anonymous.add(1, 1).await.unwrap_err() {
+        ClientError::Api(api) => {
+            assert_eq!(api.code, "missing_api_key");
+            assert_eq!(api.status, 401);
+        }
+        other => panic!("expected an api error, got: {other}"),
+    }
+
+    // Batches keep request order, item failures inside the Vec.
+    let results = client
+        .batch(&[(Operation::Add, 1, 2), (Operation::Div, 1, 0)])
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(matches!(results[0], BatchItemResponse::Ok { res: 3 }));
+    match &results[1] {
+        BatchItemResponse::Err { error } => assert_eq!(error.code, "divide_by_zero"),
+        other => panic!("expected an item error, got: {other:?}"),
+    }
+}
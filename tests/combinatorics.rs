@@ -0,0 +1,68 @@
+#![cfg(feature = "sentry")]
+
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+mod common;
+
+#[actix_web::test]
+async fn factorial_and_choose_overflow_cleanly() {
+    let events = common::bind_recording_client();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/factorial")
+        .set_json(serde_json::json!({ "x": 5 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 120);
+
+    // 34! does not fit i64, so it travels as a string.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/factorial")
+        .set_json(serde_json::json!({ "x": 34 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], "295232799039604140847618609643520000000");
+
+    // Past the representable range: a 422 naming the largest input.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/factorial")
+        .set_json(serde_json::json!({ "x": 35 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "overflow");
+    assert_eq!(
+        body["error"]["message"],
+        "factorial result overflows u128; the largest representable input is 34"
+    );
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/choose")
+        .set_json(serde_json::json!({ "n": 52, "k": 5 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 2_598_960);
+
+    // Negative inputs are the caller's mistake: a 400.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/choose")
+        .set_json(serde_json::json!({ "n": 5, "k": -1 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "negative_input");
+
+    // Overflows and bad inputs are client errors end to end: the error
+    // pipeline must not capture a single sentry event for them.
+    assert!(common::recorded_events(&events).is_empty());
+}
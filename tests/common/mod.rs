@@ -0,0 +1,110 @@
+// Shared test helpers; not every test binary uses every one.
+#![allow(dead_code)]
+
+#[cfg(feature = "sentry")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "sentry")]
+use sentry::Envelope;
+use sentry_rs_demo::health::Readiness;
+
+/// Marks every startup check as passed, since test services are never
+/// started through main(). /status and /readyz report ready afterwards.
+pub fn mark_ready() {
+    let readiness = Readiness::global();
+    readiness.mark_config_loaded();
+    readiness.mark_tracing_initialized();
+    readiness.mark_server_bound();
+}
+
+/// A transport that records outgoing envelopes instead of sending them.
+#[cfg(feature = "sentry")]
+pub struct RecordingTransport {
+    envelopes: Arc<Mutex<Vec<Envelope>>>,
+}
+
+#[cfg(feature = "sentry")]
+impl sentry::Transport for RecordingTransport {
+    fn send_envelope(&self, envelope: Envelope) {
+        self.envelopes.lock().unwrap().push(envelope);
+    }
+}
+
+/// Binds a client built from the production options — before_send and
+/// all — to the current hub, swapping only the transport. Returns the
+/// recorded envelopes.
+#[cfg(feature = "sentry")]
+pub fn bind_recording_client() -> Arc<Mutex<Vec<Envelope>>> {
+    bind_recording_client_with_traces(0.0)
+}
+
+/// Like bind_recording_client, but with a non-zero traces sample rate so
+/// performance transactions get recorded too.
+#[cfg(feature = "sentry")]
+pub fn bind_recording_client_with_traces(traces_sample_rate: f32) -> Arc<Mutex<Vec<Envelope>>> {
+    let envelopes = Arc::new(Mutex::new(Vec::new()));
+    let transport = RecordingTransport {
+        envelopes: envelopes.clone(),
+    };
+
+    let mut options = sentry_rs_demo::telemetry::client_options(
+        Some("https://public@example.com/1".parse().unwrap()),
+        traces_sample_rate,
+    );
+    options.transport = Some(Arc::new(Arc::new(transport)));
+    sentry::Hub::current().bind_client(Some(Arc::new(sentry::Client::from(options))));
+
+    envelopes
+}
+
+/// The events inside the recorded envelopes, in capture order.
+#[cfg(feature = "sentry")]
+pub fn recorded_events(
+    envelopes: &Arc<Mutex<Vec<Envelope>>>,
+) -> Vec<sentry::protocol::Event<'static>> {
+    envelopes
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|envelope| envelope.event().cloned())
+        .collect()
+}
+
+/// The monitor check-ins inside the recorded envelopes, in send order.
+#[cfg(feature = "sentry")]
+pub fn recorded_check_ins(
+    envelopes: &Arc<Mutex<Vec<Envelope>>>,
+) -> Vec<sentry::protocol::MonitorCheckIn> {
+    envelopes
+        .lock()
+        .unwrap()
+        .iter()
+        .flat_map(|envelope| {
+            envelope.items().filter_map(|item| match item {
+                sentry::protocol::EnvelopeItem::MonitorCheckIn(check_in) => Some(check_in.clone()),
+                _ => None,
+            })
+        })
+        .collect()
+}
+
+/// The performance transactions inside the recorded envelopes, in
+/// capture order.
+#[cfg(feature = "sentry")]
+pub fn recorded_transactions(
+    envelopes: &Arc<Mutex<Vec<Envelope>>>,
+) -> Vec<sentry::protocol::Transaction<'static>> {
+    envelopes
+        .lock()
+        .unwrap()
+        .iter()
+        .flat_map(|envelope| {
+            envelope.items().filter_map(|item| match item {
+                sentry::protocol::EnvelopeItem::Transaction(transaction) => {
+                    Some(transaction.clone())
+                }
+                _ => None,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,119 @@
+use std::io::{Read, Write};
+
+use actix_web::test;
+use sentry_rs_demo::create_app;
+
+mod common;
+
+/// One binary per knob configuration: Config is process-global, so every
+/// test here runs with the same thresholds.
+fn configure() {
+    std::env::set_var("COMPRESSION_MIN_BYTES", "256");
+    std::env::set_var("REQUEST_DECOMPRESS_MAX_BYTES", "4096");
+}
+
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(bytes).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn gunzip(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    flate2::read::GzDecoder::new(bytes)
+        .read_to_end(&mut out)
+        .unwrap();
+    out
+}
+
+#[actix_web::test]
+async fn a_gzipped_batch_round_trips() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let items: Vec<serde_json::Value> = (0..32)
+        .map(|i| serde_json::json!({"op": "add", "x": i, "y": i}))
+        .collect();
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/batch")
+        .insert_header(("content-type", "application/json"))
+        .insert_header(("content-encoding", "gzip"))
+        .insert_header(("accept-encoding", "gzip, deflate"))
+        .set_payload(gzip(&serde_json::to_vec(&items).unwrap()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    assert_eq!(resp.headers().get("content-encoding").unwrap(), "gzip");
+    // Appended alongside the Vary values CORS already set.
+    assert!(resp
+        .headers()
+        .get_all("vary")
+        .any(|value| value == "accept-encoding"));
+
+    let body = test::read_body(resp).await;
+    let json: serde_json::Value = serde_json::from_slice(&gunzip(&body)).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 32);
+    assert_eq!(json[5]["res"], 10);
+}
+
+#[actix_web::test]
+async fn small_responses_go_out_unencoded() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::get()
+        .uri("/api/v0/status")
+        .insert_header(("accept-encoding", "gzip, deflate, br"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    assert!(resp.headers().get("content-encoding").is_none());
+}
+
+#[actix_web::test]
+async fn oversized_decompressed_bodies_are_rejected() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    // Compresses to a few dozen bytes but inflates far past the cap.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/batch")
+        .insert_header(("content-type", "application/json"))
+        .insert_header(("content-encoding", "gzip"))
+        .set_payload(gzip(&vec![b' '; 100_000]))
+        .to_request();
+    let resp = match test::try_call_service(&app, req).await {
+        Ok(_) => panic!("the zip bomb got through"),
+        Err(err) => err.error_response(),
+    };
+    assert_eq!(
+        resp.status(),
+        actix_web::http::StatusCode::PAYLOAD_TOO_LARGE
+    );
+    let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["error"]["code"], "decompressed_too_large");
+}
+
+#[actix_web::test]
+async fn garbage_gzip_is_a_client_error() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/batch")
+        .insert_header(("content-type", "application/json"))
+        .insert_header(("content-encoding", "gzip"))
+        .set_payload("definitely not a gzip stream")
+        .to_request();
+    let resp = match test::try_call_service(&app, req).await {
+        Ok(_) => panic!("garbage decoded somehow"),
+        Err(err) => err.error_response(),
+    };
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+}
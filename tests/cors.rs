@@ -0,0 +1,50 @@
+use actix_web::{
+    http::{header, Method},
+    test,
+};
+use sentry_rs_demo::create_app;
+
+// This binary gets its own process, so the policy is set before the
+// process-wide Config is first read.
+fn configure_cors() {
+    std::env::set_var("APP_CORS_ORIGINS", "http://app.example.com");
+    std::env::set_var("APP_CORS_METHODS", "GET,POST");
+}
+
+#[actix_web::test]
+async fn preflight_allows_only_configured_origins() {
+    configure_cors();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::default()
+        .method(Method::OPTIONS)
+        .uri("/api/v0/add")
+        .insert_header((header::ORIGIN, "http://app.example.com"))
+        .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(
+        resp.headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .and_then(|value| value.to_str().ok()),
+        Some("http://app.example.com")
+    );
+}
+
+#[actix_web::test]
+async fn preflight_denies_unknown_origins() {
+    configure_cors();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::default()
+        .method(Method::OPTIONS)
+        .uri("/api/v0/add")
+        .insert_header((header::ORIGIN, "http://evil.example.com"))
+        .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp
+        .headers()
+        .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        .is_none());
+}
@@ -0,0 +1,55 @@
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+// DATABASE_URL defaults to sqlite::memory:, so this binary exercises the
+// whole persistence path against a throwaway database. One sequential
+// test keeps the row ids deterministic.
+#[actix_web::test]
+async fn calculations_are_persisted_and_fetchable_by_id() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": 2, "y": 3 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/div")
+        .set_json(serde_json::json!({ "x": 1, "y": 0 }))
+        .to_request();
+    test::call_service(&app, req).await;
+
+    // The first row is the successful add.
+    let req = test::TestRequest::get()
+        .uri("/api/v0/history/1")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["op"], "add");
+    assert_eq!(body["x"], 2);
+    assert_eq!(body["y"], 3);
+    assert_eq!(body["res"], 5);
+
+    // The second is the failure, recorded with its error code.
+    let req = test::TestRequest::get()
+        .uri("/api/v0/history/2")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["op"], "div");
+    assert_eq!(body["error"], "divide_by_zero");
+    assert!(body.get("res").is_none());
+
+    // Missing ids are a structured 404.
+    let req = test::TestRequest::get()
+        .uri("/api/v0/history/999999")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "history_not_found");
+}
@@ -0,0 +1,29 @@
+#![cfg(feature = "sentry")]
+
+use actix_web::test;
+use sentry_rs_demo::create_app;
+
+mod common;
+
+// Its own binary: the dedup window comes from Config, which caches the
+// environment on first access process-wide.
+#[actix_web::test]
+async fn duplicate_events_within_the_window_are_sent_once() {
+    // Before the first Config::global() call, which reads it.
+    std::env::set_var("SENTRY_DEDUP_WINDOW_SECS", "60");
+
+    let events = common::bind_recording_client();
+    let app = test::init_service(create_app()).await;
+
+    for _ in 0..3 {
+        let req = test::TestRequest::get().uri("/debug/panic").to_request();
+        let _ = test::try_call_service(&app, req).await;
+    }
+
+    let captured = common::recorded_events(&events);
+    let panic_events = captured
+        .iter()
+        .filter(|event| event.tags.get("code").map(String::as_str) == Some("handler_panic"))
+        .count();
+    assert_eq!(panic_events, 1, "duplicates should be suppressed");
+}
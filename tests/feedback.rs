@@ -0,0 +1,96 @@
+#![cfg(feature = "sentry")]
+
+use std::sync::Arc;
+
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+mod common;
+
+fn bind_test_transport() -> Arc<sentry::test::TestTransport> {
+    let transport = sentry::test::TestTransport::new();
+    let options = sentry::ClientOptions {
+        dsn: Some("https://public@example.com/1".parse().unwrap()),
+        transport: Some(Arc::new(transport.clone())),
+        ..Default::default()
+    };
+    sentry::Hub::current().bind_client(Some(Arc::new(options.into())));
+    transport
+}
+
+/// One sequential test: the issued-id set is process-global, and the
+/// feedback half needs the id the error half produced.
+#[actix_web::test]
+async fn server_errors_quote_an_event_id_and_feedback_attaches_to_it() {
+    let transport = bind_test_transport();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    // A 500 carries the sentry event id in the body and a header.
+    let req = test::TestRequest::get().uri("/debug/panic").to_request();
+    let resp = match test::try_call_service(&app, req).await {
+        Ok(_) => panic!("the panic did not surface as an error"),
+        Err(err) => err.error_response(),
+    };
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let header_id = resp
+        .headers()
+        .get("x-sentry-event-id")
+        .expect("no x-sentry-event-id header")
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["error"]["event_id"], header_id);
+
+    // And it is the id of the event that actually went to sentry.
+    let events = transport.fetch_and_clear_events();
+    let captured = events
+        .iter()
+        .find(|e| e.tags.get("code").map(String::as_str) == Some("handler_panic"))
+        .expect("no handler_panic event captured");
+    assert_eq!(captured.event_id.simple().to_string(), header_id);
+
+    // Feedback quoting that id becomes a feedback event linked to it.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/feedback")
+        .set_json(serde_json::json!({
+            "event_id": header_id,
+            "name": "Ada",
+            "email": "ada@example.com",
+            "comments": "the page just said error",
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+
+    let events = transport.fetch_and_clear_events();
+    let feedback = events
+        .iter()
+        .find_map(|e| match e.contexts.get("feedback") {
+            Some(sentry::protocol::Context::Other(map)) => Some(map),
+            _ => None,
+        })
+        .expect("no feedback event captured");
+    assert_eq!(feedback["associated_event_id"], header_id);
+    assert_eq!(feedback["name"], "Ada");
+    assert_eq!(feedback["contact_email"], "ada@example.com");
+    assert_eq!(feedback["message"], "the page just said error");
+
+    // An id the server never issued is refused, and nothing is sent.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/feedback")
+        .set_json(serde_json::json!({
+            "event_id": "00000000000000000000000000000000",
+            "name": "Mallory",
+            "email": "m@example.com",
+            "comments": "attach me to somebody else's event",
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let json: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(json["error"]["code"], "unknown_event_id");
+    assert!(transport.fetch_and_clear_events().is_empty());
+}
@@ -0,0 +1,49 @@
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+#[actix_web::test]
+async fn form_and_json_encodings_work_on_every_calculation_endpoint() {
+    let app = test::init_service(create_app()).await;
+
+    for (path, expected) in [
+        ("/api/v0/add", 14),
+        ("/api/v0/sub", 10),
+        ("/api/v0/mul", 24),
+        ("/api/v0/div", 6),
+    ] {
+        let req = test::TestRequest::post()
+            .uri(path)
+            .insert_header(("content-type", "application/x-www-form-urlencoded"))
+            .set_payload("x=12&y=2")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK, "form post to {path}");
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["res"], expected, "form post to {path}");
+
+        let req = test::TestRequest::post()
+            .uri(path)
+            .set_json(serde_json::json!({ "x": 12, "y": 2 }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK, "json post to {path}");
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["res"], expected, "json post to {path}");
+    }
+}
+
+#[actix_web::test]
+async fn malformed_form_data_matches_the_json_error_shape() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .insert_header(("content-type", "application/x-www-form-urlencoded"))
+        .set_payload("x=twelve&y=2")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "invalid_request_body");
+    assert_eq!(body["error"]["status"], 400);
+}
@@ -0,0 +1,76 @@
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::{create_app, health::Readiness};
+
+// One sequential test: the readiness flags are process-wide, so asserting
+// the not-ready, ready and shutting-down states in order avoids races
+// between concurrently running test functions.
+#[actix_web::test]
+async fn readiness_follows_startup_and_shutdown() {
+    let app = test::init_service(create_app()).await;
+
+    // Liveness needs nothing but a running event loop.
+    let req = test::TestRequest::get().uri("/healthz").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // Nothing has been marked yet: not ready, and the body says why.
+    let req = test::TestRequest::get().uri("/readyz").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["status"], "not_ready");
+    let failed = body["failed"].as_array().unwrap();
+    assert!(failed.contains(&serde_json::json!("server_bound")));
+
+    // /status is an alias for readiness.
+    let req = test::TestRequest::get().uri("/api/v0/status").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let readiness = Readiness::global();
+    readiness.mark_config_loaded();
+    readiness.mark_tracing_initialized();
+    readiness.mark_server_bound();
+
+    let req = test::TestRequest::get().uri("/readyz").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["status"], "ready");
+
+    let req = test::TestRequest::get().uri("/api/v0/status").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // An unreachable sentry host degrades to a warning (SENTRY_REQUIRED
+    // is off here), so readiness holds but the body says what is wrong.
+    readiness.set_sentry_reachable(false);
+    let req = test::TestRequest::get().uri("/readyz").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["status"], "ready");
+    assert_eq!(body["warnings"], serde_json::json!(["sentry"]));
+    readiness.set_sentry_reachable(true);
+
+    let req = test::TestRequest::get().uri("/readyz").to_request();
+    let resp = test::call_service(&app, req).await;
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body.get("warnings").is_none());
+
+    // Graceful shutdown drains: readiness fails, liveness holds.
+    readiness.begin_shutdown();
+
+    let req = test::TestRequest::get().uri("/readyz").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body["failed"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!("shutting_down")));
+
+    let req = test::TestRequest::get().uri("/healthz").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
@@ -0,0 +1,80 @@
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+// The history is process-wide, so one sequential test exercises the
+// whole lifecycle without races between test functions.
+#[actix_web::test]
+async fn history_records_filters_and_evicts() {
+    std::env::set_var("APP_HISTORY_CAPACITY", "5");
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": 2, "y": 3 }))
+        .to_request();
+    test::call_service(&app, req).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/div")
+        .set_json(serde_json::json!({ "x": 1, "y": 0 }))
+        .to_request();
+    test::call_service(&app, req).await;
+
+    // Both the success and the failure are on record, newest first.
+    let req = test::TestRequest::get().uri("/api/v0/history").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let entries = body.as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["op"], "div");
+    assert_eq!(entries[0]["error"], "divide_by_zero");
+    assert!(entries[0].get("res").is_none());
+    assert_eq!(entries[1]["op"], "add");
+    assert_eq!(entries[1]["res"], 5);
+    assert!(entries[1]["timestamp"].as_u64().is_some());
+
+    // ?op= filters and ?limit= truncates.
+    let req = test::TestRequest::get()
+        .uri("/api/v0/history?op=add&limit=1")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let entries = body.as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["op"], "add");
+
+    // An unknown op filter is a structured 400, not an empty match.
+    let req = test::TestRequest::get()
+        .uri("/api/v0/history?op=nope")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // Capacity 5: seven more calculations evict the oldest entries.
+    for i in 0..7 {
+        let req = test::TestRequest::post()
+            .uri("/api/v0/add")
+            .set_json(serde_json::json!({ "x": i, "y": 1 }))
+            .to_request();
+        test::call_service(&app, req).await;
+    }
+    let req = test::TestRequest::get().uri("/api/v0/history").to_request();
+    let resp = test::call_service(&app, req).await;
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let entries = body.as_array().unwrap();
+    assert_eq!(entries.len(), 5);
+    assert!(entries.iter().all(|entry| entry["op"] == "add"));
+
+    // DELETE wipes the slate.
+    let req = test::TestRequest::delete()
+        .uri("/api/v0/history")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let req = test::TestRequest::get().uri("/api/v0/history").to_request();
+    let resp = test::call_service(&app, req).await;
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body.as_array().unwrap().is_empty());
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "sentry")]
+
+use std::time::Duration;
+
+use sentry::protocol::MonitorCheckInStatus;
+use sentry_rs_demo::error::Error;
+use sentry_rs_demo::housekeeping::{self, DefaultHousekeeping, Housekeeping};
+
+mod common;
+
+struct FailingHousekeeping;
+
+impl Housekeeping for FailingHousekeeping {
+    fn run(&self) -> sentry_rs_demo::Result<()> {
+        Err(Error::Housekeeping("disk on fire".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn a_run_is_bracketed_by_in_progress_and_ok_check_ins() {
+    let envelopes = common::bind_recording_client();
+
+    housekeeping::run_once(
+        &DefaultHousekeeping,
+        "housekeeping",
+        Duration::from_secs(300),
+    );
+
+    let check_ins = common::recorded_check_ins(&envelopes);
+    assert_eq!(check_ins.len(), 2);
+    assert_eq!(check_ins[0].status, MonitorCheckInStatus::InProgress);
+    assert_eq!(check_ins[0].monitor_slug, "housekeeping");
+    assert!(
+        check_ins[0].monitor_config.is_some(),
+        "first check-in upserts"
+    );
+    assert_eq!(check_ins[1].status, MonitorCheckInStatus::Ok);
+    assert_eq!(check_ins[1].check_in_id, check_ins[0].check_in_id);
+    assert!(check_ins[1].duration.is_some());
+
+    assert!(common::recorded_events(&envelopes).is_empty());
+}
+
+#[tokio::test]
+async fn a_failing_run_emits_an_error_check_in_and_a_tagged_event() {
+    let envelopes = common::bind_recording_client();
+
+    housekeeping::run_once(
+        &FailingHousekeeping,
+        "housekeeping",
+        Duration::from_secs(300),
+    );
+
+    let check_ins = common::recorded_check_ins(&envelopes);
+    assert_eq!(check_ins.len(), 2);
+    assert_eq!(check_ins[1].status, MonitorCheckInStatus::Error);
+    assert!(check_ins[1].duration.is_some());
+
+    let events = common::recorded_events(&envelopes);
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0].tags.get("task").map(String::as_str),
+        Some("housekeeping")
+    );
+}
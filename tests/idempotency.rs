@@ -0,0 +1,84 @@
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+// One sequential test: History::global() is shared process-wide, and the
+// duplicate-suppression assertion counts its entries.
+#[actix_web::test]
+async fn replays_cached_responses_for_the_same_key_and_body() {
+    let app = test::init_service(create_app()).await;
+
+    // First request with the key: computed normally, no replay header.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .insert_header(("idempotency-key", "retry-1"))
+        .set_json(serde_json::json!({ "x": 2, "y": 3 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get("idempotency-replayed").is_none());
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 5);
+
+    // The retry: same answer, served from the cache.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .insert_header(("idempotency-key", "retry-1"))
+        .set_json(serde_json::json!({ "x": 2, "y": 3 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("idempotency-replayed").unwrap(), "true");
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 5);
+
+    // The replay never reached the handler, so the history holds exactly
+    // one entry.
+    let req = test::TestRequest::get().uri("/api/v0/history").to_request();
+    let resp = test::call_service(&app, req).await;
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body.as_array().unwrap().len(), 1);
+
+    // The same key with a different body is key reuse, not a retry.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .insert_header(("idempotency-key", "retry-1"))
+        .set_json(serde_json::json!({ "x": 9, "y": 9 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "idempotency_mismatch");
+
+    // Error responses are cached too: the retried failure replays.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/div")
+        .insert_header(("idempotency-key", "retry-2"))
+        .set_json(serde_json::json!({ "x": 1, "y": 0 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/div")
+        .insert_header(("idempotency-key", "retry-2"))
+        .set_json(serde_json::json!({ "x": 1, "y": 0 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(resp.headers().get("idempotency-replayed").unwrap(), "true");
+
+    // Without the header nothing is cached: both calls compute.
+    let history_len = |body: serde_json::Value| body.as_array().unwrap().len();
+    for _ in 0..2 {
+        let req = test::TestRequest::post()
+            .uri("/api/v0/mul")
+            .set_json(serde_json::json!({ "x": 2, "y": 2 }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+    let req = test::TestRequest::get().uri("/api/v0/history").to_request();
+    let resp = test::call_service(&app, req).await;
+    // add (1) + div (1) + mul (2); the replays added nothing.
+    assert_eq!(history_len(test::read_body_json(resp).await), 4);
+}
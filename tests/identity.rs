@@ -0,0 +1,58 @@
+#![cfg(feature = "sentry")]
+
+use actix_web::test;
+use sentry_rs_demo::create_app;
+
+mod common;
+
+// Only server errors get captured, so /debug/panic is the one
+// HTTP-reachable route that produces an event to inspect.
+#[actix_web::test]
+async fn an_x_user_id_header_becomes_the_sentry_user() {
+    let events = common::bind_recording_client();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::get()
+        .uri("/debug/panic")
+        .insert_header(("x-user-id", "client-42"))
+        .to_request();
+    let _ = test::try_call_service(&app, req).await;
+
+    let captured = common::recorded_events(&events);
+    let event = captured
+        .iter()
+        .find(|event| event.tags.get("code").map(String::as_str) == Some("handler_panic"))
+        .expect("no handler_panic event captured");
+
+    let user = event.user.as_ref().expect("event has no user");
+    assert_eq!(user.id.as_deref(), Some("client-42"));
+}
+
+#[actix_web::test]
+async fn unidentified_clients_get_a_stable_anonymous_id() {
+    let events = common::bind_recording_client();
+    let app = test::init_service(create_app()).await;
+
+    for _ in 0..2 {
+        let req = test::TestRequest::get().uri("/debug/panic").to_request();
+        let _ = test::try_call_service(&app, req).await;
+    }
+
+    let captured = common::recorded_events(&events);
+    let ids: Vec<&str> = captured
+        .iter()
+        .filter(|event| event.tags.get("code").map(String::as_str) == Some("handler_panic"))
+        .map(|event| {
+            event
+                .user
+                .as_ref()
+                .and_then(|user| user.id.as_deref())
+                .expect("event has no anonymous user id")
+        })
+        .collect();
+
+    assert_eq!(ids.len(), 2);
+    assert!(ids[0].starts_with("anon:"), "unexpected id: {}", ids[0]);
+    // The hash is salted but deterministic, so one client counts once.
+    assert_eq!(ids[0], ids[1]);
+}
@@ -0,0 +1,143 @@
+#![cfg(feature = "sentry")]
+
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+mod common;
+
+/// Polls a job until it reaches a terminal state, or fails the test.
+async fn poll_until_terminal(
+    app: &impl actix_web::dev::Service<
+        actix_http::Request,
+        Response = actix_web::dev::ServiceResponse<impl actix_web::body::MessageBody>,
+        Error = actix_web::Error,
+    >,
+    id: &str,
+) -> serde_json::Value {
+    for _ in 0..100 {
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/v0/jobs/{id}"))
+            .to_request();
+        let resp = test::call_service(app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        match body["status"].as_str() {
+            Some("queued") | Some("running") => {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await
+            }
+            _ => return body,
+        }
+    }
+    panic!("job {id} never reached a terminal state");
+}
+
+#[actix_web::test]
+async fn jobs_run_to_completion_and_failures_are_tagged() {
+    let events = common::bind_recording_client();
+    let app = test::init_service(create_app()).await;
+
+    // Enqueue: 202, a job id, and a Location header to poll.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/jobs")
+        .set_json(serde_json::json!({ "op": "eval", "expr": "2 * (3 + 4)" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+    let location = resp
+        .headers()
+        .get("location")
+        .and_then(|v| v.to_str().ok())
+        .expect("202 must carry a Location header")
+        .to_string();
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let id = body["id"].as_str().expect("job id").to_string();
+    assert_eq!(location, format!("/api/v0/jobs/{id}"));
+
+    let done = poll_until_terminal(&app, &id).await;
+    assert_eq!(done["status"], "done");
+    assert_eq!(done["result"]["res"], 14);
+
+    // A failing job: the poller sees the error, and sentry gets one
+    // event tagged with the job id.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/jobs")
+        .set_json(serde_json::json!({ "op": "eval", "expr": "1 / 0" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let failed_id = body["id"].as_str().expect("job id").to_string();
+
+    let failed = poll_until_terminal(&app, &failed_id).await;
+    assert_eq!(failed["status"], "failed");
+    assert_eq!(failed["error"]["code"], "divide_by_zero");
+
+    let tagged: Vec<_> = common::recorded_events(&events)
+        .into_iter()
+        .filter(|e| e.tags.get("job_id").map(String::as_str) == Some(failed_id.as_str()))
+        .collect();
+    assert_eq!(tagged.len(), 1, "exactly one capture for the failed job");
+}
+
+#[actix_web::test]
+async fn batch_jobs_fold_item_errors_into_the_result() {
+    common::bind_recording_client();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/jobs")
+        .set_json(serde_json::json!({
+            "op": "batch",
+            "items": [
+                { "op": "add", "x": 1, "y": 2 },
+                { "op": "div", "x": 1, "y": 0 },
+            ],
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let id = body["id"].as_str().expect("job id").to_string();
+
+    // Item failures mirror /batch: the job itself succeeds.
+    let done = poll_until_terminal(&app, &id).await;
+    assert_eq!(done["status"], "done");
+    assert_eq!(done["result"][0]["res"], 3);
+    assert_eq!(done["result"][1]["error"]["code"], "divide_by_zero");
+}
+
+#[actix_web::test]
+async fn unknown_jobs_are_404_and_finished_jobs_survive_cancellation() {
+    common::bind_recording_client();
+    let app = test::init_service(create_app()).await;
+
+    for method in [test::TestRequest::get, test::TestRequest::delete] {
+        let req = method().uri("/api/v0/jobs/no-such-job").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "job_not_found");
+    }
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/jobs")
+        .set_json(serde_json::json!({ "op": "eval", "expr": "1 + 1" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let id = body["id"].as_str().expect("job id").to_string();
+
+    let done = poll_until_terminal(&app, &id).await;
+    assert_eq!(done["status"], "done");
+
+    // Cancelling a job that already finished leaves its state alone.
+    let req = test::TestRequest::delete()
+        .uri(&format!("/api/v0/jobs/{id}"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["status"], "done");
+    assert_eq!(body["result"]["res"], 2);
+}
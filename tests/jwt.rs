@@ -0,0 +1,128 @@
+use actix_web::test;
+use sentry_rs_demo::create_app;
+
+mod common;
+
+/// One binary per knob configuration: every test here runs with HS256
+/// verification, a required audience and a required sub claim.
+fn configure() {
+    std::env::set_var("JWT_HS256_SECRET", "integration-secret");
+    std::env::set_var("JWT_AUDIENCE", "calc-clients");
+    std::env::set_var("JWT_REQUIRED_CLAIMS", "sub");
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn token(secret: &str, claims: serde_json::Value) -> String {
+    jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &claims,
+        &jsonwebtoken::EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .unwrap()
+}
+
+fn add_request(bearer: &str) -> actix_http::Request {
+    test::TestRequest::post()
+        .uri("/api/v0/add")
+        .insert_header(("authorization", format!("Bearer {bearer}")))
+        .set_json(serde_json::json!({"x": 2, "y": 3}))
+        .to_request()
+}
+
+async fn error_code(
+    resp: actix_web::dev::ServiceResponse<impl actix_web::body::MessageBody>,
+) -> String {
+    assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+    let body = test::read_body(resp).await;
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    json["error"]["code"].as_str().unwrap().to_owned()
+}
+
+#[actix_web::test]
+async fn a_valid_token_is_served() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let bearer = token(
+        "integration-secret",
+        serde_json::json!({"sub": "robot-7", "aud": "calc-clients", "exp": now() + 600}),
+    );
+    let resp = test::call_service(&app, add_request(&bearer)).await;
+    assert!(resp.status().is_success());
+    let json: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(json["res"], 5);
+}
+
+#[actix_web::test]
+async fn requests_without_a_token_are_rejected() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({"x": 2, "y": 3}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(error_code(resp).await, "missing_jwt");
+}
+
+#[actix_web::test]
+async fn expired_tokens_are_rejected() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let bearer = token(
+        "integration-secret",
+        serde_json::json!({"sub": "robot-7", "aud": "calc-clients", "exp": now() - 10_000}),
+    );
+    let resp = test::call_service(&app, add_request(&bearer)).await;
+    assert_eq!(error_code(resp).await, "invalid_jwt");
+}
+
+#[actix_web::test]
+async fn the_wrong_audience_is_rejected() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let bearer = token(
+        "integration-secret",
+        serde_json::json!({"sub": "robot-7", "aud": "someone-else", "exp": now() + 600}),
+    );
+    let resp = test::call_service(&app, add_request(&bearer)).await;
+    assert_eq!(error_code(resp).await, "invalid_jwt");
+}
+
+#[actix_web::test]
+async fn tokens_signed_with_the_wrong_key_are_rejected() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let bearer = token(
+        "not-the-secret",
+        serde_json::json!({"sub": "robot-7", "aud": "calc-clients", "exp": now() + 600}),
+    );
+    let resp = test::call_service(&app, add_request(&bearer)).await;
+    assert_eq!(error_code(resp).await, "invalid_jwt");
+}
+
+#[actix_web::test]
+async fn status_stays_open() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::get().uri("/api/v0/status").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+}
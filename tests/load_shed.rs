@@ -0,0 +1,83 @@
+#![cfg(feature = "sentry")]
+
+use std::time::Duration;
+
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+mod common;
+
+// A single sequential test in its own binary: the limiter knobs come
+// from Config, which caches the environment on first access.
+#[actix_web::test]
+async fn excess_requests_are_shed_with_503_and_probes_stay_up() {
+    // Before the first Config::global() call, which reads them.
+    std::env::set_var("APP_MAX_IN_FLIGHT", "1");
+    std::env::set_var("APP_SHED_WAIT_MS", "50");
+
+    common::mark_ready();
+    let events = common::bind_recording_client();
+    let app = test::init_service(create_app()).await;
+
+    // Fill the single slot with a slow request, then send another while
+    // it is still running.
+    let slow = async {
+        let req = test::TestRequest::get()
+            .uri("/debug/sleep/500")
+            .to_request();
+        test::call_service(&app, req).await
+    };
+    let shed = async {
+        // Let the slow request take the permit first.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/v0/add")
+            .set_json(serde_json::json!({ "x": 1, "y": 2 }))
+            .to_request();
+        test::call_service(&app, req).await
+    };
+    let probe = async {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let req = test::TestRequest::get().uri("/healthz").to_request();
+        test::call_service(&app, req).await
+    };
+    let (slow_resp, shed_resp, probe_resp) = tokio::join!(slow, shed, probe);
+
+    assert_eq!(slow_resp.status(), StatusCode::OK);
+    assert_eq!(probe_resp.status(), StatusCode::OK, "probes are exempt");
+
+    assert_eq!(shed_resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        shed_resp.headers().get("retry-after").unwrap(),
+        &"1".parse::<actix_web::http::header::HeaderValue>().unwrap()
+    );
+    let body: serde_json::Value = test::read_body_json(shed_resp).await;
+    assert_eq!(body["error"]["code"], "overloaded");
+    assert_eq!(body["error"]["status"], 503);
+
+    // Shedding is expected under load, not an incident.
+    assert!(common::recorded_events(&events).is_empty());
+
+    // The counters surface through /stats and /metrics. The snapshot
+    // counts the /stats request itself as in flight.
+    let req = test::TestRequest::get().uri("/api/v0/stats").to_request();
+    let stats: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(stats["in_flight"], 1);
+    assert_eq!(stats["shed_total"], 1);
+
+    let req = test::TestRequest::get().uri("/metrics").to_request();
+    let metrics = test::call_and_read_body(&app, req).await;
+    let metrics = String::from_utf8(metrics.to_vec()).unwrap();
+    assert!(metrics.contains("http_requests_shed_total"));
+    assert!(metrics.contains("http_in_flight_requests"));
+
+    // With the slot free again, requests go straight through.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": 1, "y": 2 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
@@ -0,0 +1,106 @@
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+struct Request {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    res: i32,
+}
+
+#[actix_web::test]
+async fn msgpack_round_trip_on_calculation_endpoints() {
+    let app = test::init_service(create_app()).await;
+
+    let body = rmp_serde::to_vec_named(&Request { x: 20, y: 22 }).unwrap();
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .insert_header(("content-type", "application/msgpack"))
+        .insert_header(("accept", "application/msgpack"))
+        .set_payload(body)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/msgpack"
+    );
+
+    let body = test::read_body(resp).await;
+    let decoded: Response = rmp_serde::from_slice(&body).unwrap();
+    assert_eq!(decoded.res, 42);
+}
+
+#[actix_web::test]
+async fn msgpack_request_with_json_response_and_vice_versa() {
+    let app = test::init_service(create_app()).await;
+
+    // msgpack in, JSON out: no Accept header means the JSON default.
+    let body = rmp_serde::to_vec_named(&Request { x: 2, y: 3 }).unwrap();
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .insert_header(("content-type", "application/x-msgpack"))
+        .set_payload(body)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 5);
+
+    // JSON in, msgpack out.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/mul")
+        .insert_header(("accept", "application/msgpack"))
+        .set_json(serde_json::json!({ "x": 6, "y": 7 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = test::read_body(resp).await;
+    let decoded: Response = rmp_serde::from_slice(&body).unwrap();
+    assert_eq!(decoded.res, 42);
+}
+
+#[actix_web::test]
+async fn unknown_content_type_is_a_structured_415() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .insert_header(("content-type", "text/plain"))
+        .set_payload("2 + 3")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "unsupported_media_type");
+    assert_eq!(body["error"]["status"], 415);
+}
+
+#[actix_web::test]
+async fn error_bodies_honour_the_accept_header() {
+    let app = test::init_service(create_app()).await;
+
+    let body = rmp_serde::to_vec_named(&Request { x: 1, y: 0 }).unwrap();
+    let req = test::TestRequest::post()
+        .uri("/api/v0/div")
+        .insert_header(("content-type", "application/msgpack"))
+        .insert_header(("accept", "application/msgpack"))
+        .set_payload(body)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/msgpack"
+    );
+
+    let body = test::read_body(resp).await;
+    let decoded: serde_json::Value = rmp_serde::from_slice(&body).unwrap();
+    assert_eq!(decoded["error"]["code"], "divide_by_zero");
+    assert_eq!(decoded["error"]["status"], 400);
+}
@@ -0,0 +1,36 @@
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+#[actix_web::test]
+async fn spec_documents_the_div_error_responses() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::get()
+        .uri("/api/v0/openapi.json")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let spec: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(spec["openapi"], "3.0.3");
+
+    let div = &spec["paths"]["/api/v0/div"]["post"];
+    assert!(div["responses"]["400"].is_object(), "div must document 400");
+    assert!(div["responses"]["422"].is_object(), "div must document 422");
+    assert!(div["responses"]["500"].is_object(), "div must document 500");
+    assert!(spec["components"]["schemas"]["ErrorBody"].is_object());
+}
+
+#[actix_web::test]
+async fn docs_page_embeds_swagger_ui() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::get().uri("/docs").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body = test::read_body(resp).await;
+    let html = String::from_utf8_lossy(&body);
+    assert!(html.contains("SwaggerUIBundle"));
+    assert!(html.contains("/api/v0/openapi.json"));
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "sentry")]
+
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+mod common;
+
+// One sequential test: the operand bounds are read once into the
+// process-global Config.
+#[actix_web::test]
+async fn out_of_policy_operands_are_rejected_with_422() {
+    // Before the first Config::global() call, which reads them.
+    std::env::set_var("OPERAND_MIN", "-1000000");
+    std::env::set_var("OPERAND_MAX", "1000000");
+
+    let events = common::bind_recording_client();
+    let app = test::init_service(create_app()).await;
+
+    // Within policy: unchanged behaviour.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": 1_000_000, "y": -1_000_000 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // An out-of-policy operand: 422 naming the offending field.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": 1, "y": 2_000_000_000 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "operand_out_of_range");
+    assert_eq!(
+        body["error"]["message"],
+        "y must be between -1000000 and 1000000, got 2000000000"
+    );
+
+    // The GET twins share the same policy.
+    let req = test::TestRequest::get()
+        .uri("/api/v0/mul?x=-2000000&y=3")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        body["error"]["message"],
+        "x must be between -1000000 and 1000000, got -2000000"
+    );
+
+    // Validation failures are the caller's fault, never sentry events.
+    assert!(common::recorded_events(&events).is_empty());
+}
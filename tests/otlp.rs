@@ -0,0 +1,37 @@
+use actix_web::test;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+use tracing_subscriber::layer::SubscriberExt;
+
+#[actix_web::test]
+async fn handler_spans_reach_the_otlp_exporter() {
+    // The in-memory exporter stands in for the collector; the layer under
+    // test is the same tracing-opentelemetry bridge init_tracing installs.
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let tracer = provider.tracer("test");
+    let subscriber =
+        tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let app = test::init_service(sentry_rs_demo::create_app()).await;
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": 20, "y": 22 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+
+    provider.force_flush().unwrap();
+    let spans = exporter.get_finished_spans().unwrap();
+    assert!(
+        spans.iter().any(|span| span.name.contains("handle_add")),
+        "no handler span among: {:?}",
+        spans
+            .iter()
+            .map(|span| span.name.clone())
+            .collect::<Vec<_>>()
+    );
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "sentry")]
+
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+mod common;
+
+#[actix_web::test]
+async fn handler_panics_become_structured_500s_and_sentry_events() {
+    let events = common::bind_recording_client();
+    let app = test::init_service(create_app()).await;
+
+    // The middleware turns the caught panic into an Err, which the real
+    // server renders via ResponseError; the test harness surfaces it as
+    // an error instead, so render it the same way here.
+    let req = test::TestRequest::get().uri("/debug/panic").to_request();
+    let resp = match test::try_call_service(&app, req).await {
+        Ok(_) => panic!("panicking handler should surface as an error"),
+        Err(err) => err.error_response(),
+    };
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(resp.headers().contains_key("x-request-id"));
+
+    let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["error"]["code"], "handler_panic");
+    assert_eq!(body["error"]["status"], 500);
+    assert!(body["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("deliberate panic"));
+    assert!(body["error"]["request_id"].is_string());
+
+    let captured = common::recorded_events(&events);
+    let panic_event = captured
+        .iter()
+        .find(|event| event.tags.get("handler_panic").map(String::as_str) == Some("true"))
+        .expect("no handler_panic event captured");
+    assert_eq!(
+        panic_event.tags.get("code").map(String::as_str),
+        Some("handler_panic")
+    );
+}
+
+#[actix_web::test]
+async fn events_carry_route_method_handler_and_environment() {
+    let events = common::bind_recording_client();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::get().uri("/debug/panic").to_request();
+    let _ = test::try_call_service(&app, req).await;
+
+    let captured = common::recorded_events(&events);
+    let event = captured
+        .iter()
+        .find(|event| event.tags.get("code").map(String::as_str) == Some("handler_panic"))
+        .expect("no handler_panic event captured");
+
+    assert_eq!(
+        event.tags.get("http.route").map(String::as_str),
+        Some("/debug/panic")
+    );
+    assert_eq!(
+        event.tags.get("http.method").map(String::as_str),
+        Some("GET")
+    );
+    assert_eq!(
+        event.tags.get("handler").map(String::as_str),
+        Some("debug_panic")
+    );
+    assert_eq!(event.environment.as_deref(), Some("development"));
+    assert_eq!(
+        event.server_name.as_deref(),
+        Some(sentry_rs_demo::config::Config::global().host.as_str())
+    );
+}
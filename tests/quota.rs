@@ -0,0 +1,83 @@
+use actix_web::test;
+use sentry_rs_demo::create_app;
+
+mod common;
+
+/// One binary per knob configuration: two API keys, a quota of 3 on the
+/// first. The counters live in the shared in-memory database, which like
+/// tests/db.rs is exercised from a single sequential test.
+fn configure() {
+    std::env::set_var("APP_API_KEYS", "alpha:key-a,beta:key-b");
+    std::env::set_var("QUOTA_LIMITS", "alpha:3");
+}
+
+fn add_request(api_key: &str) -> actix_http::Request {
+    test::TestRequest::post()
+        .uri("/api/v0/add")
+        .insert_header(("x-api-key", api_key))
+        .set_json(serde_json::json!({"x": 2, "y": 3}))
+        .to_request()
+}
+
+fn usage_request(api_key: &str) -> actix_http::Request {
+    test::TestRequest::get()
+        .uri("/api/v0/usage")
+        .insert_header(("x-api-key", api_key))
+        .to_request()
+}
+
+fn remaining(resp: &actix_web::dev::ServiceResponse<impl actix_web::body::MessageBody>) -> u64 {
+    resp.headers()
+        .get("x-quota-remaining")
+        .expect("no x-quota-remaining header")
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+#[actix_web::test]
+async fn quotas_are_enforced_and_reported() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    for expected_remaining in [2, 1, 0] {
+        let resp = test::call_service(&app, add_request("key-a")).await;
+        assert!(resp.status().is_success());
+        assert_eq!(remaining(&resp), expected_remaining);
+        assert!(resp.headers().contains_key("x-quota-reset"));
+    }
+
+    // The fourth request of the window is refused with its own code.
+    let resp = test::call_service(&app, add_request("key-a")).await;
+    assert_eq!(
+        resp.status(),
+        actix_web::http::StatusCode::TOO_MANY_REQUESTS
+    );
+    assert_eq!(remaining(&resp), 0);
+    let json: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(json["error"]["code"], "quota_exceeded");
+
+    // The usage endpoint reports the same numbers without consuming any.
+    let resp = test::call_service(&app, usage_request("key-a")).await;
+    assert!(resp.status().is_success());
+    let json: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(json["key"], "alpha");
+    assert_eq!(json["window"], "daily");
+    assert_eq!(json["limit"], 3);
+    assert_eq!(json["used"], 3);
+    assert_eq!(json["remaining"], 0);
+
+    // A key with no configured limit is neither counted nor decorated.
+    let resp = test::call_service(&app, add_request("key-b")).await;
+    assert!(resp.status().is_success());
+    assert!(resp.headers().get("x-quota-remaining").is_none());
+
+    let resp = test::call_service(&app, usage_request("key-b")).await;
+    assert!(resp.status().is_success());
+    let json: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(json["key"], "beta");
+    assert_eq!(json["used"], 0);
+    assert!(json.get("limit").is_none());
+}
@@ -0,0 +1,65 @@
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+mod common;
+
+// This binary gets its own process, so the limiter config is set before
+// the process-wide Config is first read.
+fn configure_limits() {
+    std::env::set_var("APP_RATE_LIMIT_RPS", "1");
+    std::env::set_var("APP_RATE_LIMIT_BURST", "2");
+    std::env::set_var("APP_TRUSTED_PROXY", "true");
+}
+
+#[actix_web::test]
+async fn requests_over_the_limit_get_429_with_retry_after() {
+    configure_limits();
+    let app = test::init_service(create_app()).await;
+
+    for _ in 0..2 {
+        let req = test::TestRequest::post()
+            .uri("/api/v0/add")
+            .insert_header(("x-forwarded-for", "10.0.0.1"))
+            .set_json(serde_json::json!({ "x": 1, "y": 2 }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .insert_header(("x-forwarded-for", "10.0.0.1"))
+        .set_json(serde_json::json!({ "x": 1, "y": 2 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(resp.headers().contains_key("retry-after"));
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "rate_limited");
+
+    // A different client IP has its own bucket and is not affected.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .insert_header(("x-forwarded-for", "10.0.0.2"))
+        .set_json(serde_json::json!({ "x": 1, "y": 2 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn status_probes_are_not_rate_limited() {
+    configure_limits();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    for _ in 0..5 {
+        let req = test::TestRequest::get()
+            .uri("/api/v0/status")
+            .insert_header(("x-forwarded-for", "10.0.0.9"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
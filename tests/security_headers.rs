@@ -0,0 +1,40 @@
+use actix_web::test;
+use sentry_rs_demo::create_app;
+
+mod common;
+
+#[actix_web::test]
+async fn standard_headers_are_present_on_api_responses() {
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get().uri("/api/v0/status").to_request(),
+    )
+    .await;
+    assert!(resp.status().is_success());
+    let headers = resp.headers();
+    assert_eq!(headers.get("x-content-type-options").unwrap(), "nosniff");
+    assert_eq!(headers.get("x-frame-options").unwrap(), "DENY");
+    assert_eq!(headers.get("referrer-policy").unwrap(), "no-referrer");
+    // No TLS in this configuration, so no HSTS; CSP is /docs-only.
+    assert!(headers.get("strict-transport-security").is_none());
+    assert!(headers.get("content-security-policy").is_none());
+}
+
+#[actix_web::test]
+async fn the_docs_page_gets_a_content_security_policy() {
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/docs").to_request()).await;
+    assert!(resp.status().is_success());
+    let csp = resp
+        .headers()
+        .get("content-security-policy")
+        .expect("no CSP on /docs")
+        .to_str()
+        .unwrap();
+    assert!(csp.contains("default-src 'self'"), "csp: {csp}");
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "sentry")]
+
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::{create_app, Error, HTTPError};
+
+mod common;
+
+#[actix_web::test]
+async fn before_send_filters_4xx_events() {
+    let envelopes = common::bind_recording_client();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/div")
+        .set_json(serde_json::json!({ "x": 1, "y": 0 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let events = common::recorded_events(&envelopes);
+    assert!(
+        !events
+            .iter()
+            .any(|e| e.tags.get("code").map(String::as_str) == Some("divide_by_zero")),
+        "the 400 event should never reach the transport: {events:?}"
+    );
+
+    // The conversion no longer captures client errors at all, so exercise
+    // the before_send safety net directly with a synthetic 4xx event.
+    sentry::with_scope(
+        |scope| scope.set_extra("status_code", 404.into()),
+        || sentry::capture_message("synthetic 4xx", sentry::Level::Error),
+    );
+    let events = common::recorded_events(&envelopes);
+    assert!(
+        !events
+            .iter()
+            .any(|e| e.message.as_deref() == Some("synthetic 4xx")),
+        "before_send should have dropped the synthetic 4xx: {events:?}"
+    );
+}
+
+#[actix_web::test]
+async fn five_hundreds_reach_the_transport_with_their_extras() {
+    let envelopes = common::bind_recording_client();
+
+    // Force a 500 through the same conversion every handler error takes.
+    let _ = HTTPError::from(Error::Metrics("boom".to_string()));
+
+    let events = common::recorded_events(&envelopes);
+    let event = events
+        .iter()
+        .find(|e| e.tags.get("code").map(String::as_str) == Some("metrics"))
+        .expect("no event captured for the forced 500");
+
+    assert_eq!(event.level, sentry::Level::Error);
+    assert_eq!(
+        event.extra.get("status_code").and_then(|v| v.as_u64()),
+        Some(500)
+    );
+    assert!(
+        event
+            .exception
+            .values
+            .iter()
+            .any(|exc| exc.value.as_deref().is_some_and(|v| v.contains("boom"))),
+        "expected the exception message to mention the source error: {event:?}"
+    );
+}
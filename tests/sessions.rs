@@ -0,0 +1,59 @@
+#![cfg(feature = "sentry")]
+
+use actix_web::test;
+use sentry_rs_demo::create_app;
+
+mod common;
+
+/// The session aggregate items inside the recorded envelopes.
+fn recorded_session_aggregates(
+    envelopes: &std::sync::Arc<std::sync::Mutex<Vec<sentry::Envelope>>>,
+) -> Vec<sentry::protocol::SessionAggregateItem> {
+    envelopes
+        .lock()
+        .unwrap()
+        .iter()
+        .flat_map(|envelope| {
+            envelope.items().filter_map(|item| match item {
+                sentry::protocol::EnvelopeItem::SessionAggregates(aggregates) => {
+                    Some(aggregates.aggregates.clone())
+                }
+                _ => None,
+            })
+        })
+        .flatten()
+        .collect()
+}
+
+/// One sequential test: the session flusher is per client, and closing
+/// the client to flush it would leave the second half without one.
+#[actix_web::test]
+async fn requests_contribute_sessions_and_a_500_marks_its_session_errored() {
+    let envelopes = common::bind_recording_client();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    // A request that succeeds and one that panics into a 500.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": 1, "y": 2 }))
+        .to_request();
+    test::call_service(&app, req).await;
+    let req = test::TestRequest::get().uri("/debug/panic").to_request();
+    assert!(test::try_call_service(&app, req).await.is_err());
+
+    // Aggregates only leave the flusher when the client closes.
+    sentry::Hub::current().client().unwrap().close(None);
+
+    let aggregates = recorded_session_aggregates(&envelopes);
+    let exited: u32 = aggregates.iter().map(|item| item.exited).sum();
+    let errored: u32 = aggregates.iter().map(|item| item.errored).sum();
+    assert!(
+        exited >= 1,
+        "no clean session was aggregated: {aggregates:?}"
+    );
+    assert!(
+        errored >= 1,
+        "the 500 did not mark its session errored: {aggregates:?}"
+    );
+}
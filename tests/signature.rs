@@ -0,0 +1,169 @@
+use actix_web::test;
+use sentry_rs_demo::{create_app, signature};
+
+mod common;
+
+/// One binary per knob configuration: every test here runs with one
+/// configured signing key.
+fn configure() {
+    std::env::set_var("HMAC_KEYS", "svc-a:topsecret");
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn signed(
+    path: &str,
+    body: &str,
+    key_id: &str,
+    secret: &str,
+    timestamp: u64,
+) -> actix_http::Request {
+    test::TestRequest::post()
+        .uri(path)
+        .insert_header(("content-type", "application/json"))
+        .insert_header(("x-signature-key-id", key_id))
+        .insert_header(("x-signature-timestamp", timestamp.to_string()))
+        .insert_header((
+            "x-signature",
+            signature::sign(secret, "POST", path, timestamp, body.as_bytes()),
+        ))
+        .set_payload(body.to_owned())
+        .to_request()
+}
+
+async fn error_code(
+    resp: actix_web::dev::ServiceResponse<impl actix_web::body::MessageBody>,
+) -> String {
+    assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+    let body = test::read_body(resp).await;
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    json["error"]["code"].as_str().unwrap().to_owned()
+}
+
+#[actix_web::test]
+async fn a_correctly_signed_request_is_served() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let req = signed(
+        "/api/v0/add",
+        r#"{"x": 2, "y": 3}"#,
+        "svc-a",
+        "topsecret",
+        now(),
+    );
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    let json: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(json["res"], 5);
+}
+
+#[actix_web::test]
+async fn status_stays_unsigned() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::get().uri("/api/v0/status").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+}
+
+#[actix_web::test]
+async fn unsigned_requests_are_rejected() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({"x": 2, "y": 3}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(error_code(resp).await, "missing_signature");
+}
+
+#[actix_web::test]
+async fn unknown_key_ids_are_rejected() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let req = signed(
+        "/api/v0/add",
+        r#"{"x": 2, "y": 3}"#,
+        "svc-b",
+        "topsecret",
+        now(),
+    );
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(error_code(resp).await, "unknown_signature_key");
+}
+
+#[actix_web::test]
+async fn stale_timestamps_are_rejected() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let req = signed(
+        "/api/v0/add",
+        r#"{"x": 2, "y": 3}"#,
+        "svc-a",
+        "topsecret",
+        now() - 10_000,
+    );
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(error_code(resp).await, "stale_signature");
+}
+
+#[actix_web::test]
+async fn wrong_secrets_are_rejected() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let req = signed(
+        "/api/v0/add",
+        r#"{"x": 2, "y": 3}"#,
+        "svc-a",
+        "not-the-secret",
+        now(),
+    );
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(error_code(resp).await, "invalid_signature");
+}
+
+#[actix_web::test]
+async fn a_tampered_body_fails_verification() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let timestamp = now();
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .insert_header(("content-type", "application/json"))
+        .insert_header(("x-signature-key-id", "svc-a"))
+        .insert_header(("x-signature-timestamp", timestamp.to_string()))
+        .insert_header((
+            "x-signature",
+            signature::sign(
+                "topsecret",
+                "POST",
+                "/api/v0/add",
+                timestamp,
+                br#"{"x": 2, "y": 3}"#,
+            ),
+        ))
+        .set_payload(r#"{"x": 2, "y": 4}"#)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(error_code(resp).await, "invalid_signature");
+}
@@ -0,0 +1,40 @@
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+#[actix_web::test]
+async fn batch_stream_emits_item_events_and_a_done_summary() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/batch/stream")
+        .set_json(serde_json::json!([
+            { "op": "add", "x": 1, "y": 2 },
+            { "op": "div", "x": 1, "y": 0 },
+        ]))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/event-stream"
+    );
+
+    let body = test::read_body(resp).await;
+    let body = std::str::from_utf8(&body).unwrap();
+
+    let events: Vec<&str> = body.split("\n\n").filter(|e| !e.is_empty()).collect();
+    assert_eq!(events.len(), 3, "unexpected events: {body}");
+
+    assert!(events[0].starts_with("event: item\n"));
+    assert!(events[0].contains(r#""index":0"#));
+    assert!(events[0].contains(r#""res":3"#));
+
+    assert!(events[1].starts_with("event: item\n"));
+    assert!(events[1].contains(r#""index":1"#));
+    assert!(events[1].contains(r#""code":"divide_by_zero""#));
+
+    assert!(events[2].starts_with("event: done\n"));
+    assert!(events[2].contains(r#""total":2"#));
+    assert!(events[2].contains(r#""succeeded":1"#));
+    assert!(events[2].contains(r#""failed":1"#));
+}
@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use sentry_rs_demo::create_app;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+// In its own binary: MAX_BATCH_SIZE is cached process-wide on first use,
+// so this test must not share a process with other batch tests.
+#[actix_web::test]
+async fn disconnecting_early_cancels_the_remaining_work() {
+    // Before the first max_batch_size() call, which caches the value.
+    const ITEMS: usize = 50_000;
+    std::env::set_var("MAX_BATCH_SIZE", "100000");
+
+    let server = actix_test::start(create_app);
+
+    let batch = serde_json::to_string(&vec![
+        serde_json::json!({ "op": "mul", "x": 1, "y": 1 });
+        ITEMS
+    ])
+    .unwrap();
+    let head = format!(
+        "POST /api/v0/batch/stream HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+        batch.len()
+    );
+
+    let mut stream = tokio::net::TcpStream::connect(server.addr()).await.unwrap();
+    stream.write_all(head.as_bytes()).await.unwrap();
+    stream.write_all(batch.as_bytes()).await.unwrap();
+    stream.flush().await.unwrap();
+
+    // Read just the start of the stream, then hang up without draining.
+    let mut buf = [0_u8; 256];
+    let n = stream.read(&mut buf).await.unwrap();
+    assert!(n > 0);
+    drop(stream);
+
+    // Once the disconnect is noticed the worker stops; wait for the
+    // processed count to settle and check it is nowhere near the total.
+    let mut last = 0_u64;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let mut resp = server.get("/api/v0/stats").send().await.unwrap();
+        let stats: serde_json::Value = resp.json().await.unwrap();
+        let processed = stats["operations"]["mul"]["requests"].as_u64().unwrap();
+        if processed == last && processed > 0 {
+            break;
+        }
+        last = processed;
+    }
+
+    assert!(last > 0, "no items were processed at all");
+    assert!(
+        last < ITEMS as u64,
+        "all {ITEMS} items were processed despite the disconnect"
+    );
+}
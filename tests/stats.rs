@@ -0,0 +1,64 @@
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+// Stats::global() is shared process-wide, so a single sequential test
+// keeps the counters deterministic.
+#[actix_web::test]
+async fn stats_track_requests_errors_and_latency_per_operation() {
+    // Before the first Config::global() call: the reset endpoint lives
+    // in the authenticated /admin scope.
+    std::env::set_var("ADMIN_TOKEN", "stats-test-token");
+
+    let app = test::init_service(create_app()).await;
+
+    for _ in 0..2 {
+        let req = test::TestRequest::post()
+            .uri("/api/v0/add")
+            .set_json(serde_json::json!({ "x": 2, "y": 3 }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/div")
+        .set_json(serde_json::json!({ "x": 1, "y": 0 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let req = test::TestRequest::get().uri("/api/v0/stats").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+
+    assert!(body["uptime_secs"].is_u64());
+    let add = &body["operations"]["add"];
+    assert_eq!(add["requests"], 2);
+    assert!(add["latency"]["avg_ms"].is_f64());
+    assert!(
+        add["latency"]["min_ms"].as_f64().unwrap() <= add["latency"]["max_ms"].as_f64().unwrap()
+    );
+    let div = &body["operations"]["div"];
+    assert_eq!(div["requests"], 1);
+    assert_eq!(div["errors"]["divide_by_zero"], 1);
+
+    // Operations that never ran report zero requests and no latency.
+    let sub = &body["operations"]["sub"];
+    assert_eq!(sub["requests"], 0);
+    assert!(sub.get("latency").is_none());
+
+    // Reset zeroes the counters but not the uptime.
+    let req = test::TestRequest::delete()
+        .uri("/admin/stats")
+        .insert_header(("authorization", "Bearer stats-test-token"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let req = test::TestRequest::get().uri("/api/v0/stats").to_request();
+    let resp = test::call_service(&app, req).await;
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["operations"]["add"]["requests"], 0);
+    assert_eq!(body["operations"]["div"]["errors"], serde_json::json!({}));
+}
@@ -0,0 +1,43 @@
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+// One sequential test: APP_STRICT_FIELDS is read once into the
+// process-global Config.
+#[actix_web::test]
+async fn strict_mode_rejects_unknown_fields_with_a_400() {
+    // Before the first Config::global() call, which reads it.
+    std::env::set_var("APP_STRICT_FIELDS", "true");
+
+    let app = test::init_service(create_app()).await;
+
+    // A typo'd field fails loudly, naming the field.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": 1, "z": 2 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "invalid_request_body");
+    assert!(body["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("unknown field `z`"));
+
+    // The query twins share the deserializer, so they are strict too.
+    let req = test::TestRequest::get()
+        .uri("/api/v0/add?x=1&y=2&z=3")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // Well-formed requests are unaffected.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": 1, "y": 2 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 3);
+}
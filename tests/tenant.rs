@@ -0,0 +1,99 @@
+use actix_web::test;
+use sentry_rs_demo::create_app;
+
+mod common;
+
+/// One binary per knob configuration: an allowlist of two tenants, with
+/// the default tenant still allowed for headerless callers.
+fn configure() {
+    std::env::set_var("TENANTS", "acme,globex");
+}
+
+fn calc_request(op: &str, tenant: Option<&str>, x: i64) -> actix_http::Request {
+    let req = test::TestRequest::post()
+        .uri(&format!("/api/v0/{op}"))
+        .set_json(serde_json::json!({"x": x, "y": 1}));
+    match tenant {
+        Some(tenant) => req.insert_header(("x-tenant-id", tenant)).to_request(),
+        None => req.to_request(),
+    }
+}
+
+#[actix_web::test]
+async fn unknown_tenants_are_refused() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let resp = test::call_service(&app, calc_request("add", Some("intruder"), 1)).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+    let json: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(json["error"]["code"], "unknown_tenant");
+}
+
+#[actix_web::test]
+async fn missing_header_maps_to_the_default_tenant() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let resp = test::call_service(&app, calc_request("add", None, 1)).await;
+    assert!(resp.status().is_success());
+}
+
+#[actix_web::test]
+async fn history_and_stats_are_partitioned_per_tenant() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    // Distinct operations, so each tenant's entries are recognisable
+    // even next to the default tenant the sibling tests exercise.
+    let resp = test::call_service(&app, calc_request("mul", Some("acme"), 10)).await;
+    assert!(resp.status().is_success());
+    let resp = test::call_service(&app, calc_request("sub", Some("globex"), 20)).await;
+    assert!(resp.status().is_success());
+
+    let req = test::TestRequest::get()
+        .uri("/api/v0/history")
+        .insert_header(("x-tenant-id", "acme"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let entries: serde_json::Value = test::read_body_json(resp).await;
+    let entries = entries.as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["x"], 10);
+
+    // Each tenant's stats count only its own requests.
+    let req = test::TestRequest::get()
+        .uri("/api/v0/stats")
+        .insert_header(("x-tenant-id", "acme"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let stats: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(stats["operations"]["mul"]["requests"], 1);
+    assert_eq!(stats["operations"]["sub"]["requests"], 0);
+
+    // The default tenant never multiplied; it sees a zero, not acme's
+    // counter.
+    let req = test::TestRequest::get().uri("/api/v0/stats").to_request();
+    let resp = test::call_service(&app, req).await;
+    let stats: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(stats["operations"]["mul"]["requests"], 0);
+
+    // Clearing acme's history leaves globex's intact.
+    let req = test::TestRequest::delete()
+        .uri("/api/v0/history")
+        .insert_header(("x-tenant-id", "acme"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NO_CONTENT);
+
+    let req = test::TestRequest::get()
+        .uri("/api/v0/history")
+        .insert_header(("x-tenant-id", "globex"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let entries: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(entries.as_array().unwrap().len(), 1);
+}
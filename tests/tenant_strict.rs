@@ -0,0 +1,32 @@
+use actix_web::test;
+use sentry_rs_demo::create_app;
+
+mod common;
+
+/// One binary per knob configuration: the default tenant is disallowed,
+/// so every caller has to name one.
+fn configure() {
+    std::env::set_var("TENANTS", "acme");
+    std::env::set_var("TENANT_ALLOW_DEFAULT", "false");
+}
+
+#[actix_web::test]
+async fn headerless_requests_are_refused_when_default_is_disallowed() {
+    configure();
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({"x": 1, "y": 2}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+    let json: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(json["error"]["code"], "missing_tenant");
+
+    // Probes stay exempt: health needs no tenant.
+    let req = test::TestRequest::get().uri("/healthz").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "sentry")]
+
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+mod common;
+
+// A single sequential test in its own binary: the timeout knobs come
+// from Config, which caches the environment on first access.
+#[actix_web::test]
+async fn slow_handlers_time_out_with_a_structured_504() {
+    // Before the first Config::global() call, which reads it; the slow
+    // list keeps its batch/streaming defaults, so the sleep route gets
+    // the regular limit. Which routes pick the slow limit is covered by
+    // the limit_for unit test.
+    std::env::set_var("REQUEST_TIMEOUT_MS", "100");
+
+    let events = common::bind_recording_client();
+    let app = test::init_service(create_app()).await;
+
+    // Within the limit: untouched.
+    let req = test::TestRequest::get().uri("/debug/sleep/10").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // Over the limit: the timeout renders via ResponseError, as in the
+    // panic tests.
+    let req = test::TestRequest::get()
+        .uri("/debug/sleep/400")
+        .to_request();
+    let resp = match test::try_call_service(&app, req).await {
+        Ok(_) => panic!("a handler over the limit should surface as an error"),
+        Err(err) => err.error_response(),
+    };
+    assert_eq!(resp.status(), StatusCode::GATEWAY_TIMEOUT);
+    assert!(resp.headers().contains_key("x-request-id"));
+
+    let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["error"]["code"], "timeout");
+    assert_eq!(body["error"]["status"], 504);
+    assert!(body["error"]["message"].as_str().unwrap().contains("100ms"));
+
+    let captured = common::recorded_events(&events);
+    let event = captured
+        .iter()
+        .find(|event| event.tags.get("code").map(String::as_str) == Some("timeout"))
+        .expect("no timeout event captured");
+    assert_eq!(event.tags.get("timeout").map(String::as_str), Some("true"));
+    assert_eq!(
+        event.tags.get("http.route").map(String::as_str),
+        Some("/debug/sleep/{ms}")
+    );
+    assert_eq!(event.extra.get("timeout_limit_ms"), Some(&100.into()));
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "sentry")]
+
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+mod common;
+
+const TRACE_ID: &str = "0af7651916cd43dd8448eb211c80319c";
+
+fn trace_context(
+    transaction: &sentry::protocol::Transaction<'static>,
+) -> sentry::protocol::TraceContext {
+    match transaction.contexts.get("trace") {
+        Some(sentry::protocol::Context::Trace(trace)) => (**trace).clone(),
+        other => panic!("transaction has no trace context: {other:?}"),
+    }
+}
+
+#[actix_web::test]
+async fn an_inbound_sentry_trace_header_continues_the_trace() {
+    let envelopes = common::bind_recording_client_with_traces(1.0);
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .insert_header(("sentry-trace", format!("{TRACE_ID}-b7ad6b7169203331-1")))
+        .set_json(serde_json::json!({ "x": 1, "y": 2 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let transactions = common::recorded_transactions(&envelopes);
+    let transaction = transactions.last().expect("no transaction captured");
+    assert_eq!(trace_context(transaction).trace_id.to_string(), TRACE_ID);
+}
+
+#[actix_web::test]
+async fn a_w3c_traceparent_header_continues_the_trace_too() {
+    let envelopes = common::bind_recording_client_with_traces(1.0);
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/mul")
+        .insert_header(("traceparent", format!("00-{TRACE_ID}-b7ad6b7169203331-01")))
+        .set_json(serde_json::json!({ "x": 2, "y": 3 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let transactions = common::recorded_transactions(&envelopes);
+    let transaction = transactions.last().expect("no transaction captured");
+    assert_eq!(trace_context(transaction).trace_id.to_string(), TRACE_ID);
+}
+
+#[actix_web::test]
+async fn error_bodies_carry_the_trace_id() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/div")
+        .insert_header(("sentry-trace", format!("{TRACE_ID}-b7ad6b7169203331-1")))
+        .set_json(serde_json::json!({ "x": 1, "y": 0 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "divide_by_zero");
+    assert_eq!(body["error"]["trace_id"], TRACE_ID);
+
+    // Without an inbound header a fresh trace id is minted.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/div")
+        .set_json(serde_json::json!({ "x": 1, "y": 0 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let fresh = body["error"]["trace_id"].as_str().unwrap();
+    assert_eq!(fresh.len(), 32);
+    assert_ne!(fresh, TRACE_ID);
+}
@@ -0,0 +1,117 @@
+#![cfg(feature = "sentry")]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::{Arc, Mutex};
+
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+mod common;
+
+/// The (path, body) pairs the mock upstream has accepted.
+type Forwarded = Arc<Mutex<Vec<(String, Vec<u8>)>>>;
+
+/// A stand-in ingest host: answers every request with a 200 and records
+/// the path and body so the test can assert on the forward.
+fn mock_ingest() -> (u16, Forwarded) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&received);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            if reader.read_line(&mut request_line).is_err() {
+                continue;
+            }
+            let path = request_line
+                .split_whitespace()
+                .nth(1)
+                .unwrap_or_default()
+                .to_string();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).is_err() || line.trim().is_empty() {
+                    break;
+                }
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+            let mut body = vec![0u8; content_length];
+            if reader.read_exact(&mut body).is_ok() {
+                sink.lock().unwrap().push((path, body));
+            }
+            let _ = reader
+                .into_inner()
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 10\r\n\r\n{\"id\":\"1\"}");
+        }
+    });
+
+    (port, received)
+}
+
+fn envelope_for(dsn: &str) -> Vec<u8> {
+    format!("{{\"dsn\":\"{dsn}\"}}\n{{\"type\":\"event\",\"length\":2}}\n{{}}\n").into_bytes()
+}
+
+/// One sequential test: the allowlist is frozen into the global Config,
+/// and the mock's port only exists at runtime.
+#[actix_web::test]
+async fn the_tunnel_forwards_allowed_dsns_and_refuses_the_rest() {
+    let (port, received) = mock_ingest();
+    let allowed = format!("http://publickey@127.0.0.1:{port}/42");
+    std::env::set_var("SENTRY_TUNNEL_DSNS", &allowed);
+    std::env::set_var("SENTRY_TUNNEL_MAX_BYTES", "4096");
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    // An allowlisted DSN is forwarded to its envelope endpoint, body
+    // untouched, and sentry's answer comes back verbatim.
+    let body = envelope_for(&allowed);
+    let req = test::TestRequest::post()
+        .uri("/api/v0/sentry-tunnel")
+        .set_payload(body.clone())
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let forwarded = received.lock().unwrap().clone();
+    assert_eq!(forwarded.len(), 1);
+    assert_eq!(forwarded[0].0, "/api/42/envelope/");
+    assert_eq!(forwarded[0].1, body);
+
+    // A valid DSN that is not on the allowlist gets a 403, and nothing
+    // reaches the upstream.
+    let other = format!("http://otherkey@127.0.0.1:{port}/43");
+    let req = test::TestRequest::post()
+        .uri("/api/v0/sentry-tunnel")
+        .set_payload(envelope_for(&other))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "tunnel_dsn_rejected");
+    assert_eq!(received.lock().unwrap().len(), 1);
+
+    // A body that is not an envelope at all is a 400.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/sentry-tunnel")
+        .set_payload("not an envelope")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // And the body cap holds before any parsing happens.
+    let req = test::TestRequest::post()
+        .uri("/api/v0/sentry-tunnel")
+        .set_payload(vec![b'x'; 5000])
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "envelope_too_large");
+}
@@ -0,0 +1,158 @@
+use std::os::unix::fs::FileTypeExt;
+use std::time::Duration;
+
+use sentry_rs_demo::{
+    build_server,
+    config::{Config, LogRotation, QuotaWindow},
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+mod common;
+
+#[tokio::test]
+async fn status_round_trip_over_the_unix_socket() {
+    common::mark_ready();
+    let dir = std::env::temp_dir().join(format!("sentry-rs-demo-uds-test-{}", std::process::id()));
+    // The parent directory is deliberately not created here: build_server
+    // is responsible for that. A stale file at the socket path stands in
+    // for a socket left behind by an unclean exit and must be swept.
+    let socket_path = dir.join("app.sock");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(&socket_path, "stale").unwrap();
+
+    let config = Config {
+        host: "127.0.0.1".to_string(),
+        port: 0,
+        workers: Some(1),
+        tls_cert_path: None,
+        tls_key_path: None,
+        tls_reload_secs: 0,
+        uds_path: Some(socket_path.to_string_lossy().into_owned()),
+        uds_mode: 0o660,
+        log_filter: "INFO".to_string(),
+        log_format_json: false,
+        log_dir: None,
+        log_rotation: LogRotation::Daily,
+        otlp_endpoint: None,
+        shutdown_grace_secs: 5,
+        api_keys: Vec::new(),
+        admin_token: None,
+        rate_limit_rps: None,
+        rate_limit_burst: 0.0,
+        trusted_proxy: false,
+        trusted_proxy_cidrs: Vec::new(),
+        cors_permissive: false,
+        cors_allowed_origins: Vec::new(),
+        cors_allowed_methods: Vec::new(),
+        cors_allowed_headers: Vec::new(),
+        cors_max_age: None,
+        history_capacity: 1_000,
+        operand_min: None,
+        operand_max: None,
+        strict_fields: false,
+        i64_as_string: false,
+        cache_enabled: false,
+        cache_capacity: 1_024,
+        idempotency_ttl_secs: 600,
+        idempotency_capacity: 1_024,
+        sentry_dsn: None,
+        sentry_required: false,
+        sentry_dedup_window_secs: 0,
+        sentry_spool_dir: None,
+        sentry_spool_max_bytes: 5 * 1024 * 1024,
+        sentry_spool_max_files: 200,
+        sentry_tunnel_dsns: Vec::new(),
+        sentry_tunnel_max_bytes: 1024 * 1024,
+        sentry_session_tracking: true,
+        sentry_fingerprint_rules: Vec::new(),
+        sentry_config_context: false,
+        sentry_events_per_minute: 0,
+        sentry_reporting_enabled: true,
+        sentry_http_proxy: None,
+        sentry_https_proxy: None,
+        sentry_ca_bundle: None,
+        anon_user_ids: true,
+        anon_salt: "test".to_string(),
+        max_in_flight: None,
+        shed_wait_ms: 100,
+        request_timeout_ms: 0,
+        request_timeout_slow_ms: 0,
+        slow_routes: Vec::new(),
+        sentry_event_level: tracing::Level::ERROR,
+        sentry_breadcrumb_level: tracing::Level::INFO,
+        housekeeping_interval_secs: 0,
+        housekeeping_monitor_slug: "housekeeping".to_string(),
+        scrub_keys: Vec::new(),
+        scrub_patterns: Vec::new(),
+        body_echo_enabled: false,
+        body_echo_max_bytes: 4_096,
+        body_echo_exclude_routes: Vec::new(),
+        security_content_type_options: "nosniff".to_string(),
+        security_frame_options: "DENY".to_string(),
+        security_referrer_policy: "no-referrer".to_string(),
+        security_hsts: "max-age=31536000; includeSubDomains".to_string(),
+        security_docs_csp: "default-src 'self'".to_string(),
+        compression_min_bytes: 1_024,
+        decompress_max_bytes: 2 * 1024 * 1024,
+        hmac_keys: Vec::new(),
+        hmac_skew_secs: 300,
+        jwt_hs256_secret: None,
+        jwt_rs256_pem_path: None,
+        jwt_jwks_url: None,
+        jwt_audience: None,
+        jwt_skew_secs: 60,
+        jwt_required_claims: Vec::new(),
+        quota_limits: Vec::new(),
+        quota_window: QuotaWindow::Daily,
+        tenants: Vec::new(),
+        tenant_allow_default: true,
+        webhook_url: None,
+        webhook_min_status: 500,
+        stats_flush_interval_secs: 60,
+    };
+    let (server, addrs) = build_server(&config).unwrap();
+    // TCP stays bound alongside the socket.
+    assert!(!addrs.is_empty());
+    let handle = server.handle();
+    let server_task = tokio::spawn(server);
+
+    // The stale file was replaced by a real socket with the configured
+    // mode.
+    let metadata = std::fs::metadata(&socket_path).unwrap();
+    assert!(metadata.file_type().is_socket());
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o660);
+    }
+
+    let mut stream = tokio::net::UnixStream::connect(&socket_path).await.unwrap();
+    stream
+        .write_all(b"GET /api/v0/status HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    stream.flush().await.unwrap();
+
+    let mut buf = Vec::new();
+    tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut buf))
+        .await
+        .expect("server closed the connection without responding")
+        .unwrap();
+    let resp = String::from_utf8_lossy(&buf);
+    assert!(
+        resp.starts_with("HTTP/1.1 200"),
+        "unexpected response: {resp}"
+    );
+    assert!(
+        resp.contains(r#""status":"OK""#),
+        "unexpected response: {resp}"
+    );
+
+    handle.stop(true).await;
+    tokio::time::timeout(Duration::from_secs(10), server_task)
+        .await
+        .expect("server did not shut down")
+        .unwrap()
+        .unwrap();
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
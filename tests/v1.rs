@@ -0,0 +1,54 @@
+use actix_web::{http::StatusCode, test};
+use sentry_rs_demo::create_app;
+
+#[actix_web::test]
+async fn v1_responses_are_enveloped_with_meta() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/add")
+        .set_json(serde_json::json!({ "x": 20, "y": 22 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["data"]["res"], 42);
+    assert_eq!(body["meta"]["op"], "add");
+    assert!(body["meta"]["duration_ms"].is_u64());
+    assert!(body["meta"]["request_id"].is_string());
+}
+
+#[actix_web::test]
+async fn v1_errors_keep_the_structured_format() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/div")
+        .set_json(serde_json::json!({ "x": 1, "y": 0 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "divide_by_zero");
+    assert_eq!(body["error"]["status"], 400);
+}
+
+#[actix_web::test]
+async fn v0_stays_bare_but_signals_deprecation() {
+    let app = test::init_service(create_app()).await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v0/add")
+        .set_json(serde_json::json!({ "x": 20, "y": 22 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("deprecation").unwrap(), "true");
+    assert!(resp.headers().contains_key("sunset"));
+
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["res"], 42);
+    assert!(body.get("data").is_none());
+}
@@ -0,0 +1,96 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actix_web::test;
+use sentry_rs_demo::create_app;
+
+mod common;
+
+/// A minimal HTTP sink on a loopback port: answers every POST with a 200
+/// and collects the JSON bodies for the assertions.
+fn mock_webhook() -> (String, Arc<Mutex<Vec<serde_json::Value>>>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&received);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let mut reader = BufReader::new(stream);
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).is_err() || line.trim().is_empty() {
+                    break;
+                }
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+            let mut body = vec![0u8; content_length];
+            if reader.read_exact(&mut body).is_ok() {
+                if let Ok(json) = serde_json::from_slice(&body) {
+                    sink.lock().unwrap().push(json);
+                }
+            }
+            let _ = reader
+                .into_inner()
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n");
+        }
+    });
+
+    (format!("http://{addr}/hook"), received)
+}
+
+/// One binary, one sequential test: the notifier's debounce state and
+/// delivery counters are process-global.
+#[actix_web::test]
+async fn server_errors_are_notified_once_per_code_per_minute() {
+    let (url, received) = mock_webhook();
+    // Before the first Config::global() call, which freezes the URL.
+    std::env::set_var("WEBHOOK_URL", &url);
+    common::mark_ready();
+    let app = test::init_service(create_app()).await;
+
+    // Two panics in quick succession: the first is notified, the second
+    // falls inside the debounce window.
+    for _ in 0..2 {
+        let req = test::TestRequest::get().uri("/debug/panic").to_request();
+        let resp = match test::try_call_service(&app, req).await {
+            Ok(_) => panic!("the panic did not surface as an error"),
+            Err(err) => err.error_response(),
+        };
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    // Delivery is asynchronous; give the background task a moment.
+    for _ in 0..100 {
+        if !received.lock().unwrap().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    // And a little longer to be sure the second panic stays debounced.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let notifications = received.lock().unwrap().clone();
+    assert_eq!(notifications.len(), 1, "expected exactly one notification");
+    let notification = &notifications[0];
+    assert_eq!(notification["code"], "handler_panic");
+    assert_eq!(notification["route"], "/debug/panic");
+    assert!(notification["request_id"].is_string());
+    assert!(notification["timestamp"].as_u64().unwrap() > 0);
+    assert_eq!(notification["count"], 1);
+
+    // The delivery shows up in /stats.
+    let req = test::TestRequest::get().uri("/api/v0/stats").to_request();
+    let resp = test::call_service(&app, req).await;
+    let stats: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(stats["webhook_delivered"], 1);
+    assert_eq!(stats["webhook_failed"], 0);
+}
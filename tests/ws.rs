@@ -0,0 +1,71 @@
+use futures_util::{SinkExt, StreamExt};
+use sentry_rs_demo::create_app;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::Message;
+
+// Both tests share the ws module's OnceLock-cached env knobs, so the
+// values are set once here and apply to the whole binary.
+fn ws_url(server: &actix_test::TestServer) -> String {
+    std::env::set_var("WS_IDLE_TIMEOUT_SECS", "1");
+    format!("ws://{}/api/v0/ws", server.addr())
+}
+
+#[actix_web::test]
+async fn frames_round_trip_until_the_client_closes() {
+    let server = actix_test::start(create_app);
+    let (mut socket, _) = tokio_tungstenite::connect_async(ws_url(&server))
+        .await
+        .expect("websocket handshake failed");
+
+    socket
+        .send(Message::text(r#"{"op":"add","x":20,"y":22}"#))
+        .await
+        .unwrap();
+    let reply = socket.next().await.unwrap().unwrap();
+    let body: serde_json::Value = serde_json::from_str(reply.to_text().unwrap()).unwrap();
+    assert_eq!(body["res"], 42);
+
+    // The session stays open: errors come back as structured frames.
+    socket
+        .send(Message::text(r#"{"op":"div","x":1,"y":0}"#))
+        .await
+        .unwrap();
+    let reply = socket.next().await.unwrap().unwrap();
+    let body: serde_json::Value = serde_json::from_str(reply.to_text().unwrap()).unwrap();
+    assert_eq!(body["error"]["code"], "divide_by_zero");
+    assert_eq!(body["error"]["status"], 400);
+
+    socket
+        .send(Message::text(r#"not even json"#))
+        .await
+        .unwrap();
+    let reply = socket.next().await.unwrap().unwrap();
+    let body: serde_json::Value = serde_json::from_str(reply.to_text().unwrap()).unwrap();
+    assert_eq!(body["error"]["code"], "invalid_request_body");
+
+    // A clean client close is echoed and the stream ends.
+    socket.send(Message::Close(None)).await.unwrap();
+    let reply = socket.next().await.unwrap().unwrap();
+    assert!(matches!(reply, Message::Close(_)));
+    assert!(socket.next().await.is_none());
+}
+
+#[actix_web::test]
+async fn idle_sessions_are_closed_by_the_server() {
+    let server = actix_test::start(create_app);
+    let (mut socket, _) = tokio_tungstenite::connect_async(ws_url(&server))
+        .await
+        .expect("websocket handshake failed");
+
+    // Send nothing; the server should close the session after the idle
+    // timeout (1s for this binary) with a policy close code.
+    let reply = tokio::time::timeout(std::time::Duration::from_secs(5), socket.next())
+        .await
+        .expect("server did not close the idle session")
+        .unwrap()
+        .unwrap();
+    match reply {
+        Message::Close(Some(frame)) => assert_eq!(frame.code, CloseCode::Policy),
+        other => panic!("expected a close frame, got {other:?}"),
+    }
+}